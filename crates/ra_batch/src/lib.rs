@@ -5,9 +5,9 @@ use std::{collections::HashSet, error::Error, path::Path};
 use rustc_hash::FxHashMap;
 
 use crossbeam_channel::{unbounded, Receiver};
-use ra_db::{CrateGraph, FileId, SourceRootId};
+use ra_db::{CrateGraph, FileId, SourceDatabaseExt, SourceRootId};
 use ra_ide::{AnalysisChange, AnalysisHost, FeatureFlags};
-use ra_project_model::{get_rustc_cfg_options, PackageRoot, ProjectWorkspace};
+use ra_project_model::{get_rustc_cfg_options, PackageRoot, ProcMacroClient, ProjectWorkspace};
 use ra_vfs::{RootEntry, Vfs, VfsChange, VfsTask, Watch};
 use ra_vfs_glob::RustPackageFilterBuilder;
 
@@ -50,12 +50,18 @@ pub fn load_cargo(root: &Path) -> Result<(AnalysisHost, FxHashMap<SourceRootId,
         opts
     };
 
-    let (crate_graph, _crate_names) =
-        ws.to_crate_graph(&default_cfg_options, &mut |path: &Path| {
+    let root_paths =
+        roots.iter().map(|&vfs_root| (vfs.root2path(vfs_root), vfs_root_to_id(vfs_root))).collect();
+    let (crate_graph, _crate_names) = ws.to_crate_graph(
+        &default_cfg_options,
+        &ProcMacroClient::dummy(),
+        &root_paths,
+        &mut |path: &Path| {
             let vfs_file = vfs.load(path);
             log::debug!("vfs file {:?} -> {:?}", path, vfs_file);
             vfs_file.map(vfs_file_to_id)
-        });
+        },
+    );
     log::debug!("crate graph: {:?}", crate_graph);
 
     let source_roots = roots
@@ -74,6 +80,33 @@ pub fn load_cargo(root: &Path) -> Result<(AnalysisHost, FxHashMap<SourceRootId,
     Ok((host, source_roots))
 }
 
+/// Finds the file at `path`, relative to the current directory, among the
+/// workspace members loaded by `load_cargo`.
+pub fn find_file_id(
+    host: &AnalysisHost,
+    roots: &FxHashMap<SourceRootId, PackageRoot>,
+    path: &Path,
+) -> Result<FileId> {
+    let db = host.raw_database();
+    let path = std::env::current_dir()?.join(path).canonicalize()?;
+    let file_id = roots
+        .iter()
+        .find_map(|(source_root_id, project_root)| {
+            if project_root.is_member() {
+                for file_id in db.source_root(*source_root_id).walk() {
+                    let rel_path = db.file_relative_path(file_id);
+                    let abs_path = rel_path.to_path(project_root.path());
+                    if abs_path == path {
+                        return Some(file_id);
+                    }
+                }
+            }
+            None
+        })
+        .ok_or_else(|| format!("Can't find {:?}", path))?;
+    Ok(file_id)
+}
+
 pub fn load(
     source_roots: &FxHashMap<SourceRootId, PackageRoot>,
     crate_graph: CrateGraph,
@@ -0,0 +1,688 @@
+//! Converts rustc diagnostics, as reported by `cargo --message-format=json`,
+//! to LSP diagnostics.
+
+use std::path::Path;
+
+use cargo_metadata::diagnostic::{
+    Applicability, Diagnostic as RustDiagnostic, DiagnosticLevel, DiagnosticSpan,
+};
+use lsp_types::{
+    Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, Location,
+    NumberOrString, Position, Range, Url,
+};
+
+/// A fix proposed by rustc: replacing the text at `location` with
+/// `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuggestedFix {
+    pub title: String,
+    pub location: Location,
+    pub replacement: String,
+    /// Whether rustc is confident that the fix is what the user wants.
+    pub is_preferred: bool,
+    /// The diagnostics this fix resolves.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug)]
+pub(crate) struct MappedRustDiagnostic {
+    pub(crate) location: Location,
+    pub(crate) diagnostic: Diagnostic,
+    pub(crate) suggested_fixes: Vec<SuggestedFix>,
+}
+
+/// Converts a rustc diagnostic to an LSP one, anchored at its primary span.
+///
+/// Secondary spans and notes which point somewhere become related
+/// information, notes which don't are appended to the message, and
+/// suggested replacements become fixes.
+///
+/// Returns `None` for diagnostics without a primary span, like the final
+/// "aborting due to previous error".
+pub(crate) fn map_rust_diagnostic_to_lsp(
+    rd: &RustDiagnostic,
+    workspace_root: &Path,
+) -> Option<MappedRustDiagnostic> {
+    let primary_span = rd.spans.iter().find(|s| s.is_primary)?;
+
+    let location = map_span_to_location(primary_span, workspace_root);
+
+    let severity = map_level_to_severity(rd.level);
+    let mut source = String::from("rustc");
+    let mut code = rd.code.as_ref().map(|c| c.code.clone());
+    if let Some(code_val) = &code {
+        // See if this is an RFC #2103 scoped lint (e.g. from Clippy)
+        let scoped_code: Vec<&str> = code_val.split("::").collect();
+        if scoped_code.len() == 2 {
+            source = String::from(scoped_code[0]);
+            code = Some(String::from(scoped_code[1]));
+        }
+    }
+
+    let mut related_information = vec![];
+    let mut tags = vec![];
+
+    for secondary_span in rd.spans.iter().filter(|s| !s.is_primary) {
+        if let Some(related) = map_secondary_span_to_related(secondary_span, workspace_root) {
+            related_information.push(related);
+        }
+    }
+    related_information.extend(map_macro_backtrace(primary_span, workspace_root));
+
+    let mut suggested_fixes = vec![];
+    let mut message = rd.message.clone();
+    for child in &rd.children {
+        match map_rust_child_diagnostic(child, workspace_root) {
+            MappedRustChildDiagnostic::Related(related) => related_information.push(related),
+            MappedRustChildDiagnostic::SuggestedFix(fix) => suggested_fixes.push(fix),
+            MappedRustChildDiagnostic::MessageLine(line) => {
+                message.push_str("\n");
+                message.push_str(&line);
+
+                // These secondary messages usually contain the lint name
+                // of an error in the form of
+                // `#[deny(unused_variables)]`.
+                if let Some(tag) = map_note_to_tag(&line) {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(tag) = code.as_ref().and_then(|it| map_code_to_tag(it)) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let diagnostic = Diagnostic {
+        range: location.range,
+        severity,
+        code: code.map(NumberOrString::String),
+        source: Some(source),
+        message,
+        related_information: if related_information.is_empty() {
+            None
+        } else {
+            Some(related_information)
+        },
+        tags: if tags.is_empty() { None } else { Some(tags) },
+    };
+
+    for fix in suggested_fixes.iter_mut() {
+        fix.diagnostics.push(diagnostic.clone());
+    }
+
+    Some(MappedRustDiagnostic { location, diagnostic, suggested_fixes })
+}
+
+enum MappedRustChildDiagnostic {
+    Related(DiagnosticRelatedInformation),
+    SuggestedFix(SuggestedFix),
+    MessageLine(String),
+}
+
+fn map_rust_child_diagnostic(
+    rd: &RustDiagnostic,
+    workspace_root: &Path,
+) -> MappedRustChildDiagnostic {
+    let span = match rd.spans.iter().find(|s| s.is_primary) {
+        Some(it) => it,
+        None => {
+            // `rustc` uses these spanless children as a way to print
+            // multi-line messages
+            return MappedRustChildDiagnostic::MessageLine(format!(
+                "{}: {}",
+                level_label(rd.level),
+                rd.message
+            ));
+        }
+    };
+
+    let location = map_span_to_location(span, workspace_root);
+    match &span.suggested_replacement {
+        Some(replacement) => {
+            let title = if replacement.is_empty() {
+                rd.message.clone()
+            } else {
+                format!("{}: `{}`", rd.message, replacement)
+            };
+            let is_preferred = match span.suggestion_applicability {
+                Some(Applicability::MachineApplicable) => true,
+                _ => false,
+            };
+            MappedRustChildDiagnostic::SuggestedFix(SuggestedFix {
+                title,
+                location,
+                replacement: replacement.clone(),
+                is_preferred,
+                diagnostics: vec![],
+            })
+        }
+        None => MappedRustChildDiagnostic::Related(DiagnosticRelatedInformation {
+            location,
+            message: rd.message.clone(),
+        }),
+    }
+}
+
+/// Converts a secondary span to related information, if it is labeled.
+fn map_secondary_span_to_related(
+    span: &DiagnosticSpan,
+    workspace_root: &Path,
+) -> Option<DiagnosticRelatedInformation> {
+    let message = span.label.clone()?;
+    let location = map_span_to_location(span, workspace_root);
+    Some(DiagnosticRelatedInformation { location, message })
+}
+
+/// Points at the definition of each macro the span has been expanded
+/// through, innermost first.
+fn map_macro_backtrace(
+    span: &DiagnosticSpan,
+    workspace_root: &Path,
+) -> Vec<DiagnosticRelatedInformation> {
+    let mut res = Vec::new();
+    let mut span = span;
+    while let Some(expansion) = &span.expansion {
+        if let Some(def_site) = &expansion.def_site_span {
+            if !is_from_macro(&def_site.file_name) {
+                res.push(DiagnosticRelatedInformation {
+                    location: map_span_to_location(def_site, workspace_root),
+                    message: format!("in this expansion of `{}`", expansion.macro_decl_name),
+                });
+            }
+        }
+        span = &expansion.span;
+    }
+    res
+}
+
+/// Converts a span to a location in a real file.
+///
+/// Spans inside macro expansions point to pseudo-files like
+/// `<::std::macros::panic macros>`, for those we use the location of the
+/// outermost macro call instead.
+fn map_span_to_location(span: &DiagnosticSpan, workspace_root: &Path) -> Location {
+    if is_from_macro(&span.file_name) {
+        if let Some(expansion) = &span.expansion {
+            return map_span_to_location(&expansion.span, workspace_root);
+        }
+    }
+
+    let file_name = workspace_root.join(&span.file_name);
+    let uri = Url::from_file_path(file_name).unwrap();
+
+    // FIXME: rustc's columns count chars, while LSP wants UTF-16 code units
+    let range = Range::new(
+        Position::new(span.line_start as u64 - 1, span.column_start as u64 - 1),
+        Position::new(span.line_end as u64 - 1, span.column_end as u64 - 1),
+    );
+
+    Location { uri, range }
+}
+
+fn is_from_macro(file_name: &str) -> bool {
+    file_name.starts_with('<') && file_name.ends_with('>')
+}
+
+fn map_level_to_severity(val: DiagnosticLevel) -> Option<DiagnosticSeverity> {
+    let res = match val {
+        DiagnosticLevel::Ice => DiagnosticSeverity::Error,
+        DiagnosticLevel::Error => DiagnosticSeverity::Error,
+        DiagnosticLevel::Warning => DiagnosticSeverity::Warning,
+        DiagnosticLevel::Note => DiagnosticSeverity::Information,
+        DiagnosticLevel::Help => DiagnosticSeverity::Hint,
+        _ => return None,
+    };
+    Some(res)
+}
+
+fn level_label(val: DiagnosticLevel) -> &'static str {
+    match val {
+        DiagnosticLevel::Ice => "error: internal compiler error",
+        DiagnosticLevel::Error => "error",
+        DiagnosticLevel::Warning => "warning",
+        DiagnosticLevel::Help => "help",
+        _ => "note",
+    }
+}
+
+fn map_note_to_tag(note: &str) -> Option<DiagnosticTag> {
+    let lint = note.rsplit("#[").next()?.split(|c| c == '(' || c == ')').nth(1)?;
+    map_code_to_tag(lint)
+}
+
+fn map_code_to_tag(code: &str) -> Option<DiagnosticTag> {
+    match code {
+        "dead_code" | "unknown_lints" | "unreachable_code" | "unused_attributes"
+        | "unused_imports" | "unused_macros" | "unused_variables" => {
+            Some(DiagnosticTag::Unnecessary)
+        }
+        "deprecated" => Some(DiagnosticTag::Deprecated),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_diagnostic(val: &str) -> RustDiagnostic {
+        serde_json::from_str::<RustDiagnostic>(val).expect("invalid diagnostic")
+    }
+
+    fn workspace_root() -> &'static Path {
+        if cfg!(windows) {
+            Path::new(r"C:\test")
+        } else {
+            Path::new("/test")
+        }
+    }
+
+    fn file_uri(path: &str) -> Url {
+        Url::from_file_path(workspace_root().join(path)).unwrap()
+    }
+
+    #[test]
+    fn snap_rustc_unused_variable() {
+        let diag = parse_diagnostic(
+            r##"{
+    "message": "unused variable: `foo`",
+    "code": {
+        "code": "unused_variables",
+        "explanation": null
+    },
+    "level": "warning",
+    "spans": [
+        {
+            "file_name": "driver/subcommand/repl.rs",
+            "byte_start": 9228,
+            "byte_end": 9231,
+            "line_start": 291,
+            "line_end": 291,
+            "column_start": 9,
+            "column_end": 12,
+            "is_primary": true,
+            "text": [
+                {
+                    "text": "    let foo = 42;",
+                    "highlight_start": 9,
+                    "highlight_end": 12
+                }
+            ],
+            "label": null,
+            "suggested_replacement": null,
+            "suggestion_applicability": null,
+            "expansion": null
+        }
+    ],
+    "children": [
+        {
+            "message": "#[warn(unused_variables)] on by default",
+            "code": null,
+            "level": "note",
+            "spans": [],
+            "children": [],
+            "rendered": null
+        },
+        {
+            "message": "consider prefixing with an underscore",
+            "code": null,
+            "level": "help",
+            "spans": [
+                {
+                    "file_name": "driver/subcommand/repl.rs",
+                    "byte_start": 9228,
+                    "byte_end": 9231,
+                    "line_start": 291,
+                    "line_end": 291,
+                    "column_start": 9,
+                    "column_end": 12,
+                    "is_primary": true,
+                    "text": [
+                        {
+                            "text": "    let foo = 42;",
+                            "highlight_start": 9,
+                            "highlight_end": 12
+                        }
+                    ],
+                    "label": null,
+                    "suggested_replacement": "_foo",
+                    "suggestion_applicability": "MachineApplicable",
+                    "expansion": null
+                }
+            ],
+            "children": [],
+            "rendered": null
+        }
+    ],
+    "rendered": "warning: unused variable: `foo`\n"
+}"##,
+        );
+
+        let mapped = map_rust_diagnostic_to_lsp(&diag, workspace_root()).unwrap();
+
+        let range = Range::new(Position::new(290, 8), Position::new(290, 11));
+        assert_eq!(mapped.location.uri, file_uri("driver/subcommand/repl.rs"));
+        assert_eq!(mapped.location.range, range);
+
+        let diagnostic = &mapped.diagnostic;
+        assert_eq!(diagnostic.range, range);
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::Warning));
+        assert_eq!(diagnostic.source.as_ref().map(String::as_str), Some("rustc"));
+        assert_eq!(diagnostic.code, Some(NumberOrString::String("unused_variables".into())));
+        assert_eq!(
+            diagnostic.message,
+            "unused variable: `foo`\nnote: #[warn(unused_variables)] on by default"
+        );
+        assert_eq!(diagnostic.tags, Some(vec![DiagnosticTag::Unnecessary]));
+        assert_eq!(diagnostic.related_information, None);
+
+        assert_eq!(
+            mapped.suggested_fixes,
+            vec![SuggestedFix {
+                title: "consider prefixing with an underscore: `_foo`".to_string(),
+                location: Location { uri: file_uri("driver/subcommand/repl.rs"), range },
+                replacement: "_foo".to_string(),
+                is_preferred: true,
+                diagnostics: vec![diagnostic.clone()],
+            }]
+        );
+    }
+
+    #[test]
+    fn snap_rustc_incompatible_type_for_trait() {
+        let diag = parse_diagnostic(
+            r##"{
+    "message": "method `next` has an incompatible type for trait",
+    "code": {
+        "code": "E0053",
+        "explanation": "\nThe parameters of any trait method must match between a trait implementation\nand the trait definition.\n"
+    },
+    "level": "error",
+    "spans": [
+        {
+            "file_name": "compiler/ty/list_iter.rs",
+            "byte_start": 1307,
+            "byte_end": 1350,
+            "line_start": 52,
+            "line_end": 52,
+            "column_start": 5,
+            "column_end": 48,
+            "is_primary": true,
+            "text": [
+                {
+                    "text": "    fn next(&self) -> Option<&'list ty::Ref<M>> {",
+                    "highlight_start": 5,
+                    "highlight_end": 48
+                }
+            ],
+            "label": "types differ in mutability",
+            "suggested_replacement": null,
+            "suggestion_applicability": null,
+            "expansion": null
+        }
+    ],
+    "children": [
+        {
+            "message": "expected type `fn(&mut ty::list_iter::ListIterator<'list, M>) -> std::option::Option<&ty::Ref<M>>`\n   found type `fn(&ty::list_iter::ListIterator<'list, M>) -> std::option::Option<&'list ty::Ref<M>>`",
+            "code": null,
+            "level": "note",
+            "spans": [],
+            "children": [],
+            "rendered": null
+        }
+    ],
+    "rendered": "error[E0053]: method `next` has an incompatible type for trait\n"
+}"##,
+        );
+
+        let mapped = map_rust_diagnostic_to_lsp(&diag, workspace_root()).unwrap();
+
+        let diagnostic = &mapped.diagnostic;
+        assert_eq!(mapped.location.uri, file_uri("compiler/ty/list_iter.rs"));
+        assert_eq!(diagnostic.range, Range::new(Position::new(51, 4), Position::new(51, 47)));
+        assert_eq!(diagnostic.severity, Some(DiagnosticSeverity::Error));
+        assert_eq!(diagnostic.code, Some(NumberOrString::String("E0053".into())));
+        assert!(diagnostic.message.starts_with(
+            "method `next` has an incompatible type for trait\nnote: expected type `fn(&mut"
+        ));
+        assert_eq!(diagnostic.tags, None);
+        assert!(mapped.suggested_fixes.is_empty());
+    }
+
+    #[test]
+    fn snap_clippy_pass_by_ref() {
+        let diag = parse_diagnostic(
+            r##"{
+    "message": "this argument is passed by reference, but would be more efficient if passed by value",
+    "code": {
+        "code": "clippy::trivially_copy_pass_by_ref",
+        "explanation": null
+    },
+    "level": "warning",
+    "spans": [
+        {
+            "file_name": "compiler/mir/tagset.rs",
+            "byte_start": 941,
+            "byte_end": 946,
+            "line_start": 42,
+            "line_end": 42,
+            "column_start": 24,
+            "column_end": 29,
+            "is_primary": true,
+            "text": [
+                {
+                    "text": "    pub fn is_disjoint(&self, other: Self) -> bool {",
+                    "highlight_start": 24,
+                    "highlight_end": 29
+                }
+            ],
+            "label": null,
+            "suggested_replacement": null,
+            "suggestion_applicability": null,
+            "expansion": null
+        }
+    ],
+    "children": [
+        {
+            "message": "lint level defined here",
+            "code": null,
+            "level": "note",
+            "spans": [
+                {
+                    "file_name": "compiler/lib.rs",
+                    "byte_start": 8,
+                    "byte_end": 19,
+                    "line_start": 1,
+                    "line_end": 1,
+                    "column_start": 9,
+                    "column_end": 20,
+                    "is_primary": true,
+                    "text": [
+                        {
+                            "text": "#![warn(clippy::all)]",
+                            "highlight_start": 9,
+                            "highlight_end": 20
+                        }
+                    ],
+                    "label": null,
+                    "suggested_replacement": null,
+                    "suggestion_applicability": null,
+                    "expansion": null
+                }
+            ],
+            "children": [],
+            "rendered": null
+        },
+        {
+            "message": "consider passing by value instead",
+            "code": null,
+            "level": "help",
+            "spans": [
+                {
+                    "file_name": "compiler/mir/tagset.rs",
+                    "byte_start": 941,
+                    "byte_end": 946,
+                    "line_start": 42,
+                    "line_end": 42,
+                    "column_start": 24,
+                    "column_end": 29,
+                    "is_primary": true,
+                    "text": [
+                        {
+                            "text": "    pub fn is_disjoint(&self, other: Self) -> bool {",
+                            "highlight_start": 24,
+                            "highlight_end": 29
+                        }
+                    ],
+                    "label": null,
+                    "suggested_replacement": "self",
+                    "suggestion_applicability": "Unspecified",
+                    "expansion": null
+                }
+            ],
+            "children": [],
+            "rendered": null
+        }
+    ],
+    "rendered": "warning: this argument is passed by reference\n"
+}"##,
+        );
+
+        let mapped = map_rust_diagnostic_to_lsp(&diag, workspace_root()).unwrap();
+
+        let diagnostic = &mapped.diagnostic;
+        assert_eq!(diagnostic.source.as_ref().map(String::as_str), Some("clippy"));
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String("trivially_copy_pass_by_ref".into()))
+        );
+        assert_eq!(
+            diagnostic.related_information,
+            Some(vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri: file_uri("compiler/lib.rs"),
+                    range: Range::new(Position::new(0, 8), Position::new(0, 19)),
+                },
+                message: "lint level defined here".to_string(),
+            }])
+        );
+
+        assert_eq!(mapped.suggested_fixes.len(), 1);
+        let fix = &mapped.suggested_fixes[0];
+        assert_eq!(fix.title, "consider passing by value instead: `self`");
+        assert_eq!(fix.replacement, "self");
+        assert!(!fix.is_preferred);
+    }
+
+    #[test]
+    fn snap_macro_compiler_error() {
+        let diag = parse_diagnostic(
+            r##"{
+    "message": "Please register your known path in the path module",
+    "code": null,
+    "level": "error",
+    "spans": [
+        {
+            "file_name": "<::core::macros::panic macros>",
+            "byte_start": 0,
+            "byte_end": 100,
+            "line_start": 1,
+            "line_end": 8,
+            "column_start": 1,
+            "column_end": 42,
+            "is_primary": true,
+            "text": [
+                {
+                    "text": "( ) => ( { panic ! ( \"explicit panic\" ) } ) ; ( $ msg : expr ) => (",
+                    "highlight_start": 1,
+                    "highlight_end": 70
+                }
+            ],
+            "label": null,
+            "suggested_replacement": null,
+            "suggestion_applicability": null,
+            "expansion": {
+                "span": {
+                    "file_name": "crates/ra_hir_def/src/path.rs",
+                    "byte_start": 9752,
+                    "byte_end": 9810,
+                    "line_start": 264,
+                    "line_end": 264,
+                    "column_start": 9,
+                    "column_end": 67,
+                    "is_primary": false,
+                    "text": [
+                        {
+                            "text": "        compile_error!(\"Please register your known path in the path module\")",
+                            "highlight_start": 9,
+                            "highlight_end": 67
+                        }
+                    ],
+                    "label": null,
+                    "suggested_replacement": null,
+                    "suggestion_applicability": null,
+                    "expansion": null
+                },
+                "macro_decl_name": "known_path!",
+                "def_site_span": {
+                    "file_name": "crates/ra_hir_def/src/path.rs",
+                    "byte_start": 9000,
+                    "byte_end": 9200,
+                    "line_start": 250,
+                    "line_end": 260,
+                    "column_start": 1,
+                    "column_end": 2,
+                    "is_primary": false,
+                    "text": [],
+                    "label": null,
+                    "suggested_replacement": null,
+                    "suggestion_applicability": null,
+                    "expansion": null
+                }
+            }
+        }
+    ],
+    "children": [],
+    "rendered": "error: Please register your known path in the path module\n"
+}"##,
+        );
+
+        let mapped = map_rust_diagnostic_to_lsp(&diag, workspace_root()).unwrap();
+
+        // The diagnostic is reported at the macro call, not in the pseudo-file.
+        assert_eq!(mapped.location.uri, file_uri("crates/ra_hir_def/src/path.rs"));
+        assert_eq!(
+            mapped.location.range,
+            Range::new(Position::new(263, 8), Position::new(263, 66))
+        );
+        assert_eq!(
+            mapped.diagnostic.related_information,
+            Some(vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri: file_uri("crates/ra_hir_def/src/path.rs"),
+                    range: Range::new(Position::new(249, 0), Position::new(259, 1)),
+                },
+                message: "in this expansion of `known_path!`".to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn spanless_diagnostics_are_skipped() {
+        let diag = parse_diagnostic(
+            r##"{
+    "message": "aborting due to previous error",
+    "code": null,
+    "level": "error",
+    "spans": [],
+    "children": [],
+    "rendered": "error: aborting due to previous error\n"
+}"##,
+        );
+        assert!(map_rust_diagnostic_to_lsp(&diag, workspace_root()).is_none());
+    }
+}
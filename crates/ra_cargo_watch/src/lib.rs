@@ -0,0 +1,288 @@
+//! `ra_cargo_watch` runs `cargo check` (or a compatible command, like
+//! `cargo clippy`) in a background thread and turns its JSON output into LSP
+//! diagnostics.
+//!
+//! A run is started on creation and restarted on every `CheckWatcher::update`
+//! (on save, that is). A restart kills the stale `cargo` process, so at most
+//! one check is running at any time.
+
+mod conv;
+
+use std::{
+    io::BufReader,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::Arc,
+};
+
+use cargo_metadata::Message;
+use crossbeam_channel::{never, select, unbounded, Receiver, RecvError, Sender};
+use lsp_types::{Diagnostic, Url};
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+
+use crate::conv::{map_rust_diagnostic_to_lsp, MappedRustDiagnostic};
+
+pub use crate::conv::SuggestedFix;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckOptions {
+    pub enable: bool,
+    /// Extra arguments passed to the command, after `--message-format=json`.
+    pub args: Vec<String>,
+    /// Cargo subcommand to run, `check` or `clippy` for example.
+    pub command: String,
+    /// Whether to pass `--all-targets`.
+    pub all_targets: bool,
+}
+
+/// `CheckWatcher` owns the background thread which runs the checks. The main
+/// loop listens on `task_recv` and reads the collected diagnostics from
+/// `shared`.
+#[derive(Debug)]
+pub struct CheckWatcher {
+    pub task_recv: Receiver<CheckTask>,
+    pub shared: Arc<RwLock<CheckWatcherSharedState>>,
+    // XXX: drop order is significant: closing the command channel is what
+    // makes the thread exit, so it must be dropped before joining.
+    cmd_send: Option<Sender<CheckCommand>>,
+    handle: Option<jod_thread::JoinHandle<()>>,
+}
+
+impl CheckWatcher {
+    pub fn new(options: &CheckOptions, workspace_root: PathBuf) -> CheckWatcher {
+        let options = options.clone();
+        let shared = Arc::new(RwLock::new(CheckWatcherSharedState::default()));
+
+        let (task_send, task_recv) = unbounded::<CheckTask>();
+        let (cmd_send, cmd_recv) = unbounded::<CheckCommand>();
+        let shared_ = shared.clone();
+        let handle = jod_thread::spawn(move || {
+            let mut check = CheckWatcherState::new(options, workspace_root, shared_);
+            check.run(&task_send, &cmd_recv);
+        });
+        CheckWatcher { task_recv, shared, cmd_send: Some(cmd_send), handle: Some(handle) }
+    }
+
+    /// A watcher which never runs anything.
+    pub fn dummy() -> CheckWatcher {
+        CheckWatcher {
+            task_recv: never(),
+            shared: Arc::new(RwLock::new(CheckWatcherSharedState::default())),
+            cmd_send: None,
+            handle: None,
+        }
+    }
+
+    /// Restarts the check, cancelling the current run if there is one.
+    pub fn update(&self) {
+        if let Some(cmd_send) = &self.cmd_send {
+            cmd_send.send(CheckCommand::Update).unwrap();
+        }
+    }
+}
+
+/// Diagnostics and fixes of the latest check, by file.
+#[derive(Debug, Default)]
+pub struct CheckWatcherSharedState {
+    diagnostic_collection: FxHashMap<Url, Vec<Diagnostic>>,
+    suggested_fix_collection: FxHashMap<Url, Vec<SuggestedFix>>,
+}
+
+impl CheckWatcherSharedState {
+    /// Clears the cached diagnostics, returning the files which had some.
+    fn clear(&mut self) -> Vec<Url> {
+        let cleared = self.diagnostic_collection.drain().map(|(uri, _)| uri).collect();
+        self.suggested_fix_collection.clear();
+        cleared
+    }
+
+    fn add_diagnostic_with_fixes(
+        &mut self,
+        file_uri: Url,
+        diagnostic: Diagnostic,
+        suggested_fixes: Vec<SuggestedFix>,
+    ) {
+        let diagnostics = self.diagnostic_collection.entry(file_uri.clone()).or_default();
+        // `--all-targets` checks each file once per target, so the same
+        // diagnostic is usually reported several times.
+        if diagnostics.contains(&diagnostic) {
+            return;
+        }
+        diagnostics.push(diagnostic);
+
+        let fixes = self.suggested_fix_collection.entry(file_uri).or_default();
+        for fix in suggested_fixes {
+            if !fixes.contains(&fix) {
+                fixes.push(fix);
+            }
+        }
+    }
+
+    pub fn diagnostics_for(&self, uri: &Url) -> Option<&[Diagnostic]> {
+        self.diagnostic_collection.get(uri).map(|d| d.as_slice())
+    }
+
+    pub fn fixes_for(&self, uri: &Url) -> Option<&[SuggestedFix]> {
+        self.suggested_fix_collection.get(uri).map(|d| d.as_slice())
+    }
+}
+
+#[derive(Debug)]
+pub enum CheckTask {
+    /// Diagnostics of these files have changed and should be republished.
+    Update(Vec<Url>),
+}
+
+#[derive(Debug)]
+enum CheckCommand {
+    /// Request re-start of check thread
+    Update,
+}
+
+struct CheckWatcherState {
+    options: CheckOptions,
+    workspace_root: PathBuf,
+    watcher: WatchThread,
+    shared: Arc<RwLock<CheckWatcherSharedState>>,
+}
+
+impl CheckWatcherState {
+    fn new(
+        options: CheckOptions,
+        workspace_root: PathBuf,
+        shared: Arc<RwLock<CheckWatcherSharedState>>,
+    ) -> CheckWatcherState {
+        let watcher = WatchThread::new(&options, &workspace_root);
+        CheckWatcherState { options, workspace_root, watcher, shared }
+    }
+
+    fn run(&mut self, task_send: &Sender<CheckTask>, cmd_recv: &Receiver<CheckCommand>) {
+        loop {
+            let res = select! {
+                recv(cmd_recv) -> cmd => match cmd {
+                    Ok(CheckCommand::Update) => self.restart(task_send),
+                    // The command channel has closed, so shut down
+                    Err(RecvError) => break,
+                },
+                recv(self.watcher.message_recv) -> msg => match msg {
+                    Ok(msg) => self.handle_message(msg, task_send),
+                    Err(RecvError) => {
+                        // The check has finished, wait for the next command.
+                        self.watcher = WatchThread::dummy();
+                        Ok(())
+                    }
+                },
+            };
+            if res.is_err() {
+                // The main loop has gone away.
+                break;
+            }
+        }
+    }
+
+    fn restart(&mut self, task_send: &Sender<CheckTask>) -> Result<(), ()> {
+        // Drop the stale run first, so that its messages don't mix with the
+        // ones of the new run.
+        self.watcher = WatchThread::dummy();
+        let cleared = self.shared.write().clear();
+        task_send.send(CheckTask::Update(cleared)).map_err(drop)?;
+        self.watcher = WatchThread::new(&self.options, &self.workspace_root);
+        Ok(())
+    }
+
+    fn handle_message(&mut self, msg: Message, task_send: &Sender<CheckTask>) -> Result<(), ()> {
+        let msg = match msg {
+            Message::CompilerMessage(msg) => msg,
+            _ => return Ok(()),
+        };
+        let MappedRustDiagnostic { location, diagnostic, suggested_fixes } =
+            match map_rust_diagnostic_to_lsp(&msg.message, &self.workspace_root) {
+                Some(it) => it,
+                None => return Ok(()),
+            };
+
+        self.shared.write().add_diagnostic_with_fixes(
+            location.uri.clone(),
+            diagnostic,
+            suggested_fixes,
+        );
+        task_send.send(CheckTask::Update(vec![location.uri])).map_err(drop)
+    }
+}
+
+/// A single run of the check command. Dropping it kills the process.
+struct WatchThread {
+    // XXX: drop order is significant, see `Drop` below
+    message_recv: Receiver<Message>,
+    child: Option<Child>,
+    _handle: Option<jod_thread::JoinHandle<()>>,
+}
+
+impl WatchThread {
+    fn dummy() -> WatchThread {
+        WatchThread { message_recv: never(), child: None, _handle: None }
+    }
+
+    fn new(options: &CheckOptions, workspace_root: &Path) -> WatchThread {
+        if !options.enable {
+            return WatchThread::dummy();
+        }
+
+        let mut args: Vec<String> = vec![
+            options.command.clone(),
+            "--message-format=json".to_string(),
+            "--manifest-path".to_string(),
+            workspace_root.join("Cargo.toml").display().to_string(),
+        ];
+        if options.all_targets {
+            args.push("--all-targets".to_string());
+        }
+        args.extend(options.args.iter().cloned());
+
+        let mut child = match Command::new("cargo")
+            .args(&args)
+            .current_dir(workspace_root)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .stdin(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(err) => {
+                log::error!("failed to run `cargo {}`: {}", options.command, err);
+                return WatchThread::dummy();
+            }
+        };
+
+        let stdout = child.stdout.take().unwrap();
+        let (message_send, message_recv) = unbounded();
+        let handle = jod_thread::spawn(move || {
+            for message in cargo_metadata::parse_messages(BufReader::new(stdout)) {
+                match message {
+                    Ok(message) => {
+                        if message_send.send(message).is_err() {
+                            // The run has been cancelled
+                            break;
+                        }
+                    }
+                    Err(err) => {
+                        log::error!("invalid json from cargo check, ignoring: {}", err);
+                    }
+                }
+            }
+        });
+        WatchThread { message_recv, child: Some(child), _handle: Some(handle) }
+    }
+}
+
+impl Drop for WatchThread {
+    fn drop(&mut self) {
+        // Killing the process closes its stdout, which lets the reader thread
+        // finish, so that joining it below doesn't block.
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -8,7 +8,7 @@ use std::{
 
 use ra_db::{
     salsa::{Database, Durability},
-    FileId, SourceDatabaseExt,
+    FileId,
 };
 use ra_ide::{Analysis, AnalysisChange, AnalysisHost, FilePosition, LineCol};
 
@@ -23,7 +23,6 @@ pub(crate) fn run(verbose: bool, path: &Path, op: Op) -> Result<()> {
     let start = Instant::now();
     eprint!("loading: ");
     let (mut host, roots) = ra_batch::load_cargo(path)?;
-    let db = host.raw_database();
     eprintln!("{:?}\n", start.elapsed());
 
     let file_id = {
@@ -31,22 +30,7 @@ pub(crate) fn run(verbose: bool, path: &Path, op: Op) -> Result<()> {
             Op::Highlight { path } => path,
             Op::Complete { path, .. } => path,
         };
-        let path = std::env::current_dir()?.join(path).canonicalize()?;
-        roots
-            .iter()
-            .find_map(|(source_root_id, project_root)| {
-                if project_root.is_member() {
-                    for file_id in db.source_root(*source_root_id).walk() {
-                        let rel_path = db.file_relative_path(file_id);
-                        let abs_path = rel_path.to_path(project_root.path());
-                        if abs_path == path {
-                            return Some(file_id);
-                        }
-                    }
-                }
-                None
-            })
-            .ok_or_else(|| format!("Can't find {:?}", path))?
+        ra_batch::find_file_id(&host, &roots, path)?
     };
 
     match op {
@@ -66,7 +50,8 @@ pub(crate) fn run(verbose: bool, path: &Path, op: Op) -> Result<()> {
                 .offset(LineCol { line, col_utf16: column });
             let file_postion = FilePosition { file_id, offset };
 
-            let res = do_work(&mut host, file_id, |analysis| analysis.completions(file_postion));
+            let res =
+                do_work(&mut host, file_id, |analysis| analysis.completions(file_postion, &[]));
             if verbose {
                 println!("\n{:#?}", res);
             }
@@ -11,6 +11,7 @@ FLAGS:
 SUBCOMMANDS:
     analysis-bench
     analysis-stats
+    expand
     highlight
     parse
     symbols";
@@ -48,6 +49,20 @@ OPTIONS:
 ARGS:
     <PATH>";
 
+pub const EXPAND_HELP: &str = "ra-cli-expand
+
+USAGE:
+    ra_cli expand [FLAGS] [OPTIONS] <PATH:LINE:COLUMN>
+
+FLAGS:
+    -h, --help    Prints help information
+
+OPTIONS:
+    --path <PATH>    Project to analyse
+
+ARGS:
+    <PATH:LINE:COLUMN>    Location of the macro call to expand";
+
 pub const HIGHLIGHT_HELP: &str = "ra-cli-highlight
 
 USAGE:
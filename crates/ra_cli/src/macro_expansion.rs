@@ -0,0 +1,43 @@
+//! Prints every step of the recursive expansion of a macro call, along with
+//! the rule each `macro_rules!` macro was expanded with.
+
+use std::path::{Path, PathBuf};
+
+use ra_ide::{ExpansionStep, FilePosition, LineCol};
+
+use crate::Result;
+
+pub(crate) fn run(project: &Path, path: PathBuf, line: u32, column: u32) -> Result<()> {
+    let (host, roots) = ra_batch::load_cargo(project)?;
+    let file_id = ra_batch::find_file_id(&host, &roots, &path)?;
+
+    let analysis = host.analysis();
+    let offset = analysis.file_line_index(file_id)?.offset(LineCol { line, col_utf16: column });
+    let expanded = analysis
+        .expand_macro(FilePosition { file_id, offset })?
+        .ok_or_else(|| format!("No macro call at {}:{}:{}", path.display(), line, column))?;
+
+    print_step(&expanded.steps, 0);
+    println!("\n{}", expanded.expansion);
+    Ok(())
+}
+
+fn print_step(step: &ExpansionStep, depth: usize) {
+    let indent = "    ".repeat(depth);
+    match step.rule {
+        Some(rule) if step.failed => {
+            println!("{}{}! (no rule matched, closest is rule #{})", indent, step.name, rule + 1)
+        }
+        Some(rule) => println!("{}{}! (rule #{})", indent, step.name, rule + 1),
+        None => println!("{}{}!", indent, step.name),
+    }
+    for (name, value) in step.bindings.iter() {
+        println!("{}    ${} = {}", indent, name, value);
+    }
+    for line in step.expansion.lines() {
+        println!("{}  | {}", indent, line);
+    }
+    for nested in step.nested.iter() {
+        print_step(nested, depth + 1);
+    }
+}
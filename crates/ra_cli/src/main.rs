@@ -3,6 +3,7 @@
 mod analysis_stats;
 mod analysis_bench;
 mod help;
+mod macro_expansion;
 mod progress_report;
 
 use std::{error::Error, fmt::Write, io::Read};
@@ -143,6 +144,24 @@ fn main() -> Result<()> {
             matches.finish().or_else(handle_extra_flags)?;
             analysis_bench::run(verbose, path.as_ref(), op)?;
         }
+        "expand" => {
+            if matches.contains(["-h", "--help"]) {
+                eprintln!("{}", help::EXPAND_HELP);
+                return Ok(());
+            }
+            let project: String = matches.opt_value_from_str("--path")?.unwrap_or_default();
+            let path_line_col = {
+                let mut trailing = matches.free()?;
+                if trailing.len() != 1 {
+                    eprintln!("{}", help::EXPAND_HELP);
+                    Err("Invalid flags")?;
+                }
+                trailing.pop().unwrap()
+            };
+            let (path_line, column) = rsplit_at_char(path_line_col.as_str(), ':')?;
+            let (path, line) = rsplit_at_char(path_line, ':')?;
+            macro_expansion::run(project.as_ref(), path.into(), line.parse()?, column.parse()?)?;
+        }
         _ => eprintln!("{}", help::GLOBAL_HELP),
     }
     Ok(())
@@ -1,5 +1,6 @@
 //! FIXME: write short doc here
 
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -58,6 +59,7 @@ fn with_single_file(db: &mut dyn SourceDatabaseExt, text: &str) -> FileId {
         Edition::Edition2018,
         CfgOptions::default(),
         Env::default(),
+        Default::default(),
     );
 
     db.set_file_text(file_id, Arc::new(text.to_string()));
@@ -80,6 +82,7 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
     let mut source_root = SourceRoot::default();
     let mut source_root_id = WORKSPACE;
     let mut source_root_prefix: RelativePathBuf = "/".into();
+    let mut extern_roots = Vec::new();
     let mut file_id = FileId(0);
 
     let mut file_position = None;
@@ -90,6 +93,7 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
                 let source_root = std::mem::replace(&mut source_root, SourceRoot::default());
                 db.set_source_root(source_root_id, Arc::new(source_root));
                 source_root_id.0 += 1;
+                extern_roots.push((PathBuf::from(path.as_str()), source_root_id));
                 source_root_prefix = path;
                 continue;
             }
@@ -98,8 +102,13 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
         assert!(meta.path.starts_with(&source_root_prefix));
 
         if let Some(krate) = meta.krate {
-            let crate_id =
-                crate_graph.add_crate_root(file_id, meta.edition, meta.cfg, Env::default());
+            let crate_id = crate_graph.add_crate_root(
+                file_id,
+                meta.edition,
+                meta.cfg,
+                meta.env,
+                Default::default(),
+            );
             let prev = crates.insert(krate.clone(), crate_id);
             assert!(prev.is_none());
             for dep in meta.deps {
@@ -119,10 +128,16 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
             entry.text.to_string()
         };
 
+        // Like the VFS, explicit roots store paths relative to the root.
+        let path = if source_root_id == WORKSPACE {
+            meta.path
+        } else {
+            RelativePathBuf::from(&meta.path.as_str()[source_root_prefix.as_str().len()..])
+        };
         db.set_file_text(file_id, Arc::new(text));
-        db.set_file_relative_path(file_id, meta.path.clone());
+        db.set_file_relative_path(file_id, path.clone());
         db.set_file_source_root(file_id, source_root_id);
-        source_root.insert_file(meta.path, file_id);
+        source_root.insert_file(path, file_id);
 
         file_id.0 += 1;
     }
@@ -134,6 +149,7 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
             Edition::Edition2018,
             CfgOptions::default(),
             Env::default(),
+            Default::default(),
         );
     } else {
         for (from, to) in crate_deps {
@@ -143,6 +159,14 @@ fn with_files(db: &mut dyn SourceDatabaseExt, fixture: &str) -> Option<FilePosit
         }
     }
 
+    // Every explicit root can be included by absolute path.
+    let crate_ids = crate_graph.iter().collect::<Vec<_>>();
+    for crate_id in crate_ids {
+        for (path, root) in extern_roots.iter() {
+            crate_graph.add_extern_root(crate_id, path.clone(), *root);
+        }
+    }
+
     db.set_source_root(source_root_id, Arc::new(source_root));
     db.set_crate_graph(Arc::new(crate_graph));
 
@@ -160,9 +184,10 @@ struct FileMeta {
     deps: Vec<String>,
     cfg: CfgOptions,
     edition: Edition,
+    env: Env,
 }
 
-//- /lib.rs crate:foo deps:bar,baz
+//- /lib.rs crate:foo deps:bar,baz cfg:foo=a,bar=b env:OUTDIR=path/to,OTHER=foo
 fn parse_meta(meta: &str) -> ParsedMeta {
     let components = meta.split_ascii_whitespace().collect::<Vec<_>>();
 
@@ -179,6 +204,7 @@ fn parse_meta(meta: &str) -> ParsedMeta {
     let mut deps = Vec::new();
     let mut edition = Edition::Edition2018;
     let mut cfg = CfgOptions::default();
+    let mut env = Env::default();
     for component in components[1..].iter() {
         let (key, value) = split1(component, ':').unwrap();
         match key {
@@ -193,11 +219,18 @@ fn parse_meta(meta: &str) -> ParsedMeta {
                     }
                 }
             }
+            "env" => {
+                for key in value.split(',') {
+                    if let Some((k, v)) = split1(key, '=') {
+                        env.set(k, v.into());
+                    }
+                }
+            }
             _ => panic!("bad component: {:?}", component),
         }
     }
 
-    ParsedMeta::File(FileMeta { path, krate, deps, edition, cfg })
+    ParsedMeta::File(FileMeta { path, krate, deps, edition, cfg, env })
 }
 
 fn split1(haystack: &str, delim: char) -> Option<(&str, &str)> {
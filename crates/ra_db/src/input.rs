@@ -6,10 +6,16 @@
 //! actual IO. See `vfs` and `project_model` in the `ra_lsp_server` crate for how
 //! actual IO is done and lowered to input.
 
-use std::{fmt, str::FromStr};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
 use ra_cfg::CfgOptions;
 use ra_syntax::SmolStr;
+use ra_tt::TokenExpander;
 use rustc_hash::FxHashMap;
 use rustc_hash::FxHashSet;
 
@@ -83,6 +89,39 @@ pub struct CrateGraph {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct CrateId(pub u32);
 
+/// Index of a procedural macro in the list of macros exported by its crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProcMacroId(pub u32);
+
+/// How a procedural macro is invoked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProcMacroKind {
+    /// `#[proc_macro_derive]`, used as `#[derive(Name)]`.
+    CustomDerive,
+    /// `#[proc_macro]`, used as `name!(..)`.
+    FuncLike,
+    /// `#[proc_macro_attribute]`, used as `#[name]`.
+    Attr,
+}
+
+/// A procedural macro exported by a `proc-macro` crate, together with the
+/// expander which runs it.
+#[derive(Debug, Clone)]
+pub struct ProcMacro {
+    pub name: SmolStr,
+    pub kind: ProcMacroKind,
+    pub expander: Arc<dyn TokenExpander>,
+}
+
+impl Eq for ProcMacro {}
+impl PartialEq for ProcMacro {
+    fn eq(&self, other: &ProcMacro) -> bool {
+        self.name == other.name
+            && self.kind == other.kind
+            && Arc::ptr_eq(&self.expander, &other.expander)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct CrateData {
     file_id: FileId,
@@ -90,6 +129,10 @@ struct CrateData {
     cfg_options: CfgOptions,
     env: Env,
     dependencies: Vec<Dependency>,
+    proc_macro: Vec<ProcMacro>,
+    /// Source roots outside of the crate which `include!`-like macros may
+    /// refer to by absolute path, keyed by the absolute path of the root.
+    extern_roots: Vec<(PathBuf, SourceRootId)>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -116,8 +159,9 @@ impl CrateGraph {
         edition: Edition,
         cfg_options: CfgOptions,
         env: Env,
+        proc_macro: Vec<ProcMacro>,
     ) -> CrateId {
-        let data = CrateData::new(file_id, edition, cfg_options, env);
+        let data = CrateData::new(file_id, edition, cfg_options, env, proc_macro);
         let crate_id = CrateId(self.arena.len() as u32);
         let prev = self.arena.insert(crate_id, data);
         assert!(prev.is_none());
@@ -157,6 +201,42 @@ impl CrateGraph {
         self.arena[&crate_id].edition
     }
 
+    pub fn env(&self, crate_id: CrateId) -> &Env {
+        &self.arena[&crate_id].env
+    }
+
+    /// Lets `include!`-like macros of the crate refer to the files of
+    /// `source_root`, which lives at the absolute `path` (for example, the
+    /// `OUT_DIR` of a build script).
+    pub fn add_extern_root(&mut self, crate_id: CrateId, path: PathBuf, source_root: SourceRootId) {
+        self.arena.get_mut(&crate_id).unwrap().extern_roots.push((path, source_root));
+    }
+
+    /// Finds the extern root of the crate containing the absolute `path`, and
+    /// the path relative to that root. Nested roots take precedence.
+    pub fn extern_path(
+        &self,
+        crate_id: CrateId,
+        path: &Path,
+    ) -> Option<(SourceRootId, RelativePathBuf)> {
+        self.arena[&crate_id]
+            .extern_roots
+            .iter()
+            .filter_map(|(root, source_root)| {
+                let relative = path.strip_prefix(root).ok()?;
+                Some((root.components().count(), *source_root, relative))
+            })
+            .max_by_key(|(depth, _, _)| *depth)
+            .and_then(|(_, source_root, relative)| {
+                Some((source_root, RelativePathBuf::from_path(relative).ok()?))
+            })
+    }
+
+    /// Procedural macros exported by the crate, indexed by `ProcMacroId`.
+    pub fn proc_macro(&self, crate_id: CrateId) -> &[ProcMacro] {
+        &self.arena[&crate_id].proc_macro
+    }
+
     // FIXME: this only finds one crate with the given root; we could have multiple
     pub fn crate_id_for_crate_root(&self, file_id: FileId) -> Option<CrateId> {
         let (&crate_id, _) = self.arena.iter().find(|(_crate_id, data)| data.file_id == file_id)?;
@@ -213,8 +293,22 @@ impl CrateId {
 }
 
 impl CrateData {
-    fn new(file_id: FileId, edition: Edition, cfg_options: CfgOptions, env: Env) -> CrateData {
-        CrateData { file_id, edition, dependencies: Vec::new(), cfg_options, env }
+    fn new(
+        file_id: FileId,
+        edition: Edition,
+        cfg_options: CfgOptions,
+        env: Env,
+        proc_macro: Vec<ProcMacro>,
+    ) -> CrateData {
+        CrateData {
+            file_id,
+            edition,
+            dependencies: Vec::new(),
+            cfg_options,
+            env,
+            proc_macro,
+            extern_roots: Vec::new(),
+        }
     }
 
     fn add_dep(&mut self, name: SmolStr, crate_id: CrateId) {
@@ -244,6 +338,16 @@ impl fmt::Display for Edition {
     }
 }
 
+impl Env {
+    pub fn set(&mut self, env: &str, value: String) {
+        self.entries.insert(env.to_owned(), value);
+    }
+
+    pub fn get(&self, env: &str) -> Option<String> {
+        self.entries.get(env).cloned()
+    }
+}
+
 impl Dependency {
     pub fn crate_id(&self) -> CrateId {
         self.crate_id
@@ -268,17 +372,37 @@ pub struct CyclicDependenciesError;
 
 #[cfg(test)]
 mod tests {
-    use super::{CfgOptions, CrateGraph, Edition::Edition2018, Env, FileId, SmolStr};
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        CfgOptions, CrateGraph, Edition::Edition2018, Env, FileId, RelativePathBuf, SmolStr,
+        SourceRootId,
+    };
 
     #[test]
     fn it_should_panic_because_of_cycle_dependencies() {
         let mut graph = CrateGraph::default();
-        let crate1 =
-            graph.add_crate_root(FileId(1u32), Edition2018, CfgOptions::default(), Env::default());
-        let crate2 =
-            graph.add_crate_root(FileId(2u32), Edition2018, CfgOptions::default(), Env::default());
-        let crate3 =
-            graph.add_crate_root(FileId(3u32), Edition2018, CfgOptions::default(), Env::default());
+        let crate1 = graph.add_crate_root(
+            FileId(1u32),
+            Edition2018,
+            CfgOptions::default(),
+            Env::default(),
+            Default::default(),
+        );
+        let crate2 = graph.add_crate_root(
+            FileId(2u32),
+            Edition2018,
+            CfgOptions::default(),
+            Env::default(),
+            Default::default(),
+        );
+        let crate3 = graph.add_crate_root(
+            FileId(3u32),
+            Edition2018,
+            CfgOptions::default(),
+            Env::default(),
+            Default::default(),
+        );
         assert!(graph.add_dep(crate1, SmolStr::new("crate2"), crate2).is_ok());
         assert!(graph.add_dep(crate2, SmolStr::new("crate3"), crate3).is_ok());
         assert!(graph.add_dep(crate3, SmolStr::new("crate1"), crate1).is_err());
@@ -287,13 +411,53 @@ mod tests {
     #[test]
     fn it_works() {
         let mut graph = CrateGraph::default();
-        let crate1 =
-            graph.add_crate_root(FileId(1u32), Edition2018, CfgOptions::default(), Env::default());
-        let crate2 =
-            graph.add_crate_root(FileId(2u32), Edition2018, CfgOptions::default(), Env::default());
-        let crate3 =
-            graph.add_crate_root(FileId(3u32), Edition2018, CfgOptions::default(), Env::default());
+        let crate1 = graph.add_crate_root(
+            FileId(1u32),
+            Edition2018,
+            CfgOptions::default(),
+            Env::default(),
+            Default::default(),
+        );
+        let crate2 = graph.add_crate_root(
+            FileId(2u32),
+            Edition2018,
+            CfgOptions::default(),
+            Env::default(),
+            Default::default(),
+        );
+        let crate3 = graph.add_crate_root(
+            FileId(3u32),
+            Edition2018,
+            CfgOptions::default(),
+            Env::default(),
+            Default::default(),
+        );
         assert!(graph.add_dep(crate1, SmolStr::new("crate2"), crate2).is_ok());
         assert!(graph.add_dep(crate2, SmolStr::new("crate3"), crate3).is_ok());
     }
+
+    #[test]
+    fn extern_path_prefers_nested_roots() {
+        let mut graph = CrateGraph::default();
+        let krate = graph.add_crate_root(
+            FileId(1u32),
+            Edition2018,
+            CfgOptions::default(),
+            Env::default(),
+            Default::default(),
+        );
+        graph.add_extern_root(krate, PathBuf::from("/target"), SourceRootId(1));
+        graph.add_extern_root(krate, PathBuf::from("/target/out"), SourceRootId(2));
+
+        assert_eq!(
+            graph.extern_path(krate, Path::new("/target/out/gen/x.rs")),
+            Some((SourceRootId(2), RelativePathBuf::from("gen/x.rs")))
+        );
+        assert_eq!(
+            graph.extern_path(krate, Path::new("/target/x.rs")),
+            Some((SourceRootId(1), RelativePathBuf::from("x.rs")))
+        );
+        assert_eq!(graph.extern_path(krate, Path::new("/src/x.rs")), None);
+        assert_eq!(graph.extern_path(krate, Path::new("x.rs")), None);
+    }
 }
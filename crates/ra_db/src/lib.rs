@@ -10,7 +10,10 @@ use ra_syntax::{ast, Parse, SourceFile, TextRange, TextUnit};
 
 pub use crate::{
     cancellation::Canceled,
-    input::{CrateGraph, CrateId, Dependency, Edition, Env, FileId, SourceRoot, SourceRootId},
+    input::{
+        CrateGraph, CrateId, Dependency, Edition, Env, FileId, ProcMacro, ProcMacroId,
+        ProcMacroKind, SourceRoot, SourceRootId,
+    },
 };
 pub use relative_path::{RelativePath, RelativePathBuf};
 pub use salsa;
@@ -85,6 +88,18 @@ pub trait FileLoader {
     fn resolve_relative_path(&self, anchor: FileId, relative_path: &RelativePath)
         -> Option<FileId>;
     fn relevant_crates(&self, file_id: FileId) -> Arc<Vec<CrateId>>;
+    /// Finds a file in a source root outside of the current crate, see
+    /// `CrateGraph::extern_path`.
+    fn resolve_extern_path(
+        &self,
+        source_root: SourceRootId,
+        relative_path: &RelativePath,
+    ) -> Option<FileId>;
+    /// Syntax tree of the current text of the file, if it was already
+    /// computed by incrementally reparsing the previous tree.
+    fn reparsed_file(&self, _file_id: FileId) -> Option<Parse<ast::SourceFile>> {
+        None
+    }
 }
 
 /// Database which stores all significant input facts: source code and project
@@ -102,6 +117,9 @@ pub trait SourceDatabase: CheckCanceled + FileLoader + std::fmt::Debug {
 
 fn parse_query(db: &impl SourceDatabase, file_id: FileId) -> Parse<ast::SourceFile> {
     let _p = profile("parse_query");
+    if let Some(parse) = db.reparsed_file(file_id) {
+        return parse;
+    }
     let text = db.file_text(file_id);
     SourceFile::parse(&*text)
 }
@@ -162,4 +180,12 @@ impl<T: SourceDatabaseExt> FileLoader for FileLoaderDelegate<&'_ T> {
         let source_root = self.0.file_source_root(file_id);
         self.0.source_root_crates(source_root)
     }
+
+    fn resolve_extern_path(
+        &self,
+        source_root: SourceRootId,
+        relative_path: &RelativePath,
+    ) -> Option<FileId> {
+        self.0.source_root(source_root).file_by_relative_path(relative_path)
+    }
 }
@@ -7,25 +7,29 @@ use hir_def::{
     builtin_type::BuiltinType,
     docs::Documentation,
     expr::{BindingAnnotation, Pat, PatId},
+    find_path::{find_path, find_paths},
     nameres::ModuleSource,
+    path::ModPath,
     per_ns::PerNs,
     resolver::HasResolver,
     type_ref::{Mutability, TypeRef},
+    visibility::Visibility,
     AdtId, ConstId, DefWithBodyId, EnumId, FunctionId, HasModule, ImplId, LocalEnumVariantId,
-    LocalModuleId, LocalStructFieldId, Lookup, ModuleId, StaticId, StructId, TraitId, TypeAliasId,
-    TypeParamId, UnionId,
+    LocalModuleId, LocalStructFieldId, Lookup, ModuleDefId, ModuleId, StaticId, StructId, TraitId,
+    TypeAliasId, TypeParamId, UnionId, VariantId,
 };
 use hir_expand::{
     diagnostics::DiagnosticSink,
     name::{name, AsName},
-    MacroDefId,
+    HirFileId, MacroDefId, MacroDefKind,
 };
 use hir_ty::{
-    autoderef, display::HirFormatter, expr::ExprValidator, ApplicationTy, Canonical, InEnvironment,
-    TraitEnvironment, Ty, TyDefId, TypeCtor, TypeWalk,
+    autoderef, display::HirFormatter, expr::ExprValidator, ApplicationTy, Canonical, FnTrait,
+    InEnvironment, TraitEnvironment, Ty, TyDefId, TypeCtor, TypeWalk,
 };
 use ra_db::{CrateId, Edition, FileId};
 use ra_syntax::ast;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     db::{DefDatabase, HirDatabase},
@@ -118,6 +122,46 @@ impl_froms!(
     BuiltinType
 );
 
+impl ModuleDef {
+    /// The module the item is declared in, or the parent module for modules.
+    pub fn module(self, db: &impl HirDatabase) -> Option<Module> {
+        match self {
+            ModuleDef::Module(it) => it.parent(db),
+            ModuleDef::Function(it) => Some(it.module(db)),
+            ModuleDef::Adt(it) => Some(it.module(db)),
+            ModuleDef::EnumVariant(it) => Some(it.module(db)),
+            ModuleDef::Const(it) => Some(it.module(db)),
+            ModuleDef::Static(it) => Some(it.module(db)),
+            ModuleDef::Trait(it) => Some(it.module(db)),
+            ModuleDef::TypeAlias(it) => Some(it.module(db)),
+            ModuleDef::BuiltinType(_) => None,
+        }
+    }
+
+    /// The file the item is declared in, which is a macro file for items
+    /// produced by macro calls. Unlike the item's source, this doesn't need
+    /// the file to be parsed.
+    pub fn file_id(self, db: &impl DefDatabase) -> Option<HirFileId> {
+        let file_id = match self {
+            ModuleDef::Module(it) => {
+                let def_map = db.crate_def_map(it.id.krate);
+                def_map[it.id.local_id].origin.declaration()?.file_id
+            }
+            ModuleDef::Function(it) => it.id.lookup(db).ast_id.file_id,
+            ModuleDef::Adt(Adt::Struct(it)) => it.id.lookup(db).ast_id.file_id,
+            ModuleDef::Adt(Adt::Union(it)) => it.id.lookup(db).ast_id.file_id,
+            ModuleDef::Adt(Adt::Enum(it)) => it.id.lookup(db).ast_id.file_id,
+            ModuleDef::EnumVariant(it) => it.parent.id.lookup(db).ast_id.file_id,
+            ModuleDef::Const(it) => it.id.lookup(db).ast_id.file_id,
+            ModuleDef::Static(it) => it.id.lookup(db).ast_id.file_id,
+            ModuleDef::Trait(it) => it.id.lookup(db).ast_id.file_id,
+            ModuleDef::TypeAlias(it) => it.id.lookup(db).ast_id.file_id,
+            ModuleDef::BuiltinType(_) => return None,
+        };
+        Some(file_id)
+    }
+}
+
 pub use hir_def::attr::Attrs;
 
 impl Module {
@@ -180,10 +224,29 @@ impl Module {
     }
 
     /// Returns a `ModuleScope`: a set of items, visible in this module.
-    pub fn scope(self, db: &impl HirDatabase) -> Vec<(Name, ScopeDef)> {
+    ///
+    /// If `visible_from` is given, only the items which are visible from that
+    /// module are returned.
+    pub fn scope(
+        self,
+        db: &impl HirDatabase,
+        visible_from: Option<Module>,
+    ) -> Vec<(Name, ScopeDef)> {
         db.crate_def_map(self.id.krate)[self.id.local_id]
             .scope
             .entries()
+            .filter_map(|(name, def)| {
+                if let Some(m) = visible_from {
+                    let filtered = def.filter_visibility(|vis| vis.is_visible_from(db, m.id));
+                    if filtered.is_none() {
+                        None
+                    } else {
+                        Some((name, filtered))
+                    }
+                } else {
+                    Some((name, def))
+                }
+            })
             .map(|(name, def)| (name.clone(), def.into()))
             .collect()
     }
@@ -222,6 +285,26 @@ impl Module {
         def_map[self.id.local_id].scope.impls().map(ImplBlock::from).collect()
     }
 
+    /// Finds a path that can be used to refer to the given item from within
+    /// this module, if there is one.
+    pub fn find_use_path(self, db: &impl DefDatabase, item: ModuleDef) -> Option<ModPath> {
+        find_path(db, item.into(), self.into())
+    }
+
+    /// Like `find_use_path`, but for several items at once. Items without a
+    /// path are missing from the result.
+    pub fn find_use_paths(
+        self,
+        db: &impl DefDatabase,
+        items: &FxHashSet<ModuleDef>,
+    ) -> FxHashMap<ModuleDef, ModPath> {
+        let items: FxHashSet<ModuleDefId> = items.iter().map(|&it| it.into()).collect();
+        find_paths(db, &items, self.into())
+            .into_iter()
+            .map(|(it, path)| (it.into(), path))
+            .collect()
+    }
+
     pub(crate) fn with_module_id(self, module_id: LocalModuleId) -> Module {
         Module::new(self.krate(), module_id)
     }
@@ -244,6 +327,11 @@ impl StructField {
         self.parent.variant_data(db).fields()[self.id].name.clone()
     }
 
+    /// The type of the field as written in the source, including lifetimes.
+    pub fn type_ref(&self, db: &impl HirDatabase) -> TypeRef {
+        self.parent.variant_data(db).fields()[self.id].type_ref.clone()
+    }
+
     pub fn ty(&self, db: &impl HirDatabase) -> Type {
         let var_id = self.parent.into();
         let ty = db.field_types(var_id)[self.id].clone();
@@ -255,6 +343,15 @@ impl StructField {
     }
 }
 
+impl HasVisibility for StructField {
+    fn visibility(&self, db: &impl HirDatabase) -> Visibility {
+        let variant_data = self.parent.variant_data(db);
+        let visibility = &variant_data.fields()[self.id].visibility;
+        let parent_id: VariantId = self.parent.into();
+        visibility.resolve(db, &parent_id.resolver(db))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Struct {
     pub(crate) id: StructId,
@@ -497,7 +594,13 @@ impl Function {
         db.function_data(self.id).params.clone()
     }
 
+    pub fn is_unsafe(self, db: &impl HirDatabase) -> bool {
+        db.function_data(self.id).is_unsafe
+    }
+
     pub fn diagnostics(self, db: &impl HirDatabase, sink: &mut DiagnosticSink) {
+        let (_, source_map) = db.body_with_source_map(self.id.into());
+        source_map.add_diagnostics(db, sink);
         let infer = db.infer(self.id.into());
         infer.add_diagnostics(db, self.id, sink);
         let mut validator = ExprValidator::new(self.id, infer, sink);
@@ -505,6 +608,14 @@ impl Function {
     }
 }
 
+impl HasVisibility for Function {
+    fn visibility(&self, db: &impl HirDatabase) -> Visibility {
+        let function_data = db.function_data(self.id);
+        let visibility = &function_data.visibility;
+        visibility.resolve(db, &self.id.resolver(db))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Const {
     pub(crate) id: ConstId,
@@ -599,6 +710,22 @@ pub struct MacroDef {
     pub(crate) id: MacroDefId,
 }
 
+impl MacroDef {
+    /// The crate the macro is defined in. Built-in macros have no crate.
+    pub fn krate(&self) -> Option<Crate> {
+        self.id.krate.map(|id| Crate { id })
+    }
+
+    /// Procedural macros are defined by a compiled dylib rather than by
+    /// source code, so they don't have an `ast::MacroCall` to point to.
+    pub fn is_proc_macro(&self) -> bool {
+        match self.id.kind {
+            MacroDefKind::ProcMacro(_) => true,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum AssocItem {
     Function(Function),
@@ -811,6 +938,11 @@ impl Type {
         }
     }
 
+    /// The most general `Fn` trait a closure type implements.
+    pub fn closure_kind(&self) -> Option<FnTrait> {
+        self.ty.value.closure_kind()
+    }
+
     pub fn is_unknown(&self) -> bool {
         match &self.ty.value {
             Ty::Unknown => true,
@@ -1030,3 +1162,11 @@ impl<T: Into<AttrDef> + Copy> Docs for T {
         db.documentation(def.into())
     }
 }
+
+pub trait HasVisibility {
+    fn visibility(&self, db: &impl HirDatabase) -> Visibility;
+    fn is_visible_from(&self, db: &impl HirDatabase, module: Module) -> bool {
+        let vis = self.visibility(db);
+        vis.is_visible_from(db, module.id)
+    }
+}
@@ -15,7 +15,7 @@ pub use hir_ty::db::{
     AssociatedTyDataQuery, CallableItemSignatureQuery, FieldTypesQuery, GenericDefaultsQuery,
     GenericPredicatesQuery, HirDatabase, HirDatabaseStorage, ImplDatumQuery, ImplsForTraitQuery,
     ImplsInCrateQuery, InferQuery, StructDatumQuery, TraitDatumQuery, TraitSolveQuery, TyQuery,
-    ValueTyQuery,
+    UnsafeExpressionsQuery, ValueTyQuery,
 };
 
 #[test]
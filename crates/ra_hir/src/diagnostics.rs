@@ -1,4 +1,6 @@
 //! FIXME: write short doc here
-pub use hir_def::diagnostics::UnresolvedModule;
+pub use hir_def::diagnostics::{MacroError, UnresolvedMacroCall, UnresolvedModule};
 pub use hir_expand::diagnostics::{AstDiagnostic, Diagnostic, DiagnosticSink};
-pub use hir_ty::diagnostics::{MissingFields, MissingOkInTailExpr, NoSuchField};
+pub use hir_ty::diagnostics::{
+    MissingFields, MissingOkInTailExpr, MissingUnsafe, NoSuchField, TypeMismatch,
+};
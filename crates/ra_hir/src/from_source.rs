@@ -67,7 +67,7 @@ from_source_by_container_impls![
 ];
 
 impl FromSource for MacroDef {
-    type Ast = ast::MacroCall;
+    type Ast = ast::Macro;
     fn from_source(db: &impl DefDatabase, src: InFile<Self::Ast>) -> Option<Self> {
         let kind = MacroDefKind::Declarative;
 
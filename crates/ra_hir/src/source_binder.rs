@@ -20,7 +20,7 @@ use hir_def::{
     AssocItemId, DefWithBodyId,
 };
 use hir_expand::{
-    hygiene::Hygiene, name::AsName, AstId, HirFileId, InFile, MacroCallId, MacroCallKind,
+    hygiene::Hygiene, name::AsName, AstId, HirFileId, InFile, MacroCallId, MacroCallKind, MatchInfo,
 };
 use hir_ty::{
     method_resolution::{self, implements_trait},
@@ -160,6 +160,23 @@ impl Expansion {
         exp_info.map_token_down(token)
     }
 
+    /// Expands the macro call as if its arguments were `hypothetical_args`,
+    /// returning the expansion and `token_to_map` mapped into it.
+    pub fn expand_hypothetical(
+        &self,
+        db: &impl HirDatabase,
+        hypothetical_args: &ast::TokenTree,
+        token_to_map: SyntaxToken,
+    ) -> Option<(SyntaxNode, SyntaxToken)> {
+        hir_expand::db::expand_hypothetical(db, self.macro_call_id, hypothetical_args, token_to_map)
+    }
+
+    /// Returns the rule of the macro that was used for this expansion, along
+    /// with its metavariable bindings.
+    pub fn match_info(&self, db: &impl HirDatabase) -> Option<MatchInfo> {
+        hir_expand::db::macro_match_info(db, self.macro_call_id)
+    }
+
     pub fn file_id(&self) -> HirFileId {
         self.macro_call_id.as_file()
     }
@@ -436,13 +453,18 @@ impl SourceAnalyzer {
             _ => return false,
         };
 
+        self.impls_trait(db, ty, std_future_trait)
+    }
+
+    /// Checks that particular type `ty` implements the trait `trait_`.
+    pub fn impls_trait(&self, db: &impl HirDatabase, ty: Type, trait_: Trait) -> bool {
         let krate = match self.resolver.krate() {
             Some(krate) => krate,
             _ => return false,
         };
 
         let canonical_ty = Canonical { value: ty.ty.value, num_vars: 0 };
-        implements_trait(&canonical_ty, db, &self.resolver, krate.into(), std_future_trait)
+        implements_trait(&canonical_ty, db, &self.resolver, krate.into(), trait_.id)
     }
 
     pub fn expand(
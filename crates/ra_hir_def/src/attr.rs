@@ -87,6 +87,18 @@ impl Attrs {
         Attrs { entries }
     }
 
+    /// Appends the attributes of `other`, like the ones of an item to those of
+    /// the `extern` block it is declared in.
+    pub(crate) fn merge(&self, other: Attrs) -> Attrs {
+        match (&self.entries, &other.entries) {
+            (None, _) => other,
+            (_, None) => self.clone(),
+            (Some(a), Some(b)) => {
+                Attrs { entries: Some(a.iter().chain(b.iter()).cloned().collect()) }
+            }
+        }
+    }
+
     pub fn by_key(&self, key: &'static str) -> AttrQuery<'_> {
         AttrQuery { attrs: self, key }
     }
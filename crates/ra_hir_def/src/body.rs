@@ -1,6 +1,7 @@
 //! Defines `Body`: a lowered representation of bodies of functions, statics and
 //! consts.
 mod lower;
+mod diagnostics;
 pub mod scope;
 
 use std::{mem, ops::Index, sync::Arc};
@@ -8,7 +9,8 @@ use std::{mem, ops::Index, sync::Arc};
 use drop_bomb::DropBomb;
 use either::Either;
 use hir_expand::{
-    ast_id_map::AstIdMap, hygiene::Hygiene, AstId, HirFileId, InFile, MacroCallKind, MacroDefId,
+    ast_id_map::AstIdMap, diagnostics::DiagnosticSink, hygiene::Hygiene, AstId, HirFileId, InFile,
+    MacroCallId, MacroCallKind, MacroDefId,
 };
 use ra_arena::{map::ArenaMap, Arena};
 use ra_prof::profile;
@@ -16,6 +18,7 @@ use ra_syntax::{ast, AstNode, AstPtr};
 use rustc_hash::FxHashMap;
 
 use crate::{
+    body::diagnostics::BodyDiagnostic,
     db::DefDatabase,
     expr::{Expr, ExprId, Pat, PatId},
     item_scope::BuiltinShadowMode,
@@ -32,6 +35,10 @@ pub(crate) struct Expander {
     hygiene: Hygiene,
     ast_id_map: Arc<AstIdMap>,
     module: ModuleId,
+    /// Macro calls whose path doesn't resolve to a macro.
+    unresolved_macro_calls: Vec<AstId<ast::MacroCall>>,
+    /// Macro calls which failed to expand.
+    macro_errors: Vec<MacroCallId>,
 }
 
 impl Expander {
@@ -43,7 +50,15 @@ impl Expander {
         let crate_def_map = db.crate_def_map(module.krate);
         let hygiene = Hygiene::new(db, current_file_id);
         let ast_id_map = db.ast_id_map(current_file_id);
-        Expander { crate_def_map, current_file_id, hygiene, ast_id_map, module }
+        Expander {
+            crate_def_map,
+            current_file_id,
+            hygiene,
+            ast_id_map,
+            module,
+            unresolved_macro_calls: Vec::new(),
+            macro_errors: Vec::new(),
+        }
     }
 
     pub(crate) fn enter_expand<T: ast::AstNode, DB: DefDatabase>(
@@ -59,6 +74,9 @@ impl Expander {
         if let Some(path) = macro_call.path().and_then(|path| self.parse_mod_path(path)) {
             if let Some(def) = self.resolve_path_as_macro(db, &path) {
                 let call_id = def.as_call_id(db, MacroCallKind::FnLike(ast_id));
+                if db.macro_expand(call_id).err.is_some() {
+                    self.macro_errors.push(call_id);
+                }
                 let file_id = call_id.as_file();
                 if let Some(node) = db.parse_or_expand(file_id) {
                     if let Some(expr) = T::cast(node) {
@@ -76,11 +94,15 @@ impl Expander {
                         return Some((mark, expr));
                     }
                 }
+            } else if self.crate_def_map.should_report_unresolved_macro_call(
+                db,
+                self.module.local_id,
+                &path,
+            ) {
+                self.unresolved_macro_calls.push(ast_id);
             }
         }
 
-        // FIXME: Instead of just dropping the error from expansion
-        // report it
         None
     }
 
@@ -163,6 +185,7 @@ pub struct BodySourceMap {
     pat_map: FxHashMap<PatSource, PatId>,
     pat_map_back: ArenaMap<PatId, PatSource>,
     field_map: FxHashMap<(ExprId, usize), AstPtr<ast::RecordField>>,
+    diagnostics: Vec<BodyDiagnostic>,
 }
 
 impl Body {
@@ -254,4 +277,8 @@ impl BodySourceMap {
     pub fn field_syntax(&self, expr: ExprId, field: usize) -> AstPtr<ast::RecordField> {
         self.field_map[&(expr, field)]
     }
+
+    pub fn add_diagnostics(&self, db: &impl DefDatabase, sink: &mut DiagnosticSink) {
+        self.diagnostics.iter().for_each(|it| it.add_to(db, sink));
+    }
 }
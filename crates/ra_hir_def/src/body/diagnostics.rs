@@ -0,0 +1,32 @@
+//! Diagnostics emitted during body lowering.
+
+use hir_expand::{diagnostics::DiagnosticSink, MacroCallId};
+use ra_syntax::{ast, AstPtr};
+
+use crate::{
+    db::DefDatabase,
+    diagnostics::{MacroError, UnresolvedMacroCall},
+    AstId,
+};
+
+#[derive(Debug, Eq, PartialEq)]
+pub(super) enum BodyDiagnostic {
+    UnresolvedMacroCall { ast_id: AstId<ast::MacroCall> },
+    MacroError { call: MacroCallId },
+}
+
+impl BodyDiagnostic {
+    pub(super) fn add_to(&self, db: &impl DefDatabase, sink: &mut DiagnosticSink) {
+        match self {
+            BodyDiagnostic::UnresolvedMacroCall { ast_id } => {
+                let node = ast_id.to_node(db);
+                sink.push(UnresolvedMacroCall { file: ast_id.file_id, node: AstPtr::new(&node) })
+            }
+            BodyDiagnostic::MacroError { call } => {
+                if let Some(err) = MacroError::from_call(db, *call) {
+                    sink.push(err);
+                }
+            }
+        }
+    }
+}
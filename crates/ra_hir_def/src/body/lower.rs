@@ -15,7 +15,7 @@ use ra_syntax::{
 use test_utils::tested_by;
 
 use crate::{
-    body::{Body, BodySourceMap, Expander, PatPtr},
+    body::{diagnostics::BodyDiagnostic, Body, BodySourceMap, Expander, PatPtr},
     builtin_type::{BuiltinFloat, BuiltinInt},
     db::DefDatabase,
     expr::{
@@ -24,7 +24,9 @@ use crate::{
     },
     path::GenericArgs,
     path::Path,
+    per_ns::PerNs,
     type_ref::{Mutability, TypeRef},
+    visibility::Visibility,
     ConstLoc, ContainerId, DefWithBodyId, EnumLoc, FunctionLoc, Intern, ModuleDefId, StaticLoc,
     StructLoc, TraitLoc, TypeAliasLoc, UnionLoc,
 };
@@ -95,6 +97,15 @@ where
         };
 
         self.body.body_expr = self.collect_expr_opt(body);
+        self.source_map.diagnostics.extend(
+            self.expander
+                .unresolved_macro_calls
+                .drain(..)
+                .map(|ast_id| BodyDiagnostic::UnresolvedMacroCall { ast_id }),
+        );
+        self.source_map.diagnostics.extend(
+            self.expander.macro_errors.drain(..).map(|call| BodyDiagnostic::MacroError { call }),
+        );
         (self.body, self.source_map)
     }
 
@@ -183,12 +194,24 @@ where
                 let body = self.collect_block_opt(e.body());
                 self.alloc_expr(Expr::TryBlock { body }, syntax_ptr)
             }
-            ast::Expr::BlockExpr(e) => self.collect_block(e),
+            ast::Expr::BlockExpr(e) => {
+                if e.is_async() {
+                    let body = self.collect_block(e);
+                    self.alloc_expr(Expr::Async { body }, syntax_ptr)
+                } else if e.is_unsafe() {
+                    let body = self.collect_block(e);
+                    self.alloc_expr(Expr::Unsafe { body }, syntax_ptr)
+                } else {
+                    self.collect_block(e)
+                }
+            }
             ast::Expr::LoopExpr(e) => {
+                let label = self.collect_label(e.label());
                 let body = self.collect_block_opt(e.loop_body());
-                self.alloc_expr(Expr::Loop { body }, syntax_ptr)
+                self.alloc_expr(Expr::Loop { body, label }, syntax_ptr)
             }
             ast::Expr::WhileExpr(e) => {
+                let label = self.collect_label(e.label());
                 let body = self.collect_block_opt(e.loop_body());
 
                 let condition = match e.condition() {
@@ -201,25 +224,28 @@ where
                             let pat = self.collect_pat(pat);
                             let match_expr = self.collect_expr_opt(condition.expr());
                             let placeholder_pat = self.missing_pat();
-                            let break_ = self.alloc_expr_desugared(Expr::Break { expr: None });
+                            let break_ =
+                                self.alloc_expr_desugared(Expr::Break { expr: None, label: None });
                             let arms = vec![
                                 MatchArm { pats: vec![pat], expr: body, guard: None },
                                 MatchArm { pats: vec![placeholder_pat], expr: break_, guard: None },
                             ];
                             let match_expr =
                                 self.alloc_expr_desugared(Expr::Match { expr: match_expr, arms });
-                            return self.alloc_expr(Expr::Loop { body: match_expr }, syntax_ptr);
+                            return self
+                                .alloc_expr(Expr::Loop { body: match_expr, label }, syntax_ptr);
                         }
                     },
                 };
 
-                self.alloc_expr(Expr::While { condition, body }, syntax_ptr)
+                self.alloc_expr(Expr::While { condition, body, label }, syntax_ptr)
             }
             ast::Expr::ForExpr(e) => {
+                let label = self.collect_label(e.label());
                 let iterable = self.collect_expr_opt(e.iterable());
                 let pat = self.collect_pat_opt(e.pat());
                 let body = self.collect_block_opt(e.loop_body());
-                self.alloc_expr(Expr::For { iterable, pat, body }, syntax_ptr)
+                self.alloc_expr(Expr::For { iterable, pat, body, label }, syntax_ptr)
             }
             ast::Expr::CallExpr(e) => {
                 let callee = self.collect_expr_opt(e.expr());
@@ -271,13 +297,14 @@ where
                     .unwrap_or(Expr::Missing);
                 self.alloc_expr(path, syntax_ptr)
             }
-            ast::Expr::ContinueExpr(_e) => {
-                // FIXME: labels
-                self.alloc_expr(Expr::Continue, syntax_ptr)
+            ast::Expr::ContinueExpr(e) => {
+                let label = e.lifetime_token().map(|t| Name::new_lifetime(&t));
+                self.alloc_expr(Expr::Continue { label }, syntax_ptr)
             }
             ast::Expr::BreakExpr(e) => {
+                let label = e.lifetime_token().map(|t| Name::new_lifetime(&t));
                 let expr = e.expr().map(|e| self.collect_expr(e));
-                self.alloc_expr(Expr::Break { expr }, syntax_ptr)
+                self.alloc_expr(Expr::Break { expr, label }, syntax_ptr)
             }
             ast::Expr::ParenExpr(e) => {
                 let inner = self.collect_expr_opt(e.expr());
@@ -374,7 +401,11 @@ where
                 }
                 let ret_type = e.ret_type().and_then(|r| r.type_ref()).map(TypeRef::from_ast);
                 let body = self.collect_expr_opt(e.body());
-                self.alloc_expr(Expr::Lambda { args, arg_types, ret_type, body }, syntax_ptr)
+                let is_async = e.is_async();
+                self.alloc_expr(
+                    Expr::Lambda { args, arg_types, ret_type, body, is_async },
+                    syntax_ptr,
+                )
             }
             ast::Expr::BinExpr(e) => {
                 let lhs = self.collect_expr_opt(e.lhs());
@@ -414,8 +445,9 @@ where
                 let lit = match e.kind() {
                     LiteralKind::IntNumber { suffix } => {
                         let known_name = suffix.and_then(|it| BuiltinInt::from_suffix(&it));
+                        let value = e.int_value().map(|it| it as u64).unwrap_or_default();
 
-                        Literal::Int(Default::default(), known_name)
+                        Literal::Int(value, known_name)
                     }
                     LiteralKind::FloatNumber { suffix } => {
                         let known_name = suffix.and_then(|it| BuiltinFloat::from_suffix(&it));
@@ -536,18 +568,55 @@ where
                     let ast_id = self.expander.ast_id(&def);
                     (TraitLoc { container, ast_id }.intern(self.db).into(), def.name())
                 }
+                ast::ModuleItem::ExternBlock(block) => {
+                    self.collect_extern_block_items(&block);
+                    continue;
+                }
                 ast::ModuleItem::ImplBlock(_)
                 | ast::ModuleItem::UseItem(_)
                 | ast::ModuleItem::ExternCrateItem(_)
-                | ast::ModuleItem::Module(_) => continue,
+                | ast::ModuleItem::Module(_)
+                | ast::ModuleItem::MacroDef(_) => continue,
             };
-            self.body.item_scope.define_def(def);
-            if let Some(name) = name {
-                self.body.item_scope.push_res(name.as_name(), def.into());
-            }
+            self.define_block_item(def, name);
         }
     }
 
+    /// Items of an `extern` block are defined in the enclosing block's scope,
+    /// like `RawItemsCollector::add_extern_block` does for module scopes.
+    fn collect_extern_block_items(&mut self, block: &ast::ExternBlock) {
+        let container = ContainerId::DefWithBodyId(self.def);
+        let items = block.extern_item_list().into_iter().flat_map(|it| it.extern_items());
+        for item in items {
+            let (def, name): (ModuleDefId, Option<ast::Name>) = match item {
+                ast::ExternItem::FnDef(def) => {
+                    let ast_id = self.expander.ast_id(&def);
+                    (
+                        FunctionLoc { container: container.into(), ast_id }.intern(self.db).into(),
+                        def.name(),
+                    )
+                }
+                ast::ExternItem::StaticDef(def) => {
+                    let ast_id = self.expander.ast_id(&def);
+                    (StaticLoc { container, ast_id }.intern(self.db).into(), def.name())
+                }
+            };
+            self.define_block_item(def, name);
+        }
+    }
+
+    fn define_block_item(&mut self, def: ModuleDefId, name: Option<ast::Name>) {
+        self.body.item_scope.define_def(def);
+        if let Some(name) = name {
+            let vis = Visibility::Public; // FIXME determine correctly
+            self.body.item_scope.push_res(name.as_name(), PerNs::from_def(def, vis));
+        }
+    }
+
+    fn collect_label(&self, label: Option<ast::Label>) -> Option<Name> {
+        label.and_then(|it| it.lifetime_token()).map(|it| Name::new_lifetime(&it))
+    }
+
     fn collect_block_opt(&mut self, expr: Option<ast::BlockExpr>) -> ExprId {
         if let Some(block) = expr {
             self.collect_block(block)
@@ -6,12 +6,18 @@ use hir_expand::{
     name::{name, AsName, Name},
     AstId, InFile,
 };
-use ra_syntax::ast::{self, AstNode, ImplItem, ModuleItemOwner, NameOwner, TypeAscriptionOwner};
+use ra_syntax::{
+    ast::{
+        self, AstNode, ImplItem, ModuleItemOwner, NameOwner, TypeAscriptionOwner, VisibilityOwner,
+    },
+    SmolStr,
+};
 
 use crate::{
     db::DefDatabase,
     src::HasSource,
-    type_ref::{Mutability, TypeRef},
+    type_ref::{LifetimeRef, Mutability, TypeRef},
+    visibility::RawVisibility,
     AssocContainerId, AssocItemId, ConstId, ConstLoc, Expander, FunctionId, FunctionLoc, HasModule,
     ImplId, Intern, Lookup, ModuleId, StaticId, TraitId, TypeAliasId, TypeAliasLoc,
 };
@@ -24,27 +30,43 @@ pub struct FunctionData {
     /// True if the first param is `self`. This is relevant to decide whether this
     /// can be called as a method.
     pub has_self_param: bool,
+    /// True for `async fn`, whose declared return type is the output of the
+    /// returned future.
+    pub is_async: bool,
+    /// True for `unsafe fn`, whose body is an unsafe context, and for foreign
+    /// functions, which can only be called in unsafe code.
+    pub is_unsafe: bool,
+    /// True for functions declared in an `extern { .. }` block.
+    pub is_extern: bool,
+    /// True if the parameter list ends with `...`, as in C's `printf`.
+    pub is_varargs: bool,
+    /// The ABI of `extern "abi" fn`s and of functions in `extern` blocks.
+    pub abi: Option<SmolStr>,
+    pub visibility: RawVisibility,
 }
 
 impl FunctionData {
     pub(crate) fn fn_data_query(db: &impl DefDatabase, func: FunctionId) -> Arc<FunctionData> {
-        let src = func.lookup(db).source(db);
+        let loc = func.lookup(db);
+        let src = loc.source(db);
         let name = src.value.name().map(|n| n.as_name()).unwrap_or_else(Name::missing);
         let mut params = Vec::new();
         let mut has_self_param = false;
+        let mut is_varargs = false;
         if let Some(param_list) = src.value.param_list() {
             if let Some(self_param) = param_list.self_param() {
                 let self_type = if let Some(type_ref) = self_param.ascribed_type() {
                     TypeRef::from_ast(type_ref)
                 } else {
                     let self_type = TypeRef::Path(name![Self].into());
+                    let lifetime = self_param.lifetime_token().map(LifetimeRef::from_token);
                     match self_param.kind() {
                         ast::SelfParamKind::Owned => self_type,
                         ast::SelfParamKind::Ref => {
-                            TypeRef::Reference(Box::new(self_type), Mutability::Shared)
+                            TypeRef::Reference(Box::new(self_type), lifetime, Mutability::Shared)
                         }
                         ast::SelfParamKind::MutRef => {
-                            TypeRef::Reference(Box::new(self_type), Mutability::Mut)
+                            TypeRef::Reference(Box::new(self_type), lifetime, Mutability::Mut)
                         }
                     }
                 };
@@ -55,6 +77,7 @@ impl FunctionData {
                 let type_ref = TypeRef::from_ast_opt(param.ascribed_type());
                 params.push(type_ref);
             }
+            is_varargs = param_list.is_variadic();
         }
         let ret_type = if let Some(type_ref) = src.value.ret_type().and_then(|rt| rt.type_ref()) {
             TypeRef::from_ast(type_ref)
@@ -62,7 +85,39 @@ impl FunctionData {
             TypeRef::unit()
         };
 
-        let sig = FunctionData { name, params, ret_type, has_self_param };
+        let extern_block = ast::ExternItem::from(src.value.clone()).extern_block();
+        let is_extern = extern_block.is_some();
+        let is_async = src.value.is_async();
+        let is_unsafe = src.value.is_unsafe() || is_extern;
+        let abi = match extern_block {
+            Some(block) => block.abi(),
+            None => src.value.abi(),
+        };
+        let abi = abi.and_then(|abi| abi.name()).map(SmolStr::from);
+
+        // Items of a trait are as visible as the trait itself.
+        let default_visibility = match loc.container {
+            AssocContainerId::TraitId(_) => RawVisibility::Public,
+            _ => RawVisibility::private(),
+        };
+        let visibility = RawVisibility::from_ast_with_default(
+            db,
+            default_visibility,
+            src.map(|s| s.visibility()),
+        );
+
+        let sig = FunctionData {
+            name,
+            params,
+            ret_type,
+            has_self_param,
+            is_async,
+            is_unsafe,
+            is_extern,
+            is_varargs,
+            abi,
+            visibility,
+        };
         Arc::new(sig)
     }
 }
@@ -204,11 +259,6 @@ impl ConstData {
         Arc::new(ConstData::new(&node))
     }
 
-    pub(crate) fn static_data_query(db: &impl DefDatabase, konst: StaticId) -> Arc<ConstData> {
-        let node = konst.lookup(db).source(db).value;
-        Arc::new(ConstData::new(&node))
-    }
-
     fn new<N: NameOwner + TypeAscriptionOwner>(node: &N) -> ConstData {
         let name = node.name().map(|n| n.as_name());
         let type_ref = TypeRef::from_ast_opt(node.ascribed_type());
@@ -216,6 +266,32 @@ impl ConstData {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaticData {
+    pub name: Option<Name>,
+    pub type_ref: TypeRef,
+    /// True for `static mut`, which can only be accessed in unsafe code.
+    pub mutable: bool,
+    /// True for statics declared in an `extern { .. }` block, which can only be
+    /// accessed in unsafe code as well.
+    pub is_extern: bool,
+    /// The ABI of the `extern` block an extern static is declared in.
+    pub abi: Option<SmolStr>,
+}
+
+impl StaticData {
+    pub(crate) fn static_data_query(db: &impl DefDatabase, konst: StaticId) -> Arc<StaticData> {
+        let node = konst.lookup(db).source(db).value;
+        let name = node.name().map(|n| n.as_name());
+        let type_ref = TypeRef::from_ast_opt(node.ascribed_type());
+        let mutable = node.is_mut();
+        let extern_block = ast::ExternItem::from(node).extern_block();
+        let is_extern = extern_block.is_some();
+        let abi = extern_block.and_then(|block| block.abi()?.name()).map(SmolStr::from);
+        Arc::new(StaticData { name, type_ref, mutable, is_extern, abi })
+    }
+}
+
 fn collect_impl_items_in_macros(
     db: &impl DefDatabase,
     module_id: ModuleId,
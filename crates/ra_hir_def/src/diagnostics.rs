@@ -4,9 +4,9 @@ use std::any::Any;
 
 use hir_expand::diagnostics::Diagnostic;
 use ra_db::RelativePathBuf;
-use ra_syntax::{ast, AstPtr, SyntaxNodePtr};
+use ra_syntax::{ast, AstPtr, SyntaxNodePtr, TextRange};
 
-use hir_expand::{HirFileId, InFile};
+use hir_expand::{db::AstDatabase, HirFileId, InFile, MacroCallId};
 
 #[derive(Debug)]
 pub struct UnresolvedModule {
@@ -26,3 +26,58 @@ impl Diagnostic for UnresolvedModule {
         self
     }
 }
+
+#[derive(Debug)]
+pub struct UnresolvedMacroCall {
+    pub file: HirFileId,
+    pub node: AstPtr<ast::MacroCall>,
+}
+
+impl Diagnostic for UnresolvedMacroCall {
+    fn message(&self) -> String {
+        "unresolved macro call".to_string()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.node.into() }
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct MacroError {
+    pub file: HirFileId,
+    pub node: SyntaxNodePtr,
+    /// The range of the input token the error points to, if any.
+    pub range: Option<TextRange>,
+    pub message: String,
+}
+
+impl MacroError {
+    pub(crate) fn from_call(db: &impl AstDatabase, call: MacroCallId) -> Option<MacroError> {
+        let (err, range) = call.expand_error(db)?;
+        let node = call.as_file().call_node(db)?;
+        Some(MacroError {
+            file: node.file_id,
+            node: SyntaxNodePtr::new(&node.value),
+            range,
+            message: err.to_string(),
+        })
+    }
+}
+
+impl Diagnostic for MacroError {
+    fn message(&self) -> String {
+        self.message.clone()
+    }
+    fn source(&self) -> InFile<SyntaxNodePtr> {
+        InFile { file_id: self.file, value: self.node }
+    }
+    fn highlight_range(&self) -> TextRange {
+        self.range.unwrap_or_else(|| self.node.range())
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
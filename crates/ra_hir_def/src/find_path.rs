@@ -0,0 +1,224 @@
+//! An algorithm to find a path to refer to a certain item.
+
+use std::collections::VecDeque;
+
+use hir_expand::name::Name;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    db::DefDatabase,
+    path::{ModPath, PathKind},
+    ModuleDefId, ModuleId,
+};
+
+/// Finds a path that can be used to refer to `item` from the module `from`,
+/// for example in a `use` item.
+///
+/// The search walks the module tree breadth-first, starting at the root of
+/// the current crate and at the crates in its extern prelude, so the shortest
+/// path wins. Only items and modules visible from `from` are considered, which
+/// means that re-exports are taken into account as well.
+pub fn find_path(db: &impl DefDatabase, item: ModuleDefId, from: ModuleId) -> Option<ModPath> {
+    let mut items = FxHashSet::default();
+    items.insert(item);
+    find_paths(db, &items, from).remove(&item)
+}
+
+/// Like `find_path`, but for several items at once, walking the module trees
+/// only a single time. Items without a path are missing from the result.
+pub fn find_paths(
+    db: &impl DefDatabase,
+    items: &FxHashSet<ModuleDefId>,
+    from: ModuleId,
+) -> FxHashMap<ModuleDefId, ModPath> {
+    let def_map = db.crate_def_map(from.krate);
+
+    let mut queue = VecDeque::new();
+    let crate_root = ModuleId { krate: from.krate, local_id: def_map.root };
+    queue.push_back((crate_root, ModPath::from_simple_segments(PathKind::Crate, None)));
+
+    let mut extern_prelude = def_map.extern_prelude.iter().collect::<Vec<_>>();
+    extern_prelude.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, def) in extern_prelude {
+        if let ModuleDefId::ModuleId(module) = def {
+            let path = ModPath::from_simple_segments(PathKind::Plain, Some(name.clone()));
+            queue.push_back((*module, path));
+        }
+    }
+
+    let mut visited = FxHashSet::default();
+    let mut res = FxHashMap::default();
+    while let Some((module, path)) = queue.pop_front() {
+        if res.len() == items.len() {
+            break;
+        }
+        if !visited.insert(module) {
+            continue;
+        }
+        db.check_canceled();
+        let module_def_map = db.crate_def_map(module.krate);
+        let mut entries =
+            module_def_map[module.local_id].scope.entries_without_primitives().collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, def) in entries {
+            for (it, vis) in def.types.into_iter().chain(def.values) {
+                if items.contains(&it) && vis.is_visible_from(db, from) {
+                    res.entry(it).or_insert_with(|| append_segment(&path, name));
+                }
+            }
+            if let Some((ModuleDefId::ModuleId(child), vis)) = def.types {
+                if !visited.contains(&child) && vis.is_visible_from(db, from) {
+                    queue.push_back((child, append_segment(&path, name)));
+                }
+            }
+        }
+    }
+    res
+}
+
+fn append_segment(path: &ModPath, name: &Name) -> ModPath {
+    let segments = path.segments.iter().cloned().chain(Some(name.clone()));
+    ModPath::from_simple_segments(path.kind.clone(), segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use hir_expand::hygiene::Hygiene;
+    use ra_db::{fixture::WithFixture, FileLoader};
+    use ra_syntax::ast::{self, AstNode};
+
+    use super::*;
+    use crate::{item_scope::BuiltinShadowMode, test_db::TestDB};
+
+    /// `code` must contain a cursor marker; this module will be the one
+    /// searching from. The item `path` refers to is resolved from there, and
+    /// `path` is also the expected result.
+    fn check_found_path(code: &str, path: &str) {
+        check_found_paths(code, &[path]);
+    }
+
+    /// Like `check_found_path`, but also searches the paths of all items at
+    /// once with `find_paths`.
+    fn check_found_paths(code: &str, paths: &[&str]) {
+        let (db, pos) = TestDB::with_position(code);
+        let (krate, local_id) = db
+            .relevant_crates(pos.file_id)
+            .iter()
+            .find_map(|&krate| {
+                let local_id = db.crate_def_map(krate).modules_for_file(pos.file_id).next()?;
+                Some((krate, local_id))
+            })
+            .unwrap();
+        let crate_def_map = db.crate_def_map(krate);
+        let module = ModuleId { krate, local_id };
+
+        let mut expected = FxHashMap::default();
+        for path in paths {
+            let parsed_path_file = ra_syntax::SourceFile::parse(&format!("use {};", path));
+            let ast_path = parsed_path_file
+                .syntax_node()
+                .descendants()
+                .find_map(ast::Path::cast)
+                .expect("failed to parse path");
+            let mod_path = ModPath::from_src(ast_path, &Hygiene::new_unhygienic()).unwrap();
+
+            let resolved =
+                crate_def_map.resolve_path(&db, local_id, &mod_path, BuiltinShadowMode::Module).0;
+            let item = resolved.take_types().or_else(|| resolved.take_values()).unwrap();
+
+            assert_eq!(find_path(&db, item, module), Some(mod_path.clone()));
+            expected.insert(item, mod_path);
+        }
+
+        let items = expected.keys().copied().collect();
+        assert_eq!(find_paths(&db, &items, module), expected);
+    }
+
+    #[test]
+    fn item_in_same_crate() {
+        let code = r#"
+            //- /main.rs
+            mod foo;
+            <|>
+            //- /foo.rs
+            pub struct S;
+        "#;
+        check_found_path(code, "crate::foo::S");
+    }
+
+    #[test]
+    fn function_in_child_module() {
+        let code = r#"
+            //- /main.rs
+            mod foo {
+                pub mod bar {
+                    pub fn baz() {}
+                }
+            }
+            <|>
+        "#;
+        check_found_path(code, "crate::foo::bar::baz");
+    }
+
+    #[test]
+    fn item_in_dependency() {
+        let code = r#"
+            //- /main.rs crate:main deps:std
+            <|>
+            //- /std.rs crate:std
+            pub mod collections {
+                pub struct HashMap;
+            }
+        "#;
+        check_found_path(code, "std::collections::HashMap");
+    }
+
+    #[test]
+    fn prefers_shortest_reexport() {
+        let code = r#"
+            //- /main.rs crate:main deps:std
+            <|>
+            //- /std.rs crate:std
+            pub mod collections {
+                pub use self::hash_map::HashMap;
+                pub mod hash_map {
+                    pub struct HashMap;
+                }
+            }
+        "#;
+        check_found_path(code, "std::collections::HashMap");
+    }
+
+    #[test]
+    fn skips_private_modules() {
+        let code = r#"
+            //- /main.rs crate:main deps:std
+            <|>
+            //- /std.rs crate:std
+            mod private {
+                pub struct Foo;
+            }
+            pub mod public {
+                pub use crate::private::Foo;
+            }
+        "#;
+        check_found_path(code, "std::public::Foo");
+    }
+
+    #[test]
+    fn finds_paths_of_several_items() {
+        let code = r#"
+            //- /main.rs crate:main deps:std
+            mod foo {
+                pub struct Foo;
+            }
+            <|>
+            //- /std.rs crate:std
+            pub mod collections {
+                pub struct HashMap;
+            }
+        "#;
+        check_found_paths(code, &["crate::foo::Foo", "std::collections::HashMap"]);
+    }
+}
@@ -20,8 +20,8 @@ use crate::{
     keys,
     src::HasChildSource,
     src::HasSource,
-    type_ref::{TypeBound, TypeRef},
-    AdtId, GenericDefId, LocalTypeParamId, Lookup, TypeParamId,
+    type_ref::{LifetimeRef, TypeBound, TypeRef},
+    AdtId, GenericDefId, LocalLifetimeParamId, LocalTypeParamId, Lookup, TypeParamId,
 };
 
 /// Data about a generic parameter (to a function, struct, impl, ...).
@@ -31,12 +31,19 @@ pub struct TypeParamData {
     pub default: Option<TypeRef>,
 }
 
+/// Data about a lifetime parameter, like `'a` in `fn foo<'a>()`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LifetimeParamData {
+    pub name: Name,
+}
+
 /// Data about the generic parameters of a function, struct, impl, etc.
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct GenericParams {
     pub types: Arena<LocalTypeParamId, TypeParamData>,
-    // lifetimes: Arena<LocalLifetimeParamId, LifetimeParamData>,
+    pub lifetimes: Arena<LocalLifetimeParamId, LifetimeParamData>,
     pub where_predicates: Vec<WherePredicate>,
+    pub lifetime_predicates: Vec<LifetimePredicate>,
 }
 
 /// A single predicate from a where clause, i.e. `where Type: Trait`. Combined
@@ -49,6 +56,14 @@ pub struct WherePredicate {
     pub bound: TypeBound,
 }
 
+/// A single outlives predicate between lifetimes, i.e. `'a: 'b`, either from
+/// the bounds of a lifetime parameter or from a where clause.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LifetimePredicate {
+    pub lifetime: LifetimeRef,
+    pub bound: LifetimeRef,
+}
+
 type SourceMap = ArenaMap<LocalTypeParamId, Either<ast::TraitDef, ast::TypeParam>>;
 
 impl GenericParams {
@@ -61,7 +76,12 @@ impl GenericParams {
     }
 
     fn new(db: &impl DefDatabase, def: GenericDefId) -> (GenericParams, InFile<SourceMap>) {
-        let mut generics = GenericParams { types: Arena::default(), where_predicates: Vec::new() };
+        let mut generics = GenericParams {
+            types: Arena::default(),
+            lifetimes: Arena::default(),
+            where_predicates: Vec::new(),
+            lifetime_predicates: Vec::new(),
+        };
         let mut sm = ArenaMap::default();
         // FIXME: add `: Sized` bound for everything except for `Self` in traits
         let file_id = match def {
@@ -138,6 +158,18 @@ impl GenericParams {
     }
 
     fn fill_params(&mut self, sm: &mut SourceMap, params: ast::TypeParamList) {
+        for lifetime_param in params.lifetime_params() {
+            let lifetime = match lifetime_param.lifetime_token() {
+                Some(it) => LifetimeRef::from_token(it),
+                None => continue,
+            };
+            self.lifetimes.alloc(LifetimeParamData { name: lifetime.name.clone() });
+            for bound in lifetime_param.lifetime_bounds() {
+                let bound = LifetimeRef::from_token(bound);
+                self.lifetime_predicates
+                    .push(LifetimePredicate { lifetime: lifetime.clone(), bound });
+            }
+        }
         for type_param in params.type_params() {
             let name = type_param.name().map_or_else(Name::missing, |it| it.as_name());
             // FIXME: Use `Path::from_src`
@@ -153,6 +185,16 @@ impl GenericParams {
 
     fn fill_where_predicates(&mut self, where_clause: ast::WhereClause) {
         for pred in where_clause.predicates() {
+            if let Some(lifetime) = pred.lifetime_token() {
+                let lifetime = LifetimeRef::from_token(lifetime);
+                for bound in pred.type_bound_list().iter().flat_map(|l| l.bounds()) {
+                    if let TypeBound::Lifetime(bound) = TypeBound::from_ast(bound) {
+                        self.lifetime_predicates
+                            .push(LifetimePredicate { lifetime: lifetime.clone(), bound });
+                    }
+                }
+                continue;
+            }
             let type_ref = match pred.type_ref() {
                 Some(type_ref) => type_ref,
                 None => continue,
@@ -201,3 +243,89 @@ impl ChildBySource for GenericDefId {
         res
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ra_db::fixture::WithFixture;
+
+    use super::*;
+    use crate::{test_db::TestDB, ModuleDefId};
+
+    /// Lowers the generic params of the first function or ADT in `code` and
+    /// renders the lifetime-related parts, e.g. `<'a, 'b> 'b: 'a, T: 'a`.
+    fn lifetimes(code: &str) -> String {
+        let (db, _file_id) = TestDB::with_single_file(code);
+        let def_map = db.crate_def_map(db.test_crate());
+        let def: GenericDefId = def_map[def_map.root]
+            .scope
+            .declarations()
+            .find_map(|it| match it {
+                ModuleDefId::FunctionId(it) => Some(it.into()),
+                ModuleDefId::AdtId(it) => Some(it.into()),
+                _ => None,
+            })
+            .unwrap();
+        let params = db.generic_params(def);
+
+        let names = params.lifetimes.iter().map(|(_, it)| it.name.to_string()).collect::<Vec<_>>();
+        let mut predicates = params
+            .lifetime_predicates
+            .iter()
+            .map(|it| format!("{}: {}", it.lifetime.name, it.bound.name))
+            .collect::<Vec<_>>();
+        for pred in params.where_predicates.iter() {
+            if let (TypeRef::Path(path), TypeBound::Lifetime(bound)) = (&pred.type_ref, &pred.bound)
+            {
+                predicates.push(format!("{}: {}", path.as_ident().unwrap(), bound.name));
+            }
+        }
+        format!("<{}> {}", names.join(", "), predicates.join(", ")).trim().to_string()
+    }
+
+    #[test]
+    fn lower_lifetime_params() {
+        assert_eq!(lifetimes("fn foo<'a, T>(x: &'a T) {}"), "<'a>");
+        assert_eq!(lifetimes("struct S<'a, 'b>(&'a u8, &'b u8);"), "<'a, 'b>");
+        assert_eq!(lifetimes("fn foo<T>() {}"), "<>");
+    }
+
+    #[test]
+    fn lower_lifetime_bounds() {
+        assert_eq!(lifetimes("fn foo<'a, 'b: 'a + 'static>() {}"), "<'a, 'b> 'b: 'a, 'b: 'static");
+        assert_eq!(lifetimes("fn foo<'a, T: 'a>() {}"), "<'a> T: 'a");
+    }
+
+    #[test]
+    fn lower_lifetime_where_predicates() {
+        assert_eq!(
+            lifetimes("fn foo<'a, 'b, T>() where 'a: 'b, T: Clone + 'b {}"),
+            "<'a, 'b> 'a: 'b, T: 'b"
+        );
+    }
+
+    #[test]
+    fn lower_reference_lifetimes() {
+        let (db, _file_id) = TestDB::with_single_file("fn foo<'a>(x: &'a str, y: &mut u8) {}");
+        let def_map = db.crate_def_map(db.test_crate());
+        let func = def_map[def_map.root]
+            .scope
+            .declarations()
+            .find_map(|it| match it {
+                ModuleDefId::FunctionId(it) => Some(it),
+                _ => None,
+            })
+            .unwrap();
+        let data = db.function_data(func);
+        let lifetimes = data
+            .params
+            .iter()
+            .map(|it| match it {
+                TypeRef::Reference(_, lifetime, _) => {
+                    lifetime.as_ref().map(|it| it.name.to_string())
+                }
+                _ => panic!("expected a reference, got {:?}", it),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(lifetimes, vec![Some("'a".to_string()), None]);
+    }
+}
@@ -29,6 +29,8 @@ pub mod docs;
 pub mod expr;
 pub mod body;
 pub mod resolver;
+pub mod visibility;
+pub mod find_path;
 
 mod trace;
 pub mod nameres;
@@ -171,6 +173,10 @@ pub struct TypeParamId {
 pub struct LocalTypeParamId(RawId);
 impl_arena_id!(LocalTypeParamId);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LocalLifetimeParamId(RawId);
+impl_arena_id!(LocalLifetimeParamId);
+
 macro_rules! impl_froms {
     ($e:ident: $($v:ident $(($($sv:ident),*))?),*) => {
         $(
@@ -329,7 +335,7 @@ pub enum VariantId {
     StructId(StructId),
     UnionId(UnionId),
 }
-impl_froms!(VariantId: EnumVariantId, StructId);
+impl_froms!(VariantId: EnumVariantId, StructId, UnionId);
 
 trait Intern {
     type ID;
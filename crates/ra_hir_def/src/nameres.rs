@@ -71,7 +71,7 @@ use crate::{
     db::DefDatabase,
     item_scope::{BuiltinShadowMode, ItemScope},
     nameres::{diagnostics::DefDiagnostic, path_resolution::ResolveMode},
-    path::ModPath,
+    path::{ModPath, PathKind},
     per_ns::PerNs,
     AstId, LocalModuleId, ModuleDefId, ModuleId,
 };
@@ -128,7 +128,7 @@ impl ModuleOrigin {
         }
     }
 
-    fn declaration(&self) -> Option<AstId<ast::Module>> {
+    pub fn declaration(&self) -> Option<AstId<ast::Module>> {
         match self {
             ModuleOrigin::File { declaration: module, .. }
             | ModuleOrigin::Inline { definition: module, .. } => Some(*module),
@@ -225,6 +225,42 @@ impl CrateDefMap {
             self.resolve_path_fp_with_macro(db, ResolveMode::Other, original_module, path, shadow);
         (res.resolved_def, res.segment_index)
     }
+
+    /// Checks whether a macro call with `path` which failed to resolve should
+    /// be reported. We keep quiet about calls into crates we can't see into:
+    /// `proc-macro` crates, whose macros may have failed to load, and crates
+    /// without any items, e.g. because their sources aren't loaded yet.
+    pub(crate) fn should_report_unresolved_macro_call(
+        &self,
+        db: &impl DefDatabase,
+        original_module: LocalModuleId,
+        path: &ModPath,
+    ) -> bool {
+        let prefix = match path.segments.split_last() {
+            Some((_, prefix)) if !prefix.is_empty() || path.kind != PathKind::Plain => {
+                ModPath { kind: path.kind.clone(), segments: prefix.to_vec() }
+            }
+            _ => return true,
+        };
+        let krate = match self.resolve_path(db, original_module, &prefix, BuiltinShadowMode::Module)
+        {
+            (res, None) => match res.take_types() {
+                Some(ModuleDefId::ModuleId(module)) => module.krate,
+                _ => return true,
+            },
+            (_, Some(_)) => return true,
+        };
+        if krate == self.krate {
+            return true;
+        }
+
+        let crate_graph = db.crate_graph();
+        let is_proc_macro_crate = !crate_graph.proc_macro(krate).is_empty()
+            || crate_graph.dependencies(krate).any(|dep| dep.name == "proc_macro");
+        let def_map = db.crate_def_map(krate);
+        let is_empty = def_map[def_map.root].scope.entries_without_primitives().next().is_none();
+        !is_proc_macro_crate && !is_empty
+    }
 }
 
 impl ModuleData {
@@ -278,11 +314,16 @@ impl ModuleSource {
 }
 
 mod diagnostics {
-    use hir_expand::diagnostics::DiagnosticSink;
+    use hir_expand::{diagnostics::DiagnosticSink, MacroCallId};
     use ra_db::RelativePathBuf;
     use ra_syntax::{ast, AstPtr};
 
-    use crate::{db::DefDatabase, diagnostics::UnresolvedModule, nameres::LocalModuleId, AstId};
+    use crate::{
+        db::DefDatabase,
+        diagnostics::{MacroError, UnresolvedMacroCall, UnresolvedModule},
+        nameres::LocalModuleId,
+        AstId,
+    };
 
     #[derive(Debug, PartialEq, Eq)]
     pub(super) enum DefDiagnostic {
@@ -291,6 +332,14 @@ mod diagnostics {
             declaration: AstId<ast::Module>,
             candidate: RelativePathBuf,
         },
+        UnresolvedMacroCall {
+            module: LocalModuleId,
+            ast_id: AstId<ast::MacroCall>,
+        },
+        MacroError {
+            module: LocalModuleId,
+            call: MacroCallId,
+        },
     }
 
     impl DefDiagnostic {
@@ -312,6 +361,24 @@ mod diagnostics {
                         candidate: candidate.clone(),
                     })
                 }
+                DefDiagnostic::UnresolvedMacroCall { module, ast_id } => {
+                    if *module != target_module {
+                        return;
+                    }
+                    let node = ast_id.to_node(db);
+                    sink.push(UnresolvedMacroCall {
+                        file: ast_id.file_id,
+                        node: AstPtr::new(&node),
+                    })
+                }
+                DefDiagnostic::MacroError { module, call } => {
+                    if *module != target_module {
+                        return;
+                    }
+                    if let Some(err) = MacroError::from_call(db, *call) {
+                        sink.push(err);
+                    }
+                }
             }
         }
     }
@@ -7,16 +7,17 @@ use hir_expand::{
     builtin_derive::find_builtin_derive,
     builtin_macro::find_builtin_macro,
     name::{name, AsName, Name},
+    proc_macro::ProcMacroExpander,
     HirFileId, MacroCallId, MacroCallKind, MacroDefId, MacroDefKind,
 };
 use ra_cfg::CfgOptions;
-use ra_db::{CrateId, FileId};
+use ra_db::{CrateId, FileId, ProcMacroId, ProcMacroKind};
 use ra_syntax::ast;
 use rustc_hash::FxHashMap;
 use test_utils::tested_by;
 
 use crate::{
-    attr::Attrs,
+    attr::{AttrInput, Attrs},
     db::DefDatabase,
     nameres::{
         diagnostics::DefDiagnostic, mod_resolution::ModDir, path_resolution::ReachedFixedPoint,
@@ -24,10 +25,32 @@ use crate::{
     },
     path::{ModPath, PathKind},
     per_ns::PerNs,
+    visibility::{RawVisibility, Visibility},
     AdtId, AstId, ConstLoc, ContainerId, EnumLoc, EnumVariantId, FunctionLoc, ImplLoc, Intern,
     LocalModuleId, ModuleDefId, ModuleId, StaticLoc, StructLoc, TraitLoc, TypeAliasLoc, UnionLoc,
 };
 
+/// Attributes built into the compiler, which thus can't invoke a proc macro.
+/// The list isn't complete; it just saves us from resolving the common ones.
+const BUILTIN_ATTRIBUTES: &[&str] = &[
+    "allow",
+    "cfg",
+    "cfg_attr",
+    "deny",
+    "deprecated",
+    "derive",
+    "doc",
+    "forbid",
+    "inline",
+    "macro_export",
+    "macro_use",
+    "must_use",
+    "path",
+    "repr",
+    "test",
+    "warn",
+];
+
 pub(super) fn collect_defs(db: &impl DefDatabase, mut def_map: CrateDefMap) -> CrateDefMap {
     let crate_graph = db.crate_graph();
 
@@ -108,11 +131,14 @@ struct MacroDirective {
 struct DefCollector<'a, DB> {
     db: &'a DB,
     def_map: CrateDefMap,
-    glob_imports: FxHashMap<LocalModuleId, Vec<(LocalModuleId, raw::Import)>>,
+    glob_imports: FxHashMap<LocalModuleId, Vec<(LocalModuleId, raw::Import, Visibility)>>,
     unresolved_imports: Vec<ImportDirective>,
     resolved_imports: Vec<ImportDirective>,
     unexpanded_macros: Vec<MacroDirective>,
-    unexpanded_attribute_macros: Vec<(LocalModuleId, AstId<ast::ModuleItem>, ModPath)>,
+    /// Derives and proc macro attributes, with the kind of proc macro the path
+    /// needs to resolve to.
+    unexpanded_attribute_macros:
+        Vec<(LocalModuleId, AstId<ast::ModuleItem>, ModPath, ProcMacroKind)>,
     mod_dirs: FxHashMap<LocalModuleId, ModDir>,
     cfg_options: &'a CfgOptions,
 }
@@ -127,6 +153,20 @@ where
         let raw_items = self.db.raw_items(file_id.into());
         let module_id = self.def_map.root;
         self.def_map.modules[module_id].origin = ModuleOrigin::CrateRoot { definition: file_id };
+
+        // Procedural macros of a `proc-macro` crate are visible at its root,
+        // like `#[macro_export]`ed declarative macros.
+        let krate = self.def_map.krate;
+        for (idx, proc_macro) in crate_graph.proc_macro(krate).iter().enumerate() {
+            let expander = ProcMacroExpander::new(krate, ProcMacroId(idx as u32), proc_macro.kind);
+            let macro_id = MacroDefId {
+                ast_id: None,
+                krate: Some(krate),
+                kind: MacroDefKind::ProcMacro(expander),
+            };
+            self.define_macro(module_id, proc_macro.as_name(), macro_id, true);
+        }
+
         ModCollector {
             def_collector: &mut *self,
             module_id,
@@ -152,6 +192,21 @@ where
             }
         }
 
+        // Whatever is left can't be resolved at all.
+        for directive in self.unexpanded_macros.iter() {
+            if !self.def_map.should_report_unresolved_macro_call(
+                self.db,
+                directive.module_id,
+                &directive.path,
+            ) {
+                continue;
+            }
+            self.def_map.diagnostics.push(DefDiagnostic::UnresolvedMacroCall {
+                module: directive.module_id,
+                ast_id: directive.ast_id,
+            });
+        }
+
         // Resolve all indeterminate resolved imports again
         // As some of the macros will expand newly import shadowing partial resolved imports
         // FIXME: We maybe could skip this, if we handle the Indetermine imports in `resolve_imports`
@@ -214,7 +269,7 @@ where
         // In Rust, `#[macro_export]` macros are unconditionally visible at the
         // crate root, even if the parent modules is **not** visible.
         if export {
-            self.update(self.def_map.root, &[(name, PerNs::macros(macro_))]);
+            self.update(self.def_map.root, &[(name, PerNs::macros(macro_, Visibility::Public))]);
         }
     }
 
@@ -351,6 +406,10 @@ where
         let import_id = directive.import_id;
         let import = &directive.import;
         let def = directive.status.namespaces();
+        let vis = self
+            .def_map
+            .resolve_visibility(self.db, module_id, &import.visibility)
+            .unwrap_or(Visibility::Public);
 
         if import.is_glob {
             log::debug!("glob import: {:?}", import);
@@ -366,9 +425,17 @@ where
                         let scope = &item_map[m.local_id].scope;
 
                         // Module scoped macros is included
-                        let items = scope.collect_resolutions();
-
-                        self.update(module_id, &items);
+                        let items = scope
+                            .collect_resolutions()
+                            // only keep visible names...
+                            .into_iter()
+                            .map(|(n, res)| {
+                                (n, res.filter_visibility(|v| v.is_visible_from_other_crate()))
+                            })
+                            .filter(|(_, res)| !res.is_none())
+                            .collect::<Vec<_>>();
+
+                        self.update(module_id, &items, vis);
                     } else {
                         // glob import from same crate => we do an initial
                         // import, and then need to propagate any further
@@ -376,13 +443,27 @@ where
                         let scope = &self.def_map[m.local_id].scope;
 
                         // Module scoped macros is included
-                        let items = scope.collect_resolutions();
-
-                        self.update(module_id, &items);
+                        let items = scope
+                            .collect_resolutions()
+                            // only keep visible names...
+                            .into_iter()
+                            .map(|(n, res)| {
+                                (
+                                    n,
+                                    res.filter_visibility(|v| {
+                                        v.is_visible_from_def_map(&self.def_map, module_id)
+                                    }),
+                                )
+                            })
+                            .filter(|(_, res)| !res.is_none())
+                            .collect::<Vec<_>>();
+
+                        self.update(module_id, &items, vis);
                         // record the glob import in case we add further items
                         let glob = self.glob_imports.entry(m.local_id).or_default();
-                        if !glob.iter().any(|it| *it == (module_id, import_id)) {
-                            glob.push((module_id, import_id));
+                        if !glob.iter().any(|(mid, iid, _)| *mid == module_id && *iid == import_id)
+                        {
+                            glob.push((module_id, import_id, vis));
                         }
                     }
                 }
@@ -396,11 +477,11 @@ where
                         .map(|(local_id, variant_data)| {
                             let name = variant_data.name.clone();
                             let variant = EnumVariantId { parent: e, local_id };
-                            let res = PerNs::both(variant.into(), variant.into());
+                            let res = PerNs::both(variant.into(), variant.into(), vis);
                             (name, res)
                         })
                         .collect::<Vec<_>>();
-                    self.update(module_id, &resolutions);
+                    self.update(module_id, &resolutions, vis);
                 }
                 Some(d) => {
                     log::debug!("glob import {:?} from non-module/enum {:?}", import, d);
@@ -422,21 +503,26 @@ where
                         }
                     }
 
-                    self.update(module_id, &[(name, def)]);
+                    self.update(module_id, &[(name, def)], vis);
                 }
                 None => tested_by!(bogus_paths),
             }
         }
     }
 
-    fn update(&mut self, module_id: LocalModuleId, resolutions: &[(Name, PerNs)]) {
-        self.update_recursive(module_id, resolutions, 0)
+    /// Adds `resolutions` to the scope of `module_id`, giving every name the
+    /// visibility `vis`, and propagates them through glob imports.
+    fn update(&mut self, module_id: LocalModuleId, resolutions: &[(Name, PerNs)], vis: Visibility) {
+        self.update_recursive(module_id, resolutions, vis, 0)
     }
 
     fn update_recursive(
         &mut self,
         module_id: LocalModuleId,
         resolutions: &[(Name, PerNs)],
+        // All resolutions are imported with this visibility; the visibilities in
+        // the `PerNs` values are ignored and overwritten
+        vis: Visibility,
         depth: usize,
     ) {
         if depth > 100 {
@@ -446,7 +532,7 @@ where
         let scope = &mut self.def_map.modules[module_id].scope;
         let mut changed = false;
         for (name, res) in resolutions {
-            changed |= scope.push_res(name.clone(), *res);
+            changed |= scope.push_res(name.clone(), res.with_visibility(vis));
         }
 
         if !changed {
@@ -459,9 +545,13 @@ where
             .flat_map(|v| v.iter())
             .cloned()
             .collect::<Vec<_>>();
-        for (glob_importing_module, _glob_import) in glob_imports {
-            // We pass the glob import so that the tracked import in those modules is that glob import
-            self.update_recursive(glob_importing_module, resolutions, depth + 1);
+        for (glob_importing_module, _glob_import, glob_import_vis) in glob_imports {
+            // we know all resolutions have the same visibility (`vis`), so we
+            // just need to check that once
+            if !vis.is_visible_from_def_map(&self.def_map, glob_importing_module) {
+                continue;
+            }
+            self.update_recursive(glob_importing_module, resolutions, glob_import_vis, depth + 1);
         }
     }
 
@@ -486,7 +576,11 @@ where
                 BuiltinShadowMode::Module,
             );
 
-            if let Some(def) = resolved_res.resolved_def.take_macros() {
+            let def = resolved_res.resolved_def.take_macros().filter(|def| match def.kind {
+                MacroDefKind::ProcMacro(it) => it.kind() == ProcMacroKind::FuncLike,
+                _ => true,
+            });
+            if let Some(def) = def {
                 let call_id = def.as_call_id(self.db, MacroCallKind::FnLike(directive.ast_id));
                 resolved.push((directive.module_id, call_id));
                 res = ReachedFixedPoint::No;
@@ -495,8 +589,8 @@ where
 
             true
         });
-        attribute_macros.retain(|(module_id, ast_id, path)| {
-            let resolved_res = self.resolve_attribute_macro(path);
+        attribute_macros.retain(|(module_id, ast_id, path, kind)| {
+            let resolved_res = self.resolve_attribute_macro(*module_id, path, *kind);
 
             if let Some(def) = resolved_res {
                 let call_id = def.as_call_id(self.db, MacroCallKind::Attr(*ast_id));
@@ -518,10 +612,15 @@ where
         res
     }
 
-    fn resolve_attribute_macro(&self, path: &ModPath) -> Option<MacroDefId> {
+    fn resolve_attribute_macro(
+        &self,
+        module_id: LocalModuleId,
+        path: &ModPath,
+        kind: ProcMacroKind,
+    ) -> Option<MacroDefId> {
         // FIXME this is currently super hacky, just enough to support the
         // built-in derives
-        if let Some(name) = path.as_ident() {
+        if let (ProcMacroKind::CustomDerive, Some(name)) = (kind, path.as_ident()) {
             // FIXME this should actually be handled with the normal name
             // resolution; the std lib defines built-in stubs for the derives,
             // but these are new-style `macro`s, which we don't support yet
@@ -529,10 +628,28 @@ where
                 return Some(def_id);
             }
         }
-        None
+
+        // Custom derives and attributes are resolved like any other macro, but
+        // only proc macros of the matching kind may be used there.
+        let resolved_res = self.def_map.resolve_path_fp_with_macro(
+            self.db,
+            ResolveMode::Other,
+            module_id,
+            path,
+            BuiltinShadowMode::Module,
+        );
+        resolved_res.resolved_def.take_macros().filter(|def| match def.kind {
+            MacroDefKind::ProcMacro(it) => it.kind() == kind,
+            _ => false,
+        })
     }
 
     fn collect_macro_expansion(&mut self, module_id: LocalModuleId, macro_call_id: MacroCallId) {
+        if self.db.macro_expand(macro_call_id).err.is_some() {
+            self.def_map
+                .diagnostics
+                .push(DefDiagnostic::MacroError { module: module_id, call: macro_call_id });
+        }
         let file_id: HirFileId = macro_call_id.as_file();
         let raw_items = self.db.raw_items(file_id);
         let mod_dir = self.mod_dirs[&module_id].clone();
@@ -609,6 +726,7 @@ where
                         self.define_def(&self.raw_items[def], &item.attrs)
                     }
                     raw::RawItemKind::Macro(mac) => self.collect_macro(&self.raw_items[mac]),
+                    raw::RawItemKind::MacroDef(mac) => self.collect_macro_def(&self.raw_items[mac]),
                     raw::RawItemKind::Impl(imp) => {
                         let module = ModuleId {
                             krate: self.def_collector.def_map.krate,
@@ -633,9 +751,13 @@ where
         let is_macro_use = attrs.by_key("macro_use").exists();
         match module {
             // inline module, just recurse
-            raw::ModuleData::Definition { name, items, ast_id } => {
-                let module_id =
-                    self.push_child_module(name.clone(), AstId::new(self.file_id, *ast_id), None);
+            raw::ModuleData::Definition { name, visibility, items, ast_id } => {
+                let module_id = self.push_child_module(
+                    name.clone(),
+                    AstId::new(self.file_id, *ast_id),
+                    None,
+                    visibility,
+                );
 
                 ModCollector {
                     def_collector: &mut *self.def_collector,
@@ -650,7 +772,7 @@ where
                 }
             }
             // out of line module, resolve, parse and recurse
-            raw::ModuleData::Declaration { name, ast_id } => {
+            raw::ModuleData::Declaration { name, visibility, ast_id } => {
                 let ast_id = AstId::new(self.file_id, *ast_id);
                 match self.mod_dir.resolve_declaration(
                     self.def_collector.db,
@@ -659,7 +781,8 @@ where
                     path_attr,
                 ) {
                     Ok((file_id, mod_dir)) => {
-                        let module_id = self.push_child_module(name.clone(), ast_id, Some(file_id));
+                        let module_id =
+                            self.push_child_module(name.clone(), ast_id, Some(file_id), visibility);
                         let raw_items = self.def_collector.db.raw_items(file_id.into());
                         ModCollector {
                             def_collector: &mut *self.def_collector,
@@ -690,7 +813,13 @@ where
         name: Name,
         declaration: AstId<ast::Module>,
         definition: Option<FileId>,
+        visibility: &RawVisibility,
     ) -> LocalModuleId {
+        let vis = self
+            .def_collector
+            .def_map
+            .resolve_visibility(self.def_collector.db, self.module_id, visibility)
+            .unwrap_or(Visibility::Public);
         let modules = &mut self.def_collector.def_map.modules;
         let res = modules.alloc(ModuleData::default());
         modules[res].parent = Some(self.module_id);
@@ -702,20 +831,20 @@ where
         let module = ModuleId { krate: self.def_collector.def_map.krate, local_id: res };
         let def: ModuleDefId = module.into();
         self.def_collector.def_map.modules[self.module_id].scope.define_def(def);
-        self.def_collector.update(self.module_id, &[(name, def.into())]);
+        self.def_collector.update(self.module_id, &[(name, PerNs::from_def(def, vis))], vis);
         res
     }
 
     fn define_def(&mut self, def: &raw::DefData, attrs: &Attrs) {
         let module = ModuleId { krate: self.def_collector.def_map.krate, local_id: self.module_id };
-        // FIXME: check attrs to see if this is an attribute macro invocation;
-        // in which case we don't add the invocation, just a single attribute
-        // macro invocation
-
+        // FIXME: if this is an attribute macro invocation, the item should be
+        // replaced by the expansion instead of being defined as well
+        self.collect_attr_macros(attrs, def);
         self.collect_derives(attrs, def);
 
         let name = def.name.clone();
         let container = ContainerId::ModuleId(module);
+        let vis = &def.visibility;
         let def: ModuleDefId = match def.kind {
             raw::DefKind::Function(ast_id) => FunctionLoc {
                 container: container.into(),
@@ -761,7 +890,12 @@ where
             .into(),
         };
         self.def_collector.def_map.modules[self.module_id].scope.define_def(def);
-        self.def_collector.update(self.module_id, &[(name, def.into())])
+        let vis = self
+            .def_collector
+            .def_map
+            .resolve_visibility(self.def_collector.db, self.module_id, vis)
+            .unwrap_or(Visibility::Public);
+        self.def_collector.update(self.module_id, &[(name, PerNs::from_def(def, vis))], vis)
     }
 
     fn collect_derives(&mut self, attrs: &Attrs, def: &raw::DefData) {
@@ -776,11 +910,38 @@ where
                 let path = ModPath::from_tt_ident(ident);
 
                 let ast_id = AstId::new(self.file_id, def.kind.ast_id());
-                self.def_collector.unexpanded_attribute_macros.push((self.module_id, ast_id, path));
+                self.def_collector.unexpanded_attribute_macros.push((
+                    self.module_id,
+                    ast_id,
+                    path,
+                    ProcMacroKind::CustomDerive,
+                ));
             }
         }
     }
 
+    fn collect_attr_macros(&mut self, attrs: &Attrs, def: &raw::DefData) {
+        for attr in attrs.iter() {
+            if let Some(AttrInput::Literal(_)) = attr.input {
+                continue;
+            }
+            let is_builtin = attr
+                .path
+                .as_ident()
+                .map_or(false, |name| BUILTIN_ATTRIBUTES.contains(&name.to_string().as_str()));
+            if is_builtin {
+                continue;
+            }
+            let ast_id = AstId::new(self.file_id, def.kind.ast_id());
+            self.def_collector.unexpanded_attribute_macros.push((
+                self.module_id,
+                ast_id,
+                attr.path.clone(),
+                ProcMacroKind::Attr,
+            ));
+        }
+    }
+
     fn collect_macro(&mut self, mac: &raw::MacroData) {
         let ast_id = AstId::new(self.file_id, mac.ast_id);
 
@@ -804,7 +965,7 @@ where
         if is_macro_rules(&mac.path) {
             if let Some(name) = &mac.name {
                 let macro_id = MacroDefId {
-                    ast_id: Some(ast_id),
+                    ast_id: Some(ast_id.map(|it| it.upcast())),
                     krate: Some(self.def_collector.def_map.krate),
                     kind: MacroDefKind::Declarative,
                 };
@@ -845,6 +1006,27 @@ where
         });
     }
 
+    fn collect_macro_def(&mut self, mac: &raw::MacroDefData) {
+        let ast_id = AstId::new(self.file_id, mac.ast_id.upcast());
+        let macro_id = MacroDefId {
+            ast_id: Some(ast_id),
+            krate: Some(self.def_collector.def_map.krate),
+            kind: MacroDefKind::Declarative,
+        };
+        let vis = self
+            .def_collector
+            .def_map
+            .resolve_visibility(self.def_collector.db, self.module_id, &mac.visibility)
+            .unwrap_or(Visibility::Public);
+        // Unlike `macro_rules!`, `macro` items are not textually scoped: they
+        // are defined in the module scope like any other item.
+        self.def_collector.update(
+            self.module_id,
+            &[(mac.name.clone(), PerNs::macros(macro_id, vis))],
+            vis,
+        );
+    }
+
     fn import_all_legacy_macros(&mut self, module_id: LocalModuleId) {
         let macros = self.def_collector.def_map[module_id].scope.collect_legacy_macros();
         for (name, macro_) in macros {
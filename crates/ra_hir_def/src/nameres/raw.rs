@@ -16,12 +16,15 @@ use hir_expand::{
 use ra_arena::{impl_arena_id, Arena, RawId};
 use ra_prof::profile;
 use ra_syntax::{
-    ast::{self, AttrsOwner, NameOwner},
+    ast::{self, AttrsOwner, NameOwner, VisibilityOwner},
     AstNode,
 };
 use test_utils::tested_by;
 
-use crate::{attr::Attrs, db::DefDatabase, path::ModPath, FileAstId, HirFileId, InFile};
+use crate::{
+    attr::Attrs, db::DefDatabase, path::ModPath, visibility::RawVisibility, FileAstId, HirFileId,
+    InFile,
+};
 
 /// `RawItems` is a set of top-level items in a file (except for impls).
 ///
@@ -33,6 +36,7 @@ pub struct RawItems {
     imports: Arena<Import, ImportData>,
     defs: Arena<Def, DefData>,
     macros: Arena<Macro, MacroData>,
+    macro_defs: Arena<MacroDef, MacroDefData>,
     impls: Arena<Impl, ImplData>,
     /// items for top-level module
     items: Vec<RawItem>,
@@ -94,6 +98,13 @@ impl Index<Macro> for RawItems {
     }
 }
 
+impl Index<MacroDef> for RawItems {
+    type Output = MacroDefData;
+    fn index(&self, idx: MacroDef) -> &MacroDefData {
+        &self.macro_defs[idx]
+    }
+}
+
 impl Index<Impl> for RawItems {
     type Output = ImplData;
     fn index(&self, idx: Impl) -> &ImplData {
@@ -113,6 +124,7 @@ pub(super) enum RawItemKind {
     Import(Import),
     Def(Def),
     Macro(Macro),
+    MacroDef(MacroDef),
     Impl(Impl),
 }
 
@@ -122,8 +134,17 @@ impl_arena_id!(Module);
 
 #[derive(Debug, PartialEq, Eq)]
 pub(super) enum ModuleData {
-    Declaration { name: Name, ast_id: FileAstId<ast::Module> },
-    Definition { name: Name, ast_id: FileAstId<ast::Module>, items: Vec<RawItem> },
+    Declaration {
+        name: Name,
+        visibility: RawVisibility,
+        ast_id: FileAstId<ast::Module>,
+    },
+    Definition {
+        name: Name,
+        visibility: RawVisibility,
+        ast_id: FileAstId<ast::Module>,
+        items: Vec<RawItem>,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -138,6 +159,7 @@ pub struct ImportData {
     pub(super) is_prelude: bool,
     pub(super) is_extern_crate: bool,
     pub(super) is_macro_use: bool,
+    pub(super) visibility: RawVisibility,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -148,6 +170,7 @@ impl_arena_id!(Def);
 pub(super) struct DefData {
     pub(super) name: Name,
     pub(super) kind: DefKind,
+    pub(super) visibility: RawVisibility,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -190,6 +213,18 @@ pub(super) struct MacroData {
     pub(super) builtin: bool,
 }
 
+/// A macro 2.0 definition, `macro foo { .. }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct MacroDef(RawId);
+impl_arena_id!(MacroDef);
+
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct MacroDefData {
+    pub(super) ast_id: FileAstId<ast::MacroDef>,
+    pub(super) name: Name,
+    pub(super) visibility: RawVisibility,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(super) struct Impl(RawId);
 impl_arena_id!(Impl);
@@ -218,7 +253,7 @@ impl RawItemsCollector {
 
     fn add_item(&mut self, current_module: Option<Module>, item: ast::ModuleItem) {
         let attrs = self.parse_attrs(&item);
-        let (kind, name) = match item {
+        let (kind, name, visibility) = match item {
             ast::ModuleItem::Module(module) => {
                 self.add_module(current_module, module);
                 return;
@@ -235,52 +270,89 @@ impl RawItemsCollector {
                 self.add_impl(current_module, it);
                 return;
             }
+            ast::ModuleItem::MacroDef(it) => {
+                self.add_macro_def(current_module, it);
+                return;
+            }
+            ast::ModuleItem::ExternBlock(it) => {
+                self.add_extern_block(current_module, it);
+                return;
+            }
             ast::ModuleItem::StructDef(it) => {
                 let id = self.source_ast_id_map.ast_id(&it);
                 let name = it.name();
-                (DefKind::Struct(id), name)
+                (DefKind::Struct(id), name, it.visibility())
             }
             ast::ModuleItem::UnionDef(it) => {
                 let id = self.source_ast_id_map.ast_id(&it);
                 let name = it.name();
-                (DefKind::Union(id), name)
+                (DefKind::Union(id), name, it.visibility())
             }
             ast::ModuleItem::EnumDef(it) => {
-                (DefKind::Enum(self.source_ast_id_map.ast_id(&it)), it.name())
+                (DefKind::Enum(self.source_ast_id_map.ast_id(&it)), it.name(), it.visibility())
             }
             ast::ModuleItem::FnDef(it) => {
-                (DefKind::Function(self.source_ast_id_map.ast_id(&it)), it.name())
+                (DefKind::Function(self.source_ast_id_map.ast_id(&it)), it.name(), it.visibility())
             }
             ast::ModuleItem::TraitDef(it) => {
-                (DefKind::Trait(self.source_ast_id_map.ast_id(&it)), it.name())
+                (DefKind::Trait(self.source_ast_id_map.ast_id(&it)), it.name(), it.visibility())
             }
             ast::ModuleItem::TypeAliasDef(it) => {
-                (DefKind::TypeAlias(self.source_ast_id_map.ast_id(&it)), it.name())
+                (DefKind::TypeAlias(self.source_ast_id_map.ast_id(&it)), it.name(), it.visibility())
             }
             ast::ModuleItem::ConstDef(it) => {
-                (DefKind::Const(self.source_ast_id_map.ast_id(&it)), it.name())
+                (DefKind::Const(self.source_ast_id_map.ast_id(&it)), it.name(), it.visibility())
             }
             ast::ModuleItem::StaticDef(it) => {
-                (DefKind::Static(self.source_ast_id_map.ast_id(&it)), it.name())
+                (DefKind::Static(self.source_ast_id_map.ast_id(&it)), it.name(), it.visibility())
             }
         };
         if let Some(name) = name {
             let name = name.as_name();
-            let def = self.raw_items.defs.alloc(DefData { name, kind });
+            let visibility = RawVisibility::from_ast_with_hygiene(visibility, &self.hygiene);
+            let def = self.raw_items.defs.alloc(DefData { name, kind, visibility });
             self.push_item(current_module, attrs, RawItemKind::Def(def));
         }
     }
 
+    fn add_extern_block(&mut self, current_module: Option<Module>, block: ast::ExternBlock) {
+        let item_list = match block.extern_item_list() {
+            Some(it) => it,
+            None => return,
+        };
+        // Attributes of the block, like `#[cfg]`, apply to all of its items.
+        let block_attrs = self.parse_attrs(&block);
+        for item in item_list.extern_items() {
+            let attrs = block_attrs.merge(self.parse_attrs(&item));
+            let visibility = RawVisibility::from_ast_with_hygiene(item.visibility(), &self.hygiene);
+            let (kind, name) = match item {
+                ast::ExternItem::FnDef(it) => {
+                    (DefKind::Function(self.source_ast_id_map.ast_id(&it)), it.name())
+                }
+                ast::ExternItem::StaticDef(it) => {
+                    (DefKind::Static(self.source_ast_id_map.ast_id(&it)), it.name())
+                }
+            };
+            if let Some(name) = name {
+                let name = name.as_name();
+                let def = self.raw_items.defs.alloc(DefData { name, kind, visibility });
+                self.push_item(current_module, attrs, RawItemKind::Def(def));
+            }
+        }
+    }
+
     fn add_module(&mut self, current_module: Option<Module>, module: ast::Module) {
         let name = match module.name() {
             Some(it) => it.as_name(),
             None => return,
         };
         let attrs = self.parse_attrs(&module);
+        let visibility = RawVisibility::from_ast_with_hygiene(module.visibility(), &self.hygiene);
 
         let ast_id = self.source_ast_id_map.ast_id(&module);
         if module.has_semi() {
-            let item = self.raw_items.modules.alloc(ModuleData::Declaration { name, ast_id });
+            let item =
+                self.raw_items.modules.alloc(ModuleData::Declaration { name, visibility, ast_id });
             self.push_item(current_module, attrs, RawItemKind::Module(item));
             return;
         }
@@ -288,6 +360,7 @@ impl RawItemsCollector {
         if let Some(item_list) = module.item_list() {
             let item = self.raw_items.modules.alloc(ModuleData::Definition {
                 name,
+                visibility,
                 ast_id,
                 items: Vec::new(),
             });
@@ -302,6 +375,7 @@ impl RawItemsCollector {
         // FIXME: cfg_attr
         let is_prelude = use_item.has_atom_attr("prelude_import");
         let attrs = self.parse_attrs(&use_item);
+        let visibility = RawVisibility::from_ast_with_hygiene(use_item.visibility(), &self.hygiene);
 
         let mut buf = Vec::new();
         ModPath::expand_use_item(
@@ -315,6 +389,7 @@ impl RawItemsCollector {
                     is_prelude,
                     is_extern_crate: false,
                     is_macro_use: false,
+                    visibility: visibility.clone(),
                 };
                 buf.push(import_data);
             },
@@ -333,6 +408,8 @@ impl RawItemsCollector {
             let path = ModPath::from_name_ref(&name_ref);
             let alias = extern_crate.alias().and_then(|a| a.name()).map(|it| it.as_name());
             let attrs = self.parse_attrs(&extern_crate);
+            let visibility =
+                RawVisibility::from_ast_with_hygiene(extern_crate.visibility(), &self.hygiene);
             // FIXME: cfg_attr
             let is_macro_use = extern_crate.has_atom_attr("macro_use");
             let import_data = ImportData {
@@ -342,6 +419,7 @@ impl RawItemsCollector {
                 is_prelude: false,
                 is_extern_crate: true,
                 is_macro_use,
+                visibility,
             };
             self.push_import(current_module, attrs, import_data);
         }
@@ -367,6 +445,18 @@ impl RawItemsCollector {
         self.push_item(current_module, attrs, RawItemKind::Macro(m));
     }
 
+    fn add_macro_def(&mut self, current_module: Option<Module>, m: ast::MacroDef) {
+        let attrs = self.parse_attrs(&m);
+        let name = match m.name() {
+            Some(it) => it.as_name(),
+            None => return,
+        };
+        let visibility = RawVisibility::from_ast_with_hygiene(m.visibility(), &self.hygiene);
+        let ast_id = self.source_ast_id_map.ast_id(&m);
+        let m = self.raw_items.macro_defs.alloc(MacroDefData { ast_id, name, visibility });
+        self.push_item(current_module, attrs, RawItemKind::MacroDef(m));
+    }
+
     fn add_impl(&mut self, current_module: Option<Module>, imp: ast::ImplBlock) {
         let attrs = self.parse_attrs(&imp);
         let ast_id = self.source_ast_id_map.ast_id(&imp);
@@ -3,6 +3,7 @@ mod incremental;
 mod macros;
 mod mod_resolution;
 mod primitives;
+mod visibility;
 
 use std::sync::Arc;
 
@@ -83,7 +84,7 @@ fn crate_def_map_smoke_test() {
             not_to_be: u8,
         }
 
-        enum E { V }
+        pub enum E { V }
         ",
     );
     assert_snapshot!(map, @r###"
@@ -270,14 +271,14 @@ fn edition_2015_imports() {
         mod bar;
 
         //- /bar.rs
-        struct Bar;
+        pub struct Bar;
 
         //- /foo.rs
         use bar::Bar;
         use other_crate::FromLib;
 
         //- /lib.rs crate:other_crate edition:2018
-        struct FromLib;
+        pub struct FromLib;
         ",
     );
 
@@ -353,7 +354,7 @@ fn extern_crate_rename() {
         use alloc_crate::Arc;
 
         //- /lib.rs crate:alloc
-        struct Arc;
+        pub struct Arc;
         ",
     );
 
@@ -381,7 +382,7 @@ fn extern_crate_rename_2015_edition() {
         use alloc_crate::Arc;
 
         //- /lib.rs crate:alloc
-        struct Arc;
+        pub struct Arc;
         ",
     );
 
@@ -465,6 +466,58 @@ fn values_dont_shadow_extern_crates() {
     "###);
 }
 
+#[test]
+fn extern_block_items() {
+    let map = def_map(
+        r#"
+        //- /lib.rs
+        mod ffi;
+        use ffi::{abs, errno};
+
+        //- /ffi.rs
+        extern "C" {
+            pub fn abs(x: i32) -> i32;
+            pub static errno: i32;
+            fn private_fn();
+        }
+        "#,
+    );
+
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮abs: v
+        ⋮errno: v
+        ⋮ffi: t
+        ⋮
+        ⋮crate::ffi
+        ⋮abs: v
+        ⋮errno: v
+        ⋮private_fn: v
+    "###);
+}
+
+#[test]
+fn extern_block_attrs_apply_to_items() {
+    let map = def_map(
+        r#"
+        //- /lib.rs
+        #[cfg(windows)]
+        extern "C" {
+            pub fn on_windows();
+        }
+        #[cfg(not(windows))]
+        extern "C" {
+            pub fn elsewhere();
+        }
+        "#,
+    );
+
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮elsewhere: v
+    "###);
+}
+
 #[test]
 fn std_prelude_takes_precedence_above_core_prelude() {
     let map = def_map(
@@ -483,7 +536,7 @@ fn std_prelude_takes_precedence_above_core_prelude() {
         //- /core.rs crate:core
         #[prelude_import]
         pub use self::prelude::*;
-        mod prelude {
+        pub mod prelude {
             pub struct Bar;
         }
         "#,
@@ -1,3 +1,8 @@
+use ra_cfg::CfgOptions;
+use ra_db::{CrateGraph, Edition, Env, FileId, ProcMacro, ProcMacroKind};
+
+use crate::nameres::diagnostics::DefDiagnostic;
+
 use super::*;
 
 #[test]
@@ -547,8 +552,8 @@ fn macro_dollar_crate_is_correct_in_item() {
             }
         }
 
-        struct Bar;
-        struct Baz;
+        pub struct Bar;
+        pub struct Baz;
         ",
     );
     assert_snapshot!(map, @r###"
@@ -601,6 +606,72 @@ fn macro_dollar_crate_is_correct_in_indirect_deps() {
     "###);
 }
 
+#[test]
+fn macro2_is_module_scoped() {
+    let map = def_map(
+        "
+        //- /main.rs
+        m::foo!(ByPath);
+
+        use m::foo;
+        foo!(ByImport);
+
+        mod m {
+            foo!(BeforeDefinition);
+            bar!(MultipleRules);
+            bar!();
+
+            pub macro foo($x:ident) {
+                struct $x;
+            }
+
+            macro bar {
+                ($x:ident) => { struct $x; },
+                () => { struct Empty; }
+            }
+        }
+        ",
+    );
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮ByImport: t v
+        ⋮ByPath: t v
+        ⋮foo: m
+        ⋮m: t
+        ⋮
+        ⋮crate::m
+        ⋮BeforeDefinition: t v
+        ⋮Empty: t v
+        ⋮MultipleRules: t v
+        ⋮bar: m
+        ⋮foo: m
+    "###);
+}
+
+#[test]
+fn macro2_crate_is_def_site() {
+    let map = def_map(
+        "
+        //- /main.rs crate:main deps:foo
+        struct Local;
+
+        foo::make!();
+
+        //- /lib.rs crate:foo
+        pub struct Foo;
+
+        pub macro make() {
+            use crate::Foo;
+        }
+        ",
+    );
+    assert_snapshot!(map, @r###"
+        ⋮crate
+        ⋮Foo: t v
+        ⋮Local: t v
+    "###);
+}
+
 #[test]
 fn expand_derive() {
     let map = compute_crate_def_map(
@@ -624,3 +695,184 @@ fn expand_multiple_derive() {
     );
     assert_eq!(map.modules[map.root].scope.impls().len(), 2);
 }
+
+#[test]
+fn expand_proc_macro_derive() {
+    /// A stand-in for the proc-macro server, which expands any derive on
+    /// `struct $name` to `impl Marker for $name {}`.
+    #[derive(Debug)]
+    struct MarkerExpander;
+
+    impl tt::TokenExpander for MarkerExpander {
+        fn expand(
+            &self,
+            subtree: &tt::Subtree,
+            _attrs: Option<&tt::Subtree>,
+        ) -> Result<tt::Subtree, tt::ExpansionError> {
+            let name = subtree
+                .token_trees
+                .iter()
+                .filter_map(|tt| match tt {
+                    tt::TokenTree::Leaf(tt::Leaf::Ident(it)) => Some(it.text.as_str()),
+                    _ => None,
+                })
+                .skip_while(|it| *it != "struct")
+                .nth(1)
+                .ok_or_else(|| tt::ExpansionError::ExpansionError("expected a struct".into()))?;
+            let expansion = format!("impl Marker for {} {{}}", name);
+            let file = ra_syntax::SourceFile::parse(&expansion).tree();
+            let (tt, _) = mbe::syntax_node_to_token_tree(ra_syntax::AstNode::syntax(&file))
+                .ok_or_else(|| tt::ExpansionError::Unknown("invalid expansion".into()))?;
+            Ok(tt)
+        }
+    }
+
+    let mut db = TestDB::with_files(
+        "
+        //- /main.rs crate:main deps:toy
+        use toy::{Marker, Traced};
+
+        #[derive(Marker)]
+        struct Foo;
+
+        #[derive(Traced)]
+        struct Bar;
+
+        //- /lib.rs crate:toy
+        ",
+    );
+
+    // Register the macros of `toy`. `Traced` is an attribute macro, so it
+    // must not be usable as a derive.
+    let expander: Arc<dyn tt::TokenExpander> = Arc::new(MarkerExpander);
+    let proc_macro =
+        |name: &str, kind| ProcMacro { name: name.into(), kind, expander: expander.clone() };
+    let mut crate_graph = CrateGraph::default();
+    let main = crate_graph.add_crate_root(
+        FileId(0),
+        Edition::Edition2018,
+        CfgOptions::default(),
+        Env::default(),
+        Vec::new(),
+    );
+    let toy = crate_graph.add_crate_root(
+        FileId(1),
+        Edition::Edition2018,
+        CfgOptions::default(),
+        Env::default(),
+        vec![
+            proc_macro("Marker", ProcMacroKind::CustomDerive),
+            proc_macro("Traced", ProcMacroKind::Attr),
+        ],
+    );
+    crate_graph.add_dep(main, "toy".into(), toy).unwrap();
+    db.set_crate_graph(Arc::new(crate_graph));
+
+    let map = db.crate_def_map(main);
+    assert_eq!(map.modules[map.root].scope.impls().len(), 1);
+}
+
+#[test]
+fn expand_proc_macro_attribute() {
+    /// A stand-in for the proc-macro server, which expands `#[rename(Name)]`
+    /// on any item to `struct Name;`.
+    #[derive(Debug)]
+    struct RenameExpander;
+
+    impl tt::TokenExpander for RenameExpander {
+        fn expand(
+            &self,
+            subtree: &tt::Subtree,
+            attrs: Option<&tt::Subtree>,
+        ) -> Result<tt::Subtree, tt::ExpansionError> {
+            if subtree.to_string().contains("rename") {
+                return Err(tt::ExpansionError::ExpansionError("saw the attribute".into()));
+            }
+            let name = match attrs.map(|it| it.token_trees.as_slice()) {
+                Some([tt::TokenTree::Leaf(tt::Leaf::Ident(it))]) => it.text.clone(),
+                _ => return Err(tt::ExpansionError::ExpansionError("expected a name".into())),
+            };
+            let expansion = format!("struct {};", name);
+            let file = ra_syntax::SourceFile::parse(&expansion).tree();
+            let (tt, _) = mbe::syntax_node_to_token_tree(ra_syntax::AstNode::syntax(&file))
+                .ok_or_else(|| tt::ExpansionError::Unknown("invalid expansion".into()))?;
+            Ok(tt)
+        }
+    }
+
+    let mut db = TestDB::with_files(
+        "
+        //- /main.rs crate:main deps:toy
+        #[toy::rename(Renamed)]
+        fn foo() {}
+
+        //- /lib.rs crate:toy
+        ",
+    );
+
+    let mut crate_graph = CrateGraph::default();
+    let main = crate_graph.add_crate_root(
+        FileId(0),
+        Edition::Edition2018,
+        CfgOptions::default(),
+        Env::default(),
+        Vec::new(),
+    );
+    let toy = crate_graph.add_crate_root(
+        FileId(1),
+        Edition::Edition2018,
+        CfgOptions::default(),
+        Env::default(),
+        vec![ProcMacro {
+            name: "rename".into(),
+            kind: ProcMacroKind::Attr,
+            expander: Arc::new(RenameExpander),
+        }],
+    );
+    crate_graph.add_dep(main, "toy".into(), toy).unwrap();
+    db.set_crate_graph(Arc::new(crate_graph));
+
+    let map = db.crate_def_map(main);
+    assert_snapshot!(render_crate_def_map(&map), @r###"
+        ⋮crate
+        ⋮Renamed: t v
+        ⋮foo: v
+    "###);
+}
+
+#[test]
+fn unresolved_macro_calls_into_opaque_crates_are_not_reported() {
+    let db = TestDB::with_files(
+        r"
+        //- /main.rs crate:main deps:empty,derives,other
+        foo!();
+        other::bar!();
+        empty::baz!();
+        derives::quux!();
+
+        //- /empty.rs crate:empty
+
+        //- /derives.rs crate:derives deps:proc_macro
+        pub fn quux() {}
+
+        //- /proc_macro.rs crate:proc_macro
+        pub struct TokenStream;
+
+        //- /other.rs crate:other
+        pub fn bar() {}
+        ",
+    );
+    let crate_graph = db.crate_graph();
+    let main = crate_graph.iter().find(|&it| crate_graph.crate_root(it) == FileId(0)).unwrap();
+    let map = db.crate_def_map(main);
+
+    let unresolved = map
+        .diagnostics
+        .iter()
+        .filter(|it| match it {
+            DefDiagnostic::UnresolvedMacroCall { .. } => true,
+            _ => false,
+        })
+        .count();
+    assert_eq!(unresolved, 2);
+}
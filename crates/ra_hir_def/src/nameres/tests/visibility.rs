@@ -0,0 +1,147 @@
+use super::*;
+
+#[test]
+fn private_item_import_is_unresolved() {
+    let map = def_map(
+        "
+        //- /lib.rs
+        mod m {
+            struct Private;
+            pub struct Public;
+        }
+        use m::Private;
+        use m::Public;
+        ",
+    );
+    assert_snapshot!(map, @r###"
+   ⋮crate
+   ⋮Private: _
+   ⋮Public: t v
+   ⋮m: t
+   ⋮
+   ⋮crate::m
+   ⋮Private: t v
+   ⋮Public: t v
+    "###
+    );
+}
+
+#[test]
+fn glob_import_skips_private_items() {
+    let map = def_map(
+        "
+        //- /lib.rs
+        mod m {
+            struct Private;
+            pub struct Public;
+            pub(crate) fn crate_visible() {}
+        }
+        use m::*;
+        ",
+    );
+    assert_snapshot!(map, @r###"
+   ⋮crate
+   ⋮Public: t v
+   ⋮crate_visible: v
+   ⋮m: t
+   ⋮
+   ⋮crate::m
+   ⋮Private: t v
+   ⋮Public: t v
+   ⋮crate_visible: v
+    "###
+    );
+}
+
+#[test]
+fn child_module_sees_private_glob_imports_of_parent() {
+    let map = def_map(
+        "
+        //- /main.rs crate:main deps:dep
+        use dep::*;
+        mod child {
+            use super::Foo;
+        }
+
+        //- /lib.rs crate:dep
+        pub struct Foo;
+        struct Private;
+        ",
+    );
+    assert_snapshot!(map, @r###"
+   ⋮crate
+   ⋮Foo: t v
+   ⋮child: t
+   ⋮
+   ⋮crate::child
+   ⋮Foo: t v
+    "###
+    );
+}
+
+#[test]
+fn restricted_visibilities() {
+    let map = def_map(
+        "
+        //- /lib.rs
+        mod a {
+            pub mod b {
+                pub(in crate::a) struct InA;
+                pub(super) struct InSuper;
+                pub(crate) struct InCrate;
+                struct Private;
+            }
+            use self::b::{InA, InSuper, InCrate, Private};
+        }
+        use a::b::{InA, InSuper, InCrate, Private};
+        ",
+    );
+    assert_snapshot!(map, @r###"
+   ⋮crate
+   ⋮InA: _
+   ⋮InCrate: t v
+   ⋮InSuper: _
+   ⋮Private: _
+   ⋮a: t
+   ⋮
+   ⋮crate::a
+   ⋮InA: t v
+   ⋮InCrate: t v
+   ⋮InSuper: t v
+   ⋮Private: _
+   ⋮b: t
+   ⋮
+   ⋮crate::a::b
+   ⋮InA: t v
+   ⋮InCrate: t v
+   ⋮InSuper: t v
+   ⋮Private: t v
+    "###
+    );
+}
+
+#[test]
+fn private_items_of_other_crate_are_unresolved() {
+    let map = def_map(
+        "
+        //- /main.rs crate:main deps:dep
+        use dep::{CrateVisible, Private, Public, private_mod};
+
+        //- /lib.rs crate:dep
+        pub struct Public;
+        struct Private;
+        pub(crate) struct CrateVisible;
+        mod private_mod {
+            pub struct Inner;
+        }
+        ",
+    );
+    assert_snapshot!(map, @r###"
+   ⋮crate
+   ⋮CrateVisible: _
+   ⋮Private: _
+   ⋮Public: t v
+   ⋮private_mod: _
+    "###
+    );
+}
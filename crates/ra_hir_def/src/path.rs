@@ -10,7 +10,10 @@ use hir_expand::{
 use ra_db::CrateId;
 use ra_syntax::ast;
 
-use crate::{type_ref::TypeRef, InFile};
+use crate::{
+    type_ref::{LifetimeRef, TypeRef},
+    InFile,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ModPath {
@@ -109,7 +112,16 @@ pub struct GenericArgs {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GenericArg {
     Type(TypeRef),
-    // or lifetime...
+    Lifetime(LifetimeRef),
+}
+
+impl GenericArg {
+    pub fn as_type(&self) -> Option<&TypeRef> {
+        match self {
+            GenericArg::Type(it) => Some(it),
+            GenericArg::Lifetime(_) => None,
+        }
+    }
 }
 
 impl Path {
@@ -13,7 +13,7 @@ use ra_syntax::ast::{self, AstNode, TypeAscriptionOwner};
 
 use crate::{
     path::{GenericArg, GenericArgs, ModPath, Path, PathKind},
-    type_ref::TypeRef,
+    type_ref::{LifetimeRef, TypeRef},
 };
 
 pub(super) use lower_use::lower_use_tree;
@@ -93,7 +93,10 @@ pub(super) fn lower_path(mut path: ast::Path, hygiene: &Hygiene) -> Option<Path>
                 }
             }
             ast::PathSegmentKind::CrateKw => {
-                kind = PathKind::Crate;
+                kind = match hygiene.crate_kw_to_crate(&segment) {
+                    Some(crate_id) => PathKind::DollarCrate(crate_id),
+                    None => PathKind::Crate,
+                };
                 break;
             }
             ast::PathSegmentKind::SelfKw => {
@@ -129,11 +132,15 @@ pub(super) fn lower_path(mut path: ast::Path, hygiene: &Hygiene) -> Option<Path>
 
 pub(super) fn lower_generic_args(node: ast::TypeArgList) -> Option<GenericArgs> {
     let mut args = Vec::new();
+    for lifetime_arg in node.lifetime_args() {
+        if let Some(lifetime) = lifetime_arg.lifetime_token() {
+            args.push(GenericArg::Lifetime(LifetimeRef::from_token(lifetime)));
+        }
+    }
     for type_arg in node.type_args() {
         let type_ref = TypeRef::from_ast_opt(type_arg.type_ref());
         args.push(GenericArg::Type(type_ref));
     }
-    // lifetimes ignored for now
     let mut bindings = Vec::new();
     for assoc_type_arg in node.assoc_type_args() {
         if let Some(name_ref) = assoc_type_arg.name_ref() {
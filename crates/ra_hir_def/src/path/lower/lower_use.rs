@@ -95,7 +95,11 @@ fn convert_path(prefix: Option<ModPath>, path: ast::Path, hygiene: &Hygiene) ->
             if prefix.is_some() {
                 return None;
             }
-            ModPath::from_simple_segments(PathKind::Crate, iter::empty())
+            let kind = match hygiene.crate_kw_to_crate(&segment) {
+                Some(crate_id) => PathKind::DollarCrate(crate_id),
+                None => PathKind::Crate,
+            };
+            ModPath::from_simple_segments(kind, iter::empty())
         }
         ast::PathSegmentKind::SelfKw => {
             if prefix.is_some() {
@@ -1,9 +1,16 @@
 //! HIR for references to types. Paths in these are not yet resolved. They can
 //! be directly created from an ast::TypeRef, without further queries.
 
-use ra_syntax::ast::{self, TypeAscriptionOwner, TypeBoundsOwner};
+use hir_expand::name::Name;
+use ra_syntax::{
+    ast::{self, TypeAscriptionOwner, TypeBoundsOwner},
+    SyntaxToken,
+};
 
-use crate::path::Path;
+use crate::{
+    expr::{ArithOp, BinaryOp},
+    path::Path,
+};
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Mutability {
@@ -43,8 +50,8 @@ pub enum TypeRef {
     Tuple(Vec<TypeRef>),
     Path(Path),
     RawPtr(Box<TypeRef>, Mutability),
-    Reference(Box<TypeRef>, Mutability),
-    Array(Box<TypeRef> /*, Expr*/),
+    Reference(Box<TypeRef>, Option<LifetimeRef>, Mutability),
+    Array(Box<TypeRef>, ConstRef),
     Slice(Box<TypeRef>),
     /// A fn pointer. Last element of the vector is the return type.
     Fn(Vec<TypeRef>),
@@ -54,11 +61,33 @@ pub enum TypeRef {
     Error,
 }
 
+/// A lifetime, like `'a` or `'static`, as written in a type or a bound.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct LifetimeRef {
+    pub name: Name,
+}
+
+impl LifetimeRef {
+    pub(crate) fn from_token(token: SyntaxToken) -> Self {
+        LifetimeRef { name: Name::new_lifetime(&token) }
+    }
+}
+
+/// A constant in a type, like the length in `[u8; N * 2]`. Only the
+/// expressions the const evaluator understands are kept.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ConstRef {
+    Literal(u128),
+    Path(Path),
+    BinaryOp { lhs: Box<ConstRef>, rhs: Box<ConstRef>, op: ArithOp },
+    Error,
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum TypeBound {
     Path(Path),
     // also for<> bounds
-    // also Lifetimes
+    Lifetime(LifetimeRef),
     Error,
 }
 
@@ -80,16 +109,18 @@ impl TypeRef {
                 let mutability = Mutability::from_mutable(inner.is_mut());
                 TypeRef::RawPtr(Box::new(inner_ty), mutability)
             }
-            ast::TypeRef::ArrayType(inner) => {
-                TypeRef::Array(Box::new(TypeRef::from_ast_opt(inner.type_ref())))
-            }
+            ast::TypeRef::ArrayType(inner) => TypeRef::Array(
+                Box::new(TypeRef::from_ast_opt(inner.type_ref())),
+                ConstRef::from_ast_opt(inner.expr()),
+            ),
             ast::TypeRef::SliceType(inner) => {
                 TypeRef::Slice(Box::new(TypeRef::from_ast_opt(inner.type_ref())))
             }
             ast::TypeRef::ReferenceType(inner) => {
                 let inner_ty = TypeRef::from_ast_opt(inner.type_ref());
+                let lifetime = inner.lifetime_token().map(LifetimeRef::from_token);
                 let mutability = Mutability::from_mutable(inner.is_mut());
-                TypeRef::Reference(Box::new(inner_ty), mutability)
+                TypeRef::Reference(Box::new(inner_ty), lifetime, mutability)
             }
             ast::TypeRef::PlaceholderType(_inner) => TypeRef::Placeholder,
             ast::TypeRef::FnPointerType(inner) => {
@@ -126,6 +157,41 @@ impl TypeRef {
     }
 }
 
+impl ConstRef {
+    /// Converts an `ast::Expr` to a `hir::ConstRef`.
+    pub(crate) fn from_ast(node: ast::Expr) -> Self {
+        match node {
+            ast::Expr::Literal(lit) => {
+                lit.int_value().map(ConstRef::Literal).unwrap_or(ConstRef::Error)
+            }
+            ast::Expr::ParenExpr(inner) => ConstRef::from_ast_opt(inner.expr()),
+            ast::Expr::CastExpr(inner) => ConstRef::from_ast_opt(inner.expr()),
+            ast::Expr::PathExpr(inner) => {
+                // FIXME: Use `Path::from_src`
+                inner.path().and_then(Path::from_ast).map(ConstRef::Path).unwrap_or(ConstRef::Error)
+            }
+            ast::Expr::BinExpr(inner) => {
+                let op = match inner.op_kind().map(BinaryOp::from) {
+                    Some(BinaryOp::ArithOp(op)) => op,
+                    _ => return ConstRef::Error,
+                };
+                let lhs = ConstRef::from_ast_opt(inner.lhs());
+                let rhs = ConstRef::from_ast_opt(inner.rhs());
+                ConstRef::BinaryOp { lhs: Box::new(lhs), rhs: Box::new(rhs), op }
+            }
+            _ => ConstRef::Error,
+        }
+    }
+
+    pub(crate) fn from_ast_opt(node: Option<ast::Expr>) -> Self {
+        if let Some(node) = node {
+            ConstRef::from_ast(node)
+        } else {
+            ConstRef::Error
+        }
+    }
+}
+
 pub(crate) fn type_bounds_from_ast(type_bounds_opt: Option<ast::TypeBoundList>) -> Vec<TypeBound> {
     if let Some(type_bounds) = type_bounds_opt {
         type_bounds.bounds().map(TypeBound::from_ast).collect()
@@ -149,7 +215,10 @@ impl TypeBound {
                 };
                 TypeBound::Path(path)
             }
-            ast::TypeBoundKind::ForType(_) | ast::TypeBoundKind::Lifetime(_) => TypeBound::Error,
+            ast::TypeBoundKind::Lifetime(lifetime) => {
+                TypeBound::Lifetime(LifetimeRef::from_token(lifetime))
+            }
+            ast::TypeBoundKind::ForType(_) => TypeBound::Error,
         }
     }
 
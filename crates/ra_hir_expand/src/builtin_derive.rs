@@ -68,7 +68,7 @@ fn parse_adt(tt: &tt::Subtree) -> Result<BasicAdtInfo, mbe::ExpandError> {
     })?;
     let item = macro_items.items().next().ok_or_else(|| {
         debug!("no module item parsed");
-        mbe::ExpandError::NoMatchingRule
+        mbe::ExpandError::NoMatchingRule(None)
     })?;
     let node = item.syntax();
     let (name, params) = match_ast! {
@@ -84,7 +84,7 @@ fn parse_adt(tt: &tt::Subtree) -> Result<BasicAdtInfo, mbe::ExpandError> {
     };
     let name = name.ok_or_else(|| {
         debug!("parsed item has no name");
-        mbe::ExpandError::NoMatchingRule
+        mbe::ExpandError::NoMatchingRule(None)
     })?;
     let name_token_id = token_map.token_by_range(name.syntax().text_range()).ok_or_else(|| {
         debug!("name token not found");
@@ -1,9 +1,12 @@
 //! Builtin macro
+use std::path::Path;
+
 use crate::db::AstDatabase;
 use crate::{
-    ast::{self, AstNode},
+    ast::{self, AstNode, AstToken},
     name, AstId, CrateId, HirFileId, MacroCallId, MacroDefId, MacroDefKind, TextUnit,
 };
+use ra_db::{FileId, RelativePath};
 
 use crate::quote;
 
@@ -42,7 +45,8 @@ macro_rules! register_builtin {
         ) -> Option<MacroDefId> {
             let kind = BuiltinFnLikeExpander::by_name(ident)?;
 
-            Some(MacroDefId { krate: Some(krate), ast_id: Some(ast_id), kind: MacroDefKind::BuiltIn(kind) })
+            let ast_id = Some(ast_id.map(|it| it.upcast()));
+            Some(MacroDefId { krate: Some(krate), ast_id, kind: MacroDefKind::BuiltIn(kind) })
         }
     };
 }
@@ -56,11 +60,23 @@ register_builtin! {
     (format_args, FormatArgs) => format_args_expand,
     (env, Env) => env_expand,
     (option_env, OptionEnv) => option_env_expand,
+    (concat, Concat) => concat_expand,
+    (include, Include) => include_expand,
+    (include_str, IncludeStr) => include_str_expand,
+    (include_bytes, IncludeBytes) => include_bytes_expand,
     // format_args_nl only differs in that it adds a newline in the end,
     // so we use the same stub expansion for now
     (format_args_nl, FormatArgsNl) => format_args_expand
 }
 
+impl BuiltinFnLikeExpander {
+    /// `include!` expands to the tokens of another file rather than to tokens
+    /// of its call, see `ExpansionInfo`.
+    pub(crate) fn is_include(self) -> bool {
+        self == BuiltinFnLikeExpander::Include
+    }
+}
+
 fn to_line_number(db: &dyn AstDatabase, file: HirFileId, pos: TextUnit) -> usize {
     // FIXME: Use expansion info
     let file_id = file.original_file(db);
@@ -127,28 +143,201 @@ fn stringify_expand(
     Ok(expanded)
 }
 
+fn unquote_str(lit: &tt::Literal) -> Option<String> {
+    let lit = ast::make::tokens::literal(&lit.to_string());
+    let token = ast::String::cast(lit)?;
+    token.value()
+}
+
+/// Extracts the variable name from the arguments of `env!` / `option_env!`.
+fn parse_env_var_name(tt: &tt::Subtree) -> Option<String> {
+    match tt.token_trees.get(0)? {
+        tt::TokenTree::Leaf(tt::Leaf::Literal(it)) => unquote_str(it),
+        _ => None,
+    }
+}
+
+fn get_env_inner(db: &dyn AstDatabase, arg_id: MacroCallId, key: &str) -> Option<String> {
+    // The environment belongs to the crate calling the macro, not to the one
+    // defining it (usually `std`).
+    let call_site = arg_id.as_file().original_file(db);
+    let krate = *db.relevant_crates(call_site).get(0)?;
+    db.crate_graph().env(krate).get(key)
+}
+
 fn env_expand(
-    _db: &dyn AstDatabase,
-    _id: MacroCallId,
-    _tt: &tt::Subtree,
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
 ) -> Result<tt::Subtree, mbe::ExpandError> {
-    // dummy implementation for type-checking purposes
-    let expanded = quote! { "" };
+    let key = parse_env_var_name(tt).ok_or_else(|| mbe::ExpandError::ConversionError)?;
+
+    // FIXME: rustc reports an error for undefined variables. We return a
+    // placeholder instead so that type inference still sees a `&str`. It must
+    // not be empty: `include!(concat!(env!("OUT_DIR"), "/foo.rs"))` would
+    // otherwise resolve to an unrelated `/foo.rs`.
+    let s = get_env_inner(db, id, &key).unwrap_or_else(|| "__RA_UNIMPLEMENTED__".to_string());
+    let expanded = quote! { #s };
 
     Ok(expanded)
 }
 
 fn option_env_expand(
-    _db: &dyn AstDatabase,
-    _id: MacroCallId,
-    _tt: &tt::Subtree,
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
 ) -> Result<tt::Subtree, mbe::ExpandError> {
-    // dummy implementation for type-checking purposes
-    let expanded = quote! { std::option::Option::None::<&str> };
+    let key = parse_env_var_name(tt).ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    let expanded = match get_env_inner(db, id, &key) {
+        None => quote! { std::option::Option::None::<&str> },
+        Some(s) => quote! { std::option::Option::Some(#s) },
+    };
 
     Ok(expanded)
 }
 
+/// Evaluates a string-valued macro argument, like the path of `include!`.
+///
+/// Besides plain string literals, this eagerly expands nested `concat!` and
+/// `env!` calls, which is what `include!(concat!(env!("OUT_DIR"), "/x.rs"))`
+/// needs.
+fn eval_str_arg(db: &dyn AstDatabase, id: MacroCallId, tokens: &[tt::TokenTree]) -> Option<String> {
+    use tt::{Leaf, TokenTree};
+
+    let (name, args) = match tokens {
+        [TokenTree::Leaf(Leaf::Literal(lit))] => return unquote_str(lit),
+        [TokenTree::Leaf(Leaf::Ident(name)), TokenTree::Leaf(Leaf::Punct(bang)), args]
+            if bang.char == '!' =>
+        {
+            (name, args)
+        }
+        _ => return None,
+    };
+    let args = match args {
+        TokenTree::Subtree(it) => it,
+        _ => return None,
+    };
+    match name.text.as_str() {
+        "concat" => concat_args(db, id, args),
+        "env" => get_env_inner(db, id, &parse_env_var_name(args)?),
+        _ => None,
+    }
+}
+
+/// Folds the comma separated arguments of `concat!` into a single string.
+fn concat_args(db: &dyn AstDatabase, id: MacroCallId, tt: &tt::Subtree) -> Option<String> {
+    let mut text = String::new();
+    for arg in tt.token_trees.split(|it| match it {
+        tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) => punct.char == ',',
+        _ => false,
+    }) {
+        match arg {
+            [] => (),
+            [tt::TokenTree::Leaf(tt::Leaf::Literal(lit))] => text.push_str(&concat_literal(lit)?),
+            [tt::TokenTree::Leaf(tt::Leaf::Ident(ident))]
+                if ident.text == "true" || ident.text == "false" =>
+            {
+                text.push_str(&ident.text)
+            }
+            _ => text.push_str(&eval_str_arg(db, id, arg)?),
+        }
+    }
+    Some(text)
+}
+
+/// The text a literal contributes to `concat!`: strings and chars are
+/// unescaped, numbers lose their suffix.
+fn concat_literal(lit: &tt::Literal) -> Option<String> {
+    let token = ast::make::tokens::literal(&lit.to_string());
+    if let Some(it) = ast::String::cast(token.clone()) {
+        return it.value();
+    }
+    if let Some(it) = ast::RawString::cast(token.clone()) {
+        return it.value();
+    }
+    if let Some(it) = ast::Char::cast(token.clone()) {
+        return it.value().map(|it| it.to_string());
+    }
+    let suffix = match ast::Literal::cast(token.parent())?.kind() {
+        ast::LiteralKind::IntNumber { suffix } | ast::LiteralKind::FloatNumber { suffix } => suffix,
+        // Byte and byte string literals are rejected by rustc as well.
+        _ => return None,
+    };
+    let text = token.text().as_str();
+    Some(text[..text.len() - suffix.map_or(0, |it| it.len())].to_string())
+}
+
+fn concat_expand(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    let text = concat_args(db, id, tt).ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    Ok(quote!(#text))
+}
+
+/// Resolves the path argument of `include!`-like macros. Relative paths are
+/// resolved relative to the file containing the call, absolute ones (most
+/// notably those under `OUT_DIR`) through the extern roots of the crate.
+fn relative_file(db: &dyn AstDatabase, call_id: MacroCallId, path: &str) -> Option<FileId> {
+    let call_site = call_id.as_file().original_file(db);
+    let krate = *db.relevant_crates(call_site).get(0)?;
+    if let Some((source_root, path)) = db.crate_graph().extern_path(krate, Path::new(path)) {
+        return db.resolve_extern_path(source_root, &path);
+    }
+    db.resolve_relative_path(call_site, RelativePath::new(path))
+}
+
+pub(crate) fn included_file(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> Result<FileId, mbe::ExpandError> {
+    let path =
+        eval_str_arg(db, id, &tt.token_trees).ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    relative_file(db, id, &path).ok_or_else(|| mbe::ExpandError::ConversionError)
+}
+
+fn include_expand(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    let file_id = included_file(db, id, tt)?;
+    // The token ids refer to the included file, `ExpansionInfo` maps them
+    // back into it.
+    let (subtree, _token_map) = mbe::syntax_node_to_token_tree(&db.parse(file_id).syntax_node())
+        .ok_or_else(|| mbe::ExpandError::ConversionError)?;
+    Ok(subtree)
+}
+
+fn include_str_expand(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    // A missing file still yields a `&str`, which is all type inference needs.
+    let text = match included_file(db, id, tt) {
+        Ok(file_id) => db.file_text(file_id).to_string(),
+        Err(_) => String::new(),
+    };
+    Ok(quote!(#text))
+}
+
+fn include_bytes_expand(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    tt: &tt::Subtree,
+) -> Result<tt::Subtree, mbe::ExpandError> {
+    // We only care about the type here, so the contents are not spelled out.
+    let len = match included_file(db, id, tt) {
+        Ok(file_id) => db.file_text(file_id).len(),
+        Err(_) => 0,
+    };
+    let zero = tt::Literal { text: "0u8".into(), id: tt::TokenId::unspecified() };
+    Ok(quote! { &[#zero; #len] })
+}
+
 fn to_col_number(db: &dyn AstDatabase, file: HirFileId, pos: TextUnit) -> usize {
     // FIXME: Use expansion info
     let file_id = file.original_file(db);
@@ -261,7 +450,7 @@ fn format_args_expand(
         args.push(current);
     }
     if args.is_empty() {
-        return Err(mbe::ExpandError::NoMatchingRule);
+        return Err(mbe::ExpandError::NoMatchingRule(None));
     }
     let _format_string = args.remove(0);
     let arg_tts = args.into_iter().flat_map(|arg| {
@@ -277,33 +466,57 @@ fn format_args_expand(
 mod tests {
     use super::*;
     use crate::{name::AsName, test_db::TestDB, MacroCallKind, MacroCallLoc};
-    use ra_db::{fixture::WithFixture, SourceDatabase};
+    use ra_db::{fixture::WithFixture, FileId, SourceDatabase};
     use ra_syntax::ast::NameOwner;
 
     fn expand_builtin_macro(s: &str) -> String {
         let (db, file_id) = TestDB::with_single_file(&s);
-        let parsed = db.parse(file_id);
-        let macro_calls: Vec<_> =
-            parsed.syntax_node().descendants().filter_map(|it| ast::MacroCall::cast(it)).collect();
+        expand_macro_call_in_file(&db, file_id)
+    }
+
+    fn expand_builtin_macro_in_fixture(fixture: &str) -> String {
+        let db = TestDB::with_files(fixture);
+        expand_macro_call_in_file(&db, FileId(0))
+    }
+
+    fn expand_macro_call_in_file(db: &TestDB, file_id: FileId) -> String {
+        expand_macro_call(db, (CrateId(0), file_id), file_id)
+    }
+
+    /// Expands the first macro call in `call_file` with the builtin macro
+    /// declared by the first `macro_rules` in `def_file` of crate `def_krate`.
+    fn expand_macro_call(
+        db: &TestDB,
+        (def_krate, def_file): (CrateId, FileId),
+        call_file: FileId,
+    ) -> String {
+        let macro_calls = |file_id: FileId| -> Vec<ast::MacroCall> {
+            let parsed = db.parse(file_id);
+            parsed.syntax_node().descendants().filter_map(|it| ast::MacroCall::cast(it)).collect()
+        };
+        let def_calls = macro_calls(def_file);
+        let call_calls = macro_calls(call_file);
+        let call = if def_file == call_file { &call_calls[1] } else { &call_calls[0] };
 
-        let ast_id_map = db.ast_id_map(file_id.into());
+        let def_ast_id_map = db.ast_id_map(def_file.into());
+        let call_ast_id_map = db.ast_id_map(call_file.into());
 
         let expander =
-            BuiltinFnLikeExpander::by_name(&macro_calls[0].name().unwrap().as_name()).unwrap();
+            BuiltinFnLikeExpander::by_name(&def_calls[0].name().unwrap().as_name()).unwrap();
 
         // the first one should be a macro_rules
         let def = MacroDefId {
-            krate: Some(CrateId(0)),
-            ast_id: Some(AstId::new(file_id.into(), ast_id_map.ast_id(&macro_calls[0]))),
+            krate: Some(def_krate),
+            ast_id: Some(AstId::new(
+                def_file.into(),
+                def_ast_id_map.ast_id(&def_calls[0]).upcast(),
+            )),
             kind: MacroDefKind::BuiltIn(expander),
         };
 
         let loc = MacroCallLoc {
             def,
-            kind: MacroCallKind::FnLike(AstId::new(
-                file_id.into(),
-                ast_id_map.ast_id(&macro_calls[1]),
-            )),
+            kind: MacroCallKind::FnLike(AstId::new(call_file.into(), call_ast_id_map.ast_id(call))),
         };
 
         let id = db.intern_macro(loc);
@@ -361,7 +574,38 @@ mod tests {
             "#,
         );
 
-        assert_eq!(expanded, "\"\"");
+        assert_eq!(expanded, "\"__RA_UNIMPLEMENTED__\"");
+    }
+
+    #[test]
+    fn test_env_expand_from_crate_env() {
+        let expanded = expand_builtin_macro_in_fixture(
+            r#"
+            //- /main.rs crate:main env:TEST_ENV_VAR=hello
+            #[rustc_builtin_macro]
+            macro_rules! env {() => {}}
+            env!("TEST_ENV_VAR")
+            "#,
+        );
+
+        assert_eq!(expanded, "\"hello\"");
+    }
+
+    #[test]
+    fn test_env_expand_uses_call_site_crate_env() {
+        let db = TestDB::with_files(
+            r#"
+            //- /main.rs crate:main deps:std env:TEST_ENV_VAR=hello
+            std::env!("TEST_ENV_VAR")
+
+            //- /std.rs crate:std env:TEST_ENV_VAR=wrong
+            #[rustc_builtin_macro]
+            macro_rules! env {() => {}}
+            "#,
+        );
+        let expanded = expand_macro_call(&db, (CrateId(1), FileId(1)), FileId(0));
+
+        assert_eq!(expanded, "\"hello\"");
     }
 
     #[test]
@@ -377,6 +621,101 @@ mod tests {
         assert_eq!(expanded, "std::option::Option::None:: <&str>");
     }
 
+    #[test]
+    fn test_option_env_expand_from_crate_env() {
+        let expanded = expand_builtin_macro_in_fixture(
+            r#"
+            //- /main.rs crate:main env:TEST_ENV_VAR=hello
+            #[rustc_builtin_macro]
+            macro_rules! option_env {() => {}}
+            option_env!("TEST_ENV_VAR")
+            "#,
+        );
+
+        assert_eq!(expanded, "std::option::Option::Some(\"hello\")");
+    }
+
+    #[test]
+    fn test_concat_expand() {
+        let expanded = expand_builtin_macro(
+            r#"
+            #[rustc_builtin_macro]
+            macro_rules! concat {() => {}}
+            concat!("foo", 0, 1u32, "bar", 'c', '\x41', false, 2.5f32);
+            "#,
+        );
+
+        assert_eq!(expanded, r#""foo01barcAfalse2.5""#);
+    }
+
+    #[test]
+    fn test_include_expand() {
+        let expanded = expand_builtin_macro_in_fixture(
+            r#"
+            //- /main.rs
+            #[rustc_builtin_macro]
+            macro_rules! include {() => {}}
+            include!("foo.rs");
+
+            //- /foo.rs
+            fn foo() {}
+            "#,
+        );
+
+        assert_eq!(expanded, "fnfoo(){}");
+    }
+
+    #[test]
+    fn test_include_expand_with_concat_and_env() {
+        let expanded = expand_builtin_macro_in_fixture(
+            r#"
+            //- /main.rs crate:main env:OUT_DIR=/out
+            #[rustc_builtin_macro]
+            macro_rules! include {() => {}}
+            include!(concat!(env!("OUT_DIR"), "/foo.rs"));
+
+            //- root /out/
+
+            //- /out/foo.rs
+            struct Foo;
+            "#,
+        );
+
+        assert_eq!(expanded, "structFoo;");
+    }
+
+    #[test]
+    fn test_include_str_expand() {
+        let expanded = expand_builtin_macro_in_fixture(
+            r#"
+            //- /main.rs
+            #[rustc_builtin_macro]
+            macro_rules! include_str {() => {}}
+            include_str!("foo.txt");
+
+            //- /foo.txt
+            hello"#,
+        );
+
+        assert_eq!(expanded, r#""hello\\n""#);
+    }
+
+    #[test]
+    fn test_include_bytes_expand() {
+        let expanded = expand_builtin_macro_in_fixture(
+            r#"
+            //- /main.rs
+            #[rustc_builtin_macro]
+            macro_rules! include_bytes {() => {}}
+            include_bytes!("foo.bin");
+
+            //- /foo.bin
+            abc"#,
+        );
+
+        assert_eq!(expanded, "&[0u8;4]");
+    }
+
     #[test]
     fn test_file_expand() {
         let expanded = expand_builtin_macro(
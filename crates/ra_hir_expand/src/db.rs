@@ -6,11 +6,11 @@ use mbe::MacroRules;
 use ra_db::{salsa, SourceDatabase};
 use ra_parser::FragmentKind;
 use ra_prof::profile;
-use ra_syntax::{AstNode, Parse, SyntaxKind::*, SyntaxNode};
+use ra_syntax::{algo, ast, AstNode, Parse, SyntaxKind::*, SyntaxNode, SyntaxToken};
 
 use crate::{
     ast_id_map::AstIdMap, BuiltinDeriveExpander, BuiltinFnLikeExpander, HirFileId, HirFileIdRepr,
-    MacroCallId, MacroCallLoc, MacroDefId, MacroDefKind, MacroFile,
+    MacroCallId, MacroCallLoc, MacroDefId, MacroDefKind, MacroFile, ProcMacroExpander,
 };
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -18,6 +18,7 @@ pub enum TokenExpander {
     MacroRules(mbe::MacroRules),
     Builtin(BuiltinFnLikeExpander),
     BuiltinDerive(BuiltinDeriveExpander),
+    ProcMacro(ProcMacroExpander),
 }
 
 impl TokenExpander {
@@ -26,11 +27,12 @@ impl TokenExpander {
         db: &dyn AstDatabase,
         id: MacroCallId,
         tt: &tt::Subtree,
-    ) -> Result<tt::Subtree, mbe::ExpandError> {
+    ) -> mbe::ExpandResult<tt::Subtree> {
         match self {
             TokenExpander::MacroRules(it) => it.expand(tt),
-            TokenExpander::Builtin(it) => it.expand(db, id, tt),
-            TokenExpander::BuiltinDerive(it) => it.expand(db, id, tt),
+            TokenExpander::Builtin(it) => it.expand(db, id, tt).into(),
+            TokenExpander::BuiltinDerive(it) => it.expand(db, id, tt).into(),
+            TokenExpander::ProcMacro(it) => it.expand(db, id, tt).into(),
         }
     }
 
@@ -39,6 +41,7 @@ impl TokenExpander {
             TokenExpander::MacroRules(it) => it.map_id_down(id),
             TokenExpander::Builtin(..) => id,
             TokenExpander::BuiltinDerive(..) => id,
+            TokenExpander::ProcMacro(..) => id,
         }
     }
 
@@ -47,6 +50,7 @@ impl TokenExpander {
             TokenExpander::MacroRules(it) => it.map_id_up(id),
             TokenExpander::Builtin(..) => (id, mbe::Origin::Call),
             TokenExpander::BuiltinDerive(..) => (id, mbe::Origin::Call),
+            TokenExpander::ProcMacro(..) => (id, mbe::Origin::Call),
         }
     }
 }
@@ -65,7 +69,30 @@ pub trait AstDatabase: SourceDatabase {
     fn macro_def(&self, id: MacroDefId) -> Option<Arc<(TokenExpander, mbe::TokenMap)>>;
     fn parse_macro(&self, macro_file: MacroFile)
         -> Option<(Parse<SyntaxNode>, Arc<mbe::TokenMap>)>;
-    fn macro_expand(&self, macro_call: MacroCallId) -> Result<Arc<tt::Subtree>, String>;
+    fn macro_expand(&self, macro_call: MacroCallId) -> mbe::ExpandResult<Option<Arc<tt::Subtree>>>;
+}
+
+/// Expands `actual_macro_call` as if its arguments were `hypothetical_args`,
+/// and maps `token_to_map` from the arguments into the expansion. This is
+/// used by completion, which inserts a fake identifier at the cursor.
+pub fn expand_hypothetical(
+    db: &dyn AstDatabase,
+    actual_macro_call: MacroCallId,
+    hypothetical_args: &ast::TokenTree,
+    token_to_map: SyntaxToken,
+) -> Option<(SyntaxNode, SyntaxToken)> {
+    let macro_file = MacroFile { macro_call_id: actual_macro_call };
+    let (tt, tmap_1) = mbe::syntax_node_to_token_tree(hypothetical_args.syntax())?;
+    let range =
+        token_to_map.text_range().checked_sub(hypothetical_args.syntax().text_range().start())?;
+    let token_id = tmap_1.token_by_range(range)?;
+    let loc = db.lookup_intern_macro(actual_macro_call);
+    let macro_def = db.macro_def(loc.def)?;
+    let (node, tmap_2) = parse_macro_with_arg(db, macro_file, Some(Arc::new((tt, tmap_1))))?;
+    let token_id = macro_def.0.map_id_down(token_id);
+    let range = tmap_2.range_by_token(token_id)?.by_kind(token_to_map.kind())?;
+    let token = algo::find_covering_element(&node.syntax_node(), range).into_token()?;
+    Some((node.syntax_node(), token))
 }
 
 pub(crate) fn ast_id_map(db: &dyn AstDatabase, file_id: HirFileId) -> Arc<AstIdMap> {
@@ -80,13 +107,17 @@ pub(crate) fn macro_def(
 ) -> Option<Arc<(TokenExpander, mbe::TokenMap)>> {
     match id.kind {
         MacroDefKind::Declarative => {
-            let macro_call = id.ast_id?.to_node(db);
-            let arg = macro_call.token_tree()?;
+            let macro_ = id.ast_id?.to_node(db);
+            let arg = macro_.token_tree()?;
             let (tt, tmap) = mbe::ast_to_token_tree(&arg).or_else(|| {
                 log::warn!("fail on macro_def to token tree: {:#?}", arg);
                 None
             })?;
-            let rules = MacroRules::parse(&tt).ok().or_else(|| {
+            let rules = match macro_ {
+                ast::Macro::MacroCall(_) => MacroRules::parse(&tt),
+                ast::Macro::MacroDef(_) => MacroRules::parse_macro2(&tt),
+            };
+            let rules = rules.ok().or_else(|| {
                 log::warn!("fail on macro_def parse: {:#?}", tt);
                 None
             })?;
@@ -98,6 +129,9 @@ pub(crate) fn macro_def(
         MacroDefKind::BuiltInDerive(expander) => {
             Some(Arc::new((TokenExpander::BuiltinDerive(expander), mbe::TokenMap::default())))
         }
+        MacroDefKind::ProcMacro(expander) => {
+            Some(Arc::new((TokenExpander::ProcMacro(expander), mbe::TokenMap::default())))
+        }
     }
 }
 
@@ -114,18 +148,57 @@ pub(crate) fn macro_arg(
 pub(crate) fn macro_expand(
     db: &dyn AstDatabase,
     id: MacroCallId,
-) -> Result<Arc<tt::Subtree>, String> {
+) -> mbe::ExpandResult<Option<Arc<tt::Subtree>>> {
+    macro_expand_with_arg(db, id, None)
+}
+
+/// Expands the macro call, using `arg` instead of the actual arguments of the
+/// call if it is given. If the expansion is only partially successful, both
+/// the best-effort result and the error are returned.
+fn macro_expand_with_arg(
+    db: &dyn AstDatabase,
+    id: MacroCallId,
+    arg: Option<Arc<(tt::Subtree, mbe::TokenMap)>>,
+) -> mbe::ExpandResult<Option<Arc<tt::Subtree>>> {
     let loc = db.lookup_intern_macro(id);
-    let macro_arg = db.macro_arg(id).ok_or("Fail to args in to tt::TokenTree")?;
+    let macro_arg = match arg.or_else(|| db.macro_arg(id)) {
+        Some(it) => it,
+        None => {
+            let msg = "failed to convert macro arguments".to_string();
+            return mbe::ExpandResult::only_err(mbe::ExpandError::Other(msg));
+        }
+    };
 
-    let macro_rules = db.macro_def(loc.def).ok_or("Fail to find macro definition")?;
-    let tt = macro_rules.0.expand(db, id, &macro_arg.0).map_err(|err| format!("{:?}", err))?;
+    let macro_rules = match db.macro_def(loc.def) {
+        Some(it) => it,
+        None => {
+            let msg = "failed to find macro definition".to_string();
+            return mbe::ExpandResult::only_err(mbe::ExpandError::Other(msg));
+        }
+    };
+    let mbe::ExpandResult { value: tt, err } = macro_rules.0.expand(db, id, &macro_arg.0);
     // Set a hard limit for the expanded tt
     let count = tt.count();
     if count > 65536 {
-        return Err(format!("Total tokens count exceed limit : count = {}", count));
+        let msg = format!("macro expansion is too large: {} tokens", count);
+        return mbe::ExpandResult::only_err(mbe::ExpandError::Other(msg));
+    }
+    mbe::ExpandResult { value: Some(Arc::new(tt)), err }
+}
+
+/// Returns which rule of a `macro_rules!` macro the call was expanded with,
+/// and what the metavariables of that rule were bound to. This is not a query,
+/// as it is only needed when debugging macros.
+pub fn macro_match_info(db: &dyn AstDatabase, id: MacroCallId) -> Option<mbe::MatchInfo> {
+    let loc = db.lookup_intern_macro(id);
+    let macro_arg = db.macro_arg(id)?;
+    let macro_rules = db.macro_def(loc.def)?;
+    match &macro_rules.0 {
+        TokenExpander::MacroRules(it) => it.expand_with_match_info(&macro_arg.0).value.1,
+        TokenExpander::Builtin(..)
+        | TokenExpander::BuiltinDerive(..)
+        | TokenExpander::ProcMacro(..) => None,
     }
-    Ok(Arc::new(tt))
 }
 
 pub(crate) fn parse_or_expand(db: &dyn AstDatabase, file_id: HirFileId) -> Option<SyntaxNode> {
@@ -140,19 +213,31 @@ pub(crate) fn parse_or_expand(db: &dyn AstDatabase, file_id: HirFileId) -> Optio
 pub(crate) fn parse_macro(
     db: &dyn AstDatabase,
     macro_file: MacroFile,
+) -> Option<(Parse<SyntaxNode>, Arc<mbe::TokenMap>)> {
+    parse_macro_with_arg(db, macro_file, None)
+}
+
+fn parse_macro_with_arg(
+    db: &dyn AstDatabase,
+    macro_file: MacroFile,
+    arg: Option<Arc<(tt::Subtree, mbe::TokenMap)>>,
 ) -> Option<(Parse<SyntaxNode>, Arc<mbe::TokenMap>)> {
     let _p = profile("parse_macro_query");
 
     let macro_call_id = macro_file.macro_call_id;
-    let tt = db
-        .macro_expand(macro_call_id)
-        .map_err(|err| {
-            // Note:
-            // The final goal we would like to make all parse_macro success,
-            // such that the following log will not call anyway.
-            log::warn!("fail on macro_parse: (reason: {})", err,);
-        })
-        .ok()?;
+    let expansion = match arg {
+        Some(arg) => macro_expand_with_arg(db, macro_call_id, Some(arg)),
+        None => db.macro_expand(macro_call_id),
+    };
+    if let Some(err) = &expansion.err {
+        // Note:
+        // The final goal we would like to make all parse_macro success,
+        // such that the following log will not call anyway.
+        log::warn!("fail on macro_parse: (reason: {})", err);
+    }
+    // A partial expansion is still parsed, so that the IDE has something to
+    // work with while a macro call is being typed.
+    let tt = expansion.value?;
 
     let fragment_kind = to_fragment_kind(db, macro_call_id);
 
@@ -1,41 +1,68 @@
 //! This modules handles hygiene information.
 //!
 //! Specifically, `ast` + `Hygiene` allows you to create a `Name`. Note that, at
-//! this moment, this is horribly incomplete and handles only `$crate`.
+//! this moment, this is horribly incomplete and handles only `$crate` and
+//! the def-site `crate` of macros 2.0.
 use either::Either;
 use ra_db::CrateId;
-use ra_syntax::ast;
+use ra_syntax::{ast, AstNode, T};
 
 use crate::{
     db::AstDatabase,
     name::{AsName, Name},
-    HirFileId, HirFileIdRepr, MacroDefKind,
+    ExpansionInfo, HirFileId, HirFileIdRepr, MacroDefKind, Origin,
 };
 
 #[derive(Debug)]
 pub struct Hygiene {
     // This is what `$crate` expands to
     def_crate: Option<CrateId>,
+    // Set for expansions of macros 2.0, which have def-site hygiene: `crate`
+    // written in the macro definition refers to the defining crate.
+    //
+    // FIXME: local names should be def-site hygienic as well.
+    macro2_expansion: Option<ExpansionInfo>,
 }
 
 impl Hygiene {
     pub fn new(db: &impl AstDatabase, file_id: HirFileId) -> Hygiene {
-        let def_crate = match file_id.0 {
-            HirFileIdRepr::FileId(_) => None,
+        let (def_crate, macro2_expansion) = match file_id.0 {
+            HirFileIdRepr::FileId(_) => (None, None),
             HirFileIdRepr::MacroFile(macro_file) => {
                 let loc = db.lookup_intern_macro(macro_file.macro_call_id);
                 match loc.def.kind {
-                    MacroDefKind::Declarative => loc.def.krate,
-                    MacroDefKind::BuiltIn(_) => None,
-                    MacroDefKind::BuiltInDerive(_) => None,
+                    MacroDefKind::Declarative => {
+                        let is_macro2 = loc.def.ast_id.map_or(false, |it| match it.to_node(db) {
+                            ast::Macro::MacroDef(_) => true,
+                            ast::Macro::MacroCall(_) => false,
+                        });
+                        let macro2_expansion =
+                            if is_macro2 { file_id.expansion_info(db) } else { None };
+                        (loc.def.krate, macro2_expansion)
+                    }
+                    MacroDefKind::BuiltIn(_) => (None, None),
+                    MacroDefKind::BuiltInDerive(_) => (None, None),
+                    MacroDefKind::ProcMacro(_) => (None, None),
                 }
             }
         };
-        Hygiene { def_crate }
+        Hygiene { def_crate, macro2_expansion }
     }
 
     pub fn new_unhygienic() -> Hygiene {
-        Hygiene { def_crate: None }
+        Hygiene { def_crate: None, macro2_expansion: None }
+    }
+
+    /// If `segment` is a `crate` keyword that comes from the definition of a
+    /// macro 2.0, returns the crate the macro is defined in.
+    pub fn crate_kw_to_crate(&self, segment: &ast::PathSegment) -> Option<CrateId> {
+        let expansion = self.macro2_expansion.as_ref()?;
+        let token = segment.syntax().first_token().filter(|it| it.kind() == T![crate])?;
+        let (_, origin) = expansion.map_token_up(expansion.expanded.with_value(&token))?;
+        match origin {
+            Origin::Def => self.def_crate,
+            Origin::Call => None,
+        }
     }
 
     // FIXME: this should just return name
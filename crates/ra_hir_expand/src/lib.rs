@@ -11,6 +11,7 @@ pub mod hygiene;
 pub mod diagnostics;
 pub mod builtin_derive;
 pub mod builtin_macro;
+pub mod proc_macro;
 pub mod quote;
 
 use std::hash::Hash;
@@ -20,12 +21,13 @@ use ra_db::{salsa, CrateId, FileId};
 use ra_syntax::{
     algo,
     ast::{self, AstNode},
-    SyntaxNode, SyntaxToken, TextUnit,
+    SyntaxNode, SyntaxToken, TextRange, TextUnit,
 };
 
 use crate::ast_id_map::FileAstId;
 use crate::builtin_derive::BuiltinDeriveExpander;
 use crate::builtin_macro::BuiltinFnLikeExpander;
+use crate::proc_macro::ProcMacroExpander;
 
 #[cfg(test)]
 mod test_db;
@@ -76,6 +78,14 @@ impl HirFileId {
         }
     }
 
+    /// True for the files produced by macro expansions.
+    pub fn is_macro_file(self) -> bool {
+        match self.0 {
+            HirFileIdRepr::FileId(_) => false,
+            HirFileIdRepr::MacroFile(_) => true,
+        }
+    }
+
     /// If this is a macro call, returns the syntax node of the call.
     pub fn call_node(self, db: &dyn db::AstDatabase) -> Option<InFile<SyntaxNode>> {
         match self.0 {
@@ -101,10 +111,26 @@ impl HirFileId {
                 let (parse, exp_map) = db.parse_macro(macro_file)?;
                 let macro_arg = db.macro_arg(macro_file.macro_call_id)?;
 
+                let included = match loc.def.kind {
+                    MacroDefKind::BuiltIn(expander) if expander.is_include() => {
+                        let file_id = builtin_macro::included_file(
+                            db,
+                            macro_file.macro_call_id,
+                            &macro_arg.0,
+                        )
+                        .ok()?;
+                        let file = db.parse(file_id).syntax_node();
+                        let (_, token_map) = mbe::syntax_node_to_token_tree(&file)?;
+                        Some((InFile::new(file_id.into(), file), Arc::new(token_map)))
+                    }
+                    _ => None,
+                };
+
                 Some(ExpansionInfo {
                     expanded: InFile::new(self, parse.syntax_node()),
                     arg: InFile::new(loc.kind.file_id(), arg_tt),
                     def: InFile::new(loc.def.ast_id?.file_id, def_tt),
+                    included,
                     macro_arg,
                     macro_def,
                     exp_map,
@@ -136,12 +162,11 @@ impl salsa::InternKey for MacroCallId {
 pub struct MacroDefId {
     // FIXME: krate and ast_id are currently optional because we don't have a
     // definition location for built-in derives. There is one, though: the
-    // standard library defines them. The problem is that it uses the new
-    // `macro` syntax for this, which we don't support yet. As soon as we do
-    // (which will probably require touching this code), we can instead use
-    // that (and also remove the hacks for resolving built-in derives).
+    // standard library defines them, using the new `macro` syntax. We could
+    // instead use those definitions (and also remove the hacks for resolving
+    // built-in derives).
     pub krate: Option<CrateId>,
-    pub ast_id: Option<AstId<ast::MacroCall>>,
+    pub ast_id: Option<AstId<ast::Macro>>,
     pub kind: MacroDefKind,
 }
 
@@ -157,6 +182,7 @@ pub enum MacroDefKind {
     BuiltIn(BuiltinFnLikeExpander),
     // FIXME: maybe just Builtin and rename BuiltinFnLikeExpander to BuiltinExpander
     BuiltInDerive(BuiltinDeriveExpander),
+    ProcMacro(ProcMacroExpander),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -200,6 +226,35 @@ impl MacroCallId {
     pub fn as_file(self) -> HirFileId {
         MacroFile { macro_call_id: self }.into()
     }
+
+    /// If expanding this macro call failed, returns the error along with the
+    /// range of the input token it points to, if there is one.
+    pub fn expand_error(
+        self,
+        db: &dyn db::AstDatabase,
+    ) -> Option<(mbe::ExpandError, Option<TextRange>)> {
+        let err = db.macro_expand(self).err?;
+        let range = self.error_range(db, &err);
+        Some((err, range))
+    }
+
+    fn error_range(self, db: &dyn db::AstDatabase, err: &mbe::ExpandError) -> Option<TextRange> {
+        let (token_id, text) = match err {
+            mbe::ExpandError::NoMatchingRule(Some(it)) => it.token.as_ref()?,
+            _ => return None,
+        };
+        let loc = db.lookup_intern_macro(self);
+        let arg = loc.kind.arg(db)?;
+        let macro_arg = db.macro_arg(self)?;
+        let range = match macro_arg.1.range_by_token(*token_id)? {
+            mbe::TokenTextRange::Token(it) => it,
+            mbe::TokenTextRange::Delimiter(open, close) => match text.as_str() {
+                ")" | "]" | "}" => close,
+                _ => open,
+            },
+        };
+        Some(range + arg.text_range().start())
+    }
 }
 
 /// ExpansionInfo mainly describes how to map text range between src and expanded macro
@@ -208,13 +263,16 @@ pub struct ExpansionInfo {
     expanded: InFile<SyntaxNode>,
     arg: InFile<SyntaxNode>,
     def: InFile<ast::TokenTree>,
+    /// For `include!`, the included file and its token map: the expansion
+    /// consists of the tokens of that file.
+    included: Option<(InFile<SyntaxNode>, Arc<mbe::TokenMap>)>,
 
     macro_def: Arc<(db::TokenExpander, mbe::TokenMap)>,
     macro_arg: Arc<(tt::Subtree, mbe::TokenMap)>,
     exp_map: Arc<mbe::TokenMap>,
 }
 
-pub use mbe::Origin;
+pub use mbe::{MatchInfo, Origin};
 
 impl ExpansionInfo {
     pub fn call_node(&self) -> Option<InFile<SyntaxNode>> {
@@ -223,6 +281,10 @@ impl ExpansionInfo {
 
     pub fn map_token_down(&self, token: InFile<&SyntaxToken>) -> Option<InFile<SyntaxToken>> {
         assert_eq!(token.file_id, self.arg.file_id);
+        if self.included.is_some() {
+            // The arguments of `include!` don't make it into the expansion.
+            return None;
+        }
         let range = token.value.text_range().checked_sub(self.arg.value.text_range().start())?;
         let token_id = self.macro_arg.1.token_by_range(range)?;
         let token_id = self.macro_def.0.map_id_down(token_id);
@@ -240,6 +302,12 @@ impl ExpansionInfo {
     ) -> Option<(InFile<SyntaxToken>, Origin)> {
         let token_id = self.exp_map.token_by_range(token.value.text_range())?;
 
+        if let Some((file, token_map)) = &self.included {
+            let range = token_map.range_by_token(token_id)?.by_kind(token.value.kind())?;
+            let token = algo::find_covering_element(&file.value, range).into_token()?;
+            return Some((file.with_value(token), Origin::Call));
+        }
+
         let (token_id, origin) = self.macro_def.0.map_id_up(token_id);
         let (token_map, tt) = match origin {
             mbe::Origin::Call => (&self.macro_arg.1, self.arg.clone()),
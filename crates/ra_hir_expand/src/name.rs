@@ -2,7 +2,7 @@
 
 use std::fmt;
 
-use ra_syntax::{ast, SmolStr};
+use ra_syntax::{ast, SmolStr, SyntaxToken};
 
 /// `Name` is a wrapper around string, which is used in hir for both references
 /// and declarations. In theory, names should also carry hygiene info, but we are
@@ -52,6 +52,11 @@ impl Name {
         }
     }
 
+    /// Creates a name from the text of a lifetime token, including the `'`.
+    pub fn new_lifetime(lt: &SyntaxToken) -> Name {
+        Name::new_text(lt.text().clone())
+    }
+
     pub fn missing() -> Name {
         Name::new_text("[missing name]".into())
     }
@@ -104,6 +109,12 @@ impl AsName for ra_db::Dependency {
     }
 }
 
+impl AsName for ra_db::ProcMacro {
+    fn as_name(&self) -> Name {
+        Name::new_text(self.name.clone())
+    }
+}
+
 pub mod known {
     macro_rules! known_names {
         ($($ident:ident),* $(,)?) => {
@@ -172,6 +183,10 @@ pub mod known {
         format_args_nl,
         env,
         option_env,
+        concat,
+        include,
+        include_str,
+        include_bytes,
         // Builtin derives
         Copy,
         Clone,
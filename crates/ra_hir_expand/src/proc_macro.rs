@@ -0,0 +1,220 @@
+//! Expansion of procedural macros.
+//!
+//! The macros themselves are run by the expanders stored in the crate graph
+//! (see `ra_proc_macro`), here we only look them up and prepare the input.
+
+use crate::{db::AstDatabase, MacroCallId};
+use ra_db::{CrateId, ProcMacroId, ProcMacroKind};
+use tt::buffer::{Cursor, TokenBuffer};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct ProcMacroExpander {
+    krate: CrateId,
+    proc_macro_id: ProcMacroId,
+    kind: ProcMacroKind,
+}
+
+impl ProcMacroExpander {
+    pub fn new(
+        krate: CrateId,
+        proc_macro_id: ProcMacroId,
+        kind: ProcMacroKind,
+    ) -> ProcMacroExpander {
+        ProcMacroExpander { krate, proc_macro_id, kind }
+    }
+
+    pub fn kind(&self) -> ProcMacroKind {
+        self.kind
+    }
+
+    pub fn expand(
+        &self,
+        db: &dyn AstDatabase,
+        _id: MacroCallId,
+        tt: &tt::Subtree,
+    ) -> Result<tt::Subtree, mbe::ExpandError> {
+        let krate_graph = db.crate_graph();
+        let proc_macro = krate_graph
+            .proc_macro(self.krate)
+            .get(self.proc_macro_id.0 as usize)
+            .ok_or(mbe::ExpandError::ConversionError)?;
+
+        match self.kind {
+            ProcMacroKind::FuncLike => proc_macro.expander.expand(tt, None),
+            ProcMacroKind::CustomDerive => {
+                // Proc macros have access to the whole item, but the
+                // `#[derive]` attributes themselves must not be passed along,
+                // or the derive would see itself.
+                let tt = remove_derive_attrs(tt).ok_or(mbe::ExpandError::ConversionError)?;
+                proc_macro.expander.expand(&tt, None)
+            }
+            ProcMacroKind::Attr => {
+                // Likewise, an attribute macro gets the item without the
+                // attribute which invoked it, and the attribute's arguments
+                // separately.
+                let (tt, attr) =
+                    split_attr(tt, &proc_macro.name).ok_or(mbe::ExpandError::ConversionError)?;
+                proc_macro.expander.expand(&tt, Some(&attr))
+            }
+        }
+        .map_err(mbe::ExpandError::from)
+    }
+}
+
+fn eat_punct(cursor: &mut Cursor, c: char) -> bool {
+    if let Some(tt::TokenTree::Leaf(tt::Leaf::Punct(punct))) = cursor.token_tree() {
+        if punct.char == c {
+            *cursor = cursor.bump();
+            return true;
+        }
+    }
+    false
+}
+
+fn eat_subtree(cursor: &mut Cursor, kind: tt::DelimiterKind) -> bool {
+    if let Some(tt::TokenTree::Subtree(subtree)) = cursor.token_tree() {
+        if Some(kind) == subtree.delimiter_kind() {
+            *cursor = cursor.bump_subtree();
+            return true;
+        }
+    }
+    false
+}
+
+fn eat_ident(cursor: &mut Cursor, t: &str) -> bool {
+    if let Some(tt::TokenTree::Leaf(tt::Leaf::Ident(ident))) = cursor.token_tree() {
+        if t == ident.text.as_str() {
+            *cursor = cursor.bump();
+            return true;
+        }
+    }
+    false
+}
+
+/// Checks whether the bracketed part of an attribute, like `[foo::bar(..)]`,
+/// invokes the macro called `name`, and if so, returns the arguments.
+fn attr_args(attr: &tt::Subtree, name: &str) -> Option<tt::Subtree> {
+    let mut last_ident = None;
+    let mut args = tt::Subtree::default();
+    for tt in &attr.token_trees {
+        match tt {
+            tt::TokenTree::Leaf(tt::Leaf::Ident(ident)) => last_ident = Some(ident),
+            tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) if punct.char == ':' => {}
+            tt::TokenTree::Subtree(subtree) => {
+                args.token_trees = subtree.token_trees.clone();
+                break;
+            }
+            _ => return None,
+        }
+    }
+    if last_ident?.text != name {
+        return None;
+    }
+    Some(args)
+}
+
+/// Splits an item annotated with the attribute macro `name` into the item
+/// without that attribute and the attribute's arguments.
+fn split_attr(tt: &tt::Subtree, name: &str) -> Option<(tt::Subtree, tt::Subtree)> {
+    let mut item = tt::Subtree::default();
+    let mut args = None;
+    let mut token_trees = tt.token_trees.iter().peekable();
+    while let Some(curr) = token_trees.next() {
+        if let tt::TokenTree::Leaf(tt::Leaf::Punct(punct)) = curr {
+            if punct.char == '#' && args.is_none() {
+                if let Some(tt::TokenTree::Subtree(attr)) = token_trees.peek() {
+                    if attr.delimiter_kind() == Some(tt::DelimiterKind::Bracket) {
+                        if let Some(it) = attr_args(attr, name) {
+                            args = Some(it);
+                            token_trees.next();
+                            continue;
+                        }
+                    }
+                }
+            }
+        }
+        item.token_trees.push(curr.clone());
+    }
+    Some((item, args?))
+}
+
+fn remove_derive_attrs(tt: &tt::Subtree) -> Option<tt::Subtree> {
+    let buffer = TokenBuffer::new(&tt.token_trees);
+    let mut p = buffer.begin();
+    let mut result = tt::Subtree::default();
+
+    while !p.eof() {
+        let curr = p;
+
+        if eat_punct(&mut p, '#') {
+            eat_punct(&mut p, '!');
+            let parent = p;
+            if eat_subtree(&mut p, tt::DelimiterKind::Bracket) {
+                if eat_ident(&mut p, "derive") {
+                    p = parent.bump();
+                    continue;
+                }
+            }
+        }
+
+        result.token_trees.push(curr.token_tree()?.clone());
+        p = curr.bump();
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_syntax::{AstNode, SourceFile};
+
+    use super::*;
+
+    #[test]
+    fn test_remove_derive_attrs() {
+        let source_file = SourceFile::parse(
+            r#"
+#[allow(unused)]
+#[derive(Copy)]
+#[derive(Hello)]
+struct A {
+    bar: u32
+}
+"#,
+        )
+        .ok()
+        .unwrap();
+        let (tt, _) = mbe::syntax_node_to_token_tree(source_file.syntax()).unwrap();
+
+        let result = remove_derive_attrs(&tt).unwrap();
+
+        // `#`, `[allow(unused)]`, `struct`, `A`, `{ .. }`
+        assert_eq!(result.token_trees.len(), 5);
+        let text = result.to_string();
+        assert!(text.contains("allow"));
+        assert!(!text.contains("derive"));
+    }
+
+    #[test]
+    fn test_split_attr() {
+        let source_file = SourceFile::parse(
+            r#"
+#[allow(unused)]
+#[toy::traced(level = 2)]
+fn foo() {}
+"#,
+        )
+        .ok()
+        .unwrap();
+        let (tt, _) = mbe::syntax_node_to_token_tree(source_file.syntax()).unwrap();
+
+        let (item, args) = split_attr(&tt, "traced").unwrap();
+
+        let item = item.to_string();
+        assert!(item.contains("allow"));
+        assert!(item.contains("foo"));
+        assert!(!item.contains("traced"));
+        assert_eq!(args.to_string(), "level = 2");
+        assert!(split_attr(&tt, "other").is_none());
+    }
+}
@@ -0,0 +1,92 @@
+//! A small evaluator for the constants the type system needs, like array
+//! lengths. It only understands integer literals, arithmetic and paths to
+//! `const` items; everything else evaluates to `None`.
+
+use hir_def::{
+    body::Body,
+    expr::{ArithOp, BinaryOp, Expr, ExprId, Literal},
+    path::Path,
+    resolver::{HasResolver, Resolver, ValueNs},
+    type_ref::ConstRef,
+    ConstId,
+};
+
+use crate::db::HirDatabase;
+
+pub(crate) fn const_eval_query(db: &impl HirDatabase, def: ConstId) -> Option<u64> {
+    let body = db.body(def.into());
+    let resolver = def.resolver(db);
+    eval_expr(db, &resolver, &body, body.body_expr)
+}
+
+pub(crate) fn const_eval_recover(
+    _db: &impl HirDatabase,
+    _cycle: &[String],
+    _def: &ConstId,
+) -> Option<u64> {
+    None
+}
+
+/// Evaluates a constant written in a type, like an array length.
+pub(crate) fn eval_const_ref(
+    db: &impl HirDatabase,
+    resolver: &Resolver,
+    const_ref: &ConstRef,
+) -> Option<u64> {
+    match const_ref {
+        ConstRef::Literal(value) => Some(*value as u64),
+        ConstRef::Path(path) => eval_path(db, resolver, path),
+        ConstRef::BinaryOp { lhs, rhs, op } => {
+            let lhs = eval_const_ref(db, resolver, lhs)?;
+            let rhs = eval_const_ref(db, resolver, rhs)?;
+            eval_arith(*op, lhs, rhs)
+        }
+        ConstRef::Error => None,
+    }
+}
+
+/// Evaluates an expression of a body, like the count of a repeat expression
+/// or the initializer of a `const` item.
+pub(crate) fn eval_expr(
+    db: &impl HirDatabase,
+    resolver: &Resolver,
+    body: &Body,
+    expr: ExprId,
+) -> Option<u64> {
+    match &body[expr] {
+        Expr::Literal(Literal::Int(value, _)) => Some(*value),
+        Expr::Path(path) => eval_path(db, resolver, path),
+        Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::ArithOp(op)) } => {
+            let lhs = eval_expr(db, resolver, body, *lhs)?;
+            let rhs = eval_expr(db, resolver, body, *rhs)?;
+            eval_arith(*op, lhs, rhs)
+        }
+        Expr::Cast { expr, .. } => eval_expr(db, resolver, body, *expr),
+        Expr::Block { statements, tail: Some(tail) } if statements.is_empty() => {
+            eval_expr(db, resolver, body, *tail)
+        }
+        _ => None,
+    }
+}
+
+fn eval_path(db: &impl HirDatabase, resolver: &Resolver, path: &Path) -> Option<u64> {
+    match resolver.resolve_path_in_value_ns_fully(db, path.mod_path())? {
+        ValueNs::ConstId(it) => db.const_eval(it),
+        _ => None,
+    }
+}
+
+fn eval_arith(op: ArithOp, lhs: u64, rhs: u64) -> Option<u64> {
+    match op {
+        ArithOp::Add => lhs.checked_add(rhs),
+        ArithOp::Mul => lhs.checked_mul(rhs),
+        ArithOp::Sub => lhs.checked_sub(rhs),
+        ArithOp::Div => lhs.checked_div(rhs),
+        ArithOp::Rem => lhs.checked_rem(rhs),
+        ArithOp::Shl => lhs.checked_shl(rhs as u32),
+        ArithOp::Shr => lhs.checked_shr(rhs as u32),
+        ArithOp::BitXor => Some(lhs ^ rhs),
+        ArithOp::BitOr => Some(lhs | rhs),
+        ArithOp::BitAnd => Some(lhs & rhs),
+    }
+}
@@ -3,7 +3,8 @@
 use std::sync::Arc;
 
 use hir_def::{
-    db::DefDatabase, DefWithBodyId, GenericDefId, ImplId, LocalStructFieldId, TraitId, VariantId,
+    db::DefDatabase, ConstId, DefWithBodyId, GenericDefId, ImplId, LocalStructFieldId, TraitId,
+    VariantId,
 };
 use ra_arena::map::ArenaMap;
 use ra_db::{salsa, CrateId};
@@ -11,6 +12,7 @@ use ra_db::{salsa, CrateId};
 use crate::{
     method_resolution::CrateImplBlocks,
     traits::{chalk, AssocTyValue, Impl},
+    unsafe_validation::UnsafeExpr,
     CallableDef, FnSig, GenericPredicate, InferenceResult, Substs, TraitRef, Ty, TyDefId, TypeCtor,
     ValueTyDefId,
 };
@@ -35,6 +37,13 @@ pub trait HirDatabase: DefDatabase {
     #[salsa::invoke(crate::lower::impl_trait_query)]
     fn impl_trait(&self, def: ImplId) -> Option<TraitRef>;
 
+    #[salsa::invoke(crate::consteval::const_eval_query)]
+    #[salsa::cycle(crate::consteval::const_eval_recover)]
+    fn const_eval(&self, def: ConstId) -> Option<u64>;
+
+    #[salsa::invoke(crate::unsafe_validation::unsafe_expressions_query)]
+    fn unsafe_expressions(&self, def: DefWithBodyId) -> Arc<[UnsafeExpr]>;
+
     #[salsa::invoke(crate::lower::field_types_query)]
     fn field_types(&self, var: VariantId) -> Arc<ArenaMap<LocalStructFieldId, Ty>>;
 
@@ -2,6 +2,11 @@
 
 use std::fmt;
 
+use hir_def::{
+    path::{GenericArg, Path, PathKind},
+    type_ref::{ConstRef, LifetimeRef, TypeBound, TypeRef},
+};
+
 use crate::db::HirDatabase;
 
 pub struct HirFormatter<'a, 'b, DB> {
@@ -97,3 +102,179 @@ where
         })
     }
 }
+
+impl HirDisplay for &TypeRef {
+    fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        HirDisplay::hir_fmt(*self, f)
+    }
+}
+
+impl HirDisplay for TypeRef {
+    fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        match self {
+            TypeRef::Never => write!(f, "!")?,
+            TypeRef::Placeholder => write!(f, "_")?,
+            TypeRef::Tuple(fields) => {
+                if fields.len() == 1 {
+                    write!(f, "(")?;
+                    fields[0].hir_fmt(f)?;
+                    write!(f, ",)")?;
+                } else {
+                    write!(f, "(")?;
+                    f.write_joined(fields, ", ")?;
+                    write!(f, ")")?;
+                }
+            }
+            TypeRef::Path(path) => path.hir_fmt(f)?,
+            TypeRef::RawPtr(inner, mutability) => {
+                write!(f, "*{}", mutability.as_keyword_for_ptr())?;
+                inner.hir_fmt(f)?;
+            }
+            TypeRef::Reference(inner, lifetime, mutability) => {
+                write!(f, "&")?;
+                if let Some(lifetime) = lifetime {
+                    lifetime.hir_fmt(f)?;
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", mutability.as_keyword_for_ref())?;
+                inner.hir_fmt(f)?;
+            }
+            TypeRef::Array(inner, len) => {
+                write!(f, "[")?;
+                inner.hir_fmt(f)?;
+                match len {
+                    ConstRef::Literal(len) => write!(f, "; {}]", len)?,
+                    ConstRef::Path(path) => {
+                        write!(f, "; ")?;
+                        path.hir_fmt(f)?;
+                        write!(f, "]")?;
+                    }
+                    ConstRef::BinaryOp { .. } | ConstRef::Error => write!(f, "; _]")?,
+                }
+            }
+            TypeRef::Slice(inner) => {
+                write!(f, "[")?;
+                inner.hir_fmt(f)?;
+                write!(f, "]")?;
+            }
+            TypeRef::Fn(params_and_ret) => {
+                let (ret, params) = match params_and_ret.split_last() {
+                    Some(it) => it,
+                    None => return write!(f, "fn()"),
+                };
+                write!(f, "fn(")?;
+                f.write_joined(params, ", ")?;
+                write!(f, ")")?;
+                match ret {
+                    TypeRef::Tuple(fields) if fields.is_empty() => {}
+                    _ => {
+                        write!(f, " -> ")?;
+                        ret.hir_fmt(f)?;
+                    }
+                }
+            }
+            TypeRef::ImplTrait(bounds) => {
+                write!(f, "impl ")?;
+                f.write_joined(bounds, " + ")?;
+            }
+            TypeRef::DynTrait(bounds) => {
+                write!(f, "dyn ")?;
+                f.write_joined(bounds, " + ")?;
+            }
+            TypeRef::Error => write!(f, "{{error}}")?,
+        }
+        Ok(())
+    }
+}
+
+impl HirDisplay for &TypeBound {
+    fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        HirDisplay::hir_fmt(*self, f)
+    }
+}
+
+impl HirDisplay for TypeBound {
+    fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        match self {
+            TypeBound::Path(path) => path.hir_fmt(f),
+            TypeBound::Lifetime(lifetime) => lifetime.hir_fmt(f),
+            TypeBound::Error => write!(f, "{{error}}"),
+        }
+    }
+}
+
+impl HirDisplay for LifetimeRef {
+    fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+impl HirDisplay for Path {
+    fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        if let Some(type_anchor) = self.type_anchor() {
+            write!(f, "<")?;
+            type_anchor.hir_fmt(f)?;
+            write!(f, ">")?;
+        }
+        let mut needs_separator = self.type_anchor().is_some();
+        match self.kind() {
+            PathKind::Plain => {}
+            PathKind::Abs => needs_separator = true,
+            PathKind::Super(0) => {
+                write!(f, "self")?;
+                needs_separator = true;
+            }
+            PathKind::Super(n) => {
+                for i in 0..*n {
+                    if i > 0 {
+                        write!(f, "::")?;
+                    }
+                    write!(f, "super")?;
+                }
+                needs_separator = true;
+            }
+            PathKind::Crate => {
+                write!(f, "crate")?;
+                needs_separator = true;
+            }
+            PathKind::DollarCrate(_) => {
+                write!(f, "$crate")?;
+                needs_separator = true;
+            }
+        }
+        for segment in self.segments().iter() {
+            if needs_separator {
+                write!(f, "::")?;
+            }
+            needs_separator = true;
+            write!(f, "{}", segment.name)?;
+            let generic_args = match segment.args_and_bindings {
+                Some(it) => it,
+                None => continue,
+            };
+            let skip = if generic_args.has_self_type { 1 } else { 0 };
+            let mut first = true;
+            write!(f, "<")?;
+            for arg in generic_args.args.iter().skip(skip) {
+                if !first {
+                    write!(f, ", ")?;
+                }
+                first = false;
+                match arg {
+                    GenericArg::Type(type_ref) => type_ref.hir_fmt(f)?,
+                    GenericArg::Lifetime(lifetime) => lifetime.hir_fmt(f)?,
+                }
+            }
+            for (name, type_ref) in generic_args.bindings.iter() {
+                if !first {
+                    write!(f, ", ")?;
+                }
+                first = false;
+                write!(f, "{} = ", name)?;
+                type_ref.hir_fmt(f)?;
+            }
+            write!(f, ">")?;
+        }
+        Ok(())
+    }
+}
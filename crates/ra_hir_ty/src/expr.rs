@@ -14,8 +14,9 @@ use rustc_hash::FxHashSet;
 
 use crate::{
     db::HirDatabase,
-    diagnostics::{MissingFields, MissingOkInTailExpr},
-    ApplicationTy, InferenceResult, Ty, TypeCtor,
+    diagnostics::{MissingFields, MissingOkInTailExpr, MissingUnsafe, TypeMismatch},
+    display::HirDisplay,
+    ApplicationTy, InferenceResult, Ty, TypeCtor, TypeWalk,
 };
 
 pub use hir_def::{
@@ -53,10 +54,16 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
             }
         }
 
+        let mut skip = None;
         let body_expr = &body[body.body_expr];
         if let Expr::Block { statements: _, tail: Some(t) } = body_expr {
-            self.validate_results_in_tail_expr(body.body_expr, *t, db);
+            if self.validate_results_in_tail_expr(body.body_expr, *t, db) {
+                skip = Some(*t);
+            }
         }
+
+        self.validate_type_mismatches(&body, skip, db);
+        self.validate_unsafe(db);
     }
 
     fn validate_record_literal(
@@ -117,11 +124,11 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
         body_id: ExprId,
         id: ExprId,
         db: &impl HirDatabase,
-    ) {
+    ) -> bool {
         // the mismatch will be on the whole block currently
         let mismatch = match self.infer.type_mismatch_for_expr(body_id) {
             Some(m) => m,
-            None => return,
+            None => return false,
         };
 
         let std_result_path = path![std::result::Result];
@@ -129,13 +136,13 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
         let resolver = self.func.resolver(db);
         let std_result_enum = match resolver.resolve_known_enum(db, &std_result_path) {
             Some(it) => it,
-            _ => return,
+            _ => return false,
         };
 
         let std_result_ctor = TypeCtor::Adt(AdtId::EnumId(std_result_enum));
         let params = match &mismatch.expected {
             Ty::Apply(ApplicationTy { ctor, parameters }) if ctor == &std_result_ctor => parameters,
-            _ => return,
+            _ => return false,
         };
 
         if params.len() == 2 && &params[0] == &mismatch.actual {
@@ -144,8 +151,74 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
             if let Some(source_ptr) = source_map.expr_syntax(id) {
                 if let Some(expr) = source_ptr.value.left() {
                     self.sink.push(MissingOkInTailExpr { file: source_ptr.file_id, expr });
+                    return true;
                 }
             }
         }
+        false
+    }
+
+    fn validate_type_mismatches(
+        &mut self,
+        body: &Body,
+        skip: Option<ExprId>,
+        db: &impl HirDatabase,
+    ) {
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+
+        for (id, mismatch) in self.infer.type_mismatches() {
+            if Some(id) == skip {
+                continue;
+            }
+            // Types we failed to infer unify with anything, so a mismatch
+            // involving them is most likely our fault rather than the user's.
+            if contains_unknown(&mismatch.expected) || contains_unknown(&mismatch.actual) {
+                continue;
+            }
+            // The tail of a block, and the block inside `unsafe`, are checked
+            // against the same expectation as the expression itself, so the
+            // mismatch is already reported there.
+            match &body[id] {
+                Expr::Block { tail: Some(_), .. } | Expr::Unsafe { .. } => continue,
+                _ => (),
+            }
+            let source_ptr = match source_map.expr_syntax(id) {
+                Some(it) => it,
+                None => continue,
+            };
+            if let Some(expr) = source_ptr.value.left() {
+                self.sink.push(TypeMismatch {
+                    file: source_ptr.file_id,
+                    expr,
+                    expected: mismatch.expected.display(db).to_string(),
+                    actual: mismatch.actual.display(db).to_string(),
+                });
+            }
+        }
+    }
+
+    fn validate_unsafe(&mut self, db: &impl HirDatabase) {
+        let unsafe_exprs = db.unsafe_expressions(self.func.into());
+        let (_, source_map) = db.body_with_source_map(self.func.into());
+
+        for unsafe_expr in unsafe_exprs.iter().filter(|it| !it.inside_unsafe_block) {
+            let source_ptr = match source_map.expr_syntax(unsafe_expr.expr) {
+                Some(it) => it,
+                None => continue,
+            };
+            if let Some(expr) = source_ptr.value.left() {
+                self.sink.push(MissingUnsafe { file: source_ptr.file_id, expr });
+            }
+        }
     }
 }
+
+fn contains_unknown(ty: &Ty) -> bool {
+    let mut res = false;
+    ty.walk(&mut |ty| {
+        if let Ty::Unknown | Ty::Infer(_) = ty {
+            res = true;
+        }
+    });
+    res
+}
@@ -22,23 +22,27 @@ use rustc_hash::FxHashMap;
 
 use hir_def::{
     body::Body,
-    data::{ConstData, FunctionData},
+    data::{ConstData, FunctionData, StaticData},
     expr::{BindingAnnotation, ExprId, PatId},
     path::{path, Path},
     resolver::{HasResolver, Resolver, TypeNs},
     type_ref::{Mutability, TypeRef},
     AdtId, AssocItemId, DefWithBodyId, FunctionId, StructFieldId, TypeAliasId, VariantId,
 };
-use hir_expand::{diagnostics::DiagnosticSink, name::name};
+use hir_expand::{
+    diagnostics::DiagnosticSink,
+    name::{name, Name},
+};
 use ra_arena::map::ArenaMap;
 use ra_prof::profile;
 use test_utils::tested_by;
 
 use super::{
+    lower,
     primitive::{FloatTy, IntTy},
     traits::{Guidance, Obligation, ProjectionPredicate, Solution},
-    ApplicationTy, InEnvironment, ProjectionTy, Substs, TraitEnvironment, TraitRef, Ty, TypeCtor,
-    TypeWalk, Uncertain,
+    ApplicationTy, GenericPredicate, InEnvironment, ProjectionTy, Substs, TraitEnvironment,
+    TraitRef, Ty, TypeCtor, TypeWalk, Uncertain,
 };
 use crate::{db::HirDatabase, infer::diagnostics::InferenceDiagnostic};
 
@@ -58,6 +62,7 @@ mod path;
 mod expr;
 mod pat;
 mod coerce;
+mod closure;
 
 /// The entry point of type inference.
 pub fn infer_query(db: &impl HirDatabase, def: DefWithBodyId) -> Arc<InferenceResult> {
@@ -68,7 +73,7 @@ pub fn infer_query(db: &impl HirDatabase, def: DefWithBodyId) -> Arc<InferenceRe
     match def {
         DefWithBodyId::ConstId(c) => ctx.collect_const(&db.const_data(c)),
         DefWithBodyId::FunctionId(f) => ctx.collect_fn(&db.function_data(f)),
-        DefWithBodyId::StaticId(s) => ctx.collect_const(&db.static_data(s)),
+        DefWithBodyId::StaticId(s) => ctx.collect_static(&db.static_data(s)),
     }
 
     ctx.infer_body();
@@ -159,6 +164,9 @@ impl InferenceResult {
     pub fn type_mismatch_for_expr(&self, expr: ExprId) -> Option<&TypeMismatch> {
         self.type_mismatches.get(expr)
     }
+    pub fn type_mismatches(&self) -> impl Iterator<Item = (ExprId, &TypeMismatch)> {
+        self.type_mismatches.iter()
+    }
     pub fn add_diagnostics(
         &self,
         db: &impl HirDatabase,
@@ -203,6 +211,9 @@ struct InferenceContext<'a, D: HirDatabase> {
     /// closures, but currently this is the only field that will change there,
     /// so it doesn't make sense.
     return_ty: Ty,
+    /// The loops enclosing the expression currently being inferred, innermost
+    /// last. `break` and `continue` refer to these.
+    breakables: Vec<BreakableContext>,
 
     /// Impls of `CoerceUnsized` used in coercion.
     /// (from_ty_ctor, to_ty_ctor) => coerce_generic_index
@@ -218,6 +229,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             table: unify::InferenceTable::new(),
             obligations: Vec::default(),
             return_ty: Ty::Unknown, // set in collect_fn_signature
+            breakables: Vec::new(),
             trait_env: TraitEnvironment::lower(db, &resolver),
             coerce_unsized_map: Self::init_coerce_unsized_map(db, &resolver),
             db,
@@ -238,6 +250,12 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             let resolved = self.table.resolve_ty_completely(mem::replace(ty, Ty::Unknown));
             *ty = resolved;
         }
+        for mismatch in result.type_mismatches.values_mut() {
+            mismatch.expected =
+                self.table.resolve_ty_completely(mem::replace(&mut mismatch.expected, Ty::Unknown));
+            mismatch.actual =
+                self.table.resolve_ty_completely(mem::replace(&mut mismatch.actual, Ty::Unknown));
+        }
         result
     }
 
@@ -379,6 +397,9 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
     ) -> Ty {
         match assoc_ty {
             Some(res_assoc_ty) => {
+                if let Some(ty) = self.opaque_assoc_ty_binding(&inner_ty, res_assoc_ty, params) {
+                    return ty;
+                }
                 let ty = self.table.new_type_var();
                 let builder = Substs::build_for_def(self.db, res_assoc_ty)
                     .push(inner_ty)
@@ -397,6 +418,38 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         }
     }
 
+    /// Looks up the value of an associated type in the bounds of an `impl
+    /// Trait` type, like the `Output` of the future returned by an `async fn`.
+    // FIXME: Chalk should be able to normalize these projections itself.
+    fn opaque_assoc_ty_binding(
+        &mut self,
+        ty: &Ty,
+        assoc_ty: TypeAliasId,
+        params: &[Ty],
+    ) -> Option<Ty> {
+        let ty = self.resolve_ty_shallow(ty).into_owned();
+        let predicates = match &ty {
+            Ty::Opaque(predicates) => predicates,
+            _ => return None,
+        };
+        predicates.iter().find_map(|pred| match pred {
+            GenericPredicate::Projection(proj)
+                if proj.projection_ty.associated_ty == assoc_ty
+                    && proj.projection_ty.parameters[1..] == *params =>
+            {
+                Some(proj.ty.clone().subst_bound_vars(&Substs::single(ty.clone())))
+            }
+            _ => None,
+        })
+    }
+
+    fn impl_future_ty(&self, output: Ty) -> Ty {
+        match self.resolver.krate() {
+            Some(krate) => lower::impl_future_ty(self.db, krate, output),
+            None => Ty::Unknown,
+        }
+    }
+
     /// Recurses through the given type, normalizing associated types mentioned
     /// in it by replacing them by type variables and registering obligations to
     /// resolve later. This should be done once for every type we get from some
@@ -448,6 +501,10 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         self.return_ty = self.make_ty(&data.type_ref);
     }
 
+    fn collect_static(&mut self, data: &StaticData) {
+        self.return_ty = self.make_ty(&data.type_ref);
+    }
+
     fn collect_fn(&mut self, data: &FunctionData) {
         let body = Arc::clone(&self.body); // avoid borrow checker problem
         for (type_ref, pat) in data.params.iter().zip(body.params.iter()) {
@@ -461,6 +518,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
 
     fn infer_body(&mut self) {
         self.infer_expr_coerce(self.body.body_expr, &Expectation::has_type(self.return_ty.clone()));
+        self.infer_closure_kinds();
     }
 
     fn resolve_into_iter_item(&self) -> Option<TypeAliasId> {
@@ -574,6 +632,29 @@ impl InferTy {
     }
 }
 
+/// A loop that can be exited with `break`, possibly with a value.
+#[derive(Clone, Debug)]
+struct BreakableContext {
+    /// Whether there's a `break` targeting this loop. A `loop` without one
+    /// never finishes.
+    may_break: bool,
+    /// The type of the values the loop is broken with, if any.
+    break_ty: Ty,
+    label: Option<Name>,
+}
+
+/// Finds the loop a `break` or `continue` refers to: the innermost loop with
+/// the given label, or the innermost loop if there's no label.
+fn find_breakable<'c>(
+    ctxs: &'c mut [BreakableContext],
+    label: Option<&Name>,
+) -> Option<&'c mut BreakableContext> {
+    match label {
+        Some(label) => ctxs.iter_mut().rev().find(|ctx| ctx.label.as_ref() == Some(label)),
+        None => ctxs.last_mut(),
+    }
+}
+
 /// When inferring an expression, we propagate downward whatever type hint we
 /// are able in the form of an `Expectation`.
 #[derive(Clone, PartialEq, Eq, Debug)]
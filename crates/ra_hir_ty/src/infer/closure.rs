@@ -0,0 +1,443 @@
+//! Inference specific to closures: deducing the signature of a closure from
+//! the type it is expected to have, calling values through the `Fn` traits and
+//! computing the closure kind from the way captured variables are used.
+
+use std::sync::Arc;
+
+use hir_def::{
+    body::scope::{ExprScopes, ScopeId},
+    expr::{BinaryOp, Expr, ExprId, UnaryOp},
+    lang_item::LangItemTarget,
+    path::Path,
+    type_ref::{Mutability, TypeRef},
+};
+use hir_expand::name::name;
+
+use crate::{
+    autoderef,
+    db::HirDatabase,
+    traits::{FnTrait, InEnvironment, Solution},
+    ApplicationTy, GenericPredicate, Obligation, ProjectionTy, Substs, TraitRef, Ty, TypeCtor,
+};
+
+use super::{InferTy, InferenceContext};
+
+/// How a captured variable is used inside of a closure body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CaptureUsage {
+    ByRef,
+    ByMutRef,
+    ByValue,
+}
+
+impl<'a, D: HirDatabase> InferenceContext<'a, D> {
+    /// Tries to find out the parameter types and maybe the return type of a
+    /// closure with `num_args` parameters from the type it is expected to have.
+    /// This is either a function pointer, a `dyn Fn` / `impl Fn` type, or a
+    /// type variable with `Fn` trait obligations on it, which is what we get
+    /// for closures passed to a function like `fn map<F: FnMut(T) -> U>(f: F)`.
+    pub(super) fn deduce_closure_signature(
+        &mut self,
+        expected_ty: &Ty,
+        num_args: usize,
+    ) -> Option<(Vec<Ty>, Option<Ty>)> {
+        let expected_ty = self.resolve_ty_shallow(expected_ty).into_owned();
+        let (params, ret) = match &expected_ty {
+            Ty::Apply(_) => {
+                let sig = expected_ty.callable_sig(self.db)?;
+                (sig.params().to_vec(), Some(sig.ret().clone()))
+            }
+            Ty::Dyn(predicates) | Ty::Opaque(predicates) => {
+                let self_subst = Substs::single(expected_ty.clone());
+                let predicates: Vec<_> = predicates
+                    .iter()
+                    .map(|pred| pred.clone().subst_bound_vars(&self_subst))
+                    .collect();
+                self.fn_sig_from_predicates(predicates, num_args)?
+            }
+            Ty::Infer(InferTy::TypeVar(_)) => {
+                let obligations = self.obligations.clone();
+                let predicates: Vec<_> = obligations
+                    .into_iter()
+                    .filter_map(|obligation| match obligation {
+                        Obligation::Trait(trait_ref) => {
+                            if *self.resolve_ty_shallow(trait_ref.self_ty()) == expected_ty {
+                                Some(GenericPredicate::Implemented(trait_ref))
+                            } else {
+                                None
+                            }
+                        }
+                        Obligation::Projection(proj) => {
+                            let self_ty = &proj.projection_ty.parameters[0];
+                            if *self.resolve_ty_shallow(self_ty) == expected_ty {
+                                Some(GenericPredicate::Projection(proj))
+                            } else {
+                                None
+                            }
+                        }
+                    })
+                    .collect();
+                self.fn_sig_from_predicates(predicates, num_args)?
+            }
+            _ => return None,
+        };
+        if params.len() != num_args {
+            return None;
+        }
+        let params = params.into_iter().map(|it| self.normalize_associated_types_in(it)).collect();
+        Some((params, ret))
+    }
+
+    /// Extracts the parameter types and maybe the return type from bounds like
+    /// `T: Fn<(A, B)>` and `<T as FnOnce<(A, B)>>::Output == R`.
+    fn fn_sig_from_predicates(
+        &self,
+        predicates: Vec<GenericPredicate>,
+        num_args: usize,
+    ) -> Option<(Vec<Ty>, Option<Ty>)> {
+        let krate = self.resolver.krate()?;
+        let fn_traits: Vec<_> = [FnTrait::FnOnce, FnTrait::FnMut, FnTrait::Fn]
+            .iter()
+            .filter_map(|it| it.get_id(self.db, krate))
+            .collect();
+        let output = FnTrait::FnOnce
+            .get_id(self.db, krate)
+            .and_then(|it| self.db.trait_data(it).associated_type_by_name(&name![Output]));
+
+        let mut params = None;
+        let mut ret = None;
+        for predicate in predicates {
+            match predicate {
+                GenericPredicate::Implemented(trait_ref)
+                    if fn_traits.contains(&trait_ref.trait_) =>
+                {
+                    if let Some(args) = trait_ref.substs.0.get(1).and_then(|it| it.as_tuple()) {
+                        if args.len() == num_args {
+                            params = Some(args.0.to_vec());
+                        }
+                    }
+                }
+                GenericPredicate::Projection(proj)
+                    if Some(proj.projection_ty.associated_ty) == output =>
+                {
+                    ret = Some(proj.ty);
+                }
+                _ => {}
+            }
+        }
+        Some((params?, ret))
+    }
+
+    /// Returns the parameter and return types for calling a value of the given
+    /// type. Besides functions and closures, this handles calling anything that
+    /// implements the `Fn` traits, like type parameters with `Fn` bounds or
+    /// `Box<dyn Fn()>`.
+    pub(super) fn callable_sig(&mut self, ty: &Ty, num_args: usize) -> Option<(Vec<Ty>, Ty)> {
+        if let Some(sig) = ty.callable_sig(self.db) {
+            return Some((sig.params().to_vec(), sig.ret().clone()));
+        }
+        match &*self.resolve_ty_shallow(ty) {
+            Ty::Unknown | Ty::Infer(_) => return None,
+            _ => {}
+        }
+
+        let canonicalized = self.canonicalizer().canonicalize_ty(ty.clone());
+        let derefed_tys: Vec<_> = autoderef::autoderef(
+            self.db,
+            self.resolver.krate(),
+            InEnvironment {
+                value: canonicalized.value.clone(),
+                environment: self.trait_env.clone(),
+            },
+        )
+        .map(|derefed_ty| canonicalized.decanonicalize_ty(derefed_ty.value))
+        .collect();
+
+        derefed_tys.into_iter().find_map(|derefed_ty| {
+            if let Some(sig) = derefed_ty.callable_sig(self.db) {
+                return Some((sig.params().to_vec(), sig.ret().clone()));
+            }
+            if let Ty::Dyn(_) | Ty::Opaque(_) = &derefed_ty {
+                if let Some((params, Some(ret))) =
+                    self.deduce_closure_signature(&derefed_ty, num_args)
+                {
+                    return Some((params, ret));
+                }
+            }
+            self.callable_sig_from_fn_trait(derefed_ty, num_args)
+        })
+    }
+
+    /// Calls `ty` through `FnOnce`, if the trait solver can prove that `ty`
+    /// implements it for `num_args` arguments.
+    fn callable_sig_from_fn_trait(&mut self, ty: Ty, num_args: usize) -> Option<(Vec<Ty>, Ty)> {
+        let krate = self.resolver.krate()?;
+        let fn_once_trait = FnTrait::FnOnce.get_id(self.db, krate)?;
+        let output_assoc_type =
+            self.db.trait_data(fn_once_trait).associated_type_by_name(&name![Output])?;
+
+        let arg_tys: Vec<_> = (0..num_args).map(|_| self.table.new_type_var()).collect();
+        let arg_tuple = Ty::apply(
+            TypeCtor::Tuple { cardinality: num_args as u16 },
+            Substs(arg_tys.clone().into()),
+        );
+        let substs = Substs::build_for_def(self.db, fn_once_trait).push(ty).push(arg_tuple).build();
+
+        let obligation =
+            Obligation::Trait(TraitRef { trait_: fn_once_trait, substs: substs.clone() });
+        let in_env = InEnvironment::new(self.trait_env.clone(), obligation.clone());
+        let canonicalized = self.canonicalizer().canonicalize_obligation(in_env);
+        self.db.trait_solve(krate, canonicalized.value)?;
+
+        self.obligations.push(obligation);
+        let ret_ty = self.normalize_projection_ty(ProjectionTy {
+            associated_ty: output_assoc_type,
+            parameters: substs,
+        });
+        Some((arg_tys, ret_ty))
+    }
+
+    /// Computes which `Fn` trait each closure in the body implements, from how
+    /// it uses the variables it captures: moving out of one makes it `FnOnce`,
+    /// mutating one makes it `FnMut`, and only reading them makes it `Fn`. The
+    /// kind is recorded in the closure type, which restricts the `Fn` trait
+    /// impls the closure gets.
+    ///
+    /// Closures are lowered before the expressions containing them, so the
+    /// kinds of closures which are called by other closures are known by the
+    /// time we get to their callers.
+    pub(super) fn infer_closure_kinds(&mut self) {
+        let body = Arc::clone(&self.body);
+        let scopes = self.db.expr_scopes(self.owner);
+        for (expr, data) in body.exprs.iter() {
+            let closure_body = match data {
+                Expr::Lambda { body, .. } => *body,
+                _ => continue,
+            };
+            let closure_scope = match scopes.scope_for(closure_body) {
+                Some(it) => it,
+                None => continue,
+            };
+            let mut kind = FnTrait::Fn;
+            self.walk_closure_body(
+                &scopes,
+                closure_scope,
+                closure_body,
+                CaptureUsage::ByValue,
+                &mut kind,
+            );
+            let kind_ty = match self.result.type_of_expr.get(expr) {
+                Some(Ty::Apply(ApplicationTy { ctor: TypeCtor::Closure { .. }, parameters })) => {
+                    parameters[1].clone()
+                }
+                _ => continue,
+            };
+            self.unify(&kind_ty, &kind.to_closure_kind_ty());
+        }
+    }
+
+    fn walk_closure_body(
+        &mut self,
+        scopes: &ExprScopes,
+        closure_scope: ScopeId,
+        expr: ExprId,
+        usage: CaptureUsage,
+        kind: &mut FnTrait,
+    ) {
+        // Using a `Copy` place by value just copies it.
+        let usage = if usage == CaptureUsage::ByValue && self.is_copy(expr) {
+            CaptureUsage::ByRef
+        } else {
+            usage
+        };
+        let body = Arc::clone(&self.body);
+        let place_usage = match usage {
+            CaptureUsage::ByMutRef => CaptureUsage::ByMutRef,
+            CaptureUsage::ByRef | CaptureUsage::ByValue => CaptureUsage::ByRef,
+        };
+        match &body[expr] {
+            Expr::Path(path) => {
+                if is_captured(scopes, closure_scope, expr, path) {
+                    let required = match usage {
+                        CaptureUsage::ByRef => FnTrait::Fn,
+                        CaptureUsage::ByMutRef => FnTrait::FnMut,
+                        CaptureUsage::ByValue => FnTrait::FnOnce,
+                    };
+                    *kind = (*kind).min(required);
+                }
+            }
+            Expr::Field { expr: base, .. } => {
+                self.walk_closure_body(scopes, closure_scope, *base, usage, kind)
+            }
+            Expr::UnaryOp { expr: inner, op: UnaryOp::Deref } => {
+                self.walk_closure_body(scopes, closure_scope, *inner, place_usage, kind)
+            }
+            Expr::Index { base, index } => {
+                self.walk_closure_body(scopes, closure_scope, *base, place_usage, kind);
+                self.walk_closure_body(scopes, closure_scope, *index, CaptureUsage::ByValue, kind);
+            }
+            Expr::Ref { expr: inner, mutability } => {
+                let usage = match mutability {
+                    Mutability::Mut => CaptureUsage::ByMutRef,
+                    Mutability::Shared => CaptureUsage::ByRef,
+                };
+                self.walk_closure_body(scopes, closure_scope, *inner, usage, kind);
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::Assignment { .. }) } => {
+                self.walk_closure_body(scopes, closure_scope, *lhs, CaptureUsage::ByMutRef, kind);
+                self.walk_closure_body(scopes, closure_scope, *rhs, CaptureUsage::ByValue, kind);
+            }
+            Expr::BinaryOp { lhs, rhs, op: Some(BinaryOp::CmpOp(_)) } => {
+                self.walk_closure_body(scopes, closure_scope, *lhs, CaptureUsage::ByRef, kind);
+                self.walk_closure_body(scopes, closure_scope, *rhs, CaptureUsage::ByRef, kind);
+            }
+            Expr::Call { callee, args } => {
+                // Calling a closure uses it the way its own kind requires.
+                let callee_ty =
+                    self.result.type_of_expr.get(*callee).cloned().unwrap_or(Ty::Unknown);
+                let callee_usage = match self.table.resolve_ty_completely(callee_ty).closure_kind()
+                {
+                    Some(FnTrait::Fn) => CaptureUsage::ByRef,
+                    Some(FnTrait::FnMut) => CaptureUsage::ByMutRef,
+                    Some(FnTrait::FnOnce) | None => CaptureUsage::ByValue,
+                };
+                self.walk_closure_body(scopes, closure_scope, *callee, callee_usage, kind);
+                for arg in args {
+                    self.walk_closure_body(
+                        scopes,
+                        closure_scope,
+                        *arg,
+                        CaptureUsage::ByValue,
+                        kind,
+                    );
+                }
+            }
+            Expr::MethodCall { receiver, args, .. } => {
+                let receiver_usage = match self.result.method_resolutions.get(&expr) {
+                    Some(&func) => {
+                        let data = self.db.function_data(func);
+                        match data.params.first() {
+                            Some(TypeRef::Reference(_, _, Mutability::Mut))
+                                if data.has_self_param =>
+                            {
+                                CaptureUsage::ByMutRef
+                            }
+                            Some(TypeRef::Reference(..)) if data.has_self_param => {
+                                CaptureUsage::ByRef
+                            }
+                            _ => CaptureUsage::ByValue,
+                        }
+                    }
+                    None => CaptureUsage::ByRef,
+                };
+                self.walk_closure_body(scopes, closure_scope, *receiver, receiver_usage, kind);
+                for arg in args {
+                    self.walk_closure_body(
+                        scopes,
+                        closure_scope,
+                        *arg,
+                        CaptureUsage::ByValue,
+                        kind,
+                    );
+                }
+            }
+            Expr::Match { expr: scrutinee, arms } => {
+                // FIXME: take binding modes into account, matching by value
+                // may move out of the scrutinee.
+                self.walk_closure_body(
+                    scopes,
+                    closure_scope,
+                    *scrutinee,
+                    CaptureUsage::ByRef,
+                    kind,
+                );
+                for arm in arms {
+                    if let Some(guard) = arm.guard {
+                        self.walk_closure_body(
+                            scopes,
+                            closure_scope,
+                            guard,
+                            CaptureUsage::ByValue,
+                            kind,
+                        );
+                    }
+                    self.walk_closure_body(
+                        scopes,
+                        closure_scope,
+                        arm.expr,
+                        CaptureUsage::ByValue,
+                        kind,
+                    );
+                }
+            }
+            e => e.walk_child_exprs(|child| {
+                self.walk_closure_body(scopes, closure_scope, child, CaptureUsage::ByValue, kind)
+            }),
+        }
+    }
+
+    fn is_copy(&mut self, expr: ExprId) -> bool {
+        let ty = self.result.type_of_expr.get(expr).cloned().unwrap_or(Ty::Unknown);
+        let ty = self.table.resolve_ty_completely(ty);
+        self.is_copy_ty(ty)
+    }
+
+    fn is_copy_ty(&mut self, ty: Ty) -> bool {
+        match &ty {
+            // Err on the side of `Fn` if we don't know better.
+            Ty::Unknown | Ty::Infer(_) => return true,
+            Ty::Apply(a_ty) => match a_ty.ctor {
+                TypeCtor::Bool
+                | TypeCtor::Char
+                | TypeCtor::Int(_)
+                | TypeCtor::Float(_)
+                | TypeCtor::Never
+                | TypeCtor::RawPtr(_)
+                | TypeCtor::Ref(Mutability::Shared)
+                | TypeCtor::FnPtr { .. }
+                | TypeCtor::FnDef(_) => return true,
+                TypeCtor::Ref(Mutability::Mut) | TypeCtor::Str | TypeCtor::Slice => return false,
+                TypeCtor::Tuple { .. } | TypeCtor::Array { .. } => {
+                    return a_ty.parameters.iter().all(|it| self.is_copy_ty(it.clone()))
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        let krate = match self.resolver.krate() {
+            Some(it) => it,
+            None => return true,
+        };
+        let copy_trait = match self.db.lang_item(krate, "copy".into()) {
+            Some(LangItemTarget::TraitId(it)) => it,
+            _ => return false,
+        };
+        let substs = Substs::build_for_def(self.db, copy_trait).push(ty).build();
+        let obligation = Obligation::Trait(TraitRef { trait_: copy_trait, substs });
+        let in_env = InEnvironment::new(self.trait_env.clone(), obligation);
+        let canonicalized = self.canonicalizer().canonicalize_obligation(in_env);
+        match self.db.trait_solve(krate, canonicalized.value) {
+            Some(Solution::Unique(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Whether the path refers to a local variable declared outside of the
+/// closure with the given scope.
+fn is_captured(scopes: &ExprScopes, closure_scope: ScopeId, expr: ExprId, path: &Path) -> bool {
+    let name = match path.mod_path().as_ident() {
+        Some(it) => it,
+        None => return false,
+    };
+    let mut inside = true;
+    for scope in scopes.scope_chain(scopes.scope_for(expr)) {
+        if scopes.entries(scope).iter().any(|entry| entry.name() == name) {
+            return !inside;
+        }
+        if scope == closure_scope {
+            inside = false;
+        }
+    }
+    false
+}
@@ -200,7 +200,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
 
         match (&from_ty, &to_ty) {
             // `[T; N]` -> `[T]`
-            (ty_app!(TypeCtor::Array, st1), ty_app!(TypeCtor::Slice, st2)) => {
+            (ty_app!(TypeCtor::Array { .. }, st1), ty_app!(TypeCtor::Slice, st2)) => {
                 Some(self.unify(&st1[0], &st2[0]))
             }
 
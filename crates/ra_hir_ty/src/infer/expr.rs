@@ -14,7 +14,7 @@ use hir_expand::name::{name, Name};
 use ra_syntax::ast::RangeOp;
 
 use crate::{
-    autoderef,
+    autoderef, consteval,
     db::HirDatabase,
     method_resolution, op,
     traits::InEnvironment,
@@ -23,7 +23,10 @@ use crate::{
     TypeCtor, TypeWalk, Uncertain,
 };
 
-use super::{BindingMode, Expectation, InferenceContext, InferenceDiagnostic, TypeMismatch};
+use super::{
+    find_breakable, BindingMode, BreakableContext, Expectation, InferenceContext,
+    InferenceDiagnostic, TypeMismatch,
+};
 
 impl<'a, D: HirDatabase> InferenceContext<'a, D> {
     pub(super) fn infer_expr(&mut self, tgt_expr: ExprId, expected: &Expectation) -> Ty {
@@ -81,55 +84,109 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 // FIXME should be std::result::Result<{inner}, _>
                 Ty::Unknown
             }
-            Expr::Loop { body } => {
+            Expr::Async { body } => {
+                // `return` inside an async block completes the future, not the
+                // enclosing function.
+                let ret_ty = self.table.new_type_var();
+                let prev_ret_ty = std::mem::replace(&mut self.return_ty, ret_ty.clone());
+                let prev_breakables = std::mem::replace(&mut self.breakables, Vec::new());
+
+                let inner_ty = self.infer_expr_coerce(*body, &Expectation::has_type(ret_ty));
+
+                self.breakables = prev_breakables;
+                self.return_ty = prev_ret_ty;
+
+                self.impl_future_ty(inner_ty)
+            }
+            Expr::Unsafe { body } => self.infer_expr(*body, expected),
+            Expr::Loop { body, label } => {
+                self.breakables.push(BreakableContext {
+                    may_break: false,
+                    break_ty: self.table.new_type_var(),
+                    label: label.clone(),
+                });
                 self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
-                // FIXME handle break with value
-                Ty::simple(TypeCtor::Never)
+
+                let ctxt = self.breakables.pop().expect("breakable stack broken");
+                if ctxt.may_break {
+                    ctxt.break_ty
+                } else {
+                    Ty::simple(TypeCtor::Never)
+                }
             }
-            Expr::While { condition, body } => {
+            Expr::While { condition, body, label } => {
+                self.breakables.push(BreakableContext {
+                    may_break: false,
+                    break_ty: Ty::unit(),
+                    label: label.clone(),
+                });
                 // while let is desugared to a match loop, so this is always simple while
                 self.infer_expr(*condition, &Expectation::has_type(Ty::simple(TypeCtor::Bool)));
                 self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
+                let _ctxt = self.breakables.pop().expect("breakable stack broken");
                 Ty::unit()
             }
-            Expr::For { iterable, body, pat } => {
+            Expr::For { iterable, body, pat, label } => {
                 let iterable_ty = self.infer_expr(*iterable, &Expectation::none());
 
                 let pat_ty =
                     self.resolve_associated_type(iterable_ty, self.resolve_into_iter_item());
 
                 self.infer_pat(*pat, &pat_ty, BindingMode::default());
+
+                self.breakables.push(BreakableContext {
+                    may_break: false,
+                    break_ty: Ty::unit(),
+                    label: label.clone(),
+                });
                 self.infer_expr(*body, &Expectation::has_type(Ty::unit()));
+                let _ctxt = self.breakables.pop().expect("breakable stack broken");
                 Ty::unit()
             }
-            Expr::Lambda { body, args, ret_type, arg_types } => {
+            Expr::Lambda { body, args, ret_type, arg_types, is_async } => {
                 assert_eq!(args.len(), arg_types.len());
 
                 let mut sig_tys = Vec::new();
 
-                for (arg_pat, arg_type) in args.iter().zip(arg_types.iter()) {
+                // Use the signature of the `Fn` trait bound the closure is
+                // passed to, if any, so that the parameter types are known
+                // before checking the body (e.g. for `iter.map(|x| x.foo())`).
+                let (expected_params, expected_ret) =
+                    match self.deduce_closure_signature(&expected.ty, args.len()) {
+                        Some((params, ret)) => (params, ret),
+                        None => (Vec::new(), None),
+                    };
+                for (idx, (arg_pat, arg_type)) in args.iter().zip(arg_types.iter()).enumerate() {
                     let expected = if let Some(type_ref) = arg_type {
                         self.make_ty(type_ref)
                     } else {
-                        Ty::Unknown
+                        expected_params.get(idx).cloned().unwrap_or(Ty::Unknown)
                     };
                     let arg_ty = self.infer_pat(*arg_pat, &expected, BindingMode::default());
                     sig_tys.push(arg_ty);
                 }
 
                 // add return type
-                let ret_ty = match ret_type {
-                    Some(type_ref) => self.make_ty(type_ref),
-                    None => self.table.new_type_var(),
+                let ret_ty = match (ret_type, expected_ret) {
+                    (Some(type_ref), _) => self.make_ty(type_ref),
+                    (None, Some(ret_ty)) if !*is_async => ret_ty,
+                    (None, _) => self.table.new_type_var(),
                 };
-                sig_tys.push(ret_ty.clone());
+                if *is_async {
+                    sig_tys.push(self.impl_future_ty(ret_ty.clone()));
+                } else {
+                    sig_tys.push(ret_ty.clone());
+                }
                 let sig_ty = Ty::apply(
                     TypeCtor::FnPtr { num_args: sig_tys.len() as u16 - 1 },
                     Substs(sig_tys.into()),
                 );
-                let closure_ty = Ty::apply_one(
+                // The kind is only known once the whole body has been
+                // inferred, see `infer_closure_kinds`.
+                let kind_ty = self.table.new_type_var();
+                let closure_ty = Ty::apply(
                     TypeCtor::Closure { def: self.owner.into(), expr: tgt_expr },
-                    sig_ty,
+                    Substs(vec![sig_ty, kind_ty].into()),
                 );
 
                 // Eagerly try to relate the closure type with the expected
@@ -138,17 +195,19 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 self.coerce(&closure_ty, &expected.ty);
 
                 let prev_ret_ty = std::mem::replace(&mut self.return_ty, ret_ty.clone());
+                let prev_breakables = std::mem::replace(&mut self.breakables, Vec::new());
 
                 self.infer_expr_coerce(*body, &Expectation::has_type(ret_ty));
 
+                self.breakables = prev_breakables;
                 self.return_ty = prev_ret_ty;
 
                 closure_ty
             }
             Expr::Call { callee, args } => {
                 let callee_ty = self.infer_expr(*callee, &Expectation::none());
-                let (param_tys, ret_ty) = match callee_ty.callable_sig(self.db) {
-                    Some(sig) => (sig.params().to_vec(), sig.ret().clone()),
+                let (param_tys, ret_ty) = match self.callable_sig(&callee_ty, args.len()) {
+                    Some(sig) => sig,
                     None => {
                         // Not callable
                         // FIXME: report an error
@@ -189,12 +248,24 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 let resolver = resolver_for_expr(self.db, self.owner.into(), tgt_expr);
                 self.infer_path(&resolver, p, tgt_expr.into()).unwrap_or(Ty::Unknown)
             }
-            Expr::Continue => Ty::simple(TypeCtor::Never),
-            Expr::Break { expr } => {
-                if let Some(expr) = expr {
-                    // FIXME handle break with value
-                    self.infer_expr(*expr, &Expectation::none());
+            Expr::Continue { .. } => Ty::simple(TypeCtor::Never),
+            Expr::Break { expr, label } => {
+                let last_ty = match find_breakable(&mut self.breakables, label.as_ref()) {
+                    Some(ctxt) => ctxt.break_ty.clone(),
+                    None => Ty::Unknown,
+                };
+
+                let val_ty = match expr {
+                    Some(expr) => self.infer_expr(*expr, &Expectation::has_type(last_ty.clone())),
+                    None => Ty::unit(),
+                };
+
+                let merged_ty = self.coerce_merge_branch(&last_ty, &val_ty);
+                if let Some(ctxt) = find_breakable(&mut self.breakables, label.as_ref()) {
+                    ctxt.break_ty = merged_ty;
+                    ctxt.may_break = true;
                 }
+                // FIXME: report `break` outside of a loop or with an unknown label
                 Ty::simple(TypeCtor::Never)
             }
             Expr::Return { expr } => {
@@ -270,8 +341,15 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                                     .subst(&a_ty.parameters)
                             })
                         }
-                        // FIXME:
-                        TypeCtor::Adt(AdtId::UnionId(_)) => None,
+                        TypeCtor::Adt(AdtId::UnionId(u)) => {
+                            self.db.union_data(u).variant_data.field(name).map(|local_id| {
+                                let field = StructFieldId { parent: u.into(), local_id };
+                                self.write_field_resolution(tgt_expr, field);
+                                self.db.field_types(u.into())[field.local_id]
+                                    .clone()
+                                    .subst(&a_ty.parameters)
+                            })
+                        }
                         _ => None,
                     },
                     _ => None,
@@ -461,17 +539,18 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             }
             Expr::Array(array) => {
                 let elem_ty = match &expected.ty {
-                    ty_app!(TypeCtor::Array, st) | ty_app!(TypeCtor::Slice, st) => {
+                    ty_app!(TypeCtor::Array { .. }, st) | ty_app!(TypeCtor::Slice, st) => {
                         st.as_single().clone()
                     }
                     _ => self.table.new_type_var(),
                 };
 
-                match array {
+                let len = match array {
                     Array::ElementList(items) => {
                         for expr in items.iter() {
                             self.infer_expr_coerce(*expr, &Expectation::has_type(elem_ty.clone()));
                         }
+                        Some(items.len() as u64)
                     }
                     Array::Repeat { initializer, repeat } => {
                         self.infer_expr_coerce(
@@ -484,10 +563,12 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                                 IntTy::usize(),
                             )))),
                         );
+                        let resolver = resolver_for_expr(self.db, self.owner.into(), *repeat);
+                        consteval::eval_expr(self.db, &resolver, &self.body, *repeat)
                     }
-                }
+                };
 
-                Ty::apply_one(TypeCtor::Array, elem_ty)
+                Ty::apply_one(TypeCtor::Array { len: len.into() }, elem_ty)
             }
             Expr::Literal(lit) => match lit {
                 Literal::Bool(..) => Ty::simple(TypeCtor::Bool),
@@ -657,13 +738,10 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         // handle provided type arguments
         if let Some(generic_args) = generic_args {
             // if args are provided, it should be all of them, but we can't rely on that
-            for arg in generic_args.args.iter().take(child_len) {
-                match arg {
-                    GenericArg::Type(type_ref) => {
-                        let ty = self.make_ty(type_ref);
-                        substs.push(ty);
-                    }
-                }
+            let type_args = generic_args.args.iter().filter_map(GenericArg::as_type);
+            for type_ref in type_args.take(child_len) {
+                let ty = self.make_ty(type_ref);
+                substs.push(ty);
             }
         };
         let supplied_params = substs.len();
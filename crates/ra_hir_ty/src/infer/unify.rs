@@ -9,7 +9,7 @@ use test_utils::tested_by;
 use super::{InferenceContext, Obligation};
 use crate::{
     db::HirDatabase, utils::make_mut_slice, Canonical, InEnvironment, InferTy, ProjectionPredicate,
-    ProjectionTy, Substs, TraitRef, Ty, TypeCtor, TypeWalk,
+    ProjectionTy, Substs, TraitRef, Ty, TypeCtor, TypeWalk, Uncertain,
 };
 
 impl<'a, D: HirDatabase> InferenceContext<'a, D> {
@@ -238,7 +238,7 @@ impl InferenceTable {
         let ty1 = self.resolve_ty_shallow(ty1);
         let ty2 = self.resolve_ty_shallow(ty2);
         match (&*ty1, &*ty2) {
-            (Ty::Apply(a_ty1), Ty::Apply(a_ty2)) if a_ty1.ctor == a_ty2.ctor => {
+            (Ty::Apply(a_ty1), Ty::Apply(a_ty2)) if ctors_unify(a_ty1.ctor, a_ty2.ctor) => {
                 self.unify_substs(&a_ty1.parameters, &a_ty2.parameters, depth + 1)
             }
             _ => self.unify_inner_trivial(&ty1, &ty2),
@@ -367,6 +367,16 @@ impl InferenceTable {
     }
 }
 
+/// Type constructors unify if they are equal, except that an array whose length
+/// we couldn't evaluate unifies with arrays of any length.
+fn ctors_unify(ctor1: TypeCtor, ctor2: TypeCtor) -> bool {
+    match (ctor1, ctor2) {
+        (TypeCtor::Array { len: Uncertain::Unknown }, TypeCtor::Array { .. })
+        | (TypeCtor::Array { .. }, TypeCtor::Array { len: Uncertain::Unknown }) => true,
+        _ => ctor1 == ctor2,
+    }
+}
+
 /// The ID of a type variable.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct TypeVarId(pub(super) u32);
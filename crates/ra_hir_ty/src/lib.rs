@@ -21,6 +21,7 @@ macro_rules! impl_froms {
 }
 
 mod autoderef;
+mod consteval;
 pub mod primitive;
 pub mod traits;
 pub mod method_resolution;
@@ -32,6 +33,7 @@ pub(crate) mod utils;
 pub mod db;
 pub mod diagnostics;
 pub mod expr;
+pub mod unsafe_validation;
 
 #[cfg(test)]
 mod tests;
@@ -61,7 +63,7 @@ pub use autoderef::autoderef;
 pub use infer::{infer_query, InferTy, InferenceResult};
 pub use lower::CallableDef;
 pub use lower::{callable_item_sig, TyDefId, ValueTyDefId};
-pub use traits::{InEnvironment, Obligation, ProjectionPredicate, TraitEnvironment};
+pub use traits::{FnTrait, InEnvironment, Obligation, ProjectionPredicate, TraitEnvironment};
 
 /// A type constructor or type name: this might be something like the primitive
 /// type `bool`, a struct like `Vec`, or things like function pointers or
@@ -91,7 +93,7 @@ pub enum TypeCtor {
     Slice,
 
     /// An array with the given length. Written as `[T; n]`.
-    Array,
+    Array { len: Uncertain<u64> },
 
     /// A raw pointer. Written as `*mut T` or `*const T`
     RawPtr(Mutability),
@@ -139,7 +141,7 @@ pub enum TypeCtor {
     /// The type of a specific closure.
     ///
     /// The closure signature is stored in a `FnPtr` type in the first type
-    /// parameter.
+    /// parameter, and its kind in the second one (see `FnTrait`).
     Closure { def: DefWithBodyId, expr: ExprId },
 }
 
@@ -159,12 +161,9 @@ impl TypeCtor {
             | TypeCtor::Float(_)
             | TypeCtor::Str
             | TypeCtor::Never => 0,
-            TypeCtor::Slice
-            | TypeCtor::Array
-            | TypeCtor::RawPtr(_)
-            | TypeCtor::Ref(_)
-            | TypeCtor::Closure { .. } // 1 param representing the signature of the closure
-            => 1,
+            TypeCtor::Slice | TypeCtor::Array { .. } | TypeCtor::RawPtr(_) | TypeCtor::Ref(_) => 1,
+            // the signature and the kind of the closure
+            TypeCtor::Closure { .. } => 2,
             TypeCtor::Adt(adt) => {
                 let generic_params = generics(db, AdtId::from(adt).into());
                 generic_params.len()
@@ -191,7 +190,7 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never
             | TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array { .. }
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::FnPtr { .. }
@@ -213,7 +212,7 @@ impl TypeCtor {
             | TypeCtor::Str
             | TypeCtor::Never
             | TypeCtor::Slice
-            | TypeCtor::Array
+            | TypeCtor::Array { .. }
             | TypeCtor::RawPtr(_)
             | TypeCtor::Ref(_)
             | TypeCtor::FnPtr { .. }
@@ -549,16 +548,19 @@ pub struct Canonical<T> {
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct FnSig {
     params_and_return: Arc<[Ty]>,
+    /// True for foreign functions like `printf`, which take further arguments
+    /// after `params`.
+    pub is_varargs: bool,
 }
 
 impl FnSig {
-    pub fn from_params_and_return(mut params: Vec<Ty>, ret: Ty) -> FnSig {
+    pub fn from_params_and_return(mut params: Vec<Ty>, ret: Ty, is_varargs: bool) -> FnSig {
         params.push(ret);
-        FnSig { params_and_return: params.into() }
+        FnSig { params_and_return: params.into(), is_varargs }
     }
 
     pub fn from_fn_ptr_substs(substs: &Substs) -> FnSig {
-        FnSig { params_and_return: Arc::clone(&substs.0) }
+        FnSig { params_and_return: Arc::clone(&substs.0), is_varargs: false }
     }
 
     pub fn params(&self) -> &[Ty] {
@@ -634,6 +636,17 @@ impl Ty {
         }
     }
 
+    /// The most general `Fn` trait this closure type implements, once
+    /// inference has computed it.
+    pub fn closure_kind(&self) -> Option<FnTrait> {
+        match self {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Closure { .. }, parameters }) => {
+                FnTrait::from_closure_kind_ty(&parameters[1])
+            }
+            _ => None,
+        }
+    }
+
     fn builtin_deref(&self) -> Option<Ty> {
         match self {
             Ty::Apply(a_ty) => match a_ty.ctor {
@@ -843,9 +856,12 @@ impl HirDisplay for ApplicationTy {
                 let t = self.parameters.as_single();
                 write!(f, "[{}]", t.display(f.db))?;
             }
-            TypeCtor::Array => {
+            TypeCtor::Array { len } => {
                 let t = self.parameters.as_single();
-                write!(f, "[{};_]", t.display(f.db))?;
+                match len {
+                    Uncertain::Known(len) => write!(f, "[{}; {}]", t.display(f.db), len)?,
+                    Uncertain::Unknown => write!(f, "[{}; _]", t.display(f.db))?,
+                }
             }
             TypeCtor::RawPtr(m) => {
                 let t = self.parameters.as_single();
@@ -895,6 +911,12 @@ impl HirDisplay for ApplicationTy {
                 }
                 write!(f, "(")?;
                 f.write_joined(sig.params(), ", ")?;
+                if sig.is_varargs {
+                    if !sig.params().is_empty() {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "...")?;
+                }
                 write!(f, ") -> {}", sig.ret().display(f.db))?;
             }
             TypeCtor::Adt(def_id) => {
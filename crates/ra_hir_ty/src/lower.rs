@@ -11,16 +11,19 @@ use std::sync::Arc;
 use hir_def::{
     builtin_type::BuiltinType,
     generics::WherePredicate,
+    lang_item::LangItemTarget,
     path::{GenericArg, Path, PathSegment, PathSegments},
     resolver::{HasResolver, Resolver, TypeNs},
     type_ref::{TypeBound, TypeRef},
     AdtId, ConstId, EnumId, EnumVariantId, FunctionId, GenericDefId, HasModule, ImplId,
     LocalStructFieldId, Lookup, StaticId, StructId, TraitId, TypeAliasId, UnionId, VariantId,
 };
+use hir_expand::name::name;
 use ra_arena::map::ArenaMap;
 use ra_db::CrateId;
 
 use crate::{
+    consteval,
     db::HirDatabase,
     primitive::{FloatTy, IntTy},
     utils::{
@@ -48,15 +51,16 @@ impl Ty {
                 let inner_ty = Ty::from_hir(db, resolver, inner);
                 Ty::apply_one(TypeCtor::RawPtr(*mutability), inner_ty)
             }
-            TypeRef::Array(inner) => {
+            TypeRef::Array(inner, len) => {
                 let inner_ty = Ty::from_hir(db, resolver, inner);
-                Ty::apply_one(TypeCtor::Array, inner_ty)
+                let len = consteval::eval_const_ref(db, resolver, len).into();
+                Ty::apply_one(TypeCtor::Array { len }, inner_ty)
             }
             TypeRef::Slice(inner) => {
                 let inner_ty = Ty::from_hir(db, resolver, inner);
                 Ty::apply_one(TypeCtor::Slice, inner_ty)
             }
-            TypeRef::Reference(inner, mutability) => {
+            TypeRef::Reference(inner, _, mutability) => {
                 let inner_ty = Ty::from_hir(db, resolver, inner);
                 Ty::apply_one(TypeCtor::Ref(*mutability), inner_ty)
             }
@@ -332,13 +336,10 @@ pub(super) fn substs_from_path_segment(
         // if args are provided, it should be all of them, but we can't rely on that
         let self_param_correction = if add_self_param { 1 } else { 0 };
         let child_len = child_len + self_param_correction;
-        for arg in generic_args.args.iter().take(child_len) {
-            match arg {
-                GenericArg::Type(type_ref) => {
-                    let ty = Ty::from_hir(db, resolver, type_ref);
-                    substs.push(ty);
-                }
-            }
+        let type_args = generic_args.args.iter().filter_map(GenericArg::as_type);
+        for type_ref in type_args.take(child_len) {
+            let ty = Ty::from_hir(db, resolver, type_ref);
+            substs.push(ty);
         }
     }
     // add placeholders for args that were not provided
@@ -424,7 +425,9 @@ impl TraitRef {
     ) -> Option<TraitRef> {
         match bound {
             TypeBound::Path(path) => TraitRef::from_path(db, resolver, path, Some(self_ty)),
-            TypeBound::Error => None,
+            // Outlives bounds like `T: 'a` are kept in `GenericParams`, but `Ty`
+            // has no lifetimes, so they don't turn into predicates here.
+            TypeBound::Lifetime(_) | TypeBound::Error => None,
         }
     }
 }
@@ -463,7 +466,7 @@ fn assoc_type_bindings_from_type_bound<'a>(
 ) -> impl Iterator<Item = GenericPredicate> + 'a {
     let last_segment = match bound {
         TypeBound::Path(path) => path.segments().last(),
-        TypeBound::Error => None,
+        TypeBound::Lifetime(_) | TypeBound::Error => None,
     };
     last_segment
         .into_iter()
@@ -583,7 +586,39 @@ fn fn_sig_for_fn(db: &impl HirDatabase, def: FunctionId) -> FnSig {
     let resolver = def.resolver(db);
     let params = data.params.iter().map(|tr| Ty::from_hir(db, &resolver, tr)).collect::<Vec<_>>();
     let ret = Ty::from_hir(db, &resolver, &data.ret_type);
-    FnSig::from_params_and_return(params, ret)
+    let ret = if data.is_async {
+        let krate = def.lookup(db).module(db).krate;
+        impl_future_ty(db, krate, ret)
+    } else {
+        ret
+    };
+    FnSig::from_params_and_return(params, ret, data.is_varargs)
+}
+
+/// Build `impl Future<Output = output>`, the type of `async` functions,
+/// blocks and closures. This is `{unknown}` if there's no `Future` lang item.
+pub(crate) fn impl_future_ty(db: &impl HirDatabase, krate: CrateId, output: Ty) -> Ty {
+    let future_trait = match db.lang_item(krate, "future_trait".into()) {
+        Some(LangItemTarget::TraitId(it)) => it,
+        _ => return Ty::Unknown,
+    };
+    let output_ty = match db.trait_data(future_trait).associated_type_by_name(&name![Output]) {
+        Some(it) => it,
+        None => return Ty::Unknown,
+    };
+    let self_ty = Ty::Bound(0);
+    let implemented = GenericPredicate::Implemented(TraitRef {
+        trait_: future_trait,
+        substs: Substs::single(self_ty.clone()),
+    });
+    let projection = GenericPredicate::Projection(ProjectionPredicate {
+        projection_ty: ProjectionTy {
+            associated_ty: output_ty,
+            parameters: Substs::single(self_ty),
+        },
+        ty: output,
+    });
+    Ty::Opaque(Arc::new([implemented, projection]))
 }
 
 /// Build the declared type of a function. This should not need to look at the
@@ -630,7 +665,7 @@ fn fn_sig_for_struct_constructor(db: &impl HirDatabase, def: StructId) -> FnSig
         .map(|(_, field)| Ty::from_hir(db, &resolver, &field.type_ref))
         .collect::<Vec<_>>();
     let ret = type_for_adt(db, def.into());
-    FnSig::from_params_and_return(params, ret)
+    FnSig::from_params_and_return(params, ret, false)
 }
 
 /// Build the type of a tuple struct constructor.
@@ -656,7 +691,7 @@ fn fn_sig_for_enum_variant_constructor(db: &impl HirDatabase, def: EnumVariantId
     let generics = generics(db, def.parent.into());
     let substs = Substs::identity(&generics);
     let ret = type_for_adt(db, def.parent.into()).subst(&substs);
-    FnSig::from_params_and_return(params, ret)
+    FnSig::from_params_and_return(params, ret, false)
 }
 
 /// Build the type of a tuple enum variant constructor.
@@ -13,6 +13,15 @@ pub enum Uncertain<T> {
     Known(T),
 }
 
+impl<T> From<Option<T>> for Uncertain<T> {
+    fn from(t: Option<T>) -> Self {
+        match t {
+            None => Uncertain::Unknown,
+            Some(t) => Uncertain::Known(t),
+        }
+    }
+}
+
 impl From<IntTy> for Uncertain<IntTy> {
     fn from(ty: IntTy) -> Self {
         Uncertain::Known(ty)
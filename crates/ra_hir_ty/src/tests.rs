@@ -11,7 +11,7 @@ use std::fmt::Write;
 use std::sync::Arc;
 
 use hir_def::{
-    body::BodySourceMap, child_by_source::ChildBySource, db::DefDatabase, keys,
+    body::BodySourceMap, child_by_source::ChildBySource, db::DefDatabase, expr::Expr, keys,
     nameres::CrateDefMap, AssocItemId, DefWithBodyId, LocalModuleId, Lookup, ModuleDefId,
 };
 use hir_expand::InFile;
@@ -22,7 +22,14 @@ use ra_syntax::{
     ast::{self, AstNode},
 };
 
-use crate::{db::HirDatabase, display::HirDisplay, test_db::TestDB, InferenceResult};
+use crate::{
+    db::HirDatabase,
+    display::HirDisplay,
+    test_db::TestDB,
+    traits::{FnTrait, Solution},
+    Canonical, InEnvironment, InferenceResult, Obligation, Substs, TraitEnvironment, TraitRef, Ty,
+    TypeCtor,
+};
 
 // These tests compare the inference results for all expressions in a file
 // against snapshots of the expected results using insta. Use cargo-insta to
@@ -157,6 +164,112 @@ fn infer_with_mismatches(content: &str, include_mismatches: bool) -> String {
     acc
 }
 
+fn closure_kinds(content: &str) -> String {
+    let (db, file_id) = TestDB::with_single_file(content);
+
+    let module = db.module_for_file(file_id);
+    let crate_def_map = db.crate_def_map(module.krate);
+    let mut defs: Vec<DefWithBodyId> = Vec::new();
+    visit_module(&db, &crate_def_map, module.local_id, &mut |it| defs.push(it));
+
+    let mut kinds = Vec::new();
+    for def in defs {
+        let (body, source_map) = db.body_with_source_map(def);
+        let infer = db.infer(def);
+        for (expr, _) in body.exprs.iter() {
+            let kind = match infer[expr].closure_kind() {
+                Some(it) => it,
+                None => continue,
+            };
+            let src_ptr = match source_map.expr_syntax(expr) {
+                Some(sp) => {
+                    sp.map(|ast| ast.either(|it| it.syntax_node_ptr(), |it| it.syntax_node_ptr()))
+                }
+                None => continue,
+            };
+            let node = src_ptr.value.to_node(&src_ptr.file_syntax(&db));
+            kinds.push((node.text_range(), node.text().to_string(), kind));
+        }
+    }
+    kinds.sort_by_key(|(range, ..)| range.start());
+
+    let mut acc = String::new();
+    for (range, text, kind) in kinds {
+        write!(acc, "{} '{}': {:?}\n", range, ellipsize(text, 15), kind).unwrap();
+    }
+    acc.truncate(acc.trim_end().len());
+    acc
+}
+
+/// Lists the `Fn` traits the trait solver proves for each closure type.
+fn closure_fn_impls(content: &str) -> String {
+    let (db, file_id) = TestDB::with_single_file(content);
+
+    let module = db.module_for_file(file_id);
+    let krate = module.krate;
+    let crate_def_map = db.crate_def_map(krate);
+    let mut defs: Vec<DefWithBodyId> = Vec::new();
+    visit_module(&db, &crate_def_map, module.local_id, &mut |it| defs.push(it));
+
+    let mut impls = Vec::new();
+    for def in defs {
+        let (body, source_map) = db.body_with_source_map(def);
+        let infer = db.infer(def);
+        for (expr, _) in body.exprs.iter() {
+            let ty = &infer[expr];
+            if ty.closure_kind().is_none() {
+                continue;
+            }
+            let params = match ty.callable_sig(&db) {
+                Some(sig) => sig.params().to_vec(),
+                None => continue,
+            };
+            let args = Ty::apply(
+                TypeCtor::Tuple { cardinality: params.len() as u16 },
+                Substs(params.into()),
+            );
+            let implemented = [FnTrait::FnOnce, FnTrait::FnMut, FnTrait::Fn]
+                .iter()
+                .filter(|fn_trait| {
+                    let trait_ = match fn_trait.get_id(&db, krate) {
+                        Some(it) => it,
+                        None => return false,
+                    };
+                    let substs = Substs::build_for_def(&db, trait_)
+                        .push(ty.clone())
+                        .push(args.clone())
+                        .build();
+                    let obligation = Obligation::Trait(TraitRef { trait_, substs });
+                    let env = Arc::new(TraitEnvironment { predicates: Vec::new() });
+                    let goal =
+                        Canonical { value: InEnvironment::new(env, obligation), num_vars: 0 };
+                    match db.trait_solve(krate, goal) {
+                        Some(Solution::Unique(_)) => true,
+                        _ => false,
+                    }
+                })
+                .map(|it| format!("{:?}", it))
+                .collect::<Vec<_>>();
+            let src_ptr = match source_map.expr_syntax(expr) {
+                Some(sp) => {
+                    sp.map(|ast| ast.either(|it| it.syntax_node_ptr(), |it| it.syntax_node_ptr()))
+                }
+                None => continue,
+            };
+            let node = src_ptr.value.to_node(&src_ptr.file_syntax(&db));
+            impls.push((node.text_range(), node.text().to_string(), implemented.join(", ")));
+        }
+    }
+    impls.sort_by_key(|(range, ..)| range.start());
+
+    let mut acc = String::new();
+    for (range, text, implemented) in impls {
+        write!(acc, "{} '{}': {}\n", range, ellipsize(text, 15), implemented).unwrap();
+    }
+    acc.truncate(acc.trim_end().len());
+    acc
+}
+
 fn visit_module(
     db: &TestDB,
     crate_def_map: &CrateDefMap,
@@ -281,3 +394,102 @@ fn no_such_field_diagnostics() {
     "###
     );
 }
+
+#[test]
+fn type_mismatch_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r#"
+        //- /lib.rs
+        struct S;
+        fn takes_u32(x: u32) {}
+        fn test() -> u32 {
+            let a: u32 = S;
+            let b: u64 = 1;
+            takes_u32("foo");
+            let c: bool = unknown_fn();
+            S
+        }
+        "#,
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "S": mismatched types: expected u32, found S
+    "\"foo\"": mismatched types: expected u32, found &str
+    "S": mismatched types: expected u32, found S
+    "###
+    );
+}
+
+#[test]
+fn missing_unsafe_diagnostics() {
+    let diagnostics = TestDB::with_files(
+        r#"
+        //- /lib.rs
+        unsafe fn dangerous() -> i32 { 0 }
+        static mut COUNTER: u32 = 0;
+        union U { a: u32, b: f32 }
+        struct S;
+        impl S {
+            unsafe fn unchecked(&self) {}
+        }
+        fn test(p: *const i32, u: U) {
+            dangerous();
+            let x = *p;
+            COUNTER;
+            let a = u.a;
+            S.unchecked();
+            unsafe {
+                dangerous();
+                let y = *p;
+                let b = u.b;
+            }
+        }
+        unsafe fn in_unsafe_fn(p: *const i32) {
+            dangerous();
+            *p;
+        }
+        fn write_to_union(mut u: U) {
+            u.a = 1;
+        }
+        "#,
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "dangerous()": this operation is unsafe and requires an unsafe function or block
+    "*p": this operation is unsafe and requires an unsafe function or block
+    "COUNTER": this operation is unsafe and requires an unsafe function or block
+    "u.a": this operation is unsafe and requires an unsafe function or block
+    "S.unchecked()": this operation is unsafe and requires an unsafe function or block
+    "###
+    );
+}
+
+#[test]
+fn missing_unsafe_diagnostics_for_foreign_items() {
+    let diagnostics = TestDB::with_files(
+        r#"
+        //- /lib.rs
+        extern "C" {
+            fn abs(x: i32) -> i32;
+            static errno: i32;
+        }
+        fn test() {
+            abs(-1);
+            errno;
+            unsafe {
+                abs(-1);
+                errno;
+            }
+        }
+        "#,
+    )
+    .diagnostics();
+
+    assert_snapshot!(diagnostics, @r###"
+    "abs(-1)": this operation is unsafe and requires an unsafe function or block
+    "errno": this operation is unsafe and requires an unsafe function or block
+    "###
+    );
+}
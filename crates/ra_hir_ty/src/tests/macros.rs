@@ -27,7 +27,7 @@ fn test() {
 }
 
 //- /foo.rs crate:foo
-struct S;
+pub struct S;
 
 #[cfg(not(test))]
 impl S {
@@ -338,6 +338,65 @@ fn main() {
     );
 }
 
+#[test]
+fn infer_builtin_macros_include() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! include {() => {}}
+
+include!("foo.rs");
+
+fn main() {
+    bar()<|>;
+}
+
+//- /foo.rs
+fn bar() -> u32 {0}
+"#,
+    );
+    assert_eq!("u32", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_builtin_macros_include_bytes() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs
+#[rustc_builtin_macro]
+macro_rules! include_bytes {() => {}}
+
+fn main() {
+    include_bytes!("foo.bin")<|>;
+}
+
+//- /foo.bin
+abc
+"#,
+    );
+    assert_eq!("&[u8; 5]", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_builtin_macros_concat() {
+    assert_snapshot!(
+        infer(r#"
+#[rustc_builtin_macro]
+macro_rules! concat {() => {}}
+
+fn main() {
+    let x = concat!("hello", concat!("world", "!"));
+}
+"#),
+        @r###"
+    ![0; 13) '"helloworld!"': &str
+    [66; 122) '{     ...")); }': ()
+    [76; 77) 'x': &str
+    "###
+    );
+}
+
 #[test]
 fn infer_derive_clone_simple() {
     let (db, pos) = TestDB::with_position(
@@ -353,7 +412,7 @@ fn test() {
 #[prelude_import]
 use clone::*;
 mod clone {
-    trait Clone {
+    pub trait Clone {
         fn clone(&self) -> Self;
     }
 }
@@ -380,7 +439,7 @@ fn test() {
 #[prelude_import]
 use clone::*;
 mod clone {
-    trait Clone {
+    pub trait Clone {
         fn clone(&self) -> Self;
     }
 }
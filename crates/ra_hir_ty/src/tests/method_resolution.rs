@@ -108,16 +108,16 @@ fn infer_associated_method_with_modules() {
     assert_snapshot!(
         infer(r#"
 mod a {
-    struct A;
+    pub struct A;
     impl A { pub fn thing() -> A { A {} }}
 }
 
 mod b {
-    struct B;
+    pub struct B;
     impl B { pub fn thing() -> u32 { 99 }}
 
-    mod c {
-        struct C;
+    pub mod c {
+        pub struct C;
         impl C { pub fn thing() -> C { C {} }}
     }
 }
@@ -130,22 +130,22 @@ fn test() {
 }
 "#),
         @r###"
-    [56; 64) '{ A {} }': A
-    [58; 62) 'A {}': A
-    [126; 132) '{ 99 }': u32
-    [128; 130) '99': u32
-    [202; 210) '{ C {} }': C
-    [204; 208) 'C {}': C
-    [241; 325) '{     ...g(); }': ()
-    [251; 252) 'x': A
-    [255; 266) 'a::A::thing': fn thing() -> A
-    [255; 268) 'a::A::thing()': A
-    [278; 279) 'y': u32
-    [282; 293) 'b::B::thing': fn thing() -> u32
-    [282; 295) 'b::B::thing()': u32
-    [305; 306) 'z': C
-    [309; 320) 'c::C::thing': fn thing() -> C
-    [309; 322) 'c::C::thing()': C
+    [60; 68) '{ A {} }': A
+    [62; 66) 'A {}': A
+    [134; 140) '{ 99 }': u32
+    [136; 138) '99': u32
+    [218; 226) '{ C {} }': C
+    [220; 224) 'C {}': C
+    [257; 341) '{     ...g(); }': ()
+    [267; 268) 'x': A
+    [271; 282) 'a::A::thing': fn thing() -> A
+    [271; 284) 'a::A::thing()': A
+    [294; 295) 'y': u32
+    [298; 309) 'b::B::thing': fn thing() -> u32
+    [298; 311) 'b::B::thing()': u32
+    [321; 322) 'z': C
+    [325; 336) 'c::C::thing': fn thing() -> C
+    [325; 338) 'c::C::thing()': C
     "###
     );
 }
@@ -308,8 +308,8 @@ fn test() {
 }
 
 //- /lib.rs crate:other_crate
-mod foo {
-    struct S;
+pub mod foo {
+    pub struct S;
     impl S {
         fn thing() -> i128 {}
     }
@@ -854,7 +854,7 @@ fn test() {
 #[prelude_import] use foo::*;
 
 mod foo {
-    trait Clone {
+    pub trait Clone {
         fn clone(&self) -> Self;
     }
 }
@@ -27,7 +27,7 @@ mod boxed {
 
 "#,
     );
-    assert_eq!("(Box<i32>, Box<Box<i32>>, Box<&i32>, Box<[i32;_]>)", type_at_pos(&db, pos));
+    assert_eq!("(Box<i32>, Box<Box<i32>>, Box<&i32>, Box<[i32; 1]>)", type_at_pos(&db, pos));
 }
 
 #[test]
@@ -186,7 +186,7 @@ fn infer_paths() {
 fn a() -> u32 { 1 }
 
 mod b {
-    fn c() -> u32 { 1 }
+    pub fn c() -> u32 { 1 }
 }
 
 fn test() {
@@ -197,13 +197,13 @@ fn test() {
         @r###"
     [15; 20) '{ 1 }': u32
     [17; 18) '1': u32
-    [48; 53) '{ 1 }': u32
-    [50; 51) '1': u32
-    [67; 91) '{     ...c(); }': ()
-    [73; 74) 'a': fn a() -> u32
-    [73; 76) 'a()': u32
-    [82; 86) 'b::c': fn c() -> u32
-    [82; 88) 'b::c()': u32
+    [52; 57) '{ 1 }': u32
+    [54; 55) '1': u32
+    [71; 95) '{     ...c(); }': ()
+    [77; 78) 'a': fn a() -> u32
+    [77; 80) 'a()': u32
+    [86; 90) 'b::c': fn c() -> u32
+    [86; 92) 'b::c()': u32
     "###
     );
 }
@@ -1039,59 +1039,227 @@ fn test(x: &str, y: isize) {
     [9; 10) 'x': &str
     [18; 19) 'y': isize
     [28; 293) '{     ... []; }': ()
-    [38; 39) 'a': [&str;_]
-    [42; 45) '[x]': [&str;_]
+    [38; 39) 'a': [&str; 1]
+    [42; 45) '[x]': [&str; 1]
     [43; 44) 'x': &str
-    [55; 56) 'b': [[&str;_];_]
-    [59; 65) '[a, a]': [[&str;_];_]
-    [60; 61) 'a': [&str;_]
-    [63; 64) 'a': [&str;_]
-    [75; 76) 'c': [[[&str;_];_];_]
-    [79; 85) '[b, b]': [[[&str;_];_];_]
-    [80; 81) 'b': [[&str;_];_]
-    [83; 84) 'b': [[&str;_];_]
-    [96; 97) 'd': [isize;_]
-    [100; 112) '[y, 1, 2, 3]': [isize;_]
+    [55; 56) 'b': [[&str; 1]; 2]
+    [59; 65) '[a, a]': [[&str; 1]; 2]
+    [60; 61) 'a': [&str; 1]
+    [63; 64) 'a': [&str; 1]
+    [75; 76) 'c': [[[&str; 1]; 2]; 2]
+    [79; 85) '[b, b]': [[[&str; 1]; 2]; 2]
+    [80; 81) 'b': [[&str; 1]; 2]
+    [83; 84) 'b': [[&str; 1]; 2]
+    [96; 97) 'd': [isize; 4]
+    [100; 112) '[y, 1, 2, 3]': [isize; 4]
     [101; 102) 'y': isize
     [104; 105) '1': isize
     [107; 108) '2': isize
     [110; 111) '3': isize
-    [122; 123) 'd': [isize;_]
-    [126; 138) '[1, y, 2, 3]': [isize;_]
+    [122; 123) 'd': [isize; 4]
+    [126; 138) '[1, y, 2, 3]': [isize; 4]
     [127; 128) '1': isize
     [130; 131) 'y': isize
     [133; 134) '2': isize
     [136; 137) '3': isize
-    [148; 149) 'e': [isize;_]
-    [152; 155) '[y]': [isize;_]
+    [148; 149) 'e': [isize; 1]
+    [152; 155) '[y]': [isize; 1]
     [153; 154) 'y': isize
-    [165; 166) 'f': [[isize;_];_]
-    [169; 175) '[d, d]': [[isize;_];_]
-    [170; 171) 'd': [isize;_]
-    [173; 174) 'd': [isize;_]
-    [185; 186) 'g': [[isize;_];_]
-    [189; 195) '[e, e]': [[isize;_];_]
-    [190; 191) 'e': [isize;_]
-    [193; 194) 'e': [isize;_]
-    [206; 207) 'h': [i32;_]
-    [210; 216) '[1, 2]': [i32;_]
+    [165; 166) 'f': [[isize; 4]; 2]
+    [169; 175) '[d, d]': [[isize; 4]; 2]
+    [170; 171) 'd': [isize; 4]
+    [173; 174) 'd': [isize; 4]
+    [185; 186) 'g': [[isize; 1]; 2]
+    [189; 195) '[e, e]': [[isize; 1]; 2]
+    [190; 191) 'e': [isize; 1]
+    [193; 194) 'e': [isize; 1]
+    [206; 207) 'h': [i32; 2]
+    [210; 216) '[1, 2]': [i32; 2]
     [211; 212) '1': i32
     [214; 215) '2': i32
-    [226; 227) 'i': [&str;_]
-    [230; 240) '["a", "b"]': [&str;_]
+    [226; 227) 'i': [&str; 2]
+    [230; 240) '["a", "b"]': [&str; 2]
     [231; 234) '"a"': &str
     [236; 239) '"b"': &str
-    [251; 252) 'b': [[&str;_];_]
-    [255; 265) '[a, ["b"]]': [[&str;_];_]
-    [256; 257) 'a': [&str;_]
-    [259; 264) '["b"]': [&str;_]
+    [251; 252) 'b': [[&str; 1]; 2]
+    [255; 265) '[a, ["b"]]': [[&str; 1]; 2]
+    [256; 257) 'a': [&str; 1]
+    [259; 264) '["b"]': [&str; 1]
     [260; 263) '"b"': &str
-    [275; 276) 'x': [u8;_]
-    [288; 290) '[]': [u8;_]
+    [275; 276) 'x': [u8; 0]
+    [288; 290) '[]': [u8; 0]
     "###
     );
 }
 
+#[test]
+fn infer_array_len() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const N: usize = 2;
+const M: usize = N * 2 + 1;
+
+fn test(a: [u8; 4], b: [u8; M], c: [u8; (N << 2) - 1]) {
+    (a, b, c, [0; N])<|>;
+}
+"#,
+    );
+    assert_eq!(t, "([u8; 4], [u8; 5], [u8; 7], [i32; 2])");
+}
+
+#[test]
+fn infer_loop_break_with_value() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let x = loop {
+        if true {
+            break 1u32;
+        }
+    };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn infer_loop_without_break() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let x = loop {};
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "!");
+}
+
+#[test]
+fn infer_labeled_break() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let x = 'outer: loop {
+        let y = loop {
+            if true {
+                break 'outer 1u64;
+            }
+            break "inner";
+        };
+    };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u64");
+}
+
+#[test]
+fn infer_labeled_break_from_while() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() {
+    let x = 'outer: loop {
+        while true {
+            break 'outer;
+        }
+    };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "()");
+}
+
+#[test]
+fn infer_union_field() {
+    let t = type_at(
+        r#"
+//- /main.rs
+union U<T> { a: T, b: f32 }
+fn test(u: &U<u64>) {
+    unsafe { u.a<|> };
+}
+"#,
+    );
+    assert_eq!(t, "u64");
+}
+
+#[test]
+fn infer_unsafe_block() {
+    let t = type_at(
+        r#"
+//- /main.rs
+unsafe fn get() -> u32 { 0 }
+fn test() {
+    let x = unsafe { get() };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn infer_extern_block_items() {
+    assert_snapshot!(
+        infer(r#"
+extern "C" {
+    fn printf(format: *const u8, ...) -> i32;
+    static errno: i32;
+}
+
+fn test(s: *const u8) {
+    let n = unsafe { printf(s, 1u32, 2.5) };
+    let e = unsafe { errno };
+}
+"#),
+        @r###"
+    [28; 34) 'format': *const u8
+    [94; 95) 's': *const u8
+    [108; 186) '{     ...o }; }': ()
+    [118; 119) 'n': i32
+    [122; 153) 'unsafe...2.5) }': i32
+    [122; 153) 'unsafe...2.5) }': i32
+    [131; 137) 'printf': fn printf(*const u8, ...) -> i32
+    [131; 151) 'printf..., 2.5)': i32
+    [138; 139) 's': *const u8
+    [141; 145) '1u32': u32
+    [147; 150) '2.5': f64
+    [163; 164) 'e': i32
+    [167; 183) 'unsafe...rrno }': i32
+    [167; 183) 'unsafe...rrno }': i32
+    [176; 181) 'errno': i32
+    "###
+    );
+}
+
+#[test]
+fn infer_extern_block_items_in_fn_body() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test() {
+    extern "C" {
+        fn abs(x: i32) -> i32;
+        static errno: i32;
+    }
+    let x = unsafe { abs(errno) };
+    x<|>;
+}
+"#,
+    );
+    assert_eq!(t, "i32");
+}
+
 #[test]
 fn infer_struct_generics() {
     assert_snapshot!(
@@ -1594,7 +1762,7 @@ fn not_shadowing_module_by_primitive() {
     let t = type_at(
         r#"
 //- /str.rs
-fn foo() -> u32 {0}
+pub fn foo() -> u32 {0}
 
 //- /main.rs
 mod str;
@@ -3,7 +3,7 @@ use insta::assert_snapshot;
 use ra_db::fixture::WithFixture;
 use test_utils::covers;
 
-use super::{infer, infer_with_mismatches, type_at, type_at_pos};
+use super::{closure_fn_impls, closure_kinds, infer, infer_with_mismatches, type_at, type_at_pos};
 use crate::test_db::TestDB;
 
 #[test]
@@ -27,7 +27,7 @@ fn test() {
 //- /std.rs crate:std
 #[prelude_import] use future::*;
 mod future {
-    trait Future {
+    pub trait Future {
         type Output;
     }
 }
@@ -37,6 +37,122 @@ mod future {
     assert_eq!("u64", type_at_pos(&db, pos));
 }
 
+#[test]
+fn infer_async() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+async fn foo() -> u64 {
+    128
+}
+
+fn test() {
+    let r = foo();
+    let v = r.await;
+    v<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    pub trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("u64", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_async_fn_return_type() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+async fn foo() -> u64 {
+    128
+}
+
+fn test() {
+    let r = foo();
+    r<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    pub trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("impl Future<Output = u64>", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_async_block() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+async fn test() {
+    let a = async {
+        if true {
+            return 92u64;
+        }
+        42
+    };
+    let v = a.await;
+    v<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    pub trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("u64", type_at_pos(&db, pos));
+}
+
+#[test]
+fn infer_async_closure() {
+    let (db, pos) = TestDB::with_position(
+        r#"
+//- /main.rs crate:main deps:std
+
+async fn test() {
+    let f = async move |x: i64| x;
+    let v = f(1).await;
+    v<|>;
+}
+
+//- /std.rs crate:std
+#[prelude_import] use future::*;
+mod future {
+    #[lang = "future_trait"]
+    pub trait Future {
+        type Output;
+    }
+}
+
+"#,
+    );
+    assert_eq!("i64", type_at_pos(&db, pos));
+}
+
 #[test]
 fn infer_try() {
     let (db, pos) = TestDB::with_position(
@@ -53,7 +169,7 @@ fn test() {
 
 #[prelude_import] use ops::*;
 mod ops {
-    trait Try {
+    pub trait Try {
         type Ok;
         type Error;
     }
@@ -61,7 +177,7 @@ mod ops {
 
 #[prelude_import] use result::*;
 mod result {
-    enum Result<O, E> {
+    pub enum Result<O, E> {
         Ok(O),
         Err(E)
     }
@@ -97,13 +213,13 @@ fn test() {
 
 #[prelude_import] use iter::*;
 mod iter {
-    trait IntoIterator {
+    pub trait IntoIterator {
         type Item;
     }
 }
 
 mod collections {
-    struct Vec<T> {}
+    pub struct Vec<T> {}
     impl<T> Vec<T> {
         fn new() -> Self { Vec {} }
         fn push(&mut self, t: T) { }
@@ -418,7 +534,7 @@ fn indexing_arrays() {
         @r###"
     [10; 26) '{ &mut...[2]; }': ()
     [12; 23) '&mut [9][2]': &mut {unknown}
-    [17; 20) '[9]': [i32;_]
+    [17; 20) '[9]': [i32; 1]
     [17; 23) '[9][2]': {unknown}
     [18; 19) '9': i32
     [21; 22) '2': i32
@@ -458,6 +574,83 @@ mod ops {
     assert_eq!("Foo", type_at_pos(&db, pos));
 }
 
+#[test]
+fn impl_for_array_len() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait {
+    type Out;
+    fn out(&self) -> Self::Out;
+}
+
+struct Four;
+struct Eight;
+impl Trait for [u8; 4] {
+    type Out = Four;
+}
+impl Trait for [u8; 2 * 4] {
+    type Out = Eight;
+}
+
+fn test() {
+    let x = [0u8; 8];
+    x.out()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "Eight");
+}
+
+#[test]
+fn impl_for_array_with_generic_len() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait {
+    type Out;
+    fn out(&self) -> Self::Out;
+}
+
+struct S<T>(T);
+impl<T, const N: usize> Trait for [T; N] {
+    type Out = S<T>;
+}
+
+fn test() {
+    let x = [1, 2, 3];
+    x.out()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "S<i32>");
+}
+
+#[test]
+fn impl_for_array_with_unevaluable_len() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait {
+    type Out;
+    fn out(&self) -> Self::Out;
+}
+
+struct S<T>(T);
+const fn len() -> usize { 3 }
+impl<T> Trait for [T; len()] {
+    type Out = S<T>;
+}
+
+fn test() {
+    let x = [1, 2, 3];
+    x.out()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "S<i32>");
+}
+
 #[test]
 fn deref_trait() {
     let t = type_at(
@@ -1068,10 +1261,10 @@ fn api_walkthrough() {
 //- /std.rs crate:std
 #[prelude_import] use iter::*;
 mod iter {
-    trait IntoIterator {
+    pub trait IntoIterator {
         type Item;
     }
-    trait Iterator {
+    pub trait Iterator {
         type Item;
     }
     impl<T: Iterator> IntoIterator for T {
@@ -1116,7 +1309,7 @@ fn where_clause_trait_in_scope_for_method_resolution() {
         r#"
 //- /main.rs
 mod foo {
-    trait Trait {
+    pub trait Trait {
         fn foo(&self) -> u32 {}
     }
 }
@@ -1134,7 +1327,7 @@ fn super_trait_method_resolution() {
     assert_snapshot!(
         infer(r#"
 mod foo {
-    trait SuperTrait {
+    pub trait SuperTrait {
         fn foo(&self) -> u32 {}
     }
 }
@@ -1147,15 +1340,15 @@ fn test<T: Trait1, U: Trait2>(x: T, y: U) {
 }
 "#),
         @r###"
-    [50; 54) 'self': &Self
-    [63; 65) '{}': ()
-    [182; 183) 'x': T
-    [188; 189) 'y': U
-    [194; 223) '{     ...o(); }': ()
-    [200; 201) 'x': T
-    [200; 207) 'x.foo()': u32
-    [213; 214) 'y': U
-    [213; 220) 'y.foo()': u32
+    [54; 58) 'self': &Self
+    [67; 69) '{}': ()
+    [186; 187) 'x': T
+    [192; 193) 'y': U
+    [198; 227) '{     ...o(); }': ()
+    [204; 205) 'x': T
+    [204; 211) 'x.foo()': u32
+    [217; 218) 'y': U
+    [217; 224) 'y.foo()': u32
     "###
     );
 }
@@ -1415,6 +1608,157 @@ fn test() {
     );
 }
 
+#[test]
+fn closure_param_from_fn_bound_on_projection() {
+    let t = type_at(
+        r#"
+//- /main.rs
+#[lang = "fn_once"]
+trait FnOnce<Args> {
+    type Output;
+}
+
+trait Iterator {
+    type Item;
+    fn map<B, F: FnOnce(Self::Item) -> B>(self, f: F) -> Map<Self, F> {}
+}
+struct Map<I, F>;
+
+struct S;
+impl S {
+    fn foo(&self) -> u128 {}
+}
+struct Iter;
+impl Iterator for Iter {
+    type Item = S;
+}
+
+fn test(it: Iter) {
+    it.map(|x| x.foo()<|>);
+}
+"#,
+    );
+    assert_eq!(t, "u128");
+}
+
+#[test]
+fn call_through_fn_trait_bound() {
+    let t = type_at(
+        r#"
+//- /main.rs
+#[lang = "fn_once"]
+trait FnOnce<Args> {
+    type Output;
+}
+
+fn test<F: FnOnce(u32) -> u64>(f: F) {
+    f(1)<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u64");
+}
+
+#[test]
+fn call_through_dyn_fn_trait() {
+    let t = type_at(
+        r#"
+//- /main.rs
+#[lang = "fn_once"]
+trait FnOnce<Args> {
+    type Output;
+}
+
+fn test(f: &dyn FnOnce(u32) -> u64) {
+    f(1)<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u64");
+}
+
+#[test]
+fn closure_kind_from_captures() {
+    assert_snapshot!(
+        closure_kinds(r#"
+struct S;
+fn consume(s: S) {}
+
+fn test() {
+    let s = S;
+    let mut n = 0;
+    let x = 1;
+    let a = || x + 1;
+    let b = || n += 1;
+    let c = |v: S| consume(v);
+    let d = || &s;
+    let e = || consume(s);
+}
+"#),
+        @r###"
+    [105; 113) '|| x + 1': Fn
+    [127; 136) '|| n += 1': FnMut
+    [150; 167) '|v: S|...ume(v)': Fn
+    [181; 186) '|| &s': Fn
+    [200; 213) '|| consume(s)': FnOnce
+    "###
+    );
+}
+
+#[test]
+fn closure_kind_from_called_closure() {
+    assert_snapshot!(
+        closure_kinds(r#"
+fn test() {
+    let mut n = 0;
+    let x = 1;
+    let f = || n += 1;
+    let g = || x + 1;
+    let a = || f();
+    let b = || g();
+}
+"#),
+        @r###"
+    [59; 68) '|| n += 1': FnMut
+    [82; 90) '|| x + 1': Fn
+    [104; 110) '|| f()': FnMut
+    [124; 130) '|| g()': Fn
+    "###
+    );
+}
+
+#[test]
+fn closure_fn_impls_follow_kind() {
+    assert_snapshot!(
+        closure_fn_impls(r#"
+#[lang = "fn_once"]
+trait FnOnce<Args> {
+    type Output;
+}
+#[lang = "fn_mut"]
+trait FnMut<Args>: FnOnce<Args> {}
+#[lang = "fn"]
+trait Fn<Args>: FnMut<Args> {}
+
+struct S;
+fn consume(s: S) {}
+
+fn test() {
+    let s = S;
+    let mut n = 0;
+    let a = |x: u32| x + 1;
+    let b = || n += 1;
+    let c = || consume(s);
+}
+"#),
+        @r###"
+    [251; 265) '|x: u32| x + 1': FnOnce, FnMut, Fn
+    [279; 288) '|| n += 1': FnOnce, FnMut
+    [302; 315) '|| consume(s)': FnOnce
+    "###
+    );
+}
+
 #[test]
 fn unselected_projection_in_trait_env_1() {
     let t = type_at(
@@ -2,7 +2,9 @@
 use std::sync::{Arc, Mutex};
 
 use chalk_ir::cast::Cast;
-use hir_def::{expr::ExprId, DefWithBodyId, ImplId, TraitId, TypeAliasId};
+use hir_def::{
+    expr::ExprId, lang_item::LangItemTarget, DefWithBodyId, ImplId, TraitId, TypeAliasId,
+};
 use log::debug;
 use ra_db::{impl_intern_key, salsa, CrateId};
 use ra_prof::profile;
@@ -10,7 +12,11 @@ use rustc_hash::FxHashSet;
 
 use crate::db::HirDatabase;
 
-use super::{Canonical, GenericPredicate, HirDisplay, ProjectionTy, TraitRef, Ty, TypeWalk};
+use super::{
+    primitive::{IntBitness, IntTy, Uncertain},
+    ApplicationTy, Canonical, GenericPredicate, HirDisplay, ProjectionTy, TraitRef, Ty, TypeCtor,
+    TypeWalk,
+};
 
 use self::chalk::{from_chalk, ToChalk, TypeFamily};
 
@@ -274,7 +280,10 @@ pub enum Guidance {
     Unknown,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+/// One of the `Fn` traits. These are ordered from the least to the most
+/// restrictive one, so the kind of a closure is the minimum of what its
+/// captures require.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum FnTrait {
     FnOnce,
     FnMut,
@@ -289,6 +298,40 @@ impl FnTrait {
             FnTrait::Fn => "fn",
         }
     }
+
+    pub fn get_id(self, db: &impl HirDatabase, krate: CrateId) -> Option<TraitId> {
+        let target = db.lang_item(krate, self.lang_item_name().into())?;
+        match target {
+            LangItemTarget::TraitId(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Closure types carry their kind in their second type parameter. Like
+    /// rustc, we encode it as `i8` for `Fn`, `i16` for `FnMut` and `i32` for
+    /// `FnOnce`; it's a type variable until inference has computed it.
+    pub(crate) fn to_closure_kind_ty(self) -> Ty {
+        let int_ty = match self {
+            FnTrait::Fn => IntTy::i8(),
+            FnTrait::FnMut => IntTy::i16(),
+            FnTrait::FnOnce => IntTy::i32(),
+        };
+        Ty::simple(TypeCtor::Int(Uncertain::Known(int_ty)))
+    }
+
+    pub(crate) fn from_closure_kind_ty(ty: &Ty) -> Option<FnTrait> {
+        match ty {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Int(Uncertain::Known(int_ty)), .. }) => {
+                match int_ty.bitness {
+                    IntBitness::X8 => Some(FnTrait::Fn),
+                    IntBitness::X16 => Some(FnTrait::FnMut),
+                    IntBitness::X32 => Some(FnTrait::FnOnce),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1,6 +1,6 @@
 //! This module provides the built-in trait implementations, e.g. to make
 //! closures implement `Fn`.
-use hir_def::{expr::Expr, lang_item::LangItemTarget, TraitId, TypeAliasId};
+use hir_def::{expr::Expr, TraitId, TypeAliasId};
 use hir_expand::name::name;
 use ra_db::CrateId;
 
@@ -30,10 +30,17 @@ pub(super) fn get_builtin_impls(
 ) {
     // Note: since impl_datum needs to be infallible, we need to make sure here
     // that we have all prerequisites to build the respective impls.
-    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Closure { def, expr }, .. }) = ty {
+    if let Ty::Apply(ApplicationTy { ctor: TypeCtor::Closure { def, expr }, parameters }) = ty {
+        // A closure implements the `Fn` traits up to its kind. While its body
+        // is still being inferred, the kind is unknown and we allow all of
+        // them.
+        let kind = super::FnTrait::from_closure_kind_ty(&parameters[1]);
         for &fn_trait in [super::FnTrait::FnOnce, super::FnTrait::FnMut, super::FnTrait::Fn].iter()
         {
-            if let Some(actual_trait) = get_fn_trait(db, krate, fn_trait) {
+            if kind.map_or(false, |kind| fn_trait > kind) {
+                continue;
+            }
+            if let Some(actual_trait) = fn_trait.get_id(db, krate) {
                 if trait_ == actual_trait {
                     let impl_ = super::ClosureFnTraitImplData { def: *def, expr: *expr, fn_trait };
                     if check_closure_fn_trait_impl_prerequisites(db, krate, impl_) {
@@ -71,7 +78,7 @@ fn check_closure_fn_trait_impl_prerequisites(
     data: super::ClosureFnTraitImplData,
 ) -> bool {
     // the respective Fn/FnOnce/FnMut trait needs to exist
-    if get_fn_trait(db, krate, data.fn_trait).is_none() {
+    if data.fn_trait.get_id(db, krate).is_none() {
         return false;
     }
 
@@ -79,7 +86,7 @@ fn check_closure_fn_trait_impl_prerequisites(
     // the traits having no type params, FnOnce being a supertrait
 
     // the FnOnce trait needs to exist and have an assoc type named Output
-    let fn_once_trait = match get_fn_trait(db, krate, super::FnTrait::FnOnce) {
+    let fn_once_trait = match super::FnTrait::FnOnce.get_id(db, krate) {
         Some(t) => t,
         None => return false,
     };
@@ -92,9 +99,12 @@ fn closure_fn_trait_impl_datum(
     data: super::ClosureFnTraitImplData,
 ) -> BuiltinImplData {
     // for some closure |X, Y| -> Z:
-    // impl<T, U, V> Fn<(T, U)> for closure<fn(T, U) -> V> { Output = V }
+    // impl<T, U, V, K> Fn<(T, U)> for closure<fn(T, U) -> V, K> { Output = V }
+    // The kind `K` has already been checked in `get_builtin_impls`.
 
-    let trait_ = get_fn_trait(db, krate, data.fn_trait) // get corresponding fn trait
+    let trait_ = data
+        .fn_trait
+        .get_id(db, krate) // get corresponding fn trait
         // the existence of the Fn trait has been checked before
         .expect("fn trait for closure impl missing");
 
@@ -115,7 +125,12 @@ fn closure_fn_trait_impl_datum(
         Substs::builder(num_args as usize + 1).fill_with_bound_vars(0).build(),
     );
 
-    let self_ty = Ty::apply_one(TypeCtor::Closure { def: data.def, expr: data.expr }, sig_ty);
+    let kind_ty = Ty::Bound(num_args as u32 + 1);
+
+    let self_ty = Ty::apply(
+        TypeCtor::Closure { def: data.def, expr: data.expr },
+        Substs(vec![sig_ty, kind_ty].into()),
+    );
 
     let trait_ref = TraitRef {
         trait_: trait_.into(),
@@ -125,7 +140,7 @@ fn closure_fn_trait_impl_datum(
     let output_ty_id = AssocTyValue::ClosureFnTraitImplOutput(data.clone());
 
     BuiltinImplData {
-        num_vars: num_args as usize + 1,
+        num_vars: num_args as usize + 2,
         trait_ref,
         where_clauses: Vec::new(),
         assoc_ty_values: vec![output_ty_id],
@@ -150,7 +165,7 @@ fn closure_fn_trait_output_assoc_ty_value(
     let output_ty = Ty::Bound(num_args.into());
 
     let fn_once_trait =
-        get_fn_trait(db, krate, super::FnTrait::FnOnce).expect("assoc ty value should not exist");
+        super::FnTrait::FnOnce.get_id(db, krate).expect("assoc ty value should not exist");
 
     let output_ty_id = db
         .trait_data(fn_once_trait)
@@ -160,19 +175,7 @@ fn closure_fn_trait_output_assoc_ty_value(
     BuiltinImplAssocTyValueData {
         impl_,
         assoc_ty_id: output_ty_id,
-        num_vars: num_args as usize + 1,
+        num_vars: num_args as usize + 2,
         value: output_ty,
     }
 }
-
-fn get_fn_trait(
-    db: &impl HirDatabase,
-    krate: CrateId,
-    fn_trait: super::FnTrait,
-) -> Option<TraitId> {
-    let target = db.lang_item(krate, fn_trait.lang_item_name().into())?;
-    match target {
-        LangItemTarget::TraitId(t) => Some(t),
-        _ => None,
-    }
-}
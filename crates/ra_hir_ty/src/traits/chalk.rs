@@ -13,8 +13,8 @@ use ra_db::{
 
 use super::{builtin, AssocTyValue, Canonical, ChalkContext, Impl, Obligation};
 use crate::{
-    db::HirDatabase, display::HirDisplay, utils::generics, ApplicationTy, GenericPredicate,
-    ProjectionTy, Substs, TraitRef, Ty, TypeCtor, TypeWalk,
+    db::HirDatabase, display::HirDisplay, primitive::Uncertain, utils::generics, ApplicationTy,
+    GenericPredicate, ProjectionTy, Substs, TraitRef, Ty, TypeCtor, TypeWalk,
 };
 
 #[derive(Debug, Copy, Clone, Hash, PartialOrd, Ord, PartialEq, Eq)]
@@ -112,6 +112,9 @@ impl ToChalk for Ty {
     type Chalk = chalk_ir::Ty<TypeFamily>;
     fn to_chalk(self, db: &impl HirDatabase) -> chalk_ir::Ty<TypeFamily> {
         match self {
+            Ty::Apply(ApplicationTy { ctor: TypeCtor::Array { len }, parameters }) => {
+                array_to_chalk(db, len, parameters)
+            }
             Ty::Apply(apply_ty) => {
                 let name = apply_ty.ctor.to_chalk(db);
                 let parameters = apply_ty.parameters.to_chalk(db);
@@ -159,11 +162,13 @@ impl ToChalk for Ty {
         match chalk.data().clone() {
             chalk_ir::TyData::Apply(apply_ty) => match apply_ty.name {
                 TypeName::Error => Ty::Unknown,
-                _ => {
-                    let ctor = from_chalk(db, apply_ty.name);
-                    let parameters = from_chalk(db, apply_ty.parameters);
-                    Ty::Apply(ApplicationTy { ctor, parameters })
-                }
+                _ => match from_chalk::<TypeCtor, _>(db, apply_ty.name) {
+                    TypeCtor::Array { .. } => array_from_chalk(db, apply_ty.parameters),
+                    ctor => {
+                        let parameters = from_chalk(db, apply_ty.parameters);
+                        Ty::Apply(ApplicationTy { ctor, parameters })
+                    }
+                },
             },
             chalk_ir::TyData::Placeholder(idx) => {
                 assert_eq!(idx.ui, UniverseIndex::ROOT);
@@ -193,6 +198,77 @@ impl ToChalk for Ty {
     }
 }
 
+/// Chalk can't see through array lengths, so instead of making the length part
+/// of the type's identity we pass it as an extra type parameter: `[T; 3]`
+/// becomes `Array<T, Len3>`, where `Len3` is the interned `TypeCtor::Array`
+/// with a known length and no parameters. An unknown length is lowered as an
+/// error type, except in impl headers, where `bind_unknown_array_lens` has
+/// already replaced it by a bound variable so that the impl matches arrays of
+/// any length.
+fn array_to_chalk(
+    db: &impl HirDatabase,
+    len: Uncertain<u64>,
+    parameters: Substs,
+) -> chalk_ir::Ty<TypeFamily> {
+    let name = TypeCtor::Array { len: Uncertain::Unknown }.to_chalk(db);
+    let elem_ty = parameters[0].clone().to_chalk(db);
+    let len_ty = match (parameters.get(1), len) {
+        (Some(len_var), _) => len_var.clone().to_chalk(db),
+        (None, Uncertain::Known(_)) => {
+            let name = TypeCtor::Array { len }.to_chalk(db);
+            chalk_ir::ApplicationTy { name, parameters: Vec::new() }.cast().intern()
+        }
+        (None, Uncertain::Unknown) => Ty::Unknown.to_chalk(db),
+    };
+    let parameters = vec![elem_ty.cast(), len_ty.cast()];
+    chalk_ir::ApplicationTy { name, parameters }.cast().intern()
+}
+
+fn array_from_chalk(db: &impl HirDatabase, parameters: Vec<Parameter<TypeFamily>>) -> Ty {
+    let mut parameters = parameters.into_iter();
+    let elem_ty = match parameters.next() {
+        Some(it) => from_chalk(db, it.assert_ty_ref().clone()),
+        // a length on its own doesn't correspond to any type
+        None => return Ty::Unknown,
+    };
+    let len = match parameters.next().as_ref().map(|it| it.assert_ty_ref().data()) {
+        Some(chalk_ir::TyData::Apply(chalk_ir::ApplicationTy {
+            name: TypeName::Struct(struct_id),
+            ..
+        })) => match db.lookup_intern_type_ctor((*struct_id).into()) {
+            TypeCtor::Array { len } => len,
+            _ => Uncertain::Unknown,
+        },
+        _ => Uncertain::Unknown,
+    };
+    Ty::apply_one(TypeCtor::Array { len }, elem_ty)
+}
+
+/// Replaces the unknown lengths of arrays in an impl header by new bound
+/// variables, numbered from `first_var`, and returns how many were added. The
+/// arrays get the variable as a second parameter, which `array_to_chalk` uses
+/// as their length.
+fn bind_unknown_array_lens(trait_ref: &mut TraitRef, first_var: usize) -> usize {
+    let mut num_vars = 0;
+    trait_ref.walk_mut_binders(
+        &mut |ty, binders| {
+            if let Ty::Apply(ApplicationTy {
+                ctor: TypeCtor::Array { len: Uncertain::Unknown },
+                parameters,
+            }) = ty
+            {
+                if parameters.len() == 1 {
+                    let len_var = Ty::Bound((binders + first_var + num_vars) as u32);
+                    num_vars += 1;
+                    *parameters = Substs(vec![parameters[0].clone(), len_var].into());
+                }
+            }
+        },
+        0,
+    );
+    num_vars
+}
+
 impl ToChalk for Substs {
     type Chalk = Vec<chalk_ir::Parameter<TypeFamily>>;
 
@@ -664,7 +740,12 @@ pub(crate) fn struct_datum_query(
     debug!("struct_datum {:?}", struct_id);
     let type_ctor: TypeCtor = from_chalk(db, TypeName::Struct(struct_id));
     debug!("struct {:?} = {:?}", struct_id, type_ctor);
-    let num_params = type_ctor.num_ty_params(db);
+    let num_params = match type_ctor {
+        // see `array_to_chalk`
+        TypeCtor::Array { len: Uncertain::Unknown } => 2,
+        TypeCtor::Array { .. } => 0,
+        _ => type_ctor.num_ty_params(db),
+    };
     let upstream = type_ctor.krate(db) != Some(krate);
     let where_clauses = type_ctor
         .as_generic_def()
@@ -716,7 +797,8 @@ fn impl_block_datum(
 
     let generic_params = generics(db, impl_id.into());
     let bound_vars = Substs::bound_vars(&generic_params);
-    let trait_ref = trait_ref.subst(&bound_vars);
+    let mut trait_ref = trait_ref.subst(&bound_vars);
+    let num_len_vars = bind_unknown_array_lens(&mut trait_ref, bound_vars.len());
     let trait_ = trait_ref.trait_;
     let impl_type = if impl_id.lookup(db).container.module(db).krate == krate {
         chalk_rust_ir::ImplType::Local
@@ -758,7 +840,7 @@ fn impl_block_datum(
         .collect();
     debug!("impl_datum: {:?}", impl_datum_bound);
     let impl_datum = ImplDatum {
-        binders: make_binders(impl_datum_bound, bound_vars.len()),
+        binders: make_binders(impl_datum_bound, bound_vars.len() + num_len_vars),
         impl_type,
         polarity,
         associated_ty_value_ids,
@@ -791,7 +873,7 @@ fn type_alias_associated_ty_value(
         _ => panic!("assoc ty value should be in impl"),
     };
 
-    let trait_ref = db.impl_trait(impl_id).expect("assoc ty value should not exist"); // we don't return any assoc ty values if the impl'd trait can't be resolved
+    let mut trait_ref = db.impl_trait(impl_id).expect("assoc ty value should not exist"); // we don't return any assoc ty values if the impl'd trait can't be resolved
 
     let assoc_ty = db
         .trait_data(trait_ref.trait_)
@@ -799,12 +881,14 @@ fn type_alias_associated_ty_value(
         .expect("assoc ty value should not exist"); // validated when building the impl data as well
     let generic_params = generics(db, impl_id.into());
     let bound_vars = Substs::bound_vars(&generic_params);
+    // the value has to bind the same variables as the impl
+    let num_len_vars = bind_unknown_array_lens(&mut trait_ref, bound_vars.len());
     let ty = db.ty(type_alias.into()).subst(&bound_vars);
     let value_bound = chalk_rust_ir::AssociatedTyValueBound { ty: ty.to_chalk(db) };
     let value = chalk_rust_ir::AssociatedTyValue {
         impl_id: Impl::ImplBlock(impl_id.into()).to_chalk(db),
         associated_ty_id: assoc_ty.to_chalk(db),
-        value: make_binders(value_bound, bound_vars.len()),
+        value: make_binders(value_bound, bound_vars.len() + num_len_vars),
     };
     Arc::new(value)
 }
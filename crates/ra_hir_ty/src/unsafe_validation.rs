@@ -0,0 +1,115 @@
+//! Finds the operations in a body which are only allowed in unsafe code:
+//! calls to `unsafe fn`s and foreign functions, dereferences of raw pointers,
+//! accesses to `static mut`s and foreign statics and reads of union fields.
+
+use std::sync::Arc;
+
+use hir_def::{
+    body::Body,
+    expr::{BinaryOp, Expr, ExprId, UnaryOp},
+    resolver::{resolver_for_expr, ValueNs},
+    DefWithBodyId, FunctionId, VariantId,
+};
+use rustc_hash::FxHashSet;
+
+use crate::{db::HirDatabase, ApplicationTy, CallableDef, InferenceResult, Ty, TypeCtor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsafeExpr {
+    pub expr: ExprId,
+    /// True if the operation is inside an unsafe block or an unsafe fn, where
+    /// it is allowed.
+    pub inside_unsafe_block: bool,
+}
+
+pub(crate) fn unsafe_expressions_query(
+    db: &impl HirDatabase,
+    def: DefWithBodyId,
+) -> Arc<[UnsafeExpr]> {
+    let body = db.body(def);
+    let infer = db.infer(def);
+    let is_unsafe_fn = match def {
+        DefWithBodyId::FunctionId(it) => db.function_data(it).is_unsafe,
+        DefWithBodyId::ConstId(_) | DefWithBodyId::StaticId(_) => false,
+    };
+
+    // Writing to a union field is safe, only reading from it is not.
+    let assigned: FxHashSet<ExprId> = body
+        .exprs
+        .iter()
+        .filter_map(|(_, expr)| match expr {
+            Expr::BinaryOp { lhs, op: Some(BinaryOp::Assignment { op: None }), .. } => Some(*lhs),
+            _ => None,
+        })
+        .collect();
+
+    let mut collector =
+        UnsafeCollector { db, def, body: &body, infer: &infer, assigned, res: Vec::new() };
+    collector.walk(body.body_expr, is_unsafe_fn);
+    collector.res.into()
+}
+
+struct UnsafeCollector<'a, DB: HirDatabase> {
+    db: &'a DB,
+    def: DefWithBodyId,
+    body: &'a Body,
+    infer: &'a InferenceResult,
+    assigned: FxHashSet<ExprId>,
+    res: Vec<UnsafeExpr>,
+}
+
+impl<'a, DB: HirDatabase> UnsafeCollector<'a, DB> {
+    fn walk(&mut self, id: ExprId, inside_unsafe_block: bool) {
+        let body = self.body;
+        let expr = &body[id];
+        if self.is_unsafe_operation(id, expr) {
+            self.res.push(UnsafeExpr { expr: id, inside_unsafe_block });
+        }
+        let inside_unsafe_block = match expr {
+            Expr::Unsafe { .. } => true,
+            _ => inside_unsafe_block,
+        };
+        expr.walk_child_exprs(|child| self.walk(child, inside_unsafe_block));
+    }
+
+    fn is_unsafe_operation(&self, id: ExprId, expr: &Expr) -> bool {
+        match expr {
+            Expr::Call { callee, .. } => match &self.infer[*callee] {
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::FnDef(def), .. }) => match def {
+                    CallableDef::FunctionId(func) => self.is_unsafe_fn(*func),
+                    CallableDef::StructId(_) | CallableDef::EnumVariantId(_) => false,
+                },
+                _ => false,
+            },
+            Expr::MethodCall { .. } => {
+                self.infer.method_resolution(id).map_or(false, |func| self.is_unsafe_fn(func))
+            }
+            Expr::UnaryOp { expr, op: UnaryOp::Deref } => match &self.infer[*expr] {
+                Ty::Apply(ApplicationTy { ctor: TypeCtor::RawPtr(_), .. }) => true,
+                _ => false,
+            },
+            Expr::Path(path) => {
+                let resolver = resolver_for_expr(self.db, self.def, id);
+                match resolver.resolve_path_in_value_ns_fully(self.db, path.mod_path()) {
+                    Some(ValueNs::StaticId(it)) => {
+                        let data = self.db.static_data(it);
+                        data.mutable || data.is_extern
+                    }
+                    _ => false,
+                }
+            }
+            Expr::Field { .. } => match self.infer.field_resolution(id) {
+                Some(field) => match field.parent {
+                    VariantId::UnionId(_) => !self.assigned.contains(&id),
+                    VariantId::StructId(_) | VariantId::EnumVariantId(_) => false,
+                },
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn is_unsafe_fn(&self, func: FunctionId) -> bool {
+        self.db.function_data(func).is_unsafe
+    }
+}
@@ -0,0 +1,302 @@
+//! Incoming and outgoing calls of a function, for the call hierarchy.
+
+use hir::{db::AstDatabase, FromSource};
+use ra_syntax::{ast, AstNode, SyntaxNode, TextRange};
+
+use crate::{
+    call_info::FnCallNode, db::RootDatabase, display::ToNav, expand::descend_into_macros,
+    goto_definition, references, FilePosition, NavigationTarget, RangeInfo,
+};
+
+/// A function calling, or called from, the function the hierarchy is
+/// computed for, with the ranges of the calls within the caller.
+#[derive(Debug, Clone)]
+pub struct CallItem {
+    pub target: NavigationTarget,
+    pub ranges: Vec<TextRange>,
+}
+
+impl CallItem {
+    #[cfg(test)]
+    pub(crate) fn assert_match(&self, expected: &str) {
+        let actual = self.debug_render();
+        test_utils::assert_eq_text!(expected.trim(), actual.trim(),);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn debug_render(&self) -> String {
+        format!("{} : {:?}", self.target.debug_render(), self.ranges)
+    }
+}
+
+pub(crate) fn call_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<RangeInfo<Vec<NavigationTarget>>> {
+    goto_definition::goto_definition(db, position)
+}
+
+pub(crate) fn incoming_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+    // References which are calls to the function, grouped by the function
+    // they appear in.
+    let refs = references::find_all_refs(db, position, None)?;
+
+    let mut calls = CallLocations::default();
+
+    for reference in refs.info.references() {
+        let file_id = reference.file_id;
+        let file = match db.parse_or_expand(file_id.into()) {
+            Some(it) => it,
+            None => continue,
+        };
+        let token = match file.token_at_offset(reference.range.start()).next() {
+            Some(it) => it,
+            None => continue,
+        };
+        let token = descend_into_macros(db, file_id, token);
+        let syntax = token.value.parent();
+        if !is_callee(&syntax) {
+            continue;
+        }
+
+        let fn_def = match syntax.ancestors().find_map(ast::FnDef::cast) {
+            Some(it) => it,
+            None => continue,
+        };
+        if let Some(function) = hir::Function::from_source(db, token.with_value(fn_def)) {
+            calls.add(&function.to_nav(db), reference.range);
+        }
+    }
+
+    Some(calls.into_items())
+}
+
+pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+    let file_id = position.file_id;
+    let file = db.parse_or_expand(file_id.into())?;
+    let token = file.token_at_offset(position.offset).next()?;
+    let token = descend_into_macros(db, file_id, token);
+    let fn_def = token.value.parent().ancestors().find_map(ast::FnDef::cast)?;
+    let body = fn_def.body()?;
+
+    let mut calls = CallLocations::default();
+
+    let call_nodes = body.syntax().descendants().filter_map(|it| FnCallNode::with_node_exact(&it));
+    for call_node in call_nodes {
+        let name_ref = match call_node.name_ref() {
+            Some(it) => it,
+            None => continue,
+        };
+        let analyzer = hir::SourceAnalyzer::new(db, token.with_value(name_ref.syntax()), None);
+        let function = match &call_node {
+            FnCallNode::CallExpr(expr) => {
+                let callable_def = expr
+                    .expr()
+                    .and_then(|it| analyzer.type_of(db, &it))
+                    .and_then(|it| it.as_callable());
+                match callable_def {
+                    Some(hir::CallableDef::FunctionId(it)) => hir::Function::from(it),
+                    _ => continue,
+                }
+            }
+            FnCallNode::MethodCallExpr(expr) => match analyzer.resolve_method_call(expr) {
+                Some(it) => it,
+                None => continue,
+            },
+            // FIXME: macro calls should be expanded and searched for calls
+            FnCallNode::MacroCallExpr(_) => continue,
+        };
+        calls.add(&function.to_nav(db), name_ref.syntax().text_range());
+    }
+
+    Some(calls.into_items())
+}
+
+/// Checks whether `syntax` is the name of the function called by the call
+/// enclosing it, as opposed to e.g. an import or a function passed as a value.
+fn is_callee(syntax: &SyntaxNode) -> bool {
+    let name_ref = match ast::NameRef::cast(syntax.clone()) {
+        Some(it) => it,
+        None => return false,
+    };
+    match FnCallNode::with_node(syntax) {
+        Some(FnCallNode::MacroCallExpr(_)) | None => false,
+        Some(call) => call.name_ref() == Some(name_ref),
+    }
+}
+
+/// Groups call ranges by the function they belong to, keeping the order in
+/// which the functions were first seen.
+#[derive(Default)]
+struct CallLocations {
+    items: Vec<CallItem>,
+}
+
+impl CallLocations {
+    fn add(&mut self, target: &NavigationTarget, range: TextRange) {
+        let existing = self.items.iter_mut().find(|it| {
+            it.target.file_id() == target.file_id() && it.target.full_range() == target.full_range()
+        });
+        match existing {
+            Some(item) => item.ranges.push(range),
+            None => self.items.push(CallItem { target: target.clone(), ranges: vec![range] }),
+        }
+    }
+
+    fn into_items(self) -> Vec<CallItem> {
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_db::FilePosition;
+
+    use crate::mock_analysis::analysis_and_position;
+
+    fn check_hierarchy(
+        fixture: &str,
+        expected: &str,
+        expected_incoming: &[&str],
+        expected_outgoing: &[&str],
+    ) {
+        let (analysis, pos) = analysis_and_position(fixture);
+
+        let mut navs = analysis.call_hierarchy(pos).unwrap().unwrap().info;
+        assert_eq!(navs.len(), 1);
+        let nav = navs.pop().unwrap();
+        nav.assert_match(expected);
+
+        let item_pos = FilePosition { file_id: nav.file_id(), offset: nav.range().start() };
+        let incoming_calls = analysis.incoming_calls(item_pos).unwrap().unwrap();
+        assert_eq!(incoming_calls.len(), expected_incoming.len());
+
+        for call in 0..incoming_calls.len() {
+            incoming_calls[call].assert_match(expected_incoming[call]);
+        }
+
+        let outgoing_calls = analysis.outgoing_calls(item_pos).unwrap().unwrap();
+        assert_eq!(outgoing_calls.len(), expected_outgoing.len());
+
+        for call in 0..outgoing_calls.len() {
+            outgoing_calls[call].assert_match(expected_outgoing[call]);
+        }
+    }
+
+    #[test]
+    fn test_call_hierarchy_on_ref() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn callee() {}
+            fn caller() {
+                call<|>ee();
+            }
+            "#,
+            "callee FN_DEF FileId(1) [0; 14) [3; 9)",
+            &["caller FN_DEF FileId(1) [15; 44) [18; 24) : [[33; 39)]"],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_on_def() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn call<|>ee() {}
+            fn caller() {
+                callee();
+            }
+            "#,
+            "callee FN_DEF FileId(1) [0; 14) [3; 9)",
+            &["caller FN_DEF FileId(1) [15; 44) [18; 24) : [[33; 39)]"],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_in_same_fn() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn callee() {}
+            fn caller() {
+                call<|>ee();
+                callee();
+            }
+            "#,
+            "callee FN_DEF FileId(1) [0; 14) [3; 9)",
+            &["caller FN_DEF FileId(1) [15; 58) [18; 24) : [[33; 39), [47; 53)]"],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_in_different_fn() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn callee() {}
+            fn caller1() {
+                call<|>ee();
+            }
+
+            fn caller2() {
+                callee();
+            }
+            "#,
+            "callee FN_DEF FileId(1) [0; 14) [3; 9)",
+            &[
+                "caller1 FN_DEF FileId(1) [15; 45) [18; 25) : [[34; 40)]",
+                "caller2 FN_DEF FileId(1) [47; 77) [50; 57) : [[66; 72)]",
+            ],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_incoming_ignores_non_calls() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn call<|>ee() {}
+            fn map(f: fn()) {}
+            fn caller() {
+                let f = callee;
+                map(callee);
+                callee();
+            }
+            mod m {
+                use crate::callee;
+            }
+            "#,
+            "callee FN_DEF FileId(1) [0; 14) [3; 9)",
+            &["caller FN_DEF FileId(1) [34; 100) [37; 43) : [[89; 95)]"],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_outgoing() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            struct S;
+            impl S { fn method(&self) {} }
+            fn callee() {}
+            fn call<|>er(s: S) {
+                callee();
+                s.method();
+                callee();
+            }
+            "#,
+            "caller FN_DEF FileId(1) [56; 119) [59; 65)",
+            &[],
+            &[
+                "callee FN_DEF FileId(1) [41; 55) [44; 50) : [[78; 84), [108; 114)]",
+                "method FN_DEF FileId(1) [19; 38) [22; 28) : [[94; 100)]",
+            ],
+        );
+    }
+}
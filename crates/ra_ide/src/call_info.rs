@@ -88,14 +88,15 @@ pub(crate) fn call_info(db: &RootDatabase, position: FilePosition) -> Option<Cal
 }
 
 #[derive(Debug)]
-enum FnCallNode {
+pub(crate) enum FnCallNode {
     CallExpr(ast::CallExpr),
     MethodCallExpr(ast::MethodCallExpr),
     MacroCallExpr(ast::MacroCall),
 }
 
 impl FnCallNode {
-    fn with_node(syntax: &SyntaxNode) -> Option<FnCallNode> {
+    /// Finds the call enclosing `syntax`.
+    pub(crate) fn with_node(syntax: &SyntaxNode) -> Option<FnCallNode> {
         syntax.ancestors().find_map(|node| {
             match_ast! {
                 match node {
@@ -108,7 +109,19 @@ impl FnCallNode {
         })
     }
 
-    fn name_ref(&self) -> Option<ast::NameRef> {
+    /// Returns the call if `syntax` itself is one.
+    pub(crate) fn with_node_exact(syntax: &SyntaxNode) -> Option<FnCallNode> {
+        match_ast! {
+            match syntax {
+                ast::CallExpr(it) => { Some(FnCallNode::CallExpr(it)) },
+                ast::MethodCallExpr(it) => { Some(FnCallNode::MethodCallExpr(it)) },
+                ast::MacroCall(it) => { Some(FnCallNode::MacroCallExpr(it)) },
+                _ => { None },
+            }
+        }
+    }
+
+    pub(crate) fn name_ref(&self) -> Option<ast::NameRef> {
         match self {
             FnCallNode::CallExpr(call_expr) => Some(match call_expr.expr()? {
                 ast::Expr::PathExpr(path_expr) => path_expr.path()?.segment()?.name_ref()?,
@@ -516,6 +529,20 @@ fn main() {
         assert_eq!(info.active_parameter, Some(1));
     }
 
+    #[test]
+    fn works_for_tuple_structs_with_lifetimes() {
+        let info = call_info(
+            r#"
+struct Wrap<'a, T>(&'a mut T, Option<&'a str>);
+fn main() {
+    let s = Wrap(<|>);
+}"#,
+        );
+
+        assert_eq!(info.label(), "struct Wrap<'a, T>(&'a mut T, Option<&'a str>) -> Wrap");
+        assert_eq!(info.active_parameter, Some(0));
+    }
+
     #[test]
     #[should_panic]
     fn cant_call_named_structs() {
@@ -9,6 +9,7 @@ use ra_db::{
 };
 use ra_prof::{memory_usage, profile, Bytes};
 use ra_syntax::SourceFile;
+use ra_text_edit::AtomTextEdit;
 #[cfg(not(feature = "wasm"))]
 use rayon::prelude::*;
 use rustc_hash::FxHashMap;
@@ -22,7 +23,7 @@ use crate::{
 pub struct AnalysisChange {
     new_roots: Vec<(SourceRootId, bool)>,
     roots_changed: FxHashMap<SourceRootId, RootChange>,
-    files_changed: Vec<(FileId, Arc<String>)>,
+    files_changed: Vec<(FileId, Arc<String>, Option<Vec<AtomTextEdit>>)>,
     libraries_added: Vec<LibraryData>,
     crate_graph: Option<CrateGraph>,
     debug_data: DebugData,
@@ -71,7 +72,13 @@ impl AnalysisChange {
     }
 
     pub fn change_file(&mut self, file_id: FileId, new_text: Arc<String>) {
-        self.files_changed.push((file_id, new_text))
+        self.files_changed.push((file_id, new_text, None))
+    }
+
+    /// Like `change_file`, but `edits` (applied in order to the current text)
+    /// are used to reparse the current syntax tree incrementally.
+    pub fn edit_file(&mut self, file_id: FileId, new_text: Arc<String>, edits: Vec<AtomTextEdit>) {
+        self.files_changed.push((file_id, new_text, Some(edits)))
     }
 
     pub fn remove_file(&mut self, root_id: SourceRootId, file_id: FileId, path: RelativePathBuf) {
@@ -189,10 +196,19 @@ impl RootDatabase {
         for (root_id, root_change) in change.roots_changed {
             self.apply_root_change(root_id, root_change);
         }
-        for (file_id, text) in change.files_changed {
+        for (file_id, text, edits) in change.files_changed {
             let source_root_id = self.file_source_root(file_id);
             let source_root = self.source_root(source_root_id);
             let durability = durability(&source_root);
+            let reparsed = edits.map(|edits| {
+                let _p = profile("RootDatabase::apply_change/reparse");
+                edits.iter().fold(self.parse(file_id), |parse, edit| parse.reparse(edit))
+            });
+            let reparsed_files = Arc::make_mut(&mut self.reparsed_files);
+            match reparsed {
+                Some(parse) => reparsed_files.insert(file_id, (Arc::clone(&text), parse)),
+                None => reparsed_files.remove(&file_id),
+            };
             self.set_file_text_with_durability(file_id, text, durability)
         }
         if !change.libraries_added.is_empty() {
@@ -223,6 +239,12 @@ impl RootDatabase {
     fn apply_root_change(&mut self, root_id: SourceRootId, root_change: RootChange) {
         let mut source_root = SourceRoot::clone(&self.source_root(root_id));
         let durability = durability(&source_root);
+        let reparsed_files = Arc::make_mut(&mut self.reparsed_files);
+        let added = root_change.added.iter().map(|it| it.file_id);
+        let removed = root_change.removed.iter().map(|it| it.file_id);
+        for file_id in added.chain(removed) {
+            reparsed_files.remove(&file_id);
+        }
         for add_file in root_change.added {
             self.set_file_text_with_durability(add_file.file_id, add_file.text, durability);
             self.set_file_relative_path_with_durability(
@@ -274,6 +296,7 @@ impl RootDatabase {
 
         self.query(hir::db::ExprScopesQuery).sweep(sweep);
         self.query(hir::db::InferQuery).sweep(sweep);
+        self.query(hir::db::UnsafeExpressionsQuery).sweep(sweep);
         self.query(hir::db::BodyQuery).sweep(sweep);
     }
 
@@ -321,6 +344,7 @@ impl RootDatabase {
             hir::db::DocumentationQuery
             hir::db::ExprScopesQuery
             hir::db::InferQuery
+            hir::db::UnsafeExpressionsQuery
             hir::db::TyQuery
             hir::db::ValueTyQuery
             hir::db::FieldTypesQuery
@@ -350,3 +374,36 @@ fn durability(source_root: &SourceRoot) -> Durability {
         Durability::LOW
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ra_syntax::{AstNode, SourceFile, TextRange};
+    use ra_text_edit::AtomTextEdit;
+    use test_utils::covers;
+
+    use crate::{mock_analysis::MockAnalysis, AnalysisChange};
+
+    #[test]
+    fn edited_file_is_reparsed_incrementally() {
+        let mut analysis = MockAnalysis::new();
+        let file_id = analysis.add_file("/main.rs", "fn foo() {\n    1 + 1\n}\n");
+        let mut host = analysis.analysis_host();
+        host.analysis().parse(file_id).unwrap();
+
+        let new_text = "fn foo() {\n    1 + 92\n}\n";
+        let edit = AtomTextEdit::replace(TextRange::from_to(19.into(), 20.into()), "92".into());
+        let mut change = AnalysisChange::new();
+        change.edit_file(file_id, Arc::new(new_text.to_string()), vec![edit]);
+        host.apply_change(change);
+
+        covers!(reparsed_file_is_reused);
+        let tree = host.analysis().parse(file_id).unwrap();
+        assert_eq!(tree.syntax().to_string(), new_text);
+        assert_eq!(
+            format!("{:#?}", tree.syntax()),
+            format!("{:#?}", SourceFile::parse(new_text).tree().syntax())
+        );
+    }
+}
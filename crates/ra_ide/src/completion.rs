@@ -2,6 +2,7 @@
 
 mod completion_item;
 mod completion_context;
+mod completion_template;
 mod presentation;
 
 mod complete_dot;
@@ -19,7 +20,7 @@ mod complete_macro_in_item_position;
 use ra_db::SourceDatabase;
 
 #[cfg(test)]
-use crate::completion::completion_item::do_completion;
+use crate::completion::completion_item::{do_completion, do_completion_with_templates};
 use crate::{
     completion::{
         completion_context::CompletionContext,
@@ -28,8 +29,9 @@ use crate::{
     db, FilePosition,
 };
 
-pub use crate::completion::completion_item::{
-    CompletionItem, CompletionItemKind, InsertTextFormat,
+pub use crate::completion::{
+    completion_item::{CompletionItem, CompletionItemKind, InsertTextFormat},
+    completion_template::CompletionTemplate,
 };
 
 /// Main entry point for completion. We run completion as a two-phase process.
@@ -54,7 +56,14 @@ pub use crate::completion::completion_item::{
 /// `foo` *should* be present among the completion variants. Filtering by
 /// identifier prefix/fuzzy match should be done higher in the stack, together
 /// with ordering of completions (currently this is done by the client).
-pub(crate) fn completions(db: &db::RootDatabase, position: FilePosition) -> Option<Completions> {
+///
+/// The user-defined `templates` are offered next to the built-in postfix
+/// completions and snippets.
+pub(crate) fn completions(
+    db: &db::RootDatabase,
+    position: FilePosition,
+    templates: &[CompletionTemplate],
+) -> Option<Completions> {
     let original_parse = db.parse(position.file_id);
     let ctx = CompletionContext::new(db, &original_parse, position)?;
 
@@ -63,15 +72,15 @@ pub(crate) fn completions(db: &db::RootDatabase, position: FilePosition) -> Opti
     complete_fn_param::complete_fn_param(&mut acc, &ctx);
     complete_keyword::complete_expr_keyword(&mut acc, &ctx);
     complete_keyword::complete_use_tree_keyword(&mut acc, &ctx);
-    complete_snippet::complete_expr_snippet(&mut acc, &ctx);
-    complete_snippet::complete_item_snippet(&mut acc, &ctx);
+    complete_snippet::complete_expr_snippet(&mut acc, &ctx, templates);
+    complete_snippet::complete_item_snippet(&mut acc, &ctx, templates);
     complete_path::complete_path(&mut acc, &ctx);
     complete_scope::complete_scope(&mut acc, &ctx);
     complete_dot::complete_dot(&mut acc, &ctx);
     complete_record_literal::complete_record_literal(&mut acc, &ctx);
     complete_record_pattern::complete_record_pattern(&mut acc, &ctx);
     complete_pattern::complete_pattern(&mut acc, &ctx);
-    complete_postfix::complete_postfix(&mut acc, &ctx);
+    complete_postfix::complete_postfix(&mut acc, &ctx, templates);
     complete_macro_in_item_position::complete_macro_in_item_position(&mut acc, &ctx);
     Some(acc)
 }
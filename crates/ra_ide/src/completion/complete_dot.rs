@@ -1,6 +1,6 @@
 //! FIXME: write short doc here
 
-use hir::Type;
+use hir::{HasVisibility, Type};
 
 use crate::completion::completion_item::CompletionKind;
 use crate::{
@@ -38,6 +38,11 @@ pub(super) fn complete_dot(acc: &mut Completions, ctx: &CompletionContext) {
 fn complete_fields(acc: &mut Completions, ctx: &CompletionContext, receiver: &Type) {
     for receiver in receiver.autoderef(ctx.db) {
         for (field, ty) in receiver.fields(ctx.db) {
+            if ctx.module.map_or(false, |m| !field.is_visible_from(ctx.db, m)) {
+                // Skip private field. FIXME: If the definition location of the
+                // field is editable, we should show the completion
+                continue;
+            }
             acc.add_field(ctx, field, &ty);
         }
         for (i, ty) in receiver.tuple_fields(ctx.db).into_iter().enumerate() {
@@ -49,7 +54,10 @@ fn complete_fields(acc: &mut Completions, ctx: &CompletionContext, receiver: &Ty
 fn complete_methods(acc: &mut Completions, ctx: &CompletionContext, receiver: &Type) {
     let mut seen_methods = FxHashSet::default();
     ctx.analyzer.iterate_method_candidates(ctx.db, receiver, None, |_ty, func| {
-        if func.has_self_param(ctx.db) && seen_methods.insert(func.name(ctx.db)) {
+        if func.has_self_param(ctx.db)
+            && ctx.module.map_or(true, |m| func.is_visible_from(ctx.db, m))
+            && seen_methods.insert(func.name(ctx.db))
+        {
             acc.add_function(ctx, func);
         }
         None::<()>
@@ -134,6 +142,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_struct_field_and_method_visibility() {
+        assert_debug_snapshot!(
+        do_ref_completion(
+            r"
+            mod inner {
+                pub struct A {
+                    private_field: u32,
+                    pub pub_field: u32,
+                    pub(crate) crate_field: u32,
+                    pub(super) super_field: u32,
+                }
+                impl A {
+                    fn private_method(&self) {}
+                    pub fn pub_method(&self) {}
+                }
+            }
+            fn foo(a: inner::A) {
+                a.<|>
+            }
+            ",
+        ),
+        @r###"
+        [
+            CompletionItem {
+                label: "crate_field",
+                source_range: [457; 457),
+                delete: [457; 457),
+                insert: "crate_field",
+                kind: Field,
+                detail: "u32",
+            },
+            CompletionItem {
+                label: "pub_field",
+                source_range: [457; 457),
+                delete: [457; 457),
+                insert: "pub_field",
+                kind: Field,
+                detail: "u32",
+            },
+            CompletionItem {
+                label: "pub_method()",
+                source_range: [457; 457),
+                delete: [457; 457),
+                insert: "pub_method()$0",
+                kind: Method,
+                lookup: "pub_method",
+                detail: "pub fn pub_method(&self)",
+            },
+            CompletionItem {
+                label: "super_field",
+                source_range: [457; 457),
+                delete: [457; 457),
+                insert: "super_field",
+                kind: Field,
+                detail: "u32",
+            },
+        ]
+        "###
+        );
+    }
+
     #[test]
     fn test_struct_field_completion_autoderef() {
         assert_debug_snapshot!(
@@ -455,6 +525,60 @@ mod tests {
         );
     }
 
+    #[test]
+    fn works_in_simple_macro() {
+        assert_debug_snapshot!(
+        do_ref_completion(
+                r"
+                macro_rules! m { ($e:expr) => { $e } }
+                struct A { the_field: u32 }
+                fn foo(a: A) {
+                    m!(a.x<|>)
+                }
+                ",
+        ),
+            @r###"
+        [
+            CompletionItem {
+                label: "the_field",
+                source_range: [156; 157),
+                delete: [156; 157),
+                insert: "the_field",
+                kind: Field,
+                detail: "u32",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn works_in_incomplete_macro_call() {
+        assert_debug_snapshot!(
+        do_ref_completion(
+                r"
+                macro_rules! m { ($e:expr) => { $e } }
+                struct A { the_field: u32 }
+                fn foo(a: A) {
+                    m!(a.<|>)
+                }
+                ",
+        ),
+            @r###"
+        [
+            CompletionItem {
+                label: "the_field",
+                source_range: [156; 156),
+                delete: [156; 156),
+                insert: "the_field",
+                kind: Field,
+                detail: "u32",
+            },
+        ]
+        "###
+        );
+    }
+
     #[test]
     fn test_completion_await_impls_future() {
         assert_debug_snapshot!(
@@ -17,7 +17,7 @@ pub(super) fn complete_path(acc: &mut Completions, ctx: &CompletionContext) {
     };
     match def {
         hir::ModuleDef::Module(module) => {
-            let module_scope = module.scope(ctx.db);
+            let module_scope = module.scope(ctx.db, ctx.module);
             for (name, def) in module_scope {
                 if ctx.use_item_syntax.is_some() {
                     if let hir::ScopeDef::ModuleDef(hir::ModuleDef::BuiltinType(..)) = def {
@@ -174,7 +174,7 @@ mod tests {
                 use self::m::<|>;
 
                 mod m {
-                    struct Bar;
+                    pub struct Bar;
                 }
                 "
             ),
@@ -192,6 +192,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dont_complete_items_not_visible_from_current_module() {
+        assert_debug_snapshot!(
+            do_reference_completion(
+                r"
+                use self::m::<|>;
+
+                mod m {
+                    pub struct Bar;
+                    pub(crate) struct Baz;
+                    struct Private;
+                    pub mod inner {}
+                    mod private_mod {}
+                }
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "Bar",
+                source_range: [30; 30),
+                delete: [30; 30),
+                insert: "Bar",
+                kind: Struct,
+            },
+            CompletionItem {
+                label: "Baz",
+                source_range: [30; 30),
+                delete: [30; 30),
+                insert: "Baz",
+                kind: Struct,
+            },
+            CompletionItem {
+                label: "inner",
+                source_range: [30; 30),
+                delete: [30; 30),
+                insert: "inner",
+                kind: Module,
+            },
+        ]
+        "###
+        );
+    }
+
     #[test]
     fn completes_use_item_starting_with_crate() {
         assert_debug_snapshot!(
@@ -7,11 +7,16 @@ use crate::{
     completion::{
         completion_context::CompletionContext,
         completion_item::{Builder, CompletionKind, Completions},
+        completion_template::{CompletionTemplate, TemplateScope},
     },
     CompletionItem,
 };
 
-pub(super) fn complete_postfix(acc: &mut Completions, ctx: &CompletionContext) {
+pub(super) fn complete_postfix(
+    acc: &mut Completions,
+    ctx: &CompletionContext,
+    templates: &[CompletionTemplate],
+) {
     if !ctx.db.feature_flags.get("completion.enable-postfix") {
         return;
     }
@@ -21,6 +26,12 @@ pub(super) fn complete_postfix(acc: &mut Completions, ctx: &CompletionContext) {
         None => return,
     };
 
+    // FIXME: inside of a macro call, the receiver comes from the expansion and
+    // its range can't be used for the edit.
+    if ctx.token != ctx.original_token {
+        return;
+    }
+
     let receiver_text = if ctx.dot_receiver_is_ambiguous_float_literal {
         let text = dot_receiver.syntax().text();
         let without_dot = ..text.len() - TextUnit::of_char('.');
@@ -63,30 +74,64 @@ pub(super) fn complete_postfix(acc: &mut Completions, ctx: &CompletionContext) {
 
     postfix_snippet(ctx, "box", "Box::new(expr)", &format!("Box::new({})", receiver_text))
         .add_to(acc);
+
+    for template in templates.iter().filter(|it| it.scope == TemplateScope::Postfix) {
+        if !template.matches_receiver(ctx, &receiver_ty) {
+            continue;
+        }
+        let edit = template.edit(ctx, postfix_range(ctx), template.snippet(&receiver_text));
+        CompletionItem::new(CompletionKind::Postfix, ctx.source_range(), template.label.as_str())
+            .detail(template.detail())
+            .snippet_edit(edit)
+            .add_to(acc);
+    }
 }
 
 fn postfix_snippet(ctx: &CompletionContext, label: &str, detail: &str, snippet: &str) -> Builder {
-    let edit = {
-        let receiver_range =
-            ctx.dot_receiver.as_ref().expect("no receiver available").syntax().text_range();
-        let delete_range = TextRange::from_to(receiver_range.start(), ctx.source_range().end());
-        TextEdit::replace(delete_range, snippet.to_string())
-    };
+    let edit = TextEdit::replace(postfix_range(ctx), snippet.to_string());
     CompletionItem::new(CompletionKind::Postfix, ctx.source_range(), label)
         .detail(detail)
         .snippet_edit(edit)
 }
 
+/// The range replaced by a postfix completion: the receiver, the dot and the
+/// text typed after it.
+fn postfix_range(ctx: &CompletionContext) -> TextRange {
+    let receiver_range =
+        ctx.dot_receiver.as_ref().expect("no receiver available").syntax().text_range();
+    TextRange::from_to(receiver_range.start(), ctx.source_range().end())
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
+    use test_utils::assert_eq_text;
 
-    use crate::completion::{do_completion, CompletionItem, CompletionKind};
+    use crate::{
+        completion::{
+            do_completion, do_completion_with_templates, CompletionItem, CompletionKind,
+            CompletionTemplate,
+        },
+        mock_analysis::analysis_and_position,
+    };
 
     fn do_postfix_completion(code: &str) -> Vec<CompletionItem> {
         do_completion(code, CompletionKind::Postfix)
     }
 
+    fn postfix_template(label: &str, body: &str) -> CompletionTemplate {
+        CompletionTemplate::new("postfix", label.to_string(), body.to_string()).unwrap()
+    }
+
+    /// Only returns the completions coming from `templates`, the built-in ones
+    /// are checked by the other tests.
+    fn do_template_completion(code: &str, templates: &[CompletionTemplate]) -> Vec<CompletionItem> {
+        do_completion_with_templates(code, CompletionKind::Postfix, templates)
+            .into_iter()
+            .filter(|item| templates.iter().any(|it| it.label == item.label()))
+            .collect()
+    }
+
     #[test]
     fn postfix_completion_works_for_trivial_path_expression() {
         assert_debug_snapshot!(
@@ -279,4 +324,158 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn postfix_completion_works_for_templates() {
+        let ok = postfix_template("ok", "Ok($receiver)");
+        let mut spawn = postfix_template("spawn", "spawn($receiver)");
+        spawn.set_requirement("impl Future").unwrap();
+        spawn.set_description("spawn the task".to_string());
+        let mut task = postfix_template("task", "Box::new($receiver)");
+        task.set_requirement("Task").unwrap();
+        let mut foo = postfix_template("foo", "Some($receiver)");
+        foo.set_requirement("Foo").unwrap();
+
+        assert_debug_snapshot!(
+            do_template_completion(
+                r#"
+                //- /main.rs
+                trait Future {}
+                struct Task;
+                impl Future for Task {}
+                struct Foo;
+                fn main() {
+                    let task = Task;
+                    task.<|>
+                }
+                "#,
+                &[ok, spawn, task, foo],
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "ok",
+                source_range: [107; 107),
+                delete: [102; 107),
+                insert: "Ok(task)",
+                detail: "Ok(expr)",
+            },
+            CompletionItem {
+                label: "spawn",
+                source_range: [107; 107),
+                delete: [102; 107),
+                insert: "spawn(task)",
+                detail: "spawn the task",
+            },
+            CompletionItem {
+                label: "task",
+                source_range: [107; 107),
+                delete: [102; 107),
+                insert: "Box::new(task)",
+                detail: "Box::new(expr)",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn postfix_template_type_requirement_autoderefs() {
+        let mut foo = postfix_template("foo", "Some($receiver)");
+        foo.set_requirement("Foo").unwrap();
+        let mut alias = postfix_template("alias", "Ok($receiver)");
+        alias.set_requirement("Alias").unwrap();
+
+        assert_debug_snapshot!(
+            do_template_completion(
+                r#"
+                //- /main.rs
+                struct Foo;
+                type Alias = Foo;
+                fn main() {
+                    let foo = &Foo;
+                    foo.<|>
+                }
+                "#,
+                &[foo, alias],
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "alias",
+                source_range: [70; 70),
+                delete: [66; 70),
+                insert: "Ok(foo)",
+                detail: "Ok(expr)",
+            },
+            CompletionItem {
+                label: "foo",
+                source_range: [70; 70),
+                delete: [66; 70),
+                insert: "Some(foo)",
+                detail: "Some(expr)",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn postfix_template_escapes_receiver() {
+        assert_debug_snapshot!(
+            do_template_completion(
+                r#"
+                //- /main.rs
+                fn main() {
+                    "$a}".<|>
+                }
+                "#,
+                &[postfix_template("ok", "Ok($receiver)")],
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "ok",
+                source_range: [22; 22),
+                delete: [16; 22),
+                insert: "Ok(\"\\$a\\}\")",
+                detail: "Ok(expr)",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn postfix_template_adds_imports() {
+        let mut arc = postfix_template("arc", "Arc::new($receiver)");
+        arc.add_import("std::sync::Arc").unwrap();
+
+        let (analysis, position) = analysis_and_position(
+            "
+            //- /main.rs
+            fn main() {
+                let x = 92;
+                x.<|>
+            }
+            ",
+        );
+        let completions = analysis.completions(position, &[arc]).unwrap().unwrap();
+        let item = completions.iter().find(|it| it.label() == "arc").unwrap();
+        let text = analysis.file_text(position.file_id).unwrap();
+        assert_eq_text!(
+            "use std::sync::Arc;\n\nfn main() {\n    let x = 92;\n    Arc::new(x)\n}\n",
+            &item.text_edit().apply(&text)
+        );
+    }
+
+    #[test]
+    fn invalid_templates_are_rejected() {
+        assert!(CompletionTemplate::new("statement", "x".to_string(), "x".to_string()).is_err());
+        assert!(CompletionTemplate::new("expr", "x".to_string(), "$receiver".to_string()).is_err());
+        let mut template = postfix_template("arc", "Arc::new($receiver)");
+        assert!(template.add_import("std::sync::{Arc, Mutex}").is_err());
+        assert!(template.add_import("std::sync::*").is_err());
+        assert!(template.set_requirement("impl").is_err());
+    }
 }
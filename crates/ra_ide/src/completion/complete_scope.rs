@@ -1,59 +1,196 @@
 //! FIXME: write short doc here
 
+use hir::{db::AstDatabase, Crate, FromSource, InFile, ModPath, ModuleDef, PathKind, ScopeDef};
 use ra_assists::auto_import_text_edit;
-use ra_syntax::{ast, AstNode, SmolStr};
+use ra_syntax::{ast, match_ast, AstNode, SmolStr};
 use ra_text_edit::TextEditBuilder;
-use rustc_hash::FxHashMap;
+use rustc_hash::FxHashSet;
 
-use crate::completion::{CompletionContext, CompletionItem, CompletionKind, Completions};
+use crate::{
+    completion::{CompletionContext, CompletionItem, CompletionKind, Completions},
+    db::RootDatabase,
+    symbol_index::{self, FileSymbol},
+    Query,
+};
+
+/// The maximum number of auto-import completions. We search the import paths
+/// of all of them, so this keeps the latency of the completion in check.
+const AUTO_IMPORT_LIMIT: usize = 40;
 
 pub(super) fn complete_scope(acc: &mut Completions, ctx: &CompletionContext) {
     if !ctx.is_trivial_path {
         return;
     }
 
+    let mut in_scope = FxHashSet::default();
     ctx.analyzer.process_all_names(ctx.db, &mut |name, res| {
+        if let ScopeDef::ModuleDef(def) = res {
+            in_scope.insert(def);
+        }
         acc.add_resolution(ctx, name.to_string(), &res)
     });
 
-    // auto-import
+    if ctx.db.feature_flags.get("completion.enable-auto-import") {
+        complete_auto_import(acc, ctx, &in_scope);
+    }
+}
+
+/// Completes items which are not in scope yet, but can be imported from
+/// somewhere in the crate graph. Accepting such a completion adds the `use`.
+fn complete_auto_import(
+    acc: &mut Completions,
+    ctx: &CompletionContext,
+    in_scope: &FxHashSet<ModuleDef>,
+) {
     // We fetch ident from the original file, because we need to pre-filter auto-imports
-    if ast::NameRef::cast(ctx.token.parent()).is_some() {
-        let import_resolver = ImportResolver::new();
-        let import_names = import_resolver.all_names(ctx.token.text());
-        import_names.into_iter().for_each(|(name, path)| {
-            let edit = {
-                let mut builder = TextEditBuilder::default();
-                builder.replace(ctx.source_range(), name.to_string());
-                auto_import_text_edit(
-                    &ctx.token.parent(),
-                    &ctx.token.parent(),
-                    &path,
-                    &mut builder,
-                );
-                builder.finish()
-            };
-
-            // Hack: copied this check form conv.rs beacause auto import can produce edits
-            // that invalidate assert in conv_with.
-            if edit
-                .as_atoms()
-                .iter()
-                .filter(|atom| !ctx.source_range().is_subrange(&atom.delete))
-                .all(|atom| ctx.source_range().intersection(&atom.delete).is_none())
-            {
-                CompletionItem::new(
-                    CompletionKind::Reference,
-                    ctx.source_range(),
-                    build_import_label(&name, &path),
-                )
-                .text_edit(edit)
-                .add_to(acc);
-            }
-        });
+    if ast::NameRef::cast(ctx.original_token.parent()).is_none() {
+        return;
+    }
+    let module = match ctx.module {
+        Some(it) => it,
+        None => return,
+    };
+    let prefix = ctx.original_token.text();
+    if prefix.len() < 2 {
+        return;
+    }
+
+    // Items of crates outside of the dependency closure can't be reachable, so
+    // they are skipped before searching for paths.
+    let reachable_crates = dependency_closure(ctx.db, module.krate());
+    let candidates = importable_defs(ctx.db, prefix)
+        .into_iter()
+        .filter(|(_, def)| !in_scope.contains(def))
+        .filter(|(_, def)| match def.module(ctx.db) {
+            Some(module) => reachable_crates.contains(&module.krate()),
+            None => false,
+        })
+        .collect::<Vec<_>>();
+    let targets = candidates.iter().map(|(_, def)| *def).collect();
+    let paths = module.find_use_paths(ctx.db, &targets);
+    let candidates = candidates
+        .into_iter()
+        .filter_map(|(name, def)| Some((name, mod_path_segments(paths.get(&def)?))))
+        .take(AUTO_IMPORT_LIMIT);
+
+    for (name, path) in candidates {
+        let edit = {
+            let mut builder = TextEditBuilder::default();
+            builder.replace(ctx.source_range(), name.to_string());
+            auto_import_text_edit(
+                &ctx.original_token.parent(),
+                &ctx.original_token.parent(),
+                &path,
+                &mut builder,
+            );
+            builder.finish()
+        };
+
+        // Hack: copied this check form conv.rs beacause auto import can produce edits
+        // that invalidate assert in conv_with.
+        if edit
+            .as_atoms()
+            .iter()
+            .filter(|atom| !ctx.source_range().is_subrange(&atom.delete))
+            .all(|atom| ctx.source_range().intersection(&atom.delete).is_none())
+        {
+            CompletionItem::new(
+                CompletionKind::Reference,
+                ctx.source_range(),
+                build_import_label(&name, &path),
+            )
+            .text_edit(edit)
+            .add_to(acc);
+        }
     }
 }
 
+/// Looks up the items whose names start with `prefix` in the symbol index of
+/// the workspace and of the libraries.
+fn importable_defs(db: &RootDatabase, prefix: &str) -> Vec<(SmolStr, ModuleDef)> {
+    let query = |libs: bool| {
+        let mut query = Query::new(prefix.to_string());
+        query.prefix();
+        query.limit(AUTO_IMPORT_LIMIT);
+        if libs {
+            query.libs();
+        }
+        query
+    };
+    let symbols = symbol_index::world_symbols(db, query(false))
+        .into_iter()
+        .chain(symbol_index::world_symbols(db, query(true)));
+
+    let mut seen = FxHashSet::default();
+    symbols
+        .filter_map(|symbol| Some((symbol.name.clone(), symbol_to_def(db, &symbol)?)))
+        .filter(|(_, def)| seen.insert(*def))
+        .collect()
+}
+
+fn symbol_to_def(db: &RootDatabase, symbol: &FileSymbol) -> Option<ModuleDef> {
+    let root = db.parse_or_expand(symbol.file_id)?;
+    let node = symbol.ptr.to_node(&root);
+    let file_id = symbol.file_id;
+    match_ast! {
+        match node {
+            ast::FnDef(it) => {
+                let src = InFile::new(file_id, it);
+                hir::Function::from_source(db, src).map(Into::into)
+            },
+            ast::StructDef(it) => {
+                let src = InFile::new(file_id, it);
+                hir::Struct::from_source(db, src).map(Into::into)
+            },
+            ast::EnumDef(it) => {
+                let src = InFile::new(file_id, it);
+                hir::Enum::from_source(db, src).map(Into::into)
+            },
+            ast::TraitDef(it) => {
+                let src = InFile::new(file_id, it);
+                hir::Trait::from_source(db, src).map(Into::into)
+            },
+            ast::Module(it) => {
+                let src = InFile::new(file_id, it);
+                hir::Module::from_declaration(db, src).map(Into::into)
+            },
+            ast::TypeAliasDef(it) => {
+                let src = InFile::new(file_id, it);
+                hir::TypeAlias::from_source(db, src).map(Into::into)
+            },
+            ast::ConstDef(it) => {
+                let src = InFile::new(file_id, it);
+                hir::Const::from_source(db, src).map(Into::into)
+            },
+            ast::StaticDef(it) => {
+                let src = InFile::new(file_id, it);
+                hir::Static::from_source(db, src).map(Into::into)
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The crate and all the crates it depends on, directly or transitively.
+fn dependency_closure(db: &RootDatabase, krate: Crate) -> FxHashSet<Crate> {
+    let mut res = FxHashSet::default();
+    let mut stack = vec![krate];
+    while let Some(krate) = stack.pop() {
+        if res.insert(krate) {
+            stack.extend(krate.dependencies(db).into_iter().map(|dep| dep.krate));
+        }
+    }
+    res
+}
+
+fn mod_path_segments(path: &ModPath) -> Vec<SmolStr> {
+    let kind = match path.kind {
+        PathKind::Crate => Some(SmolStr::new("crate")),
+        _ => None,
+    };
+    kind.into_iter().chain(path.segments.iter().map(|it| SmolStr::new(it.to_string()))).collect()
+}
+
 fn build_import_label(name: &str, path: &[SmolStr]) -> String {
     let mut buf = String::with_capacity(64);
     buf.push_str(name);
@@ -74,65 +211,35 @@ fn fmt_import_path(path: &[SmolStr], buf: &mut String) {
     }
 }
 
-#[derive(Debug, Clone, Default)]
-pub(crate) struct ImportResolver {
-    // todo: use fst crate or something like that
-    dummy_names: Vec<(SmolStr, Vec<SmolStr>)>,
-}
-
-impl ImportResolver {
-    pub(crate) fn new() -> Self {
-        let dummy_names = vec![
-            (SmolStr::new("fmt"), vec![SmolStr::new("std"), SmolStr::new("fmt")]),
-            (SmolStr::new("io"), vec![SmolStr::new("std"), SmolStr::new("io")]),
-            (SmolStr::new("iter"), vec![SmolStr::new("std"), SmolStr::new("iter")]),
-            (SmolStr::new("hash"), vec![SmolStr::new("std"), SmolStr::new("hash")]),
-            (
-                SmolStr::new("Debug"),
-                vec![SmolStr::new("std"), SmolStr::new("fmt"), SmolStr::new("Debug")],
-            ),
-            (
-                SmolStr::new("Display"),
-                vec![SmolStr::new("std"), SmolStr::new("fmt"), SmolStr::new("Display")],
-            ),
-            (
-                SmolStr::new("Hash"),
-                vec![SmolStr::new("std"), SmolStr::new("hash"), SmolStr::new("Hash")],
-            ),
-            (
-                SmolStr::new("Hasher"),
-                vec![SmolStr::new("std"), SmolStr::new("hash"), SmolStr::new("Hasher")],
-            ),
-            (
-                SmolStr::new("Iterator"),
-                vec![SmolStr::new("std"), SmolStr::new("iter"), SmolStr::new("Iterator")],
-            ),
-        ];
-
-        ImportResolver { dummy_names }
-    }
-
-    // Returns a map of importable items filtered by name.
-    // The map associates item name with its full path.
-    // todo: should return Resolutions
-    pub(crate) fn all_names(&self, name: &str) -> FxHashMap<SmolStr, Vec<SmolStr>> {
-        if name.len() > 1 {
-            self.dummy_names.iter().filter(|(n, _)| n.contains(name)).cloned().collect()
-        } else {
-            FxHashMap::default()
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::completion::{do_completion, CompletionItem, CompletionKind};
+    use crate::{
+        completion::{do_completion, CompletionItem, CompletionKind},
+        mock_analysis::MockAnalysis,
+        FeatureFlags,
+    };
     use insta::assert_debug_snapshot;
+    use test_utils::assert_eq_text;
 
     fn do_reference_completion(code: &str) -> Vec<CompletionItem> {
         do_completion(code, CompletionKind::Reference)
     }
 
+    fn check_auto_import(fixture: &str, label: &str, after: &str) {
+        let (mock, position) = MockAnalysis::with_files_and_position(fixture);
+        let mut feature_flags = FeatureFlags::default();
+        feature_flags.set("completion.enable-auto-import", true).unwrap();
+        let analysis = mock.analysis_host_with_feature_flags(feature_flags).analysis();
+        let completions = analysis.completions(position, &[]).unwrap().unwrap();
+        let item = completions
+            .iter()
+            .find(|it| it.label() == label)
+            .unwrap_or_else(|| panic!("no completion item labeled {:?}", label));
+        let text = analysis.file_text(position.file_id).unwrap();
+        let actual = item.text_edit().apply(&text);
+        assert_eq_text!(after, &actual);
+    }
+
     #[test]
     fn completes_bindings_from_let() {
         assert_debug_snapshot!(
@@ -574,7 +681,7 @@ mod tests {
                 use prelude::*;
 
                 mod prelude {
-                    struct Option;
+                    pub struct Option;
                 }
                 "
             ),
@@ -621,7 +728,7 @@ mod tests {
                 use prelude::*;
 
                 mod prelude {
-                    struct Option;
+                    pub struct Option;
                 }
 
                 //- /std/lib.rs
@@ -629,7 +736,7 @@ mod tests {
                 use prelude::*;
 
                 mod prelude {
-                    struct String;
+                    pub struct String;
                 }
                 "
             ),
@@ -910,4 +1017,42 @@ mod tests {
         "###
         )
     }
+
+    #[test]
+    fn completes_unresolved_names_from_dependencies_with_import() {
+        check_auto_import(
+            "
+            //- /main.rs
+            fn main() {
+                HashM<|>
+            }
+            //- /std/lib.rs
+            pub mod collections {
+                pub use self::hash_map::HashMap;
+                mod hash_map {
+                    pub struct HashMap;
+                }
+            }
+            ",
+            "HashMap (std::collections::HashMap)",
+            "use std::collections::HashMap;\n\nfn main() {\n    HashMap\n}\n",
+        );
+    }
+
+    #[test]
+    fn completes_unresolved_names_from_other_modules_with_import() {
+        check_auto_import(
+            "
+            //- /main.rs
+            mod foo;
+            fn main() {
+                Fro<|>
+            }
+            //- /foo.rs
+            pub fn frobnicate() {}
+            ",
+            "frobnicate (crate::foo::frobnicate)",
+            "use crate::foo::frobnicate;\n\nmod foo;\nfn main() {\n    frobnicate\n}\n",
+        );
+    }
 }
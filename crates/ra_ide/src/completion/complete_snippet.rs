@@ -1,8 +1,9 @@
 //! FIXME: write short doc here
 
 use crate::completion::{
-    completion_item::Builder, CompletionContext, CompletionItem, CompletionItemKind,
-    CompletionKind, Completions,
+    completion_item::Builder,
+    completion_template::{CompletionTemplate, TemplateScope},
+    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
 };
 
 fn snippet(ctx: &CompletionContext, label: &str, snippet: &str) -> Builder {
@@ -11,16 +12,36 @@ fn snippet(ctx: &CompletionContext, label: &str, snippet: &str) -> Builder {
         .kind(CompletionItemKind::Snippet)
 }
 
-pub(super) fn complete_expr_snippet(acc: &mut Completions, ctx: &CompletionContext) {
+fn template_snippet(ctx: &CompletionContext, template: &CompletionTemplate) -> Builder {
+    let edit = template.edit(ctx, ctx.source_range(), template.body.clone());
+    CompletionItem::new(CompletionKind::Snippet, ctx.source_range(), template.label.as_str())
+        .detail(template.detail())
+        .snippet_edit(edit)
+        .kind(CompletionItemKind::Snippet)
+}
+
+pub(super) fn complete_expr_snippet(
+    acc: &mut Completions,
+    ctx: &CompletionContext,
+    templates: &[CompletionTemplate],
+) {
     if !(ctx.is_trivial_path && ctx.function_syntax.is_some()) {
         return;
     }
 
     snippet(ctx, "pd", "eprintln!(\"$0 = {:?}\", $0);").add_to(acc);
     snippet(ctx, "ppd", "eprintln!(\"$0 = {:#?}\", $0);").add_to(acc);
+
+    for template in templates.iter().filter(|it| it.scope == TemplateScope::Expr) {
+        template_snippet(ctx, template).add_to(acc);
+    }
 }
 
-pub(super) fn complete_item_snippet(acc: &mut Completions, ctx: &CompletionContext) {
+pub(super) fn complete_item_snippet(
+    acc: &mut Completions,
+    ctx: &CompletionContext,
+    templates: &[CompletionTemplate],
+) {
     if !ctx.is_new_item {
         return;
     }
@@ -37,17 +58,37 @@ fn ${1:feature}() {
     .add_to(acc);
 
     snippet(ctx, "pub(crate)", "pub(crate) $0").add_to(acc);
+
+    for template in templates.iter().filter(|it| it.scope == TemplateScope::Item) {
+        template_snippet(ctx, template).add_to(acc);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::completion::{do_completion, CompletionItem, CompletionKind};
+    use crate::completion::{
+        do_completion, do_completion_with_templates, CompletionItem, CompletionKind,
+        CompletionTemplate,
+    };
     use insta::assert_debug_snapshot;
 
     fn do_snippet_completion(code: &str) -> Vec<CompletionItem> {
         do_completion(code, CompletionKind::Snippet)
     }
 
+    fn do_template_completion(code: &str) -> Vec<CompletionItem> {
+        let ok =
+            CompletionTemplate::new("expr", "ok".to_string(), "Ok(${1:()})".to_string()).unwrap();
+        let mut main = CompletionTemplate::new(
+            "item",
+            "main".to_string(),
+            "fn main() {\n    $0\n}".to_string(),
+        )
+        .unwrap();
+        main.set_description("main function".to_string());
+        do_completion_with_templates(code, CompletionKind::Snippet, &[ok, main])
+    }
+
     #[test]
     fn completes_snippets_in_expressions() {
         assert_debug_snapshot!(
@@ -117,4 +158,74 @@ mod tests {
         "###
         );
     }
+
+    #[test]
+    fn completes_user_defined_snippets() {
+        assert_debug_snapshot!(
+            do_template_completion(r"fn foo(x: i32) { <|> }"),
+            @r###"
+        [
+            CompletionItem {
+                label: "ok",
+                source_range: [17; 17),
+                delete: [17; 17),
+                insert: "Ok(${1:()})",
+                kind: Snippet,
+                detail: "Ok(${1:()})",
+            },
+            CompletionItem {
+                label: "pd",
+                source_range: [17; 17),
+                delete: [17; 17),
+                insert: "eprintln!(\"$0 = {:?}\", $0);",
+                kind: Snippet,
+            },
+            CompletionItem {
+                label: "ppd",
+                source_range: [17; 17),
+                delete: [17; 17),
+                insert: "eprintln!(\"$0 = {:#?}\", $0);",
+                kind: Snippet,
+            },
+        ]
+        "###
+        );
+        assert_debug_snapshot!(
+            do_template_completion(
+                r"
+                #[cfg(test)]
+                mod tests {
+                    <|>
+                }
+                "
+            ),
+            @r###"
+        [
+            CompletionItem {
+                label: "Test function",
+                source_range: [78; 78),
+                delete: [78; 78),
+                insert: "#[test]\nfn ${1:feature}() {\n    $0\n}",
+                kind: Snippet,
+                lookup: "tfn",
+            },
+            CompletionItem {
+                label: "main",
+                source_range: [78; 78),
+                delete: [78; 78),
+                insert: "fn main() {\n    $0\n}",
+                kind: Snippet,
+                detail: "main function",
+            },
+            CompletionItem {
+                label: "pub(crate)",
+                source_range: [78; 78),
+                delete: [78; 78),
+                insert: "pub(crate) $0",
+                kind: Snippet,
+            },
+        ]
+        "###
+        );
+    }
 }
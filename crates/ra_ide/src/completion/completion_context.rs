@@ -1,8 +1,9 @@
 //! FIXME: write short doc here
 
+use hir::db::AstDatabase;
 use ra_syntax::{
     algo::{find_covering_element, find_node_at_offset},
-    ast, AstNode, Parse, SourceFile,
+    ast, AstNode, Parse,
     SyntaxKind::*,
     SyntaxNode, SyntaxToken, TextRange, TextUnit,
 };
@@ -17,7 +18,11 @@ pub(crate) struct CompletionContext<'a> {
     pub(super) db: &'a db::RootDatabase,
     pub(super) analyzer: hir::SourceAnalyzer,
     pub(super) offset: TextUnit,
+    /// The token at the cursor. If the cursor is inside a macro call, this
+    /// is the corresponding token in the expansion.
     pub(super) token: SyntaxToken,
+    /// The token at the cursor in the original file.
+    pub(super) original_token: SyntaxToken,
     pub(super) module: Option<hir::Module>,
     pub(super) name_ref_syntax: Option<ast::NameRef>,
     pub(super) function_syntax: Option<ast::FnDef>,
@@ -57,17 +62,86 @@ impl<'a> CompletionContext<'a> {
             db,
             hir::InFile { file_id: position.file_id.into(), value: src },
         );
-        let token =
+        // Insert a fake ident to get a valid parse tree. We will use this file
+        // to determine context, though the original_file will be used for
+        // actual completion.
+        let file_with_fake_ident = {
+            let edit = AtomTextEdit::insert(position.offset, "intellijRulezz".to_string());
+            original_parse.reparse(&edit).tree()
+        };
+        let original_token =
             original_parse.tree().syntax().token_at_offset(position.offset).left_biased()?;
-        let analyzer = hir::SourceAnalyzer::new(
-            db,
-            hir::InFile::new(position.file_id.into(), &token.parent()),
-            Some(position.offset),
-        );
+
+        let mut file_id: hir::HirFileId = position.file_id.into();
+        let mut token = original_token.clone();
+        let mut original_file = original_parse.tree().syntax().clone();
+        let mut hypothetical_file = file_with_fake_ident.syntax().clone();
+        let mut offset = position.offset;
+        let mut fake_ident_token = hypothetical_file.token_at_offset(offset).right_biased()?;
+
+        // If the cursor is inside a macro call, continue in its expansion. The
+        // call is expanded both as it is and with the fake ident, as the
+        // incomplete code at the cursor might not expand to anything useful.
+        while let (Some(actual_macro_call), Some(macro_call_with_fake_ident)) = (
+            find_node_at_offset::<ast::MacroCall>(&original_file, offset),
+            find_node_at_offset::<ast::MacroCall>(&hypothetical_file, offset),
+        ) {
+            // Completing the name of the macro itself
+            if actual_macro_call.path().map(|it| it.syntax().to_string())
+                != macro_call_with_fake_ident.path().map(|it| it.syntax().to_string())
+            {
+                break;
+            }
+            let hypothetical_args = match macro_call_with_fake_ident.token_tree() {
+                Some(it) => it,
+                None => break,
+            };
+            let analyzer = hir::SourceAnalyzer::new(
+                db,
+                hir::InFile::new(file_id, actual_macro_call.syntax()),
+                None,
+            );
+            let expansion = match analyzer.expand(db, hir::InFile::new(file_id, &actual_macro_call))
+            {
+                Some(it) => it,
+                None => break,
+            };
+            let actual_expansion = match db.parse_or_expand(expansion.file_id()) {
+                Some(it) => it,
+                None => break,
+            };
+            // The cursor might be in the middle of the fake ident token, as the
+            // ident is glued to what was typed before it
+            let offset_in_token = offset - fake_ident_token.text_range().start();
+            let (hypothetical_expansion, new_fake_ident_token) =
+                match expansion.expand_hypothetical(db, &hypothetical_args, fake_ident_token) {
+                    Some(it) => it,
+                    None => break,
+                };
+            let new_offset = new_fake_ident_token.text_range().start() + offset_in_token;
+            // The two expansions don't line up, e.g. because different rules
+            // of the macro matched
+            if new_offset > actual_expansion.text_range().end() {
+                break;
+            }
+            token = match actual_expansion.token_at_offset(new_offset).left_biased() {
+                Some(it) => it,
+                None => break,
+            };
+            file_id = expansion.file_id();
+            original_file = actual_expansion;
+            hypothetical_file = hypothetical_expansion;
+            fake_ident_token = new_fake_ident_token;
+            offset = new_offset;
+        }
+
+        let analyzer =
+            hir::SourceAnalyzer::new(db, hir::InFile::new(file_id, &token.parent()), Some(offset));
         let mut ctx = CompletionContext {
             db,
             analyzer,
             token,
+            original_token,
             offset: position.offset,
             module,
             name_ref_syntax: None,
@@ -88,42 +162,44 @@ impl<'a> CompletionContext<'a> {
             has_type_args: false,
             dot_receiver_is_ambiguous_float_literal: false,
         };
-        ctx.fill(&original_parse, position.offset);
+        ctx.fill(&original_file, hypothetical_file, offset);
         Some(ctx)
     }
 
     // The range of the identifier that is being completed.
     pub(crate) fn source_range(&self) -> TextRange {
+        // The token might come from a macro expansion, but the range has to
+        // be in the original file.
         match self.token.kind() {
             // workaroud when completion is triggered by trigger characters.
-            IDENT => self.token.text_range(),
+            IDENT => self.original_token.text_range(),
             _ => TextRange::offset_len(self.offset, 0.into()),
         }
     }
 
-    fn fill(&mut self, original_parse: &'a Parse<ast::SourceFile>, offset: TextUnit) {
-        // Insert a fake ident to get a valid parse tree. We will use this file
-        // to determine context, though the original_file will be used for
-        // actual completion.
-        let file = {
-            let edit = AtomTextEdit::insert(offset, "intellijRulezz".to_string());
-            original_parse.reparse(&edit).tree()
-        };
-
+    /// `original_file` and `file_with_fake_ident` are either the file being
+    /// edited or the expansion of the macro call the cursor is in, and
+    /// `offset` is the position of the cursor in them.
+    fn fill(
+        &mut self,
+        original_file: &SyntaxNode,
+        file_with_fake_ident: SyntaxNode,
+        offset: TextUnit,
+    ) {
         // First, let's try to complete a reference to some declaration.
-        if let Some(name_ref) = find_node_at_offset::<ast::NameRef>(file.syntax(), offset) {
+        if let Some(name_ref) = find_node_at_offset::<ast::NameRef>(&file_with_fake_ident, offset) {
             // Special case, `trait T { fn foo(i_am_a_name_ref) {} }`.
             // See RFC#1685.
             if is_node::<ast::Param>(name_ref.syntax()) {
                 self.is_param = true;
                 return;
             }
-            self.classify_name_ref(original_parse.tree(), name_ref);
+            self.classify_name_ref(original_file, name_ref, offset);
         }
 
         // Otherwise, see if this is a declaration. We can use heuristics to
         // suggest declaration names, see `CompletionKind::Magic`.
-        if let Some(name) = find_node_at_offset::<ast::Name>(file.syntax(), offset) {
+        if let Some(name) = find_node_at_offset::<ast::Name>(&file_with_fake_ident, offset) {
             if let Some(bind_pat) = name.syntax().ancestors().find_map(ast::BindPat::cast) {
                 let parent = bind_pat.syntax().parent();
                 if parent.clone().and_then(ast::MatchArm::cast).is_some()
@@ -137,18 +213,22 @@ impl<'a> CompletionContext<'a> {
                 return;
             }
             if name.syntax().ancestors().find_map(ast::RecordFieldPatList::cast).is_some() {
-                self.record_lit_pat =
-                    find_node_at_offset(original_parse.tree().syntax(), self.offset);
+                self.record_lit_pat = find_node_at_offset(original_file, offset);
             }
         }
     }
 
-    fn classify_name_ref(&mut self, original_file: SourceFile, name_ref: ast::NameRef) {
+    fn classify_name_ref(
+        &mut self,
+        original_file: &SyntaxNode,
+        name_ref: ast::NameRef,
+        offset: TextUnit,
+    ) {
         self.name_ref_syntax =
-            find_node_at_offset(original_file.syntax(), name_ref.syntax().text_range().start());
+            find_node_at_offset(original_file, name_ref.syntax().text_range().start());
         let name_range = name_ref.syntax().text_range();
         if name_ref.syntax().parent().and_then(ast::RecordField::cast).is_some() {
-            self.record_lit_syntax = find_node_at_offset(original_file.syntax(), self.offset);
+            self.record_lit_syntax = find_node_at_offset(original_file, offset);
         }
 
         let top_node = name_ref
@@ -166,10 +246,11 @@ impl<'a> CompletionContext<'a> {
             _ => (),
         }
 
-        self.use_item_syntax = self.token.parent().ancestors().find_map(ast::UseItem::cast);
+        self.use_item_syntax =
+            self.original_token.parent().ancestors().find_map(ast::UseItem::cast);
 
         self.function_syntax = self
-            .token
+            .original_token
             .parent()
             .ancestors()
             .take_while(|it| it.kind() != SOURCE_FILE && it.kind() != MODULE)
@@ -224,9 +305,7 @@ impl<'a> CompletionContext<'a> {
                     .unwrap_or(false);
 
                 if let Some(off) = name_ref.syntax().text_range().start().checked_sub(2.into()) {
-                    if let Some(if_expr) =
-                        find_node_at_offset::<ast::IfExpr>(original_file.syntax(), off)
-                    {
+                    if let Some(if_expr) = find_node_at_offset::<ast::IfExpr>(original_file, off) {
                         if if_expr.syntax().text_range().end()
                             < name_ref.syntax().text_range().start()
                         {
@@ -242,7 +321,7 @@ impl<'a> CompletionContext<'a> {
             self.dot_receiver = field_expr
                 .expr()
                 .map(|e| e.syntax().text_range())
-                .and_then(|r| find_node_with_range(original_file.syntax(), r));
+                .and_then(|r| find_node_with_range(original_file, r));
             self.dot_receiver_is_ambiguous_float_literal =
                 if let Some(ast::Expr::Literal(l)) = &self.dot_receiver {
                     match l.kind() {
@@ -258,7 +337,7 @@ impl<'a> CompletionContext<'a> {
             self.dot_receiver = method_call_expr
                 .expr()
                 .map(|e| e.syntax().text_range())
-                .and_then(|r| find_node_with_range(original_file.syntax(), r));
+                .and_then(|r| find_node_with_range(original_file, r));
             self.is_call = true;
         }
     }
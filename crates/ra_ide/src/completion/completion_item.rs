@@ -306,6 +306,15 @@ impl Into<Vec<CompletionItem>> for Completions {
 
 #[cfg(test)]
 pub(crate) fn do_completion(code: &str, kind: CompletionKind) -> Vec<CompletionItem> {
+    do_completion_with_templates(code, kind, &[])
+}
+
+#[cfg(test)]
+pub(crate) fn do_completion_with_templates(
+    code: &str,
+    kind: CompletionKind,
+    templates: &[crate::completion::CompletionTemplate],
+) -> Vec<CompletionItem> {
     use crate::completion::completions;
     use crate::mock_analysis::{analysis_and_position, single_file_with_position};
     let (analysis, position) = if code.contains("//-") {
@@ -313,7 +322,7 @@ pub(crate) fn do_completion(code: &str, kind: CompletionKind) -> Vec<CompletionI
     } else {
         single_file_with_position(code)
     };
-    let completions = completions(&analysis.db, position).unwrap();
+    let completions = completions(&analysis.db, position, templates).unwrap();
     let completion_items: Vec<CompletionItem> = completions.into();
     let mut kind_completions: Vec<CompletionItem> =
         completion_items.into_iter().filter(|c| c.completion_kind == kind).collect();
@@ -0,0 +1,213 @@
+//! User-defined completions: postfix templates (like `.arc` for
+//! `Arc::new(expr)`) and expression or item snippets.
+//!
+//! The body of a template is a snippet in the LSP syntax (`$0`, `${1:_}`). In
+//! postfix templates, `$receiver` is replaced with the text of the receiver
+//! expression. A postfix template can be restricted to certain receivers:
+//!
+//! * `impl path::to::Trait` requires the receiver type to implement the trait,
+//! * `path::to::Type` requires the receiver to be of the given type, or a
+//!   reference to it.
+//!
+//! A template can also declare paths which are imported (through the same
+//! machinery as the `add_import` assist) when the completion is accepted.
+
+use hir::{ModuleDef, PathResolution};
+use ra_assists::auto_import_text_edit;
+use ra_syntax::{ast, AstNode, SmolStr, SourceFile, TextRange};
+use ra_text_edit::{TextEdit, TextEditBuilder};
+
+use crate::completion::CompletionContext;
+
+const RECEIVER_PLACEHOLDER: &str = "$receiver";
+
+/// Where a `CompletionTemplate` is offered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TemplateScope {
+    /// After a `.` following an expression.
+    Postfix,
+    /// Where an expression is expected inside of a function.
+    Expr,
+    /// Where an item is expected.
+    Item,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Requirement {
+    /// The receiver type implements the trait.
+    Implements(hir::Path),
+    /// The receiver is of the type.
+    Type(hir::Path),
+}
+
+/// A completion defined by the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionTemplate {
+    pub(super) scope: TemplateScope,
+    pub(super) label: String,
+    pub(super) body: String,
+    description: Option<String>,
+    requirement: Option<Requirement>,
+    imports: Vec<Vec<SmolStr>>,
+}
+
+impl CompletionTemplate {
+    /// Creates a template from the name of its scope (`postfix`, `expr` or
+    /// `item`), the label to complete and the body.
+    pub fn new(scope: &str, label: String, body: String) -> Result<CompletionTemplate, String> {
+        let scope = match scope {
+            "postfix" => TemplateScope::Postfix,
+            "expr" => TemplateScope::Expr,
+            "item" => TemplateScope::Item,
+            _ => return Err(format!("unknown template scope: {:?}", scope)),
+        };
+        if label.is_empty() {
+            return Err("template label is empty".to_string());
+        }
+        if scope != TemplateScope::Postfix && body.contains(RECEIVER_PLACEHOLDER) {
+            return Err(format!("{} is only available in postfix templates", RECEIVER_PLACEHOLDER));
+        }
+        Ok(CompletionTemplate {
+            scope,
+            label,
+            body,
+            description: None,
+            requirement: None,
+            imports: Vec::new(),
+        })
+    }
+
+    pub fn set_description(&mut self, description: String) {
+        self.description = Some(description);
+    }
+
+    /// Restricts a postfix template to receivers matching `requirement`,
+    /// which is either `impl path::to::Trait` or `path::to::Type`.
+    pub fn set_requirement(&mut self, requirement: &str) -> Result<(), String> {
+        if self.scope != TemplateScope::Postfix {
+            return Err("only postfix templates can have requirements".to_string());
+        }
+        let requirement = requirement.trim();
+        let (path, is_trait) = if requirement.starts_with("impl ") {
+            (&requirement["impl ".len()..], true)
+        } else {
+            (requirement, false)
+        };
+        let path = parse_path(path).and_then(hir::Path::from_ast);
+        let path = path.ok_or_else(|| format!("invalid requirement: {:?}", requirement))?;
+        self.requirement =
+            Some(if is_trait { Requirement::Implements(path) } else { Requirement::Type(path) });
+        Ok(())
+    }
+
+    /// Adds a path which is imported when the completion is accepted.
+    pub fn add_import(&mut self, path: &str) -> Result<(), String> {
+        if parse_path(path).is_none() {
+            return Err(format!("invalid import: {:?}", path));
+        }
+        let segments = path.split("::").map(|it| SmolStr::new(it.trim())).collect();
+        self.imports.push(segments);
+        Ok(())
+    }
+
+    pub(super) fn detail(&self) -> String {
+        match &self.description {
+            Some(it) => it.clone(),
+            None => self.body.replace(RECEIVER_PLACEHOLDER, "expr"),
+        }
+    }
+
+    pub(super) fn snippet(&self, receiver_text: &str) -> String {
+        self.body.replace(RECEIVER_PLACEHOLDER, &escape_snippet_text(receiver_text))
+    }
+
+    pub(super) fn matches_receiver(
+        &self,
+        ctx: &CompletionContext,
+        receiver_ty: &hir::Type,
+    ) -> bool {
+        let (path, is_trait) = match &self.requirement {
+            None => return true,
+            Some(Requirement::Implements(path)) => (path, true),
+            Some(Requirement::Type(path)) => (path, false),
+        };
+        let adt = match ctx.analyzer.resolve_hir_path(ctx.db, path) {
+            Some(PathResolution::Def(ModuleDef::Trait(trait_))) if is_trait => {
+                return ctx.analyzer.impls_trait(ctx.db, receiver_ty.clone(), trait_);
+            }
+            Some(PathResolution::Def(ModuleDef::Adt(adt))) if !is_trait => adt,
+            Some(PathResolution::Def(ModuleDef::TypeAlias(alias))) if !is_trait => {
+                match alias.ty(ctx.db).as_adt() {
+                    Some(adt) => adt,
+                    None => return false,
+                }
+            }
+            _ => return false,
+        };
+        // Like method calls, the template applies to references to the type.
+        receiver_ty.autoderef(ctx.db).any(|ty| ty.as_adt() == Some(adt))
+    }
+
+    /// Builds an edit which replaces `range` with `snippet` and adds the
+    /// imports of the template.
+    pub(super) fn edit(
+        &self,
+        ctx: &CompletionContext,
+        range: TextRange,
+        snippet: String,
+    ) -> TextEdit {
+        if self.imports.is_empty() {
+            return TextEdit::replace(range, snippet);
+        }
+        let mut builder = TextEditBuilder::default();
+        builder.replace(range, snippet.clone());
+        let anchor = ctx.original_token.parent();
+        for import in self.imports.iter() {
+            auto_import_text_edit(&anchor, &anchor, import, &mut builder);
+        }
+        let edit = builder.finish();
+
+        // LSP can't apply additional edits which touch the completed range, so
+        // we drop the imports in this (rare) case.
+        let source_range = ctx.source_range();
+        if edit
+            .as_atoms()
+            .iter()
+            .filter(|atom| !source_range.is_subrange(&atom.delete))
+            .all(|atom| source_range.intersection(&atom.delete).is_none())
+        {
+            edit
+        } else {
+            TextEdit::replace(range, snippet)
+        }
+    }
+}
+
+/// Escapes the characters which have a meaning in snippets, so that the text
+/// is inserted as is.
+fn escape_snippet_text(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '$' || c == '}' || c == '\\' {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+fn parse_path(text: &str) -> Option<ast::Path> {
+    let parse = SourceFile::parse(&format!("use {};", text.trim()));
+    if !parse.errors().is_empty() {
+        return None;
+    }
+    let use_tree = parse.tree().syntax().descendants().find_map(ast::UseTree::cast)?;
+    if use_tree.syntax().text().to_string() != text.trim()
+        || use_tree.has_star()
+        || use_tree.use_tree_list().is_some()
+        || use_tree.alias().is_some()
+    {
+        return None;
+    }
+    use_tree.path()
+}
@@ -166,6 +166,10 @@ impl Completions {
             Some(it) => it,
             None => return,
         };
+        // FIXME: complete proc macros too, they just lack a source for the label
+        if macro_.is_proc_macro() {
+            return;
+        }
 
         let ast_node = macro_.source(ctx.db).value;
         let detail = macro_label(&ast_node);
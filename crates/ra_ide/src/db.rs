@@ -5,9 +5,11 @@ use std::sync::Arc;
 use ra_db::{
     salsa::{self, Database, Durability},
     Canceled, CheckCanceled, CrateId, FileId, FileLoader, FileLoaderDelegate, RelativePath,
-    SourceDatabase, SourceRootId,
+    SourceDatabase, SourceDatabaseExt, SourceRootId,
 };
+use ra_syntax::{Parse, SourceFile};
 use rustc_hash::FxHashMap;
+use test_utils::tested_by;
 
 use crate::{
     symbol_index::{self, SymbolsDatabase},
@@ -29,6 +31,9 @@ pub(crate) struct RootDatabase {
     runtime: salsa::Runtime<RootDatabase>,
     pub(crate) feature_flags: Arc<FeatureFlags>,
     pub(crate) debug_data: Arc<DebugData>,
+    /// Trees produced by incremental reparsing in `apply_change`, together
+    /// with the text they were reparsed into.
+    pub(crate) reparsed_files: Arc<FxHashMap<FileId, (Arc<String>, Parse<SourceFile>)>>,
     pub(crate) last_gc: crate::wasm_shims::Instant,
     pub(crate) last_gc_check: crate::wasm_shims::Instant,
 }
@@ -47,6 +52,22 @@ impl FileLoader for RootDatabase {
     fn relevant_crates(&self, file_id: FileId) -> Arc<Vec<CrateId>> {
         FileLoaderDelegate(self).relevant_crates(file_id)
     }
+    fn resolve_extern_path(
+        &self,
+        source_root: SourceRootId,
+        relative_path: &RelativePath,
+    ) -> Option<FileId> {
+        FileLoaderDelegate(self).resolve_extern_path(source_root, relative_path)
+    }
+    fn reparsed_file(&self, file_id: FileId) -> Option<Parse<SourceFile>> {
+        let (text, parse) = self.reparsed_files.get(&file_id)?;
+        if Arc::ptr_eq(text, &SourceDatabaseExt::file_text(self, file_id)) {
+            tested_by!(reparsed_file_is_reused);
+            Some(parse.clone())
+        } else {
+            None
+        }
+    }
 }
 
 impl salsa::Database for RootDatabase {
@@ -84,6 +105,7 @@ impl RootDatabase {
             last_gc_check: crate::wasm_shims::Instant::now(),
             feature_flags: Arc::new(feature_flags),
             debug_data: Default::default(),
+            reparsed_files: Default::default(),
         };
         db.set_crate_graph_with_durability(Default::default(), Durability::HIGH);
         db.set_local_roots_with_durability(Default::default(), Durability::HIGH);
@@ -104,6 +126,7 @@ impl salsa::ParallelDatabase for RootDatabase {
             last_gc_check: self.last_gc_check,
             feature_flags: Arc::clone(&self.feature_flags),
             debug_data: Arc::clone(&self.debug_data),
+            reparsed_files: Arc::clone(&self.reparsed_files),
         })
     }
 }
@@ -93,6 +93,60 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
             severity: Severity::Error,
             fix: Some(fix),
         })
+    })
+    .on::<hir::diagnostics::TypeMismatch, _>(|d| {
+        if !db.feature_flags.get("diagnostics.type-mismatch") {
+            return;
+        }
+        // FIXME: map mismatches inside macro expansions back to the call site
+        if d.file != file_id.into() {
+            return;
+        }
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::UnresolvedMacroCall, _>(|d| {
+        if !db.feature_flags.get("diagnostics.unresolved-macro-call") {
+            return;
+        }
+        // FIXME: map unresolved calls inside macro expansions back to the call site
+        if d.file != file_id.into() {
+            return;
+        }
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::MacroError, _>(|d| {
+        // FIXME: map errors of macro calls inside macro expansions back to the call site
+        if d.file != file_id.into() {
+            return;
+        }
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix: None,
+        })
+    })
+    .on::<hir::diagnostics::MissingUnsafe, _>(|d| {
+        // FIXME: map unsafe operations inside macro expansions back to the call site
+        if d.file != file_id.into() {
+            return;
+        }
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix: None,
+        })
     });
     let source_file = db.parse(file_id).tree();
     let src =
@@ -194,7 +248,10 @@ mod tests {
     use ra_syntax::SourceFile;
     use test_utils::assert_eq_text;
 
-    use crate::mock_analysis::{analysis_and_position, single_file};
+    use crate::{
+        mock_analysis::{analysis_and_position, single_file, MockAnalysis},
+        FeatureFlags,
+    };
 
     use super::*;
 
@@ -551,6 +608,74 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_macro_error_diagnostic() {
+        let (analysis, file_id) = single_file(
+            r"
+            macro_rules! foo {
+                ($a:ident) => {};
+            }
+
+            foo!(a, b);
+
+            fn f() {
+                foo!(1);
+            }
+            ",
+        );
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        assert_debug_snapshot!(diagnostics, @r###"
+        [
+            Diagnostic {
+                message: "no rules expected the token `,` (rule #1 expected the end of the input)",
+                range: [99; 100),
+                fix: None,
+                severity: Error,
+            },
+            Diagnostic {
+                message: "no rules expected the token `1` (rule #1 expected an identifier)",
+                range: [148; 149),
+                fix: None,
+                severity: Error,
+            },
+        ]
+        "###);
+    }
+
+    #[test]
+    fn test_unresolved_macro_call_diagnostic() {
+        let mut mock = MockAnalysis::new();
+        let file_id = mock.add_file(
+            "/main.rs",
+            r"
+            macro_rules! foo {
+                () => { 92 };
+            }
+
+            bar! {}
+
+            fn f() {
+                let x = foo!();
+                let y = baz!();
+                let z = empty::qux!();
+            }
+            ",
+        );
+        mock.add_file("/empty/lib.rs", "");
+        let mut feature_flags = FeatureFlags::default();
+        feature_flags.set("diagnostics.unresolved-macro-call", true).unwrap();
+        let analysis = mock.analysis_host_with_feature_flags(feature_flags).analysis();
+
+        let text = analysis.file_text(file_id).unwrap();
+        let diagnostics = analysis.diagnostics(file_id).unwrap();
+        let diagnostics =
+            diagnostics.iter().map(|it| (it.message.as_str(), &text[it.range])).collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![("unresolved macro call", "bar! {}"), ("unresolved macro call", "baz!()")]
+        );
+    }
+
     #[test]
     fn test_check_unnecessary_braces_in_use_statement() {
         check_not_applicable(
@@ -26,6 +26,8 @@ pub struct FunctionSignature {
     pub kind: CallableKind,
     /// Optional visibility
     pub visibility: Option<String>,
+    /// Optional ABI, for `extern` functions
+    pub abi: Option<String>,
     /// Name of the function
     pub name: Option<String>,
     /// Documentation for the function
@@ -63,8 +65,8 @@ impl FunctionSignature {
             .fields(db)
             .into_iter()
             .map(|field: hir::StructField| {
-                let ty = field.ty(db);
-                format!("{}", ty.display(db))
+                let type_ref = field.type_ref(db);
+                format!("{}", type_ref.display(db))
             })
             .collect();
 
@@ -72,6 +74,7 @@ impl FunctionSignature {
             FunctionSignature {
                 kind: CallableKind::StructConstructor,
                 visibility: node.visibility().map(|n| n.syntax().text().to_string()),
+                abi: None,
                 name: node.name().map(|n| n.text().to_string()),
                 ret_type: node.name().map(|n| n.text().to_string()),
                 parameters: params,
@@ -102,8 +105,8 @@ impl FunctionSignature {
             .into_iter()
             .map(|field: hir::StructField| {
                 let name = field.name(db);
-                let ty = field.ty(db);
-                format!("{}: {}", name, ty.display(db))
+                let type_ref = field.type_ref(db);
+                format!("{}: {}", name, type_ref.display(db))
             })
             .collect();
 
@@ -111,6 +114,7 @@ impl FunctionSignature {
             FunctionSignature {
                 kind: CallableKind::VariantConstructor,
                 visibility: None,
+                abi: None,
                 name: Some(name),
                 ret_type: None,
                 parameters: params,
@@ -123,7 +127,10 @@ impl FunctionSignature {
     }
 
     pub(crate) fn from_macro(db: &db::RootDatabase, macro_def: hir::MacroDef) -> Option<Self> {
-        let node: ast::MacroCall = macro_def.source(db).value;
+        if macro_def.is_proc_macro() {
+            return None;
+        }
+        let node: ast::Macro = macro_def.source(db).value;
 
         let params = vec![];
 
@@ -131,6 +138,7 @@ impl FunctionSignature {
             FunctionSignature {
                 kind: CallableKind::Macro,
                 visibility: None,
+                abi: None,
                 name: node.name().map(|n| n.text().to_string()),
                 ret_type: None,
                 parameters: params,
@@ -153,13 +161,25 @@ impl From<&'_ ast::FnDef> for FunctionSignature {
                 }
 
                 res.extend(param_list.params().map(|param| param.syntax().text().to_string()));
+                if param_list.is_variadic() {
+                    res.push("...".to_string());
+                }
             }
             res
         }
 
+        fn abi(node: &ast::FnDef) -> Option<String> {
+            let abi = match ast::ExternItem::from(node.clone()).extern_block() {
+                Some(block) => block.abi(),
+                None => node.abi(),
+            };
+            abi?.name()
+        }
+
         FunctionSignature {
             kind: CallableKind::Function,
             visibility: node.visibility().map(|n| n.syntax().text().to_string()),
+            abi: abi(node),
             name: node.name().map(|n| n.text().to_string()),
             ret_type: node
                 .ret_type()
@@ -180,6 +200,10 @@ impl Display for FunctionSignature {
             write!(f, "{} ", t)?;
         }
 
+        if let Some(abi) = &self.abi {
+            write!(f, "extern {:?} ", abi)?;
+        }
+
         if let Some(name) = &self.name {
             match self.kind {
                 CallableKind::Function => write!(f, "fn {}", name)?,
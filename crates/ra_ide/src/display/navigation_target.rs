@@ -1,9 +1,10 @@
 //! FIXME: write short doc here
 
 use either::Either;
-use hir::{AssocItem, FieldSource, HasSource, InFile, ModuleSource};
-use ra_db::{FileId, SourceDatabase};
+use hir::{db::AstDatabase, AssocItem, FieldSource, HasSource, InFile, ModuleSource};
+use ra_db::FileId;
 use ra_syntax::{
+    algo::find_covering_element,
     ast::{self, DocCommentsOwner, NameOwner},
     match_ast, AstNode, SmolStr,
     SyntaxKind::{self, BIND_PAT, TYPE_PARAM},
@@ -163,6 +164,25 @@ impl NavigationTarget {
         )
     }
 
+    /// Creates a `NavigationTarget` for a loop label. The full range covers
+    /// the labeled loop, the focus range is the `'label` itself.
+    pub(crate) fn from_label(file_id: FileId, label: &ast::Label) -> NavigationTarget {
+        let lifetime = label.lifetime_token();
+        let name = lifetime.as_ref().map(|it| it.text().clone()).unwrap_or_default();
+        let focus_range = lifetime.map(|it| it.text_range());
+        let full_range = label.syntax().parent().unwrap_or_else(|| label.syntax().clone());
+
+        NavigationTarget::from_syntax(
+            file_id,
+            name,
+            focus_range,
+            full_range.text_range(),
+            label.syntax().kind(),
+            None,
+            None,
+        )
+    }
+
     fn from_syntax(
         file_id: FileId,
         name: SmolStr,
@@ -187,12 +207,13 @@ impl NavigationTarget {
 
 impl ToNav for FileSymbol {
     fn to_nav(&self, db: &RootDatabase) -> NavigationTarget {
+        let (file_id, full_range, focus_range) = original_symbol_ranges(db, self);
         NavigationTarget {
-            file_id: self.file_id,
+            file_id,
             name: self.name.clone(),
             kind: self.ptr.kind(),
-            full_range: self.ptr.range(),
-            focus_range: self.name_range,
+            full_range,
+            focus_range,
             container_name: self.container_name.clone(),
             description: description_from_symbol(db, self),
             docs: docs_from_symbol(db, self),
@@ -200,6 +221,31 @@ impl ToNav for FileSymbol {
     }
 }
 
+/// Symbols of items produced by macros point into the expansion, so we map
+/// them back to the macro call.
+fn original_symbol_ranges(
+    db: &RootDatabase,
+    symbol: &FileSymbol,
+) -> (FileId, TextRange, Option<TextRange>) {
+    let root = symbol.file_id.call_node(db).and_then(|_| db.parse_or_expand(symbol.file_id));
+    let root = match root {
+        Some(it) => it,
+        None => return (symbol.file_id.original_file(db), symbol.ptr.range(), symbol.name_range),
+    };
+    let node = symbol.ptr.to_node(&root);
+    let full_range = original_range(db, InFile::new(symbol.file_id, &node));
+    let focus_range = symbol.name_range.and_then(|range| {
+        let name = find_covering_element(&root, range).ancestors().next()?;
+        let name_range = original_range(db, InFile::new(symbol.file_id, &name));
+        if name_range.file_id == full_range.file_id {
+            Some(name_range.range)
+        } else {
+            None
+        }
+    });
+    (full_range.file_id, full_range.range, focus_range)
+}
+
 pub(crate) trait ToNavFromAst {}
 impl ToNavFromAst for hir::Function {}
 impl ToNavFromAst for hir::Const {}
@@ -373,8 +419,8 @@ impl ToNav for hir::TypeParam {
 }
 
 pub(crate) fn docs_from_symbol(db: &RootDatabase, symbol: &FileSymbol) -> Option<String> {
-    let parse = db.parse(symbol.file_id);
-    let node = symbol.ptr.to_node(parse.tree().syntax());
+    let root = db.parse_or_expand(symbol.file_id)?;
+    let node = symbol.ptr.to_node(&root);
 
     match_ast! {
         match node {
@@ -389,6 +435,7 @@ pub(crate) fn docs_from_symbol(db: &RootDatabase, symbol: &FileSymbol) -> Option
             ast::RecordFieldDef(it) => { it.doc_comment_text() },
             ast::EnumVariant(it) => { it.doc_comment_text() },
             ast::MacroCall(it) => { it.doc_comment_text() },
+            ast::MacroDef(it) => { it.doc_comment_text() },
             _ => None,
         }
     }
@@ -398,8 +445,8 @@ pub(crate) fn docs_from_symbol(db: &RootDatabase, symbol: &FileSymbol) -> Option
 ///
 /// e.g. `struct Name`, `enum Name`, `fn Name`
 pub(crate) fn description_from_symbol(db: &RootDatabase, symbol: &FileSymbol) -> Option<String> {
-    let parse = db.parse(symbol.file_id);
-    let node = symbol.ptr.to_node(parse.tree().syntax());
+    let root = db.parse_or_expand(symbol.file_id)?;
+    let node = symbol.ptr.to_node(&root);
 
     match_ast! {
         match node {
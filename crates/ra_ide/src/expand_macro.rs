@@ -14,6 +14,27 @@ use ra_syntax::{
 pub struct ExpandedMacro {
     pub name: String,
     pub expansion: String,
+    /// The individual expansion steps, starting with the macro call under the
+    /// cursor.
+    pub steps: ExpansionStep,
+}
+
+/// A single macro expansion, along with the expansions of the macro calls it
+/// produced.
+#[derive(Debug)]
+pub struct ExpansionStep {
+    pub name: String,
+    /// Index of the `macro_rules!` rule used for the expansion, if the macro
+    /// is defined by `macro_rules!`.
+    pub rule: Option<usize>,
+    /// Whether the call didn't match any rule, so `rule` is only the closest
+    /// one.
+    pub failed: bool,
+    /// Metavariables of the rule and what they were bound to.
+    pub bindings: Vec<(String, String)>,
+    /// The expansion, with nested macro calls left unexpanded.
+    pub expansion: String,
+    pub nested: Vec<ExpansionStep>,
 }
 
 pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<ExpandedMacro> {
@@ -23,31 +44,56 @@ pub(crate) fn expand_macro(db: &RootDatabase, position: FilePosition) -> Option<
     let mac = name_ref.syntax().ancestors().find_map(ast::MacroCall::cast)?;
 
     let source = hir::InFile::new(position.file_id.into(), mac.syntax());
-    let expanded = expand_macro_recur(db, source, source.with_value(&mac))?;
+    let (expanded, steps) = expand_macro_recur(db, source, source.with_value(&mac))?;
 
     // FIXME:
     // macro expansion may lose all white space information
     // But we hope someday we can use ra_fmt for that
     let expansion = insert_whitespaces(expanded);
-    Some(ExpandedMacro { name: name_ref.text().to_string(), expansion })
+    Some(ExpandedMacro { name: name_ref.text().to_string(), expansion, steps })
 }
 
 fn expand_macro_recur(
     db: &RootDatabase,
     source: hir::InFile<&SyntaxNode>,
     macro_call: hir::InFile<&ast::MacroCall>,
-) -> Option<SyntaxNode> {
+) -> Option<(SyntaxNode, ExpansionStep)> {
     let analyzer = hir::SourceAnalyzer::new(db, source, None);
     let expansion = analyzer.expand(db, macro_call)?;
     let macro_file_id = expansion.file_id();
     let mut expanded: SyntaxNode = db.parse_or_expand(macro_file_id)?;
 
+    let name = macro_call
+        .value
+        .path()
+        .and_then(|it| it.segment())
+        .and_then(|it| it.name_ref())
+        .map(|it| it.text().to_string())
+        .unwrap_or_default();
+    let (rule, failed, bindings) = match expansion.match_info(db) {
+        Some(info) => (
+            Some(info.rule),
+            info.failed,
+            info.bindings.into_iter().map(|(name, value)| (name.to_string(), value)).collect(),
+        ),
+        None => (None, false, Vec::new()),
+    };
+    let mut step = ExpansionStep {
+        name,
+        rule,
+        failed,
+        bindings,
+        expansion: insert_whitespaces(expanded.clone()),
+        nested: Vec::new(),
+    };
+
     let children = expanded.descendants().filter_map(ast::MacroCall::cast);
     let mut replaces = FxHashMap::default();
 
     for child in children.into_iter() {
         let node = hir::InFile::new(macro_file_id, &child);
-        if let Some(new_node) = expand_macro_recur(db, source, node) {
+        if let Some((new_node, nested)) = expand_macro_recur(db, source, node) {
+            step.nested.push(nested);
             // Replace the whole node if it is root
             // `replace_descendants` will not replace the parent node
             // but `SyntaxNode::descendants include itself
@@ -59,7 +105,7 @@ fn expand_macro_recur(
         }
     }
 
-    Some(replace_descendants(&expanded, &replaces))
+    Some((replace_descendants(&expanded, &replaces), step))
 }
 
 // FIXME: It would also be cool to share logic here and in the mbe tests,
@@ -122,7 +168,7 @@ fn insert_whitespaces(syn: SyntaxNode) -> String {
 mod tests {
     use super::*;
     use crate::mock_analysis::analysis_and_position;
-    use insta::assert_snapshot;
+    use insta::{assert_debug_snapshot, assert_snapshot};
 
     fn check_expand_macro(fixture: &str) -> ExpandedMacro {
         let (analysis, pos) = analysis_and_position(fixture);
@@ -289,4 +335,55 @@ fn some_thing() -> u32 {
         assert_eq!(res.name, "foo");
         assert_snapshot!(res.expansion, @r###"0"###);
     }
+
+    #[test]
+    fn macro_expand_steps() {
+        let res = check_expand_macro(
+            r#"
+        //- /lib.rs
+        macro_rules! bar {
+            ($e:expr) => { fn b() { $e; } }
+        }
+        macro_rules! foo {
+            () => { bar!(0); };
+            ($($i:ident),*) => { bar!(1); };
+        }
+        f<|>oo!(x, y);
+        "#,
+        );
+
+        assert_debug_snapshot!(res.steps, @r###"
+        ExpansionStep {
+            name: "foo",
+            rule: Some(
+                1,
+            ),
+            failed: false,
+            bindings: [
+                (
+                    "i",
+                    "[x, y]",
+                ),
+            ],
+            expansion: "bar!(1);\n",
+            nested: [
+                ExpansionStep {
+                    name: "bar",
+                    rule: Some(
+                        0,
+                    ),
+                    failed: false,
+                    bindings: [
+                        (
+                            "e",
+                            "1",
+                        ),
+                    ],
+                    expansion: "fn b(){\n  1;\n  \n}",
+                    nested: [],
+                },
+            ],
+        }
+        "###);
+    }
 }
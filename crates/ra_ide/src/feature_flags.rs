@@ -55,7 +55,13 @@ impl Default for FeatureFlags {
             ("lsp.diagnostics", true),
             ("completion.insertion.add-call-parenthesis", true),
             ("completion.enable-postfix", true),
+            ("completion.enable-auto-import", false),
             ("notifications.workspace-loaded", true),
+            ("diagnostics.type-mismatch", false),
+            ("diagnostics.unresolved-macro-call", false),
+            ("inlay-hints.type-hints", true),
+            ("inlay-hints.parameter-hints", true),
+            ("inlay-hints.chaining-hints", true),
         ])
     }
 }
@@ -12,7 +12,7 @@ use crate::{
     db::RootDatabase,
     display::{ShortLabel, ToNav},
     expand::descend_into_macros,
-    references::{classify_name_ref, NameKind::*},
+    references::{classify_name_ref, label_definition, NameKind::*},
     FilePosition, NavigationTarget, RangeInfo,
 };
 
@@ -22,6 +22,10 @@ pub(crate) fn goto_definition(
 ) -> Option<RangeInfo<Vec<NavigationTarget>>> {
     let file = db.parse_or_expand(position.file_id.into())?;
     let original_token = pick_best(file.token_at_offset(position.offset))?;
+    if let Some(label) = label_definition(&original_token) {
+        let nav = NavigationTarget::from_label(position.file_id, &label);
+        return Some(RangeInfo::new(original_token.text_range(), vec![nav]));
+    }
     let token = descend_into_macros(db, position.file_id, original_token.clone());
 
     let nav_targets = match_ast! {
@@ -214,6 +218,14 @@ fn named_target(db: &RootDatabase, node: InFile<&SyntaxNode>) -> Option<Navigati
                     None,
                 ))
             },
+            ast::MacroDef(it) => {
+                Some(NavigationTarget::from_named(
+                    db,
+                    node.with_value(&it),
+                    it.doc_comment_text(),
+                    None,
+                ))
+            },
             _ => None,
         }
     }
@@ -294,13 +306,13 @@ mod tests {
             enum E { X(Foo<|>) }
 
             //- /a.rs
-            struct Foo;
+            pub struct Foo;
 
             //- /b.rs
-            struct Foo;
+            pub struct Foo;
             ",
-            "Foo STRUCT_DEF FileId(2) [0; 11) [7; 10)",
-            "struct Foo;|Foo",
+            "Foo STRUCT_DEF FileId(2) [0; 15) [11; 14)",
+            "pub struct Foo;|Foo",
         );
     }
 
@@ -348,6 +360,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn goto_def_for_macro2() {
+        covers!(goto_def_for_macros);
+        check_goto(
+            "
+            //- /lib.rs
+            macro foo() { () }
+
+            fn bar() {
+                <|>foo!();
+            }
+            ",
+            "foo MACRO_DEF FileId(1) [0; 18) [6; 9)",
+            "macro foo() { () }|foo",
+        );
+    }
+
     #[test]
     fn goto_def_for_macros_from_other_crates() {
         covers!(goto_def_for_macros);
@@ -424,6 +453,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn goto_def_falls_back_to_macro_defined_methods() {
+        check_goto(
+            "
+            //- /lib.rs
+            macro_rules! define_id_type {
+                ($name:ident) => {
+                    pub struct $name(u32);
+                    impl $name {
+                        pub fn from_raw(raw: u32) -> $name { $name(raw) }
+                    }
+                };
+            }
+
+            define_id_type!(UserId);
+
+            fn main() {
+                unknown().from_<|>raw(92);
+            }
+            ",
+            "from_raw FN_DEF FileId(1) [187; 211) [187; 211)",
+            "define_id_type!(UserId);|define_id_type!(UserId);",
+        );
+    }
+
     #[test]
     fn goto_definition_works_for_macro_inside_pattern() {
         check_goto(
@@ -840,6 +894,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn goto_into_included_file() {
+        check_goto(
+            "
+            //- /main.rs
+            #[rustc_builtin_macro]
+            macro_rules! include {() => {}}
+
+            include!(\"foo.rs\");
+
+            fn main() {
+                bar<|>();
+            }
+
+            //- /foo.rs
+            fn bar() {}
+            ",
+            "bar FN_DEF FileId(2) [0; 11) [3; 6)",
+            "fn bar() {}|bar",
+        );
+    }
+
     #[test]
     fn goto_for_type_param() {
         check_goto(
@@ -928,4 +1004,40 @@ mod tests {
             "x: i32|x",
         )
     }
+
+    #[test]
+    fn goto_def_for_loop_label() {
+        check_goto(
+            "
+            //- /lib.rs
+            fn foo() {
+                'outer: loop {
+                    loop {
+                        break 'outer<|>;
+                    }
+                }
+            }
+            ",
+            "'outer LABEL FileId(1) [15; 86) [15; 21)",
+            "'outer: loop {...}|'outer",
+        );
+    }
+
+    #[test]
+    fn goto_def_for_shadowed_loop_label() {
+        check_goto(
+            "
+            //- /lib.rs
+            fn foo() {
+                'a: loop {
+                    'a: while true {
+                        continue 'a<|>;
+                    }
+                }
+            }
+            ",
+            "'a LABEL FileId(1) [34; 85) [34; 36)",
+            "'a: while true {...}|'a",
+        );
+    }
 }
@@ -318,6 +318,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hover_shows_extern_fn_signature() {
+        check_hover_result(
+            r#"
+            //- /main.rs
+            extern "C" {
+                pub fn printf(format: *const u8, ...) -> i32;
+            }
+
+            fn main() {
+                unsafe { prin<|>tf(b"%d\0".as_ptr(), 92) };
+            }
+        "#,
+            &["pub extern \"C\" fn printf(format: *const u8, ...) -> i32"],
+        );
+    }
+
     #[test]
     fn hover_shows_fn_signature_on_fn_name() {
         check_hover_result(
@@ -1,11 +1,11 @@
 //! FIXME: write short doc here
 
-use hir::{HirDisplay, SourceAnalyzer};
+use hir::{HasSource, HirDisplay, SourceAnalyzer};
 use once_cell::unsync::Lazy;
 use ra_prof::profile;
 use ra_syntax::{
-    ast::{self, AstNode, TypeAscriptionOwner},
-    match_ast, SmolStr, SourceFile, SyntaxKind, SyntaxNode, TextRange,
+    ast::{self, ArgListOwner, AstNode, NameOwner, TypeAscriptionOwner},
+    match_ast, Direction, SmolStr, SourceFile, SyntaxKind, SyntaxNode, TextRange, T,
 };
 
 use crate::{db::RootDatabase, FileId};
@@ -13,6 +13,8 @@ use crate::{db::RootDatabase, FileId};
 #[derive(Debug, PartialEq, Eq)]
 pub enum InlayKind {
     TypeHint,
+    ParameterHint,
+    ChainingHint,
 }
 
 #[derive(Debug)]
@@ -44,6 +46,27 @@ fn get_inlay_hints(
     let _p = profile("get_inlay_hints");
     let analyzer =
         Lazy::new(|| SourceAnalyzer::new(db, hir::InFile::new(file_id.into(), node), None));
+    let mut res = Vec::new();
+    if db.feature_flags.get("inlay-hints.chaining-hints") {
+        if let Some(expr) = ast::Expr::cast(node.clone()) {
+            res.extend(get_chaining_hints(db, analyzer, expr, max_inlay_hint_length));
+        }
+    }
+    if db.feature_flags.get("inlay-hints.parameter-hints") {
+        res.extend(get_param_name_hints(db, analyzer, node, max_inlay_hint_length));
+    }
+    if db.feature_flags.get("inlay-hints.type-hints") {
+        res.extend(get_type_hints(db, analyzer, node, max_inlay_hint_length).unwrap_or_default());
+    }
+    Some(res)
+}
+
+fn get_type_hints(
+    db: &RootDatabase,
+    analyzer: &SourceAnalyzer,
+    node: &SyntaxNode,
+    max_inlay_hint_length: Option<usize>,
+) -> Option<Vec<InlayHint>> {
     match_ast! {
         match node {
             ast::LetStmt(it) => {
@@ -51,7 +74,7 @@ fn get_inlay_hints(
                     return None;
                 }
                 let pat = it.pat()?;
-                Some(get_pat_type_hints(db, &analyzer, pat, false, max_inlay_hint_length))
+                Some(get_pat_type_hints(db, analyzer, pat, false, max_inlay_hint_length))
             },
             ast::LambdaExpr(it) => {
                 it.param_list().map(|param_list| {
@@ -59,22 +82,22 @@ fn get_inlay_hints(
                         .params()
                         .filter(|closure_param| closure_param.ascribed_type().is_none())
                         .filter_map(|closure_param| closure_param.pat())
-                        .map(|root_pat| get_pat_type_hints(db, &analyzer, root_pat, false, max_inlay_hint_length))
+                        .map(|root_pat| get_pat_type_hints(db, analyzer, root_pat, false, max_inlay_hint_length))
                         .flatten()
                         .collect()
                 })
             },
             ast::ForExpr(it) => {
                 let pat = it.pat()?;
-                Some(get_pat_type_hints(db, &analyzer, pat, false, max_inlay_hint_length))
+                Some(get_pat_type_hints(db, analyzer, pat, false, max_inlay_hint_length))
             },
             ast::IfExpr(it) => {
                 let pat = it.condition()?.pat()?;
-                Some(get_pat_type_hints(db, &analyzer, pat, true, max_inlay_hint_length))
+                Some(get_pat_type_hints(db, analyzer, pat, true, max_inlay_hint_length))
             },
             ast::WhileExpr(it) => {
                 let pat = it.condition()?.pat()?;
-                Some(get_pat_type_hints(db, &analyzer, pat, true, max_inlay_hint_length))
+                Some(get_pat_type_hints(db, analyzer, pat, true, max_inlay_hint_length))
             },
             ast::MatchArmList(it) => {
                 Some(
@@ -82,7 +105,7 @@ fn get_inlay_hints(
                         .arms()
                         .map(|match_arm| match_arm.pats())
                         .flatten()
-                        .map(|root_pat| get_pat_type_hints(db, &analyzer, root_pat, true, max_inlay_hint_length))
+                        .map(|root_pat| get_pat_type_hints(db, analyzer, root_pat, true, max_inlay_hint_length))
                         .flatten()
                         .collect(),
                 )
@@ -92,6 +115,140 @@ fn get_inlay_hints(
     }
 }
 
+/// Shows the type of each intermediate expression of a method chain that is
+/// split over several lines, at the end of the line.
+fn get_chaining_hints(
+    db: &RootDatabase,
+    analyzer: &SourceAnalyzer,
+    expr: ast::Expr,
+    max_inlay_hint_length: Option<usize>,
+) -> Option<InlayHint> {
+    if let ast::Expr::RecordLit(_) = expr {
+        return None;
+    }
+    // The expression has to be the receiver of a method call or field access
+    // which starts on the next line.
+    let mut tokens = expr.syntax().siblings_with_tokens(Direction::Next).skip(1);
+    let mut has_newline = false;
+    loop {
+        let token = tokens.next()?;
+        match token.kind() {
+            SyntaxKind::WHITESPACE => has_newline |= token.as_token()?.text().contains('\n'),
+            SyntaxKind::COMMENT => (),
+            T![.] => break,
+            _ => return None,
+        }
+    }
+    if !has_newline {
+        return None;
+    }
+    let ty = analyzer.type_of(db, &expr)?;
+    if ty.is_unknown() {
+        return None;
+    }
+    Some(InlayHint {
+        range: expr.syntax().text_range(),
+        kind: InlayKind::ChainingHint,
+        label: ty.display_truncated(db, max_inlay_hint_length).to_string().into(),
+    })
+}
+
+/// Shows the names of the parameters at the call sites of functions and
+/// methods, unless they are obvious from the arguments.
+fn get_param_name_hints(
+    db: &RootDatabase,
+    analyzer: &SourceAnalyzer,
+    node: &SyntaxNode,
+    max_inlay_hint_length: Option<usize>,
+) -> Vec<InlayHint> {
+    let (function, args, skip_self) = match_ast! {
+        match node {
+            ast::CallExpr(it) => {
+                let path = match it.expr() {
+                    Some(ast::Expr::PathExpr(it)) => it.path(),
+                    _ => None,
+                };
+                let function = match path.and_then(|it| analyzer.resolve_path(db, &it)) {
+                    Some(hir::PathResolution::Def(hir::ModuleDef::Function(it)))
+                    | Some(hir::PathResolution::AssocItem(hir::AssocItem::Function(it))) => it,
+                    _ => return Vec::new(),
+                };
+                // In `Foo::method(foo, arg)`, the first argument is `self`.
+                (function, it.arg_list(), function.has_self_param(db))
+            },
+            ast::MethodCallExpr(it) => {
+                match analyzer.resolve_method_call(&it) {
+                    Some(function) => (function, it.arg_list(), false),
+                    None => return Vec::new(),
+                }
+            },
+            _ => return Vec::new(),
+        }
+    };
+    let args = match args {
+        Some(it) => it.args(),
+        None => return Vec::new(),
+    };
+    let param_names = function
+        .source(db)
+        .value
+        .param_list()
+        .into_iter()
+        .flat_map(|it| it.params())
+        .map(|param| match param.pat() {
+            Some(ast::Pat::BindPat(it)) => it.name().map(|it| it.text().clone()),
+            _ => None,
+        });
+
+    args.skip(if skip_self { 1 } else { 0 })
+        .zip(param_names)
+        .filter_map(|(arg, param_name)| {
+            let param_name = param_name?;
+            let param_name = param_name.trim_start_matches('_');
+            if is_obvious_param(param_name, &arg) {
+                return None;
+            }
+            Some(InlayHint {
+                range: arg.syntax().text_range(),
+                kind: InlayKind::ParameterHint,
+                label: truncate_label(param_name, max_inlay_hint_length),
+            })
+        })
+        .collect()
+}
+
+/// Cuts `label` down to `max_len` characters, marking the cut with `…` the
+/// same way truncated types are displayed.
+fn truncate_label(label: &str, max_len: Option<usize>) -> SmolStr {
+    match max_len {
+        Some(max_len) if label.chars().count() > max_len => {
+            let mut res: String = label.chars().take(max_len).collect();
+            res.push('…');
+            res.into()
+        }
+        _ => label.into(),
+    }
+}
+
+fn is_obvious_param(param_name: &str, arg: &ast::Expr) -> bool {
+    if param_name.len() <= 1 {
+        return true;
+    }
+    let mut arg = arg.clone();
+    while let ast::Expr::RefExpr(it) = &arg {
+        arg = match it.expr() {
+            Some(it) => it,
+            None => return false,
+        };
+    }
+    let arg_name = match &arg {
+        ast::Expr::PathExpr(it) => it.path().and_then(|it| it.segment()?.name_ref()),
+        ast::Expr::FieldExpr(it) => it.name_ref(),
+        _ => None,
+    };
+    arg_name.map_or(false, |it| it.text().trim_start_matches('_') == param_name)
+}
+
 fn get_pat_type_hints(
     db: &RootDatabase,
     analyzer: &SourceAnalyzer,
@@ -570,4 +727,132 @@ fn main() {
         "###
         );
     }
+
+    #[test]
+    fn function_call_parameter_hint() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn max(a: i32, b: i32) -> i32 { a }
+
+struct Test;
+
+impl Test {
+    fn method(&self, param: i32) -> i32 { param }
+}
+
+fn foo(first: i32, second: &i32, _third: i32) {}
+
+fn main() {
+    let second = 2;
+    let t = Test;
+    foo(1, &second, 3);
+    max(1, 2);
+    t.method(123);
+    Test::method(&t, 3);
+}"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, None).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [187; 193),
+                kind: TypeHint,
+                label: "i32",
+            },
+            InlayHint {
+                range: [207; 208),
+                kind: TypeHint,
+                label: "Test",
+            },
+            InlayHint {
+                range: [225; 226),
+                kind: ParameterHint,
+                label: "first",
+            },
+            InlayHint {
+                range: [237; 238),
+                kind: ParameterHint,
+                label: "third",
+            },
+            InlayHint {
+                range: [269; 272),
+                kind: ParameterHint,
+                label: "param",
+            },
+            InlayHint {
+                range: [296; 297),
+                kind: ParameterHint,
+                label: "param",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn parameter_hint_truncation() {
+        let (analysis, file_id) = single_file(
+            r#"
+fn foo(short: i32, very_long_parameter_name: i32) {}
+
+fn main() {
+    foo(1, 2);
+}"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, Some(8)).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [75; 76),
+                kind: ParameterHint,
+                label: "short",
+            },
+            InlayHint {
+                range: [78; 79),
+                kind: ParameterHint,
+                label: "very_lon…",
+            },
+        ]
+        "###
+        );
+    }
+
+    #[test]
+    fn chaining_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct A(B);
+impl A { fn into_b(self) -> B { self.0 } }
+struct B(C);
+impl B { fn into_c(self) -> C { self.0 } }
+struct C;
+
+fn main() {
+    let c = A(B(C))
+        .into_b() // This is a comment
+        .into_c();
+}"#,
+        );
+
+        assert_debug_snapshot!(analysis.inlay_hints(file_id, None).unwrap(), @r###"
+        [
+            InlayHint {
+                range: [144; 145),
+                kind: TypeHint,
+                label: "C",
+            },
+            InlayHint {
+                range: [148; 173),
+                kind: ChainingHint,
+                label: "B",
+            },
+            InlayHint {
+                range: [148; 155),
+                kind: ChainingHint,
+                label: "A",
+            },
+        ]
+        "###
+        );
+    }
 }
@@ -25,6 +25,7 @@ mod goto_type_definition;
 mod extend_selection;
 mod hover;
 mod call_info;
+mod call_hierarchy;
 mod syntax_highlighting;
 mod parent_module;
 mod references;
@@ -62,11 +63,12 @@ use crate::{db::LineIndexDatabase, display::ToNav, symbol_index::FileSymbol};
 
 pub use crate::{
     assists::{Assist, AssistId},
+    call_hierarchy::CallItem,
     change::{AnalysisChange, LibraryData},
-    completion::{CompletionItem, CompletionItemKind, InsertTextFormat},
+    completion::{CompletionItem, CompletionItemKind, CompletionTemplate, InsertTextFormat},
     diagnostics::Severity,
     display::{file_structure, FunctionSignature, NavigationTarget, StructureNode},
-    expand_macro::ExpandedMacro,
+    expand_macro::{ExpandedMacro, ExpansionStep},
     feature_flags::FeatureFlags,
     folding_ranges::{Fold, FoldKind},
     hover::HoverResult,
@@ -76,7 +78,7 @@ pub use crate::{
     references::{ReferenceSearchResult, SearchScope},
     runnables::{Runnable, RunnableKind},
     source_change::{FileSystemEdit, SourceChange, SourceFileEdit},
-    syntax_highlighting::HighlightedRange,
+    syntax_highlighting::{tags, HighlightModifiers, HighlightedRange},
 };
 
 pub use hir::Documentation;
@@ -101,6 +103,7 @@ pub struct Query {
     only_types: bool,
     libs: bool,
     exact: bool,
+    prefix: bool,
     limit: usize,
 }
 
@@ -113,6 +116,7 @@ impl Query {
             only_types: false,
             libs: false,
             exact: false,
+            prefix: false,
             limit: usize::max_value(),
         }
     }
@@ -129,6 +133,12 @@ impl Query {
         self.exact = true;
     }
 
+    /// Only match symbols whose names start with the query, instead of
+    /// fuzzy-matching them.
+    pub fn prefix(&mut self) {
+        self.prefix = true;
+    }
+
     pub fn limit(&mut self, limit: usize) {
         self.limit = limit
     }
@@ -240,7 +250,13 @@ impl Analysis {
         // Default to enable test for single file.
         let mut cfg_options = CfgOptions::default();
         cfg_options.insert_atom("test".into());
-        crate_graph.add_crate_root(file_id, Edition::Edition2018, cfg_options, Env::default());
+        crate_graph.add_crate_root(
+            file_id,
+            Edition::Edition2018,
+            cfg_options,
+            Env::default(),
+            Default::default(),
+        );
         change.add_file(source_root, file_id, "main.rs".into(), Arc::new(text));
         change.set_crate_graph(crate_graph);
         host.apply_change(change);
@@ -402,6 +418,24 @@ impl Analysis {
         self.with_db(|db| references::find_all_refs(db, position, search_scope).map(|it| it.info))
     }
 
+    /// Computes call hierarchy candidates for the given file position.
+    pub fn call_hierarchy(
+        &self,
+        position: FilePosition,
+    ) -> Cancelable<Option<RangeInfo<Vec<NavigationTarget>>>> {
+        self.with_db(|db| call_hierarchy::call_hierarchy(db, position))
+    }
+
+    /// Computes incoming calls for the given file position.
+    pub fn incoming_calls(&self, position: FilePosition) -> Cancelable<Option<Vec<CallItem>>> {
+        self.with_db(|db| call_hierarchy::incoming_calls(db, position))
+    }
+
+    /// Computes outgoing calls for the given file position.
+    pub fn outgoing_calls(&self, position: FilePosition) -> Cancelable<Option<Vec<CallItem>>> {
+        self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
+    }
+
     /// Returns a short text describing element at position.
     pub fn hover(&self, position: FilePosition) -> Cancelable<Option<RangeInfo<HoverResult>>> {
         self.with_db(|db| hover::hover(db, position))
@@ -448,8 +482,12 @@ impl Analysis {
     }
 
     /// Computes completions at the given position.
-    pub fn completions(&self, position: FilePosition) -> Cancelable<Option<Vec<CompletionItem>>> {
-        self.with_db(|db| completion::completions(db, position).map(Into::into))
+    pub fn completions(
+        &self,
+        position: FilePosition,
+        templates: &[CompletionTemplate],
+    ) -> Cancelable<Option<Vec<CompletionItem>>> {
+        self.with_db(|db| completion::completions(db, position, templates).map(Into::into))
     }
 
     /// Computes assists (aka code actions aka intentions) for the given
@@ -7,8 +7,8 @@ use ra_db::{Env, RelativePathBuf};
 use test_utils::{extract_offset, extract_range, parse_fixture, CURSOR_MARKER};
 
 use crate::{
-    Analysis, AnalysisChange, AnalysisHost, CrateGraph, Edition::Edition2018, FileId, FilePosition,
-    FileRange, SourceRootId,
+    Analysis, AnalysisChange, AnalysisHost, CrateGraph, Edition::Edition2018, FeatureFlags, FileId,
+    FilePosition, FileRange, SourceRootId,
 };
 
 /// Mock analysis is used in test to bootstrap an AnalysisHost/Analysis
@@ -84,7 +84,10 @@ impl MockAnalysis {
         FileId(idx as u32 + 1)
     }
     pub fn analysis_host(self) -> AnalysisHost {
-        let mut host = AnalysisHost::default();
+        self.analysis_host_with_feature_flags(FeatureFlags::default())
+    }
+    pub fn analysis_host_with_feature_flags(self, feature_flags: FeatureFlags) -> AnalysisHost {
+        let mut host = AnalysisHost::new(None, feature_flags);
         let source_root = SourceRootId(0);
         let mut change = AnalysisChange::new();
         change.add_root(source_root, true);
@@ -101,10 +104,16 @@ impl MockAnalysis {
                     Edition2018,
                     cfg_options,
                     Env::default(),
+                    Default::default(),
                 ));
             } else if path.ends_with("/lib.rs") {
-                let other_crate =
-                    crate_graph.add_crate_root(file_id, Edition2018, cfg_options, Env::default());
+                let other_crate = crate_graph.add_crate_root(
+                    file_id,
+                    Edition2018,
+                    cfg_options,
+                    Env::default(),
+                    Default::default(),
+                );
                 let crate_name = path.parent().unwrap().file_name().unwrap();
                 if let Some(root_crate) = root_crate {
                     crate_graph.add_dep(root_crate, crate_name.into(), other_crate).unwrap();
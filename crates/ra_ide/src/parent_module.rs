@@ -95,6 +95,7 @@ mod tests {
             Edition2018,
             CfgOptions::default(),
             Env::default(),
+            Default::default(),
         );
         let mut change = AnalysisChange::new();
         change.set_crate_graph(crate_graph);
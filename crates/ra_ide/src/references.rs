@@ -10,6 +10,7 @@
 //! resolved to the search element definition, we get a reference.
 
 mod classify;
+mod label;
 mod name_definition;
 mod rename;
 mod search_scope;
@@ -26,6 +27,7 @@ use crate::{
 
 pub(crate) use self::{
     classify::{classify_name, classify_name_ref},
+    label::{find_label_token, label_definition},
     name_definition::{NameDefinition, NameKind},
     rename::rename,
 };
@@ -76,6 +78,15 @@ pub(crate) fn find_all_refs(
 ) -> Option<RangeInfo<ReferenceSearchResult>> {
     let parse = db.parse(position.file_id);
     let syntax = parse.tree().syntax().clone();
+    if let Some(token) = label::find_label_token(&syntax, position.offset) {
+        let label = label::label_definition(&token)?;
+        let declaration = NavigationTarget::from_label(position.file_id, &label);
+        let references = label::label_references(position.file_id, &label);
+        return Some(RangeInfo::new(
+            token.text_range(),
+            ReferenceSearchResult { declaration, references },
+        ));
+    }
     let RangeInfo { range, info: (name, def) } = find_name(db, &syntax, position)?;
 
     let declaration = match def.kind {
@@ -382,6 +393,39 @@ mod tests {
         assert_eq!(refs.len(), 3);
     }
 
+    #[test]
+    fn test_find_all_refs_for_loop_label() {
+        let code = r#"
+    fn main() {
+        'outer: loop {
+            'inner: loop {
+                if true {
+                    break 'outer<|>;
+                }
+                continue 'inner;
+            }
+            continue 'outer;
+        }
+    }"#;
+
+        let refs = get_all_refs(code);
+        assert_eq!(refs.len(), 3);
+    }
+
+    #[test]
+    fn test_find_all_refs_for_label_ignores_closures() {
+        let code = r#"
+    fn main() {
+        'a<|>: loop {
+            let f = || loop { break 'a; };
+            break 'a;
+        }
+    }"#;
+
+        let refs = get_all_refs(code);
+        assert_eq!(refs.len(), 2);
+    }
+
     fn get_all_refs(text: &str) -> ReferenceSearchResult {
         let (analysis, position) = single_file_with_position(text);
         analysis.find_all_refs(position, None).unwrap().unwrap()
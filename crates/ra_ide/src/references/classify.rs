@@ -98,7 +98,20 @@ pub(crate) fn classify_name(db: &RootDatabase, name: InFile<&ast::Name>) -> Opti
                 }
             },
             ast::MacroCall(it) => {
-                let src = name.with_value(it);
+                let src = name.with_value(ast::Macro::from(it));
+                let def = hir::MacroDef::from_source(db, src.clone())?;
+
+                let module_src = ModuleSource::from_child_node(db, src.as_ref().map(|it| it.syntax()));
+                let module = Module::from_definition(db, src.with_value(module_src))?;
+
+                Some(NameDefinition {
+                    visibility: None,
+                    container: module,
+                    kind: NameKind::Macro(def),
+                })
+            },
+            ast::MacroDef(it) => {
+                let src = name.with_value(ast::Macro::from(it));
                 let def = hir::MacroDef::from_source(db, src.clone())?;
 
                 let module_src = ModuleSource::from_child_node(db, src.as_ref().map(|it| it.syntax()));
@@ -162,7 +175,10 @@ pub(crate) fn classify_name_ref(
 
     if let Some(macro_call) = parent.ancestors().find_map(ast::MacroCall::cast) {
         tested_by!(goto_def_for_macros);
-        if let Some(macro_def) = analyzer.resolve_macro_call(db, name_ref.with_value(&macro_call)) {
+        if let Some(macro_def) = analyzer
+            .resolve_macro_call(db, name_ref.with_value(&macro_call))
+            .filter(|it| !it.is_proc_macro())
+        {
             let kind = NameKind::Macro(macro_def);
             return Some(NameDefinition { kind, container, visibility });
         }
@@ -183,6 +199,9 @@ pub(crate) fn classify_name_ref(
             Some(NameDefinition { kind, container, visibility })
         }
         PathResolution::Macro(def) => {
+            if def.is_proc_macro() {
+                return None;
+            }
             let kind = NameKind::Macro(def);
             Some(NameDefinition { kind, container, visibility })
         }
@@ -0,0 +1,78 @@
+//! Loop labels are not items and are not tracked by name resolution, so they
+//! are resolved syntactically: `break 'a` and `continue 'a` refer to the
+//! innermost enclosing loop labeled `'a` within the same function, closure or
+//! async block. This mirrors how labels are handled when lowering bodies.
+
+use ra_syntax::{
+    ast::{self, LoopBodyOwner},
+    AstNode,
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken, TextUnit,
+};
+
+use crate::{FileId, FileRange};
+
+/// Finds a `'label` token at `offset`, either in a label definition or in a
+/// `break`/`continue` expression.
+pub(crate) fn find_label_token(syntax: &SyntaxNode, offset: TextUnit) -> Option<SyntaxToken> {
+    syntax.token_at_offset(offset).find(|token| {
+        token.kind() == LIFETIME
+            && match token.parent().kind() {
+                LABEL | BREAK_EXPR | CONTINUE_EXPR => true,
+                _ => false,
+            }
+    })
+}
+
+/// Returns the label definition that `token` refers to. For a token inside a
+/// label, that's the label itself.
+pub(crate) fn label_definition(token: &SyntaxToken) -> Option<ast::Label> {
+    let parent = token.parent();
+    match parent.kind() {
+        LABEL => return ast::Label::cast(parent),
+        BREAK_EXPR | CONTINUE_EXPR => (),
+        _ => return None,
+    }
+    for node in parent.ancestors().skip(1) {
+        match node.kind() {
+            FN_DEF | LAMBDA_EXPR | CONST_DEF | STATIC_DEF => return None,
+            BLOCK_EXPR if ast::BlockExpr::cast(node.clone()).map_or(false, |it| it.is_async()) => {
+                return None
+            }
+            _ => (),
+        }
+        let label = match node.kind() {
+            LOOP_EXPR => ast::LoopExpr::cast(node).and_then(|it| it.label()),
+            WHILE_EXPR => ast::WhileExpr::cast(node).and_then(|it| it.label()),
+            FOR_EXPR => ast::ForExpr::cast(node).and_then(|it| it.label()),
+            _ => None,
+        };
+        let label = match label {
+            Some(it) => it,
+            None => continue,
+        };
+        if label.lifetime_token().map_or(false, |it| it.text() == token.text()) {
+            return Some(label);
+        }
+    }
+    None
+}
+
+/// Collects the `'label` tokens of all `break` and `continue` expressions
+/// that refer to `label`.
+pub(crate) fn label_references(file_id: FileId, label: &ast::Label) -> Vec<FileRange> {
+    let loop_expr = match label.syntax().parent() {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    loop_expr
+        .descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|token| {
+            token.kind() == LIFETIME
+                && token.parent().kind() != LABEL
+                && label_definition(token).as_ref() == Some(label)
+        })
+        .map(|token| FileRange { file_id, range: token.text_range() })
+        .collect()
+}
@@ -10,7 +10,7 @@ use crate::{
     SourceFileEdit, TextRange,
 };
 
-use super::find_all_refs;
+use super::{find_all_refs, find_label_token};
 
 pub(crate) fn rename(
     db: &RootDatabase,
@@ -18,13 +18,21 @@ pub(crate) fn rename(
     new_name: &str,
 ) -> Option<RangeInfo<SourceChange>> {
     let tokens = tokenize(new_name);
-    if tokens.len() != 1
-        || (tokens[0].kind != SyntaxKind::IDENT && tokens[0].kind != SyntaxKind::UNDERSCORE)
-    {
+    if tokens.len() != 1 {
         return None;
     }
 
     let parse = db.parse(position.file_id);
+    if find_label_token(parse.tree().syntax(), position.offset).is_some() {
+        if tokens[0].kind != SyntaxKind::LIFETIME {
+            return None;
+        }
+        return rename_reference(db, position, new_name);
+    }
+    if tokens[0].kind != SyntaxKind::IDENT && tokens[0].kind != SyntaxKind::UNDERSCORE {
+        return None;
+    }
+
     if let Some((ast_name, ast_module)) =
         find_name_and_module_at_offset(parse.tree().syntax(), position)
     {
@@ -219,6 +227,43 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rename_loop_label() {
+        test_rename(
+            r#"
+    fn foo() {
+        'a: loop {
+            while true {
+                break 'a<|>;
+            }
+            continue 'a;
+        }
+    }"#,
+            "'outer",
+            r#"
+    fn foo() {
+        'outer: loop {
+            while true {
+                break 'outer;
+            }
+            continue 'outer;
+        }
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_loop_label_to_ident() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn foo() {
+        'a<|>: loop { break 'a; }
+    }"#,
+        );
+        let source_change = analysis.rename(position, "b").unwrap();
+        assert!(source_change.is_none());
+    }
+
     #[test]
     fn test_rename_refs_for_fn_param() {
         test_rename(
@@ -4,11 +4,14 @@ use hir::InFile;
 use itertools::Itertools;
 use ra_db::SourceDatabase;
 use ra_syntax::{
-    ast::{self, AstNode, AttrsOwner, ModuleItemOwner, NameOwner},
-    match_ast, SyntaxNode, TextRange,
+    ast::{self, AstNode, AttrsOwner, DocCommentsOwner, ModuleItemOwner, NameOwner},
+    match_ast, NodeOrToken, SyntaxNode, TextRange,
 };
 
-use crate::{db::RootDatabase, FileId};
+use crate::{
+    db::{LineIndexDatabase, RootDatabase},
+    FileId,
+};
 
 #[derive(Debug)]
 pub struct Runnable {
@@ -21,12 +24,20 @@ pub enum RunnableKind {
     Test { name: String },
     TestMod { path: String },
     Bench { name: String },
+    DocTest { test_id: String },
     Bin,
 }
 
 pub(crate) fn runnables(db: &RootDatabase, file_id: FileId) -> Vec<Runnable> {
     let parse = db.parse(file_id);
-    parse.tree().syntax().descendants().filter_map(|i| runnable(db, file_id, i)).collect()
+    parse
+        .tree()
+        .syntax()
+        .descendants()
+        .flat_map(|i| {
+            runnable_doctests(db, file_id, &i).into_iter().chain(runnable(db, file_id, i))
+        })
+        .collect()
 }
 
 fn runnable(db: &RootDatabase, file_id: FileId, item: SyntaxNode) -> Option<Runnable> {
@@ -43,7 +54,7 @@ fn runnable_fn(fn_def: ast::FnDef) -> Option<Runnable> {
     let name = fn_def.name()?.text().clone();
     let kind = if name == "main" {
         RunnableKind::Bin
-    } else if fn_def.has_atom_attr("test") {
+    } else if has_test_attr(&fn_def) {
         RunnableKind::Test { name: name.to_string() }
     } else if fn_def.has_atom_attr("bench") {
         RunnableKind::Bench { name: name.to_string() }
@@ -53,6 +64,16 @@ fn runnable_fn(fn_def: ast::FnDef) -> Option<Runnable> {
     Some(Runnable { range: fn_def.syntax().text_range(), kind })
 }
 
+/// Recognizes both `#[test]` and the attributes of custom test frameworks,
+/// like `#[tokio::test]`.
+fn has_test_attr(fn_def: &ast::FnDef) -> bool {
+    fn_def
+        .attrs()
+        .filter(|attr| attr.input().is_none())
+        .filter_map(|attr| attr.path()?.segment()?.name_ref())
+        .any(|name_ref| name_ref.text() == "test")
+}
+
 fn runnable_mod(db: &RootDatabase, file_id: FileId, module: ast::Module) -> Option<Runnable> {
     let has_test_function = module
         .item_list()?
@@ -61,7 +82,7 @@ fn runnable_mod(db: &RootDatabase, file_id: FileId, module: ast::Module) -> Opti
             ast::ModuleItem::FnDef(it) => Some(it),
             _ => None,
         })
-        .any(|f| f.has_atom_attr("test"));
+        .any(|f| has_test_attr(&f));
     if !has_test_function {
         return None;
     }
@@ -73,6 +94,162 @@ fn runnable_mod(db: &RootDatabase, file_id: FileId, module: ast::Module) -> Opti
     Some(Runnable { range, kind: RunnableKind::TestMod { path } })
 }
 
+/// Returns one runnable per fenced code block in the doc comments of `item`,
+/// identified the same way as `rustdoc` names its tests.
+fn runnable_doctests(db: &RootDatabase, file_id: FileId, item: &SyntaxNode) -> Vec<Runnable> {
+    let (name, comments): (Option<ast::Name>, Vec<ast::Comment>) = match_ast! {
+        match item {
+            // The `//!` comments at the top of a file document its module.
+            ast::SourceFile(it) => { (None, inner_doc_comments(it.syntax())) },
+            ast::FnDef(it) => { (it.name(), outer_doc_comments(&it)) },
+            ast::StructDef(it) => { (it.name(), outer_doc_comments(&it)) },
+            ast::EnumDef(it) => { (it.name(), outer_doc_comments(&it)) },
+            ast::UnionDef(it) => { (it.name(), outer_doc_comments(&it)) },
+            ast::TraitDef(it) => { (it.name(), outer_doc_comments(&it)) },
+            ast::TypeAliasDef(it) => { (it.name(), outer_doc_comments(&it)) },
+            ast::ConstDef(it) => { (it.name(), outer_doc_comments(&it)) },
+            ast::StaticDef(it) => { (it.name(), outer_doc_comments(&it)) },
+            ast::Module(it) => {
+                let mut comments = outer_doc_comments(&it);
+                if let Some(item_list) = it.item_list() {
+                    comments.extend(inner_doc_comments(item_list.syntax()));
+                }
+                (it.name(), comments)
+            },
+            _ => return Vec::new(),
+        }
+    };
+    let mut blocks = Vec::new();
+    // `Some(None)` while inside of a code block that is not Rust code.
+    let mut block_start = None;
+    for comment in comments {
+        let kind = comment.kind();
+        if kind.doc.is_none() || kind.shape.is_block() {
+            continue;
+        }
+        let line = comment.text().as_str()[comment.prefix().len()..].trim();
+        if !line.starts_with("```") {
+            continue;
+        }
+        let range = comment.syntax().text_range();
+        match block_start.take() {
+            Some(Some(start)) => blocks.push(TextRange::from_to(start, range.end())),
+            Some(None) => (),
+            None => {
+                let is_rust = is_rust_fence(&line["```".len()..]);
+                block_start = Some(if is_rust { Some(range.start()) } else { None });
+            }
+        }
+    }
+    if blocks.is_empty() {
+        return Vec::new();
+    }
+    let path = match name {
+        Some(name) => doctest_path(db, file_id, item, &name),
+        None => file_doctest_path(db, file_id, item),
+    };
+    let path = match path {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    let line_index = db.line_index(file_id);
+    blocks
+        .into_iter()
+        .map(|range| {
+            let line = line_index.line_col(range.start()).line + 1;
+            // `rustdoc` names doc tests like `src/lib.rs - foo::bar (line 3)`,
+            // or `src/lib.rs - (line 3)` for the crate root.
+            let test_id = if path.is_empty() {
+                format!("(line {})", line)
+            } else {
+                format!("{} (line {})", path, line)
+            };
+            Runnable { range, kind: RunnableKind::DocTest { test_id } }
+        })
+        .collect()
+}
+
+fn outer_doc_comments(item: &impl DocCommentsOwner) -> Vec<ast::Comment> {
+    item.doc_comments().filter(|it| it.kind().doc == Some(ast::CommentPlacement::Outer)).collect()
+}
+
+/// Collects the `//!` comments documenting the module `node` defines. The
+/// parser attaches comments right before an item to that item, so we look at
+/// the leading comments of the items as well.
+fn inner_doc_comments(node: &SyntaxNode) -> Vec<ast::Comment> {
+    node.children_with_tokens()
+        .flat_map(|it| match it {
+            NodeOrToken::Token(it) => vec![it],
+            NodeOrToken::Node(it) => it
+                .children_with_tokens()
+                .take_while(|it| it.kind().is_trivia())
+                .filter_map(|it| it.into_token())
+                .collect(),
+        })
+        .filter_map(ast::Comment::cast)
+        .filter(|it| it.kind().doc == Some(ast::CommentPlacement::Inner))
+        .collect()
+}
+
+/// Computes the path `rustdoc` uses for the doc tests of `item`, like
+/// `foo::Bar::baz`.
+fn doctest_path(
+    db: &RootDatabase,
+    file_id: FileId,
+    item: &SyntaxNode,
+    name: &ast::Name,
+) -> Option<String> {
+    let parent = item.parent()?;
+    // Items declared inside of function bodies are not documented.
+    if parent.ancestors().any(|it| ast::BlockExpr::can_cast(it.kind())) {
+        return None;
+    }
+    let src = hir::ModuleSource::from_child_node(db, InFile::new(file_id.into(), &parent));
+    let module = hir::Module::from_definition(db, InFile::new(file_id.into(), src))?;
+    let mut segments = module_path(db, module);
+    let owner = parent.ancestors().find_map(|it| {
+        match_ast! {
+            match it {
+                ast::ImplBlock(it) => { it.target_type().map(|it| it.syntax().text().to_string()) },
+                ast::TraitDef(it) => { it.name().map(|it| it.text().to_string()) },
+                _ => { None },
+            }
+        }
+    });
+    segments.extend(owner);
+    segments.push(name.text().to_string());
+    Some(segments.join("::"))
+}
+
+/// Computes the path of the module a whole file defines, which is empty for
+/// the crate root.
+fn file_doctest_path(db: &RootDatabase, file_id: FileId, file: &SyntaxNode) -> Option<String> {
+    let file = ast::SourceFile::cast(file.clone())?;
+    let src = hir::ModuleSource::SourceFile(file);
+    let module = hir::Module::from_definition(db, InFile::new(file_id.into(), src))?;
+    Some(module_path(db, module).join("::"))
+}
+
+fn module_path(db: &RootDatabase, module: hir::Module) -> Vec<String> {
+    module
+        .path_to_root(db)
+        .into_iter()
+        .rev()
+        .filter_map(|it| it.name(db))
+        .map(|it| it.to_string())
+        .collect()
+}
+
+/// Checks whether the info string of a code fence marks the block as Rust
+/// code, which is what `rustdoc` assumes for unmarked blocks.
+fn is_rust_fence(info: &str) -> bool {
+    const RUST_FENCE_ATTRS: &[&str] =
+        &["rust", "should_panic", "ignore", "no_run", "compile_fail", "allow_fail", "test_harness"];
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|it| !it.is_empty())
+        .all(|it| RUST_FENCE_ATTRS.contains(&it) || it.starts_with("edition"))
+}
+
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
@@ -120,6 +297,150 @@ mod tests {
                 );
     }
 
+    #[test]
+    fn test_runnables_doc_test_and_custom_test_attr() {
+        let (analysis, pos) = analysis_and_position(
+            r#"
+        //- /lib.rs
+        <|> //empty
+        fn main() {}
+
+        /// ```
+        /// let x = 5;
+        /// ```
+        fn foo() {}
+
+        /// ```text
+        /// not rust
+        /// ```
+        ///
+        /// ```no_run
+        /// bar();
+        /// ```
+        struct Bar;
+
+        impl Bar {
+            /// ```
+            /// Bar::baz();
+            /// ```
+            fn baz() {}
+        }
+
+        #[tokio::test]
+        async fn test_async() {}
+        "#,
+        );
+        let runnables = analysis.runnables(pos.file_id).unwrap();
+        assert_debug_snapshot!(&runnables,
+        @r###"
+        [
+            Runnable {
+                range: [1; 21),
+                kind: Bin,
+            },
+            Runnable {
+                range: [23; 53),
+                kind: DocTest {
+                    test_id: "foo (line 4)",
+                },
+            },
+            Runnable {
+                range: [104; 136),
+                kind: DocTest {
+                    test_id: "Bar (line 13)",
+                },
+            },
+            Runnable {
+                range: [165; 204),
+                kind: DocTest {
+                    test_id: "Bar::baz (line 19)",
+                },
+            },
+            Runnable {
+                range: [224; 263),
+                kind: Test {
+                    name: "test_async",
+                },
+            },
+        ]
+        "###
+                );
+    }
+
+    #[test]
+    fn test_runnables_module_doc_tests() {
+        let (analysis, pos) = analysis_and_position(
+            r#"
+        //- /lib.rs
+        <|>//! ```
+        //! let x = 1;
+        //! ```
+
+        mod foo;
+
+        mod bar {
+            //! ```
+            //! bar::baz();
+            //! ```
+        }
+
+        //- /foo.rs
+        //! ```
+        //! foo();
+        //! ```
+        fn foo() {}
+        "#,
+        );
+        let runnables = analysis.runnables(pos.file_id).unwrap();
+        assert_debug_snapshot!(&runnables,
+        @r###"
+        [
+            Runnable {
+                range: [0; 30),
+                kind: DocTest {
+                    test_id: "(line 1)",
+                },
+            },
+            Runnable {
+                range: [56; 95),
+                kind: DocTest {
+                    test_id: "bar (line 8)",
+                },
+            },
+        ]
+        "###
+                );
+    }
+
+    #[test]
+    fn test_runnables_module_file_doc_tests() {
+        let (analysis, pos) = analysis_and_position(
+            r#"
+        //- /lib.rs
+        mod foo;
+
+        //- /foo.rs
+        <|>//! ```
+        //! foo();
+        //! ```
+        fn foo() {}
+        "#,
+        );
+        let runnables = analysis.runnables(pos.file_id).unwrap();
+        assert_debug_snapshot!(&runnables,
+        @r###"
+        [
+            Runnable {
+                range: [0; 26),
+                kind: DocTest {
+                    test_id: "foo (line 1)",
+                },
+            },
+        ]
+        "###
+                );
+    }
+
     #[test]
     fn test_runnables_module() {
         let (analysis, pos) = analysis_and_position(
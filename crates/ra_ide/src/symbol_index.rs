@@ -19,6 +19,10 @@
 //! for each library (which is assumed to never change) and an FST for each Rust
 //! file in the current workspace, and run a query against the union of all
 //! those FSTs.
+//!
+//! Items produced by macros are not visible in the syntax trees of the files,
+//! so for each crate we additionally build an index of the macro-generated
+//! items found in its `CrateDefMap`.
 use std::{
     fmt,
     hash::{Hash, Hasher},
@@ -26,7 +30,8 @@ use std::{
     sync::Arc,
 };
 
-use fst::{self, Streamer};
+use fst::{self, Automaton, Streamer};
+use hir::{Adt, AssocItem, Crate, HasSource, HirFileId, InFile, Module, ModuleDef};
 use ra_db::{
     salsa::{self, ParallelDatabase},
     SourceDatabaseExt, SourceRootId,
@@ -45,6 +50,8 @@ use crate::{db::RootDatabase, FileId, Query};
 #[salsa::query_group(SymbolsDatabaseStorage)]
 pub(crate) trait SymbolsDatabase: hir::db::HirDatabase {
     fn file_symbols(&self, file_id: FileId) -> Arc<SymbolIndex>;
+    /// The items of the crate which come from macro expansions.
+    fn crate_macro_symbols(&self, krate: Crate) -> Arc<SymbolIndex>;
     #[salsa::input]
     fn library_symbols(&self, id: SourceRootId) -> Arc<SymbolIndex>;
     /// The set of "local" (that is, from the current workspace) roots.
@@ -63,8 +70,29 @@ fn file_symbols(db: &impl SymbolsDatabase, file_id: FileId) -> Arc<SymbolIndex>
 
     let symbols = source_file_to_file_symbols(&parse.tree(), file_id);
 
-    // FIXME: add macros here
+    Arc::new(SymbolIndex::new(symbols))
+}
 
+fn crate_macro_symbols(db: &impl SymbolsDatabase, krate: Crate) -> Arc<SymbolIndex> {
+    db.check_canceled();
+    let mut symbols = Vec::new();
+    let mut modules: Vec<Module> = krate.root_module(db).into_iter().collect();
+    while let Some(module) = modules.pop() {
+        modules.extend(module.children(db));
+        let container_name = module.name(db).map(|it| SmolStr::from(it.to_string()));
+        let impl_items = module.impl_blocks(db).into_iter().flat_map(|it| it.items(db));
+        let impl_items = impl_items.map(|item| match item {
+            AssocItem::Function(it) => ModuleDef::from(it),
+            AssocItem::Const(it) => ModuleDef::from(it),
+            AssocItem::TypeAlias(it) => ModuleDef::from(it),
+        });
+        for def in module.declarations(db).into_iter().chain(impl_items) {
+            if let Some(mut symbol) = macro_def_to_file_symbol(db, def) {
+                symbol.container_name = container_name.clone();
+                symbols.push(symbol);
+            }
+        }
+    }
     Arc::new(SymbolIndex::new(symbols))
 }
 
@@ -78,38 +106,64 @@ pub(crate) fn world_symbols(db: &RootDatabase, query: Query) -> Vec<FileSymbol>
     }
 
     let buf: Vec<Arc<SymbolIndex>> = if query.libs {
+        let roots = db.library_roots();
+        let crates = crates_in_roots(db, &roots);
         let snap = Snap(db.snapshot());
+        // Building the `CrateDefMap`s for the macro symbols is the expensive
+        // part, so it happens in parallel with indexing the libraries.
         #[cfg(not(feature = "wasm"))]
-        let buf = db
-            .library_roots()
+        let buf = roots
             .par_iter()
-            .map_with(snap, |db, &lib_id| db.0.library_symbols(lib_id))
+            .map_with(snap.clone(), |db, &lib_id| db.0.library_symbols(lib_id))
+            .chain(crates.par_iter().map_with(snap, |db, &krate| db.0.crate_macro_symbols(krate)))
             .collect();
 
         #[cfg(feature = "wasm")]
-        let buf = db.library_roots().iter().map(|&lib_id| snap.0.library_symbols(lib_id)).collect();
+        let buf = roots
+            .iter()
+            .map(|&lib_id| snap.0.library_symbols(lib_id))
+            .chain(crates.iter().map(|&krate| snap.0.crate_macro_symbols(krate)))
+            .collect();
 
         buf
     } else {
+        let roots = db.local_roots();
         let mut files = Vec::new();
-        for &root in db.local_roots().iter() {
+        for &root in roots.iter() {
             let sr = db.source_root(root);
             files.extend(sr.walk())
         }
+        let crates = crates_in_roots(db, &roots);
 
         let snap = Snap(db.snapshot());
         #[cfg(not(feature = "wasm"))]
-        let buf =
-            files.par_iter().map_with(snap, |db, &file_id| db.0.file_symbols(file_id)).collect();
+        let buf = files
+            .par_iter()
+            .map_with(snap.clone(), |db, &file_id| db.0.file_symbols(file_id))
+            .chain(crates.par_iter().map_with(snap, |db, &krate| db.0.crate_macro_symbols(krate)))
+            .collect();
 
         #[cfg(feature = "wasm")]
-        let buf = files.iter().map(|&file_id| snap.0.file_symbols(file_id)).collect();
+        let buf = files
+            .iter()
+            .map(|&file_id| snap.0.file_symbols(file_id))
+            .chain(crates.iter().map(|&krate| snap.0.crate_macro_symbols(krate)))
+            .collect();
 
         buf
     };
+
     query.search(&buf)
 }
 
+/// The crates whose root file is in one of `roots`.
+fn crates_in_roots(db: &RootDatabase, roots: &[SourceRootId]) -> Vec<Crate> {
+    Crate::all(db)
+        .into_iter()
+        .filter(|krate| roots.contains(&db.file_source_root(krate.root_file(db))))
+        .collect()
+}
+
 pub(crate) fn index_resolve(db: &RootDatabase, name_ref: &ast::NameRef) -> Vec<FileSymbol> {
     let name = name_ref.text();
     let mut query = Query::new(name.to_string());
@@ -224,8 +278,13 @@ impl Query {
     pub(crate) fn search(self, indices: &[Arc<SymbolIndex>]) -> Vec<FileSymbol> {
         let mut op = fst::map::OpBuilder::new();
         for file_symbols in indices.iter() {
-            let automaton = fst::automaton::Subsequence::new(&self.lowercased);
-            op = op.add(file_symbols.map.search(automaton))
+            if self.prefix {
+                let automaton = fst::automaton::Str::new(&self.lowercased).starts_with();
+                op = op.add(file_symbols.map.search(automaton))
+            } else {
+                let automaton = fst::automaton::Subsequence::new(&self.lowercased);
+                op = op.add(file_symbols.map.search(automaton))
+            }
         }
         let mut stream = op.union();
         let mut res = Vec::new();
@@ -254,7 +313,7 @@ impl Query {
 
 fn is_type(kind: SyntaxKind) -> bool {
     match kind {
-        STRUCT_DEF | ENUM_DEF | TRAIT_DEF | TYPE_ALIAS_DEF => true,
+        STRUCT_DEF | ENUM_DEF | UNION_DEF | TRAIT_DEF | TYPE_ALIAS_DEF => true,
         _ => false,
     }
 }
@@ -263,7 +322,8 @@ fn is_type(kind: SyntaxKind) -> bool {
 /// possible.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct FileSymbol {
-    pub(crate) file_id: FileId,
+    /// For items produced by macros, this is the macro file.
+    pub(crate) file_id: HirFileId,
     pub(crate) name: SmolStr,
     pub(crate) ptr: SyntaxNodePtr,
     pub(crate) name_range: Option<TextRange>,
@@ -277,7 +337,7 @@ fn source_file_to_file_symbols(source_file: &SourceFile, file_id: FileId) -> Vec
     for event in source_file.syntax().preorder() {
         match event {
             WalkEvent::Enter(node) => {
-                if let Some(mut symbol) = to_file_symbol(&node, file_id) {
+                if let Some(mut symbol) = to_file_symbol(&node, file_id.into()) {
                     symbol.container_name = stack.last().cloned();
 
                     stack.push(symbol.name.clone());
@@ -310,6 +370,7 @@ fn to_symbol(node: &SyntaxNode) -> Option<(SmolStr, SyntaxNodePtr, TextRange)> {
             ast::FnDef(it) => { decl(it) },
             ast::StructDef(it) => { decl(it) },
             ast::EnumDef(it) => { decl(it) },
+            ast::UnionDef(it) => { decl(it) },
             ast::TraitDef(it) => { decl(it) },
             ast::Module(it) => { decl(it) },
             ast::TypeAliasDef(it) => { decl(it) },
@@ -320,7 +381,7 @@ fn to_symbol(node: &SyntaxNode) -> Option<(SmolStr, SyntaxNodePtr, TextRange)> {
     }
 }
 
-fn to_file_symbol(node: &SyntaxNode, file_id: FileId) -> Option<FileSymbol> {
+fn to_file_symbol(node: &SyntaxNode, file_id: HirFileId) -> Option<FileSymbol> {
     to_symbol(node).map(move |(name, ptr, name_range)| FileSymbol {
         name,
         ptr,
@@ -330,12 +391,33 @@ fn to_file_symbol(node: &SyntaxNode, file_id: FileId) -> Option<FileSymbol> {
     })
 }
 
+fn macro_def_to_file_symbol(db: &impl SymbolsDatabase, def: ModuleDef) -> Option<FileSymbol> {
+    // Items written out in source files are already indexed by `file_symbols`,
+    // so we skip them before parsing anything.
+    if !def.file_id(db)?.is_macro_file() {
+        return None;
+    }
+    let src: InFile<SyntaxNode> = match def {
+        ModuleDef::Module(it) => it.declaration_source(db)?.map(|it| it.syntax().clone()),
+        ModuleDef::Function(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Adt(Adt::Struct(it)) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Adt(Adt::Enum(it)) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Adt(Adt::Union(it)) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Const(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Static(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::Trait(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::TypeAlias(it) => it.source(db).map(|it| it.syntax().clone()),
+        ModuleDef::EnumVariant(_) | ModuleDef::BuiltinType(_) => return None,
+    };
+    to_file_symbol(&src.value, src.file_id)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{display::NavigationTarget, mock_analysis::single_file, Query};
     use ra_syntax::{
         SmolStr,
-        SyntaxKind::{FN_DEF, STRUCT_DEF},
+        SyntaxKind::{FN_DEF, STRUCT_DEF, UNION_DEF},
     };
 
     #[test]
@@ -398,6 +480,53 @@ struct Foo;
         assert_eq!(struct_match, Some(STRUCT_DEF));
     }
 
+    #[test]
+    fn test_world_symbols_include_macro_generated_items() {
+        let code = r#"
+macro_rules! define_id_type {
+    ($name:ident) => {
+        pub struct $name(u32);
+    };
+}
+
+mod ids {
+    define_id_type!(UserId);
+}
+        "#;
+
+        let mut symbols = get_symbols_matching(code, "UserId");
+
+        let s = symbols.pop().unwrap();
+        assert!(symbols.is_empty());
+
+        assert_eq!(s.name(), "UserId");
+        assert_eq!(s.kind(), STRUCT_DEF);
+        assert_eq!(s.container_name(), Some(&SmolStr::new("ids")));
+        assert_eq!(&code[s.focus_range().unwrap()], "UserId");
+    }
+
+    #[test]
+    fn test_world_symbols_include_macro_generated_unions() {
+        let code = r#"
+macro_rules! define_union {
+    ($name:ident) => {
+        union $name { int: u32, float: f32 }
+    };
+}
+
+define_union!(IntOrFloat);
+        "#;
+
+        let mut symbols = get_symbols_matching(code, "IntOrFloat");
+
+        let s = symbols.pop().unwrap();
+        assert!(symbols.is_empty());
+
+        assert_eq!(s.name(), "IntOrFloat");
+        assert_eq!(s.kind(), UNION_DEF);
+        assert_eq!(&code[s.focus_range().unwrap()], "IntOrFloat");
+    }
+
     fn get_symbols_matching(text: &str, query: &str) -> Vec<NavigationTarget> {
         let (analysis, _) = single_file(text);
         analysis.symbol_search(Query::new(query.into())).unwrap()
@@ -3,7 +3,7 @@
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use hir::{InFile, Name};
-use ra_db::SourceDatabase;
+use ra_db::{SourceDatabase, SourceDatabaseExt};
 use ra_prof::profile;
 use ra_syntax::{ast, AstNode, Direction, SyntaxElement, SyntaxKind, SyntaxKind::*, TextRange, T};
 
@@ -17,40 +17,58 @@ use crate::{
 };
 
 pub mod tags {
-    pub(crate) const FIELD: &str = "field";
-    pub(crate) const FUNCTION: &str = "function";
-    pub(crate) const MODULE: &str = "module";
-    pub(crate) const TYPE: &str = "type";
-    pub(crate) const CONSTANT: &str = "constant";
-    pub(crate) const MACRO: &str = "macro";
-    pub(crate) const VARIABLE: &str = "variable";
-    pub(crate) const VARIABLE_MUT: &str = "variable.mut";
-    pub(crate) const TEXT: &str = "text";
-
-    pub(crate) const TYPE_BUILTIN: &str = "type.builtin";
-    pub(crate) const TYPE_SELF: &str = "type.self";
-    pub(crate) const TYPE_PARAM: &str = "type.param";
-    pub(crate) const TYPE_LIFETIME: &str = "type.lifetime";
-
-    pub(crate) const LITERAL_BYTE: &str = "literal.byte";
-    pub(crate) const LITERAL_NUMERIC: &str = "literal.numeric";
-    pub(crate) const LITERAL_CHAR: &str = "literal.char";
-    pub(crate) const LITERAL_COMMENT: &str = "comment";
-    pub(crate) const LITERAL_STRING: &str = "string";
-    pub(crate) const LITERAL_ATTRIBUTE: &str = "attribute";
-
-    pub(crate) const KEYWORD_UNSAFE: &str = "keyword.unsafe";
-    pub(crate) const KEYWORD_CONTROL: &str = "keyword.control";
-    pub(crate) const KEYWORD: &str = "keyword";
+    pub const FIELD: &str = "field";
+    pub const FUNCTION: &str = "function";
+    pub const MODULE: &str = "module";
+    pub const TYPE: &str = "type";
+    pub const CONSTANT: &str = "constant";
+    pub const MACRO: &str = "macro";
+    pub const VARIABLE: &str = "variable";
+    pub const VARIABLE_MUT: &str = "variable.mut";
+    pub const TEXT: &str = "text";
+
+    pub const TYPE_BUILTIN: &str = "type.builtin";
+    pub const TYPE_SELF: &str = "type.self";
+    pub const TYPE_PARAM: &str = "type.param";
+    pub const TYPE_LIFETIME: &str = "type.lifetime";
+
+    pub const LITERAL_BYTE: &str = "literal.byte";
+    pub const LITERAL_NUMERIC: &str = "literal.numeric";
+    pub const LITERAL_CHAR: &str = "literal.char";
+    pub const LITERAL_COMMENT: &str = "comment";
+    pub const LITERAL_STRING: &str = "string";
+    pub const LITERAL_ATTRIBUTE: &str = "attribute";
+
+    pub const KEYWORD_UNSAFE: &str = "keyword.unsafe";
+    pub const KEYWORD_CONTROL: &str = "keyword.control";
+    pub const KEYWORD: &str = "keyword";
 }
 
 #[derive(Debug)]
 pub struct HighlightedRange {
     pub range: TextRange,
     pub tag: &'static str,
+    pub modifiers: HighlightModifiers,
     pub binding_hash: Option<u64>,
 }
 
+/// Properties of a highlighted name which are orthogonal to its tag.
+///
+/// Mutability is not listed here, as it is already part of the tag
+/// (`variable.mut`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HighlightModifiers {
+    /// The name is the one of a definition, rather than a reference to it.
+    pub is_declaration: bool,
+    /// A `static` item, or an associated function without `self`.
+    pub is_static: bool,
+    /// The definition lives in a library (like `std`), rather than in the
+    /// workspace.
+    pub is_library: bool,
+    /// An `unsafe fn`, which can only be called from unsafe code.
+    pub is_unsafe: bool,
+}
+
 fn is_control_keyword(kind: SyntaxKind) -> bool {
     match kind {
         T![for]
@@ -94,6 +112,7 @@ pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HighlightedRa
             continue;
         }
         let mut binding_hash = None;
+        let mut modifiers = HighlightModifiers::default();
         let tag = match node.kind() {
             FN_DEF => {
                 bindings_shadow_count.clear();
@@ -110,6 +129,9 @@ pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HighlightedRa
                 let name_kind =
                     classify_name_ref(db, InFile::new(file_id.into(), &name_ref)).map(|d| d.kind);
 
+                if let Some(name_kind) = &name_kind {
+                    modifiers = highlight_modifiers(db, name_kind);
+                }
                 if let Some(Local(local)) = &name_kind {
                     if let Some(name) = local.name(db) {
                         let shadow_count = bindings_shadow_count.entry(name.clone()).or_default();
@@ -124,6 +146,10 @@ pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HighlightedRa
                 let name_kind =
                     classify_name(db, InFile::new(file_id.into(), &name)).map(|d| d.kind);
 
+                if let Some(name_kind) = &name_kind {
+                    modifiers = highlight_modifiers(db, name_kind);
+                }
+                modifiers.is_declaration = true;
                 if let Some(Local(local)) = &name_kind {
                     if let Some(name) = local.name(db) {
                         let shadow_count = bindings_shadow_count.entry(name.clone()).or_default();
@@ -166,6 +192,7 @@ pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HighlightedRa
                                 res.push(HighlightedRange {
                                     range: TextRange::from_to(range_start, range_end),
                                     tag: tags::MACRO,
+                                    modifiers: HighlightModifiers::default(),
                                     binding_hash: None,
                                 })
                             }
@@ -175,7 +202,7 @@ pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HighlightedRa
                 continue;
             }
         };
-        res.push(HighlightedRange { range: node.text_range(), tag, binding_hash })
+        res.push(HighlightedRange { range: node.text_range(), tag, modifiers, binding_hash })
     }
     res
 }
@@ -267,6 +294,45 @@ fn highlight_name(db: &RootDatabase, name_kind: NameKind) -> &'static str {
     }
 }
 
+fn highlight_modifiers(db: &RootDatabase, name_kind: &NameKind) -> HighlightModifiers {
+    let is_static = match name_kind {
+        Def(hir::ModuleDef::Static(_)) => true,
+        AssocItem(hir::AssocItem::Function(it)) => !it.has_self_param(db),
+        _ => false,
+    };
+    let module = match name_kind {
+        Macro(_) | Def(hir::ModuleDef::BuiltinType(_)) => None,
+        Field(it) => Some(it.parent_def(db).module(db)),
+        AssocItem(it) => Some(it.module(db)),
+        Def(hir::ModuleDef::Module(it)) => Some(*it),
+        Def(hir::ModuleDef::Function(it)) => Some(it.module(db)),
+        Def(hir::ModuleDef::Adt(it)) => Some(it.module(db)),
+        Def(hir::ModuleDef::EnumVariant(it)) => Some(it.module(db)),
+        Def(hir::ModuleDef::Const(it)) => Some(it.module(db)),
+        Def(hir::ModuleDef::Static(it)) => Some(it.module(db)),
+        Def(hir::ModuleDef::Trait(it)) => Some(it.module(db)),
+        Def(hir::ModuleDef::TypeAlias(it)) => Some(it.module(db)),
+        SelfType(it) => Some(it.module(db)),
+        Local(it) => Some(it.module(db)),
+        TypeParam(it) => Some(it.module(db)),
+    };
+    let krate = match name_kind {
+        Macro(it) => it.krate(),
+        _ => module.map(|it| it.krate()),
+    };
+    let is_library = krate.map_or(false, |krate| {
+        let root_file = krate.root_file(db);
+        db.source_root(db.file_source_root(root_file)).is_library
+    });
+    let is_unsafe = match name_kind {
+        Def(hir::ModuleDef::Function(it)) | AssocItem(hir::AssocItem::Function(it)) => {
+            it.is_unsafe(db)
+        }
+        _ => false,
+    };
+    HighlightModifiers { is_declaration: false, is_static, is_library, is_unsafe }
+}
+
 //FIXME: like, real html escaping
 fn html_escape(text: &str) -> String {
     text.replace("<", "&lt;").replace(">", "&gt;")
@@ -304,6 +370,8 @@ mod tests {
     use crate::mock_analysis::single_file;
     use test_utils::{assert_eq_text, project_dir, read_text};
 
+    use super::{tags, HighlightModifiers};
+
     #[test]
     fn test_highlighting() {
         let (analysis, file_id) = single_file(
@@ -379,4 +447,85 @@ fn bar() {
         std::fs::write(dst_file, &actual_html).unwrap();
         assert_eq_text!(expected_html, actual_html);
     }
+
+    #[test]
+    fn test_highlight_modifiers() {
+        let (analysis, file_id) = single_file(
+            r#"
+static FOO: i32 = 92;
+struct S;
+impl S {
+    fn new() -> S { S }
+    fn get(&self) -> i32 { FOO }
+}
+fn main() {
+    let s = S::new();
+    s.get();
+}
+"#
+            .trim(),
+        );
+        let text = analysis.file_text(file_id).unwrap();
+        let highlights = analysis.highlight(file_id).unwrap();
+        let modifiers = |tag: &str, name: &str, nth: usize| -> HighlightModifiers {
+            highlights
+                .iter()
+                .filter(|it| {
+                    it.tag == tag
+                        && &text[it.range.start().to_usize()..it.range.end().to_usize()] == name
+                })
+                .nth(nth)
+                .unwrap_or_else(|| panic!("no highlight for {}", name))
+                .modifiers
+        };
+
+        let static_decl = modifiers(tags::CONSTANT, "FOO", 0);
+        assert!(static_decl.is_declaration && static_decl.is_static);
+        let static_ref = modifiers(tags::CONSTANT, "FOO", 1);
+        assert!(!static_ref.is_declaration && static_ref.is_static);
+
+        assert!(modifiers(tags::FUNCTION, "new", 1).is_static);
+        assert!(!modifiers(tags::FUNCTION, "get", 1).is_static);
+        assert!(!modifiers(tags::VARIABLE, "s", 0).is_library);
+    }
+
+    #[test]
+    fn test_highlight_unsafe_fn_calls() {
+        let (analysis, file_id) = single_file(
+            r#"
+unsafe fn dangerous() {}
+fn safe() {}
+struct S;
+impl S {
+    unsafe fn unchecked(&self) {}
+}
+fn main() {
+    unsafe {
+        dangerous();
+        S.unchecked();
+    }
+    safe();
+}
+"#
+            .trim(),
+        );
+        let text = analysis.file_text(file_id).unwrap();
+        let highlights = analysis.highlight(file_id).unwrap();
+        let is_unsafe = |name: &str, nth: usize| -> bool {
+            highlights
+                .iter()
+                .filter(|it| {
+                    it.tag == tags::FUNCTION
+                        && &text[it.range.start().to_usize()..it.range.end().to_usize()] == name
+                })
+                .nth(nth)
+                .unwrap_or_else(|| panic!("no highlight for {}", name))
+                .modifiers
+                .is_unsafe
+        };
+
+        assert!(is_unsafe("dangerous", 1));
+        assert!(is_unsafe("unchecked", 1));
+        assert!(!is_unsafe("safe", 1));
+    }
 }
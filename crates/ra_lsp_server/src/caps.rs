@@ -3,20 +3,23 @@
 use lsp_types::{
     CodeActionProviderCapability, CodeLensOptions, CompletionOptions,
     DocumentOnTypeFormattingOptions, FoldingRangeProviderCapability,
-    ImplementationProviderCapability, RenameOptions, RenameProviderCapability,
+    ImplementationProviderCapability, RenameOptions, RenameProviderCapability, SaveOptions,
     SelectionRangeProviderCapability, ServerCapabilities, SignatureHelpOptions,
     TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
     TypeDefinitionProviderCapability, WorkDoneProgressOptions,
 };
+use serde_json::{json, Value};
+
+use crate::semantic_tokens::{self, SemanticTokensFullOptions, SemanticTokensOptions};
 
 pub fn server_capabilities() -> ServerCapabilities {
     ServerCapabilities {
         text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
             open_close: Some(true),
-            change: Some(TextDocumentSyncKind::Full),
+            change: Some(TextDocumentSyncKind::Incremental),
             will_save: None,
             will_save_wait_until: None,
-            save: None,
+            save: Some(SaveOptions::default()),
         })),
         hover_provider: Some(true),
         completion_provider: Some(CompletionOptions {
@@ -58,3 +61,17 @@ pub fn server_capabilities() -> ServerCapabilities {
         workspace: None,
     }
 }
+
+/// Capabilities `lsp-types` doesn't know about yet, to be merged into the
+/// serialized `ServerCapabilities`.
+pub fn experimental_server_capabilities() -> Value {
+    let semantic_tokens_provider = SemanticTokensOptions {
+        legend: semantic_tokens::legend(),
+        range: Some(true),
+        full: Some(SemanticTokensFullOptions { delta: Some(true) }),
+    };
+    json!({
+        "semanticTokensProvider": semantic_tokens_provider,
+        "callHierarchyProvider": true,
+    })
+}
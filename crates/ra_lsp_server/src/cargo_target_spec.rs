@@ -40,6 +40,19 @@ pub(crate) fn runnable_args(
             res.push(name.to_string());
             res.push("--nocapture".to_string());
         }
+        RunnableKind::DocTest { test_id } => {
+            res.push("test".to_string());
+            res.push("--doc".to_string());
+            if let Some(spec) = spec {
+                spec.push_package_to(&mut res);
+            }
+            res.push("--".to_string());
+            // The filter is matched as a substring of the full test name, so
+            // anchor it on the ` - ` after the file name to keep `foo (line 4)`
+            // from also running `a::foo (line 4)`.
+            res.push(format!(" - {}", test_id));
+            res.push("--nocapture".to_string());
+        }
         RunnableKind::Bin => {
             res.push("run".to_string());
             if let Some(spec) = spec {
@@ -54,6 +67,7 @@ pub struct CargoTargetSpec {
     pub package: String,
     pub target: String,
     pub target_kind: TargetKind,
+    pub required_features: Vec<String>,
 }
 
 impl CargoTargetSpec {
@@ -71,6 +85,7 @@ impl CargoTargetSpec {
                     package: tgt.package(&cargo).name(&cargo).to_string(),
                     target: tgt.name(&cargo).to_string(),
                     target_kind: tgt.kind(&cargo),
+                    required_features: tgt.required_features(&cargo).to_vec(),
                 })
             }
             ProjectWorkspace::Json { .. } => None,
@@ -78,9 +93,19 @@ impl CargoTargetSpec {
         Ok(res)
     }
 
-    pub fn push_to(self, buf: &mut Vec<String>) {
+    /// Pushes the package selection without the target, as `cargo test --doc`
+    /// can't be combined with any other target selection flag.
+    pub fn push_package_to(&self, buf: &mut Vec<String>) {
         buf.push("--package".to_string());
-        buf.push(self.package);
+        buf.push(self.package.clone());
+        if !self.required_features.is_empty() {
+            buf.push("--features".to_string());
+            buf.push(self.required_features.join(" "));
+        }
+    }
+
+    pub fn push_to(self, buf: &mut Vec<String>) {
+        self.push_package_to(buf);
         match self.target_kind {
             TargetKind::Bin => {
                 buf.push("--bin".to_string());
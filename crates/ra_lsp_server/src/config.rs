@@ -7,6 +7,8 @@
 //! configure the server itself, feature flags are passed into analysis, and
 //! tweak things like automatic insertion of `()` in completions.
 
+use std::path::PathBuf;
+
 use rustc_hash::FxHashMap;
 
 use ra_project_model::CargoFeatures;
@@ -39,8 +41,25 @@ pub struct ServerConfig {
     /// Fine grained feature flags to disable specific features.
     pub feature_flags: FxHashMap<String, bool>,
 
+    /// User-defined postfix completions and snippets.
+    pub completion_templates: Vec<CompletionTemplateConfig>,
+
     /// Cargo feature configurations.
     pub cargo_features: CargoFeatures,
+
+    /// Path to the proc-macro server executable, which expands procedural
+    /// macros. Proc macros are not expanded if it is not set.
+    pub proc_macro_srv: Option<PathBuf>,
+
+    /// Whether to run `cargo check` on save and publish its diagnostics.
+    #[serde(deserialize_with = "nullable_bool_false")]
+    pub cargo_watch_enable: bool,
+    /// Extra arguments for the check command.
+    pub cargo_watch_args: Vec<String>,
+    /// Cargo subcommand to run on save, `check` by default.
+    pub cargo_watch_command: String,
+    #[serde(deserialize_with = "nullable_bool_true")]
+    pub cargo_watch_all_targets: bool,
 }
 
 impl Default for ServerConfig {
@@ -53,11 +72,38 @@ impl Default for ServerConfig {
             max_inlay_hint_length: None,
             with_sysroot: true,
             feature_flags: FxHashMap::default(),
+            completion_templates: Vec::new(),
             cargo_features: Default::default(),
+            proc_macro_srv: None,
+            cargo_watch_enable: false,
+            cargo_watch_args: Vec::new(),
+            cargo_watch_command: "check".to_string(),
+            cargo_watch_all_targets: true,
         }
     }
 }
 
+/// A user-defined completion, converted to a `ra_ide::CompletionTemplate`.
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionTemplateConfig {
+    /// Where the template is offered: `postfix`, `expr` or `item`.
+    pub scope: String,
+    pub label: String,
+    /// The snippet to insert. `$receiver` stands for the receiver expression
+    /// of postfix templates.
+    pub body: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Restricts postfix templates to receivers implementing a trait
+    /// (`impl path::to::Trait`) or of a certain type (`path::to::Type`).
+    #[serde(default)]
+    pub requires: Option<String>,
+    /// Paths to import when the completion is accepted.
+    #[serde(default)]
+    pub imports: Vec<String>,
+}
+
 /// Deserializes a null value to a bool false by default
 fn nullable_bool_false<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
@@ -90,4 +136,26 @@ mod test {
             serde_json::from_str(r#"{"publishDecorations":null, "lruCapacity":null}"#).unwrap()
         );
     }
+
+    #[test]
+    fn deserialize_completion_templates() {
+        let config: ServerConfig = serde_json::from_str(
+            r#"{"completionTemplates": [
+                {"scope": "postfix", "label": "arc", "body": "Arc::new($receiver)",
+                 "imports": ["std::sync::Arc"]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.completion_templates,
+            vec![CompletionTemplateConfig {
+                scope: "postfix".to_string(),
+                label: "arc".to_string(),
+                body: "Arc::new($receiver)".to_string(),
+                description: None,
+                requires: None,
+                imports: vec!["std::sync::Arc".to_string()],
+            }]
+        );
+    }
 }
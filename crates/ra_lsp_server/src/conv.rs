@@ -210,6 +210,24 @@ impl Conv for ra_ide::FunctionSignature {
     }
 }
 
+impl Conv for ra_ide::ExpansionStep {
+    type Output = req::ExpansionStep;
+    fn conv(self) -> req::ExpansionStep {
+        req::ExpansionStep {
+            name: self.name,
+            rule: self.rule,
+            failed: self.failed,
+            bindings: self
+                .bindings
+                .into_iter()
+                .map(|(name, value)| req::MacroBinding { name, value })
+                .collect(),
+            expansion: self.expansion,
+            nested: self.nested.into_iter().map(|it| it.conv()).collect(),
+        }
+    }
+}
+
 impl ConvWith<(&LineIndex, LineEndings)> for TextEdit {
     type Output = Vec<lsp_types::TextEdit>;
 
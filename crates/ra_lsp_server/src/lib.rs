@@ -28,12 +28,13 @@ mod main_loop;
 mod markdown;
 pub mod req;
 mod config;
+mod semantic_tokens;
 mod world;
 
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 pub use crate::{
-    caps::server_capabilities,
-    config::ServerConfig,
+    caps::{experimental_server_capabilities, server_capabilities},
+    config::{CompletionTemplateConfig, ServerConfig},
     main_loop::LspError,
     main_loop::{main_loop, show_message},
 };
@@ -42,7 +42,13 @@ fn run_server() -> Result<()> {
     log::info!("lifecycle: server started");
 
     let (connection, io_threads) = Connection::stdio();
-    let server_capabilities = serde_json::to_value(ra_lsp_server::server_capabilities()).unwrap();
+    let mut server_capabilities =
+        serde_json::to_value(ra_lsp_server::server_capabilities()).unwrap();
+    if let (Some(caps), serde_json::Value::Object(extra)) =
+        (server_capabilities.as_object_mut(), ra_lsp_server::experimental_server_capabilities())
+    {
+        caps.extend(extra);
+    }
 
     let initialize_params = connection.initialize(server_capabilities)?;
     let initialize_params: lsp_types::InitializeParams = serde_json::from_value(initialize_params)?;
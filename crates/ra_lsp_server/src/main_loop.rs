@@ -9,23 +9,30 @@ use std::{error::Error, fmt, panic, path::PathBuf, sync::Arc, time::Instant};
 
 use crossbeam_channel::{select, unbounded, RecvError, Sender};
 use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
-use lsp_types::{ClientCapabilities, NumberOrString};
-use ra_ide::{Canceled, FeatureFlags, FileId, LibraryData, SourceRootId};
+use lsp_types::{
+    ClientCapabilities, FileChangeType, NumberOrString, TextDocumentContentChangeEvent, Url,
+};
+use ra_cargo_watch::{CheckOptions, CheckTask};
+use ra_ide::{
+    Canceled, CompletionTemplate, FeatureFlags, FileId, LibraryData, LineIndex, SourceRootId,
+};
 use ra_prof::profile;
-use ra_vfs::{VfsTask, Watch};
+use ra_text_edit::AtomTextEdit;
+use ra_vfs::{LineEndings, VfsTask, Watch};
 use relative_path::RelativePathBuf;
 use rustc_hash::FxHashSet;
 use serde::{de::DeserializeOwned, Serialize};
 use threadpool::ThreadPool;
 
 use crate::{
+    conv::ConvWith,
     main_loop::{
         pending_requests::{PendingRequest, PendingRequests},
         subscriptions::Subscriptions,
     },
     req,
     world::{Options, WorldSnapshot, WorldState},
-    Result, ServerConfig,
+    CompletionTemplateConfig, Result, ServerConfig,
 };
 
 const THREADPOOL_SIZE: usize = 8;
@@ -113,6 +120,20 @@ pub fn main_loop(
             connection.sender.send(request.into()).unwrap();
         }
 
+        let completion_templates = config
+            .completion_templates
+            .iter()
+            .filter_map(|it| match completion_template(it) {
+                Ok(template) => Some(template),
+                Err(err) => {
+                    let msg = format!("invalid completion template {:?}: {}", it.label, err);
+                    log::error!("{}", msg);
+                    show_message(req::MessageType::Error, msg, &connection.sender);
+                    None
+                }
+            })
+            .collect();
+
         let options = {
             let text_document_caps = client_caps.text_document.as_ref();
             Options {
@@ -126,6 +147,14 @@ pub fn main_loop(
                     .and_then(|it| it.line_folding_only)
                     .unwrap_or(false),
                 max_inlay_hint_length: config.max_inlay_hint_length,
+                proc_macro_srv: config.proc_macro_srv.clone(),
+                cargo_watch: CheckOptions {
+                    enable: config.cargo_watch_enable,
+                    args: config.cargo_watch_args,
+                    command: config.cargo_watch_command,
+                    all_targets: config.cargo_watch_all_targets,
+                },
+                completion_templates,
             }
         };
 
@@ -176,7 +205,11 @@ pub fn main_loop(
                     Ok(task) => Event::Vfs(task),
                     Err(RecvError) => Err("vfs died")?,
                 },
-                recv(libdata_receiver) -> data => Event::Lib(data.unwrap())
+                recv(libdata_receiver) -> data => Event::Lib(data.unwrap()),
+                recv(world_state.check_watcher.task_recv) -> task => match task {
+                    Ok(task) => Event::CheckWatcher(task),
+                    Err(RecvError) => Err("check watcher died")?,
+                }
             };
             if let Event::Msg(Message::Request(req)) = &event {
                 if connection.handle_shutdown(&req)? {
@@ -222,6 +255,7 @@ enum Event {
     Task(Task),
     Vfs(VfsTask),
     Lib(LibraryData),
+    CheckWatcher(CheckTask),
 }
 
 impl fmt::Debug for Event {
@@ -259,6 +293,7 @@ impl fmt::Debug for Event {
             Event::Task(it) => fmt::Debug::fmt(it, f),
             Event::Vfs(it) => fmt::Debug::fmt(it, f),
             Event::Lib(it) => fmt::Debug::fmt(it, f),
+            Event::CheckWatcher(it) => fmt::Debug::fmt(it, f),
         }
     }
 }
@@ -318,6 +353,9 @@ fn loop_turn(
             world_state.maybe_collect_garbage();
             loop_state.in_flight_libraries -= 1;
         }
+        Event::CheckWatcher(task) => {
+            on_check_task(pool, task, world_state, &loop_state.subscriptions, task_sender)?
+        }
         Event::Msg(msg) => match msg {
             Message::Request(req) => on_request(
                 world_state,
@@ -411,6 +449,54 @@ fn on_task(
     }
 }
 
+fn on_check_task(
+    pool: &ThreadPool,
+    task: CheckTask,
+    world_state: &WorldState,
+    subscriptions: &Subscriptions,
+    task_sender: &Sender<Task>,
+) -> Result<()> {
+    match task {
+        CheckTask::Update(uris) => {
+            // We manually send a diagnostic update when the watcher asks
+            // us to, to avoid the issue of having to change the file to
+            // receive updated diagnostics.
+            let subscriptions = subscriptions.subscriptions();
+            let mut file_ids = Vec::new();
+            for uri in uris {
+                let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
+                let file_id = world_state.vfs.read().path2file(&path).map(|it| FileId(it.0));
+                match file_id {
+                    // Open files get check diagnostics merged with our own.
+                    Some(file_id) if subscriptions.contains(&file_id) => file_ids.push(file_id),
+                    _ => publish_check_diagnostics(world_state, uri, task_sender),
+                }
+            }
+            update_file_notifications_on_threadpool(
+                pool,
+                world_state.snapshot(),
+                false,
+                task_sender.clone(),
+                file_ids,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn publish_check_diagnostics(world_state: &WorldState, uri: Url, task_sender: &Sender<Task>) {
+    let diagnostics = world_state
+        .check_watcher
+        .shared
+        .read()
+        .diagnostics_for(&uri)
+        .map(|it| it.to_vec())
+        .unwrap_or_default();
+    let params = req::PublishDiagnosticsParams { uri, diagnostics, version: None };
+    let not = notification_new::<req::PublishDiagnostics>(params);
+    task_sender.send(Task::Notify(not)).unwrap();
+}
+
 fn on_request(
     world: &mut WorldState,
     pending_requests: &mut PendingRequests,
@@ -464,6 +550,12 @@ fn on_request(
         .on::<req::Formatting>(handlers::handle_formatting)?
         .on::<req::DocumentHighlightRequest>(handlers::handle_document_highlight)?
         .on::<req::InlayHints>(handlers::handle_inlay_hints)?
+        .on::<req::SemanticTokensRequest>(handlers::handle_semantic_tokens)?
+        .on::<req::SemanticTokensDeltaRequest>(handlers::handle_semantic_tokens_delta)?
+        .on::<req::SemanticTokensRangeRequest>(handlers::handle_semantic_tokens_range)?
+        .on::<req::CallHierarchyPrepare>(handlers::handle_call_hierarchy_prepare)?
+        .on::<req::CallHierarchyIncomingCalls>(handlers::handle_call_hierarchy_incoming)?
+        .on::<req::CallHierarchyOutgoingCalls>(handlers::handle_call_hierarchy_outgoing)?
         .finish();
     Ok(())
 }
@@ -507,19 +599,39 @@ fn on_notification(
         Err(not) => not,
     };
     let not = match notification_cast::<req::DidChangeTextDocument>(not) {
-        Ok(mut params) => {
+        Ok(params) => {
             let uri = params.text_document.uri;
             let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
-            let text =
-                params.content_changes.pop().ok_or_else(|| "empty changes".to_string())?.text;
+            let (file_id, line_endings) = {
+                let vfs = state.vfs.read();
+                let file = vfs.path2file(&path).ok_or_else(|| format!("unknown file: {}", uri))?;
+                (FileId(file.0), vfs.file_line_endings(file))
+            };
+            let mut text = state.analysis_host.analysis().file_text(file_id)?.to_string();
+            match apply_document_changes(&mut text, line_endings, params.content_changes) {
+                Some(edits) => state.file_edits.entry(file_id).or_default().extend(edits),
+                None => {
+                    state.file_edits.remove(&file_id);
+                }
+            }
             state.vfs.write().change_file_overlay(path.as_path(), text);
             return Ok(());
         }
         Err(not) => not,
     };
+    let not = match notification_cast::<req::DidSaveTextDocument>(not) {
+        Ok(_params) => {
+            state.check_watcher.update();
+            return Ok(());
+        }
+        Err(not) => not,
+    };
     let not = match notification_cast::<req::DidCloseTextDocument>(not) {
         Ok(params) => {
             let uri = params.text_document.uri;
+            // Drop the cached tokens first, so that they don't outlive the
+            // document even if its uri turns out to be invalid.
+            state.semantic_tokens_cache.write().remove(&uri);
             let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
             if let Some(file_id) = state.vfs.write().remove_file_overlay(path.as_path()) {
                 subs.remove_sub(FileId(file_id.0));
@@ -543,6 +655,9 @@ fn on_notification(
             let mut vfs = state.vfs.write();
             for change in params.changes {
                 let uri = change.uri;
+                if change.typ == FileChangeType::Deleted {
+                    state.semantic_tokens_cache.write().remove(&uri);
+                }
                 let path = uri.to_file_path().map_err(|()| format!("invalid uri: {}", uri))?;
                 vfs.notify_changed(path)
             }
@@ -554,6 +669,40 @@ fn on_notification(
     Ok(())
 }
 
+/// Applies a batch of LSP content changes to `old_text`.
+///
+/// Changes are applied in order, each range being relative to the text
+/// produced by the previous change. A change without a range replaces the
+/// whole document, so everything before the last such change is skipped.
+///
+/// Returns the applied edits, or `None` if the whole document was replaced.
+fn apply_document_changes(
+    old_text: &mut String,
+    line_endings: LineEndings,
+    content_changes: Vec<TextDocumentContentChangeEvent>,
+) -> Option<Vec<AtomTextEdit>> {
+    let start = content_changes.iter().rposition(|change| change.range.is_none());
+    let mut edits = if start.is_none() { Some(Vec::new()) } else { None };
+    for change in content_changes.into_iter().skip(start.unwrap_or(0)) {
+        let mut new_text = change.text;
+        if line_endings == LineEndings::Dos {
+            new_text = new_text.replace("\r\n", "\n");
+        }
+        match change.range {
+            Some(range) => {
+                let line_index = LineIndex::new(old_text);
+                let range = range.conv_with(&line_index);
+                old_text.replace_range(range.start().to_usize()..range.end().to_usize(), &new_text);
+                if let Some(edits) = &mut edits {
+                    edits.push(AtomTextEdit::replace(range, new_text));
+                }
+            }
+            None => *old_text = new_text,
+        }
+    }
+    edits
+}
+
 struct PoolDispatcher<'a> {
     req: Option<Request>,
     pool: &'a ThreadPool,
@@ -727,6 +876,23 @@ fn update_file_notifications_on_threadpool(
     });
 }
 
+fn completion_template(
+    config: &CompletionTemplateConfig,
+) -> std::result::Result<CompletionTemplate, String> {
+    let mut template =
+        CompletionTemplate::new(&config.scope, config.label.clone(), config.body.clone())?;
+    if let Some(description) = &config.description {
+        template.set_description(description.clone());
+    }
+    if let Some(requirement) = &config.requires {
+        template.set_requirement(requirement)?;
+    }
+    for import in config.imports.iter() {
+        template.add_import(import)?;
+    }
+    Ok(template)
+}
+
 pub fn show_message(typ: req::MessageType, message: impl Into<String>, sender: &Sender<Message>) {
     let message = message.into();
     let params = req::ShowMessageParams { typ, message };
@@ -765,3 +931,97 @@ where
 {
     Request::new(id, R::METHOD.to_string(), params)
 }
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+    use ra_syntax::{TextRange, TextUnit};
+    use ra_text_edit::AtomTextEdit;
+    use ra_vfs::LineEndings;
+
+    use super::apply_document_changes;
+
+    #[test]
+    fn test_apply_document_changes() {
+        macro_rules! c {
+            [$($sl:expr, $sc:expr; $el:expr, $ec:expr => $text:expr),+] => {
+                vec![$(TextDocumentContentChangeEvent {
+                    range: Some(Range {
+                        start: Position { line: $sl, character: $sc },
+                        end: Position { line: $el, character: $ec },
+                    }),
+                    range_length: None,
+                    text: String::from($text),
+                }),+]
+            };
+        }
+
+        let mut text = String::new();
+        apply_document_changes(&mut text, LineEndings::Unix, vec![]);
+        assert_eq!(text, "");
+        apply_document_changes(
+            &mut text,
+            LineEndings::Unix,
+            vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: String::from("the"),
+            }],
+        );
+        assert_eq!(text, "the");
+        apply_document_changes(&mut text, LineEndings::Unix, c![0, 3; 0, 3 => " quick"]);
+        assert_eq!(text, "the quick");
+        apply_document_changes(
+            &mut text,
+            LineEndings::Unix,
+            c![0, 0; 0, 4 => "", 0, 5; 0, 5 => " brown"],
+        );
+        assert_eq!(text, "quick brown");
+        apply_document_changes(&mut text, LineEndings::Unix, c![0, 11; 0, 11 => "\nfox"]);
+        assert_eq!(text, "quick brown\nfox");
+        apply_document_changes(
+            &mut text,
+            LineEndings::Unix,
+            c![1, 0; 1, 3 => "dog", 0, 0; 1, 0 => ""],
+        );
+        assert_eq!(text, "dog");
+        apply_document_changes(&mut text, LineEndings::Dos, c![0, 3; 0, 3 => "\r\ncat"]);
+        assert_eq!(text, "dog\ncat");
+    }
+
+    #[test]
+    fn document_changes_are_returned_as_edits() {
+        let change = |range: Option<(u64, u64)>, text: &str| TextDocumentContentChangeEvent {
+            range: range.map(|(start, end)| Range {
+                start: Position { line: 0, character: start },
+                end: Position { line: 0, character: end },
+            }),
+            range_length: None,
+            text: text.to_string(),
+        };
+        let range = |start: u32, end: u32| TextRange::from_to(start.into(), end.into());
+
+        let mut text = String::from("fn foo() {}");
+        let edits = apply_document_changes(
+            &mut text,
+            LineEndings::Unix,
+            vec![change(Some((3, 6)), "bar"), change(Some((10, 10)), " 92 ")],
+        );
+        assert_eq!(text, "fn bar() { 92 }");
+        assert_eq!(
+            edits,
+            Some(vec![
+                AtomTextEdit::replace(range(3, 6), "bar".to_string()),
+                AtomTextEdit::insert(TextUnit::from(10), " 92 ".to_string()),
+            ])
+        );
+
+        let edits = apply_document_changes(
+            &mut text,
+            LineEndings::Unix,
+            vec![change(None, "fn baz() {}"), change(Some((3, 6)), "quux")],
+        );
+        assert_eq!(text, "fn quux() {}");
+        assert_eq!(edits, None);
+    }
+}
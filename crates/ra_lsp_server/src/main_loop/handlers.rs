@@ -11,9 +11,11 @@ use lsp_types::{
     Range, RenameParams, SymbolInformation, TextDocumentIdentifier, TextEdit, WorkspaceEdit,
 };
 use ra_ide::{
-    AssistId, FileId, FilePosition, FileRange, Query, Runnable, RunnableKind, SearchScope,
+    AssistId, CallItem, FileId, FilePosition, FileRange, NavigationTarget, Query, Runnable,
+    RunnableKind, SearchScope,
 };
 use ra_prof::profile;
+use ra_project_model::TargetKind;
 use ra_syntax::{AstNode, SyntaxKind, TextRange, TextUnit};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
@@ -23,6 +25,7 @@ use crate::{
     cargo_target_spec::{runnable_args, CargoTargetSpec},
     conv::{to_location, Conv, ConvWith, FoldConvCtx, MapConvWith, TryConvWith, TryConvWithToVec},
     req::{self, Decoration, InlayHint, InlayHintsParams, InlayKind},
+    semantic_tokens::{self, SemanticTokensBuilder},
     world::WorldSnapshot,
     LspError, Result,
 };
@@ -61,7 +64,11 @@ pub fn handle_expand_macro(
         None => Ok(None),
         Some(offset) => {
             let res = world.analysis().expand_macro(FilePosition { file_id, offset })?;
-            Ok(res.map(|it| req::ExpandedMacro { name: it.name, expansion: it.expansion }))
+            Ok(res.map(|it| req::ExpandedMacro {
+                name: it.name,
+                expansion: it.expansion,
+                steps: it.steps.conv(),
+            }))
         }
     }
 }
@@ -333,7 +340,7 @@ pub fn handle_runnables(
     let offset = params.position.map(|it| it.conv_with(&line_index));
     let mut res = Vec::new();
     let workspace_root = world.workspace_root_for(file_id);
-    for runnable in world.analysis().runnables(file_id)? {
+    for runnable in runnables(&world, file_id)? {
         if let Some(offset) = offset {
             if !runnable.range.contains_inclusive(offset) {
                 continue;
@@ -402,7 +409,8 @@ pub fn handle_completion(
         return Ok(None);
     }
 
-    let items = match world.analysis().completions(position)? {
+    let templates = &world.options.completion_templates;
+    let items = match world.analysis().completions(position, templates)? {
         None => return Ok(None),
         Some(items) => items,
     };
@@ -549,6 +557,106 @@ pub fn handle_references(
     Ok(Some(locations))
 }
 
+pub fn handle_call_hierarchy_prepare(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<Vec<req::CallHierarchyItem>>> {
+    let _p = profile("handle_call_hierarchy_prepare");
+    let position = params.try_conv_with(&world)?;
+    let nav_info = match world.analysis().call_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let res = nav_info
+        .info
+        .into_iter()
+        .filter(|it| it.kind() == SyntaxKind::FN_DEF)
+        .map(|it| to_call_hierarchy_item(&world, &it))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(res))
+}
+
+pub fn handle_call_hierarchy_incoming(
+    world: WorldSnapshot,
+    params: req::CallHierarchyIncomingCallsParams,
+) -> Result<Option<Vec<req::CallHierarchyIncomingCall>>> {
+    let _p = profile("handle_call_hierarchy_incoming");
+    let position = call_hierarchy_item_position(&world, &params.item)?;
+    let call_items = match world.analysis().incoming_calls(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let mut res = vec![];
+    for item in call_items {
+        let file_id = item.target.file_id();
+        let (from, from_ranges) = to_call_hierarchy_call(&world, item, file_id)?;
+        res.push(req::CallHierarchyIncomingCall { from, from_ranges });
+    }
+    Ok(Some(res))
+}
+
+pub fn handle_call_hierarchy_outgoing(
+    world: WorldSnapshot,
+    params: req::CallHierarchyOutgoingCallsParams,
+) -> Result<Option<Vec<req::CallHierarchyOutgoingCall>>> {
+    let _p = profile("handle_call_hierarchy_outgoing");
+    let position = call_hierarchy_item_position(&world, &params.item)?;
+    let call_items = match world.analysis().outgoing_calls(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+
+    let mut res = vec![];
+    for item in call_items {
+        let (to, from_ranges) = to_call_hierarchy_call(&world, item, position.file_id)?;
+        res.push(req::CallHierarchyOutgoingCall { to, from_ranges });
+    }
+    Ok(Some(res))
+}
+
+fn call_hierarchy_item_position(
+    world: &WorldSnapshot,
+    item: &req::CallHierarchyItem,
+) -> Result<FilePosition> {
+    let doc = TextDocumentIdentifier::new(item.uri.clone());
+    let file_id = doc.try_conv_with(world)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+    let offset = item.selection_range.start.conv_with(&line_index);
+    Ok(FilePosition { file_id, offset })
+}
+
+fn to_call_hierarchy_item(
+    world: &WorldSnapshot,
+    nav: &NavigationTarget,
+) -> Result<req::CallHierarchyItem> {
+    let line_index = world.analysis().file_line_index(nav.file_id())?;
+    let range = nav.full_range().conv_with(&line_index);
+    let selection_range = nav.focus_range().map(|it| it.conv_with(&line_index)).unwrap_or(range);
+    Ok(req::CallHierarchyItem {
+        name: nav.name().to_string(),
+        kind: nav.kind().conv(),
+        detail: nav.description().map(|it| it.to_string()),
+        uri: nav.file_id().try_conv_with(world)?,
+        range,
+        selection_range,
+    })
+}
+
+/// Converts a call item into the called or calling function and the ranges of
+/// the calls, which live in `file_id`, the file of the caller.
+fn to_call_hierarchy_call(
+    world: &WorldSnapshot,
+    item: CallItem,
+    file_id: FileId,
+) -> Result<(req::CallHierarchyItem, Vec<Range>)> {
+    let hierarchy_item = to_call_hierarchy_item(world, &item.target)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+    let ranges = item.ranges.into_iter().map(|it| it.conv_with(&line_index)).collect();
+    Ok((hierarchy_item, ranges))
+}
+
 pub fn handle_formatting(
     world: WorldSnapshot,
     params: DocumentFormattingParams,
@@ -654,6 +762,32 @@ pub fn handle_code_action(
         res.push(action.into());
     }
 
+    if let Some(fixes) = world.check_watcher.read().fixes_for(&params.text_document.uri) {
+        for fix in fixes {
+            let fix_range = fix.location.range.conv_with(&line_index);
+            if fix_range.intersection(&range).is_none() {
+                continue;
+            }
+
+            let edit = {
+                let edits = vec![TextEdit::new(fix.location.range, fix.replacement.clone())];
+                let mut edit_map = std::collections::HashMap::new();
+                edit_map.insert(fix.location.uri.clone(), edits);
+                WorkspaceEdit::new(edit_map)
+            };
+
+            let action = CodeAction {
+                title: fix.title.clone(),
+                kind: Some("quickfix".to_string()),
+                diagnostics: Some(fix.diagnostics.clone()),
+                edit: Some(edit),
+                command: None,
+                is_preferred: Some(fix.is_preferred),
+            };
+            res.push(action.into());
+        }
+    }
+
     for assist in assists {
         let title = assist.change.label.clone();
         let edit = assist.change.try_conv_with(&world)?;
@@ -691,10 +825,11 @@ pub fn handle_code_lens(
     let mut lenses: Vec<CodeLens> = Default::default();
 
     // Gather runnables
-    for runnable in world.analysis().runnables(file_id)? {
+    for runnable in runnables(&world, file_id)? {
         let title = match &runnable.kind {
             RunnableKind::Test { .. } | RunnableKind::TestMod { .. } => "▶️Run Test",
             RunnableKind::Bench { .. } => "Run Bench",
+            RunnableKind::DocTest { .. } => "▶️Run Doctest",
             RunnableKind::Bin => "Run",
         }
         .to_string();
@@ -820,7 +955,7 @@ pub fn publish_diagnostics(
     let _p = profile("publish_diagnostics");
     let uri = world.file_id_to_uri(file_id)?;
     let line_index = world.analysis().file_line_index(file_id)?;
-    let diagnostics = world
+    let mut diagnostics: Vec<Diagnostic> = world
         .analysis()
         .diagnostics(file_id)?
         .into_iter()
@@ -834,6 +969,11 @@ pub fn publish_diagnostics(
             tags: None,
         })
         .collect();
+
+    if let Some(check_diags) = world.check_watcher.read().diagnostics_for(&uri) {
+        diagnostics.extend(check_diags.iter().cloned());
+    }
+
     Ok(req::PublishDiagnosticsParams { uri, diagnostics, version: None })
 }
 
@@ -846,6 +986,21 @@ pub fn publish_decorations(
     Ok(req::PublishDecorationsParams { uri, decorations: highlight(&world, file_id)? })
 }
 
+/// Returns the runnables of the file, without the doc tests when they can't be
+/// run: `cargo test --doc` only runs the doc tests of library targets.
+fn runnables(world: &WorldSnapshot, file_id: FileId) -> Result<Vec<Runnable>> {
+    let has_doctests = match CargoTargetSpec::for_file(world, file_id)? {
+        Some(spec) => spec.target_kind == TargetKind::Lib,
+        None => false,
+    };
+    let mut res = world.analysis().runnables(file_id)?;
+    res.retain(|runnable| match runnable.kind {
+        RunnableKind::DocTest { .. } => has_doctests,
+        _ => true,
+    });
+    Ok(res)
+}
+
 fn to_lsp_runnable(
     world: &WorldSnapshot,
     file_id: FileId,
@@ -857,6 +1012,7 @@ fn to_lsp_runnable(
         RunnableKind::Test { name } => format!("test {}", name),
         RunnableKind::TestMod { path } => format!("test-mod {}", path),
         RunnableKind::Bench { name } => format!("bench {}", name),
+        RunnableKind::DocTest { test_id } => format!("doctest {}", test_id),
         RunnableKind::Bin => "run binary".to_string(),
     };
     Ok(req::Runnable {
@@ -903,7 +1059,108 @@ pub fn handle_inlay_hints(
             range: api_type.range.conv_with(&line_index),
             kind: match api_type.kind {
                 ra_ide::InlayKind::TypeHint => InlayKind::TypeHint,
+                ra_ide::InlayKind::ParameterHint => InlayKind::ParameterHint,
+                ra_ide::InlayKind::ChainingHint => InlayKind::ChainingHint,
             },
         })
         .collect())
 }
+
+pub fn handle_semantic_tokens(
+    world: WorldSnapshot,
+    params: req::SemanticTokensParams,
+) -> Result<Option<req::SemanticTokens>> {
+    let _p = profile("handle_semantic_tokens");
+
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let tokens = semantic_tokens(&world, file_id, None)?;
+
+    world.semantic_tokens_cache.write().insert(params.text_document.uri, tokens.clone());
+    Ok(Some(tokens))
+}
+
+pub fn handle_semantic_tokens_delta(
+    world: WorldSnapshot,
+    params: req::SemanticTokensDeltaParams,
+) -> Result<Option<req::SemanticTokensFullDeltaResult>> {
+    let _p = profile("handle_semantic_tokens_delta");
+
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let tokens = semantic_tokens(&world, file_id, None)?;
+
+    let mut cache = world.semantic_tokens_cache.write();
+    let cached = cache.get(&params.text_document.uri);
+    let res = match cached {
+        Some(prev) if prev.result_id.as_ref() == Some(&params.previous_result_id) => {
+            let edits = semantic_tokens::diff_tokens(&prev.data, &tokens.data);
+            req::SemanticTokensFullDeltaResult::TokensDelta(req::SemanticTokensDelta {
+                result_id: tokens.result_id.clone(),
+                edits,
+            })
+        }
+        _ => req::SemanticTokensFullDeltaResult::Tokens(tokens.clone()),
+    };
+    cache.insert(params.text_document.uri, tokens);
+    Ok(Some(res))
+}
+
+pub fn handle_semantic_tokens_range(
+    world: WorldSnapshot,
+    params: req::SemanticTokensRangeParams,
+) -> Result<Option<req::SemanticTokens>> {
+    let _p = profile("handle_semantic_tokens_range");
+
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+    let range = params.range.conv_with(&line_index);
+    Ok(Some(semantic_tokens(&world, file_id, Some(range))?))
+}
+
+fn semantic_tokens(
+    world: &WorldSnapshot,
+    file_id: FileId,
+    range: Option<TextRange>,
+) -> Result<req::SemanticTokens> {
+    let text = world.analysis().file_text(file_id)?;
+    let line_index = world.analysis().file_line_index(file_id)?;
+
+    let mut highlights = world.analysis().highlight(file_id)?;
+    // Tokens may not overlap, so when ranges are nested (e.g. a string in an
+    // attribute), only the outermost one is kept.
+    highlights.sort_by_key(|it| (it.range.start(), std::cmp::Reverse(it.range.end())));
+
+    let mut builder = SemanticTokensBuilder::new();
+    let mut prev_end = TextUnit::from(0);
+    for highlight in highlights {
+        if highlight.range.start() < prev_end {
+            continue;
+        }
+        if let Some(range) = range {
+            if highlight.range.intersection(&range).is_none() {
+                continue;
+            }
+        }
+        let (token_type, modifiers) =
+            match semantic_tokens::token_type_and_modifiers(highlight.tag, highlight.modifiers) {
+                Some(it) => it,
+                None => continue,
+            };
+        prev_end = highlight.range.end();
+
+        // Clients are not required to support multiline tokens, so comments
+        // and strings are split by lines.
+        let mut line_start = highlight.range.start();
+        let highlighted_text =
+            &text[highlight.range.start().to_usize()..highlight.range.end().to_usize()];
+        for line in highlighted_text.split('\n') {
+            let line_range = TextRange::offset_len(line_start, TextUnit::of_str(line));
+            line_start = line_range.end() + TextUnit::of_char('\n');
+            if line.is_empty() {
+                continue;
+            }
+            builder.push(line_range.conv_with(&line_index), token_type, modifiers);
+        }
+    }
+
+    Ok(builder.build())
+}
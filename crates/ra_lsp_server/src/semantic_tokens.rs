@@ -0,0 +1,378 @@
+//! Semantic tokens, the standard (LSP 3.16) replacement for our custom
+//! `publishDecorations` notification.
+//!
+//! `lsp-types` doesn't support them yet, so the protocol types are defined
+//! here, together with the legend and the encoding of highlighted ranges.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use lsp_types::{Range, TextDocumentIdentifier};
+use ra_ide::{tags, HighlightModifiers};
+use serde::{de::Deserializer, ser::Serializer, Deserialize, Serialize};
+
+pub(crate) const SUPPORTED_TYPES: &[&str] = &[
+    "comment",
+    "keyword",
+    "string",
+    "number",
+    "namespace",
+    "type",
+    "typeParameter",
+    "function",
+    "macro",
+    "variable",
+    "property",
+    // Non-standard types
+    "attribute",
+    "builtinType",
+    "lifetime",
+];
+
+pub(crate) const SUPPORTED_MODIFIERS: &[&str] = &[
+    "declaration",
+    "static",
+    "readonly",
+    "library",
+    // Non-standard modifiers
+    "mutable",
+    "unsafe",
+    "controlFlow",
+];
+
+pub(crate) fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: SUPPORTED_TYPES.iter().map(|it| it.to_string()).collect(),
+        token_modifiers: SUPPORTED_MODIFIERS.iter().map(|it| it.to_string()).collect(),
+    }
+}
+
+fn type_index(ty: &str) -> u32 {
+    SUPPORTED_TYPES.iter().position(|it| *it == ty).unwrap() as u32
+}
+
+fn modifier_bit(modifier: &str) -> u32 {
+    1 << SUPPORTED_MODIFIERS.iter().position(|it| *it == modifier).unwrap()
+}
+
+/// Maps a highlighting tag to the token type and the modifier bitset from
+/// the legend. Returns `None` for tags which shouldn't produce a token.
+pub(crate) fn token_type_and_modifiers(
+    tag: &str,
+    modifiers: HighlightModifiers,
+) -> Option<(u32, u32)> {
+    let (ty, tag_modifier) = match tag {
+        tags::FIELD => ("property", None),
+        tags::FUNCTION => ("function", None),
+        tags::MODULE => ("namespace", None),
+        tags::TYPE | tags::TYPE_SELF => ("type", None),
+        tags::CONSTANT => ("variable", Some("readonly")),
+        tags::MACRO => ("macro", None),
+        tags::VARIABLE => ("variable", None),
+        tags::VARIABLE_MUT => ("variable", Some("mutable")),
+        tags::TYPE_BUILTIN => ("builtinType", None),
+        tags::TYPE_PARAM => ("typeParameter", None),
+        tags::TYPE_LIFETIME => ("lifetime", None),
+        tags::LITERAL_BYTE | tags::LITERAL_CHAR | tags::LITERAL_STRING => ("string", None),
+        tags::LITERAL_NUMERIC => ("number", None),
+        tags::LITERAL_COMMENT => ("comment", None),
+        tags::LITERAL_ATTRIBUTE => ("attribute", None),
+        tags::KEYWORD => ("keyword", None),
+        tags::KEYWORD_UNSAFE => ("keyword", Some("unsafe")),
+        tags::KEYWORD_CONTROL => ("keyword", Some("controlFlow")),
+        _ => return None,
+    };
+
+    let mut bitset = tag_modifier.map_or(0, modifier_bit);
+    if modifiers.is_declaration {
+        bitset |= modifier_bit("declaration");
+    }
+    if modifiers.is_static {
+        bitset |= modifier_bit("static");
+    }
+    if modifiers.is_library {
+        bitset |= modifier_bit("library");
+    }
+    if modifiers.is_unsafe {
+        bitset |= modifier_bit("unsafe");
+    }
+    Some((type_index(ty), bitset))
+}
+
+/// Tokens are encoded relative to the previous one, so they must be pushed
+/// in order, and each must fit on a single line.
+pub(crate) struct SemanticTokensBuilder {
+    id: String,
+    prev_line: u32,
+    prev_char: u32,
+    data: Vec<SemanticToken>,
+}
+
+impl SemanticTokensBuilder {
+    pub(crate) fn new() -> SemanticTokensBuilder {
+        static NEXT_RESULT_ID: AtomicU32 = AtomicU32::new(1);
+        let id = NEXT_RESULT_ID.fetch_add(1, Ordering::SeqCst).to_string();
+        SemanticTokensBuilder { id, prev_line: 0, prev_char: 0, data: Vec::new() }
+    }
+
+    pub(crate) fn push(&mut self, range: Range, token_index: u32, modifier_bitset: u32) {
+        assert_eq!(range.start.line, range.end.line);
+
+        let mut push_line = range.start.line as u32;
+        let mut push_char = range.start.character as u32;
+
+        if !self.data.is_empty() {
+            push_line -= self.prev_line;
+            if push_line == 0 {
+                push_char -= self.prev_char;
+            }
+        }
+
+        let token_len = range.end.character - range.start.character;
+
+        let token = SemanticToken {
+            delta_line: push_line,
+            delta_start: push_char,
+            length: token_len as u32,
+            token_type: token_index,
+            token_modifiers_bitset: modifier_bitset,
+        };
+
+        self.data.push(token);
+
+        self.prev_line = range.start.line as u32;
+        self.prev_char = range.start.character as u32;
+    }
+
+    pub(crate) fn build(self) -> SemanticTokens {
+        SemanticTokens { result_id: Some(self.id), data: self.data }
+    }
+}
+
+/// Computes the edits turning `old` into `new`.
+///
+/// We only look for the common prefix and suffix, which is enough for the
+/// usual case of a single local modification.
+pub(crate) fn diff_tokens(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let offset = new.iter().zip(old.iter()).take_while(|&(n, p)| n == p).count();
+
+    let (_, old) = old.split_at(offset);
+    let (_, new) = new.split_at(offset);
+
+    let offset_from_end =
+        new.iter().rev().zip(old.iter().rev()).take_while(|&(n, p)| n == p).count();
+
+    let (old, _) = old.split_at(old.len() - offset_from_end);
+    let (new, _) = new.split_at(new.len() - offset_from_end);
+
+    if old.is_empty() && new.is_empty() {
+        vec![]
+    } else {
+        // Edits index into the serialized data, where each token takes five
+        // integers.
+        vec![SemanticTokensEdit {
+            start: 5 * offset as u32,
+            delete_count: 5 * old.len() as u32,
+            data: new.into(),
+        }]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensLegend {
+    pub token_types: Vec<String>,
+    pub token_modifiers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensOptions {
+    pub legend: SemanticTokensLegend,
+    pub range: Option<bool>,
+    pub full: Option<SemanticTokensFullOptions>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensFullOptions {
+    pub delta: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub delta_line: u32,
+    pub delta_start: u32,
+    pub length: u32,
+    pub token_type: u32,
+    pub token_modifiers_bitset: u32,
+}
+
+impl SemanticToken {
+    fn serialize_tokens<S>(tokens: &[SemanticToken], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let data = tokens.iter().flat_map(|it| {
+            vec![it.delta_line, it.delta_start, it.length, it.token_type, it.token_modifiers_bitset]
+        });
+        serializer.collect_seq(data)
+    }
+
+    fn deserialize_tokens<'de, D>(deserializer: D) -> Result<Vec<SemanticToken>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let data = Vec::<u32>::deserialize(deserializer)?;
+        let tokens = data
+            .chunks(5)
+            .filter(|chunk| chunk.len() == 5)
+            .map(|chunk| SemanticToken {
+                delta_line: chunk[0],
+                delta_start: chunk[1],
+                length: chunk[2],
+                token_type: chunk[3],
+                token_modifiers_bitset: chunk[4],
+            })
+            .collect();
+        Ok(tokens)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokens {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+    #[serde(
+        serialize_with = "SemanticToken::serialize_tokens",
+        deserialize_with = "SemanticToken::deserialize_tokens"
+    )]
+    pub data: Vec<SemanticToken>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensEdit {
+    pub start: u32,
+    pub delete_count: u32,
+    #[serde(
+        serialize_with = "SemanticToken::serialize_tokens",
+        deserialize_with = "SemanticToken::deserialize_tokens"
+    )]
+    pub data: Vec<SemanticToken>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensDelta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+    pub edits: Vec<SemanticTokensEdit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SemanticTokensFullDeltaResult {
+    Tokens(SemanticTokens),
+    TokensDelta(SemanticTokensDelta),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensRangeParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticTokensDeltaParams {
+    pub text_document: TextDocumentIdentifier,
+    pub previous_result_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+
+    use super::*;
+
+    fn from(t: (u32, u32, u32, u32, u32)) -> SemanticToken {
+        SemanticToken {
+            delta_line: t.0,
+            delta_start: t.1,
+            length: t.2,
+            token_type: t.3,
+            token_modifiers_bitset: t.4,
+        }
+    }
+
+    #[test]
+    fn test_builder_encodes_relative_positions() {
+        let mut builder = SemanticTokensBuilder::new();
+        builder.push(Range::new(Position::new(1, 4), Position::new(1, 7)), 1, 0);
+        builder.push(Range::new(Position::new(1, 8), Position::new(1, 11)), 7, 1);
+        builder.push(Range::new(Position::new(3, 2), Position::new(3, 3)), 9, 0);
+        let tokens = builder.build();
+
+        assert_eq!(
+            tokens.data,
+            vec![from((1, 4, 3, 1, 0)), from((0, 4, 3, 7, 1)), from((2, 2, 1, 9, 0))]
+        );
+        let json = serde_json::to_value(&tokens).unwrap();
+        assert_eq!(json["data"], serde_json::json!([1, 4, 3, 1, 0, 0, 4, 3, 7, 1, 2, 2, 1, 9, 0]));
+    }
+
+    #[test]
+    fn test_diff_insert_at_end() {
+        let before = [from((1, 2, 3, 4, 5)), from((6, 7, 8, 9, 10))];
+        let after = [from((1, 2, 3, 4, 5)), from((6, 7, 8, 9, 10)), from((11, 12, 13, 14, 15))];
+
+        let edits = diff_tokens(&before, &after);
+        assert_eq!(
+            edits[0],
+            SemanticTokensEdit {
+                start: 10,
+                delete_count: 0,
+                data: vec![from((11, 12, 13, 14, 15))]
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_insert_at_beginning() {
+        let before = [from((1, 2, 3, 4, 5)), from((6, 7, 8, 9, 10))];
+        let after = [from((11, 12, 13, 14, 15)), from((1, 2, 3, 4, 5)), from((6, 7, 8, 9, 10))];
+
+        let edits = diff_tokens(&before, &after);
+        assert_eq!(
+            edits[0],
+            SemanticTokensEdit {
+                start: 0,
+                delete_count: 0,
+                data: vec![from((11, 12, 13, 14, 15))]
+            }
+        );
+    }
+
+    #[test]
+    fn test_diff_remove_from_middle() {
+        let before = [from((1, 2, 3, 4, 5)), from((10, 20, 30, 40, 50)), from((6, 7, 8, 9, 10))];
+        let after = [from((1, 2, 3, 4, 5)), from((6, 7, 8, 9, 10))];
+
+        let edits = diff_tokens(&before, &after);
+        assert_eq!(edits[0], SemanticTokensEdit { start: 5, delete_count: 5, data: vec![] });
+    }
+
+    #[test]
+    fn test_diff_no_change() {
+        let tokens = [from((1, 2, 3, 4, 5)), from((6, 7, 8, 9, 10))];
+        assert!(diff_tokens(&tokens, &tokens).is_empty());
+    }
+}
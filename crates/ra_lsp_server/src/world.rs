@@ -12,18 +12,22 @@ use crossbeam_channel::{unbounded, Receiver};
 use lsp_server::ErrorCode;
 use lsp_types::Url;
 use parking_lot::RwLock;
+use ra_cargo_watch::{CheckOptions, CheckWatcher, CheckWatcherSharedState};
 use ra_ide::{
-    Analysis, AnalysisChange, AnalysisHost, CrateGraph, FeatureFlags, FileId, LibraryData,
-    SourceRootId,
+    Analysis, AnalysisChange, AnalysisHost, CompletionTemplate, CrateGraph, FeatureFlags, FileId,
+    LibraryData, SourceRootId,
 };
-use ra_project_model::{get_rustc_cfg_options, ProjectWorkspace};
+use ra_project_model::{get_rustc_cfg_options, ProcMacroClient, ProjectWorkspace};
+use ra_text_edit::AtomTextEdit;
 use ra_vfs::{LineEndings, RootEntry, Vfs, VfsChange, VfsFile, VfsRoot, VfsTask, Watch};
 use ra_vfs_glob::{Glob, RustPackageFilterBuilder};
 use relative_path::RelativePathBuf;
+use rustc_hash::FxHashMap;
 use std::path::{Component, Prefix};
 
 use crate::{
     main_loop::pending_requests::{CompletedRequest, LatestRequests},
+    semantic_tokens::SemanticTokens,
     LspError, Result,
 };
 use std::str::FromStr;
@@ -34,6 +38,9 @@ pub struct Options {
     pub supports_location_link: bool,
     pub line_folding_only: bool,
     pub max_inlay_hint_length: Option<usize>,
+    pub proc_macro_srv: Option<PathBuf>,
+    pub cargo_watch: CheckOptions,
+    pub completion_templates: Vec<CompletionTemplate>,
 }
 
 /// `WorldState` is the primary mutable state of the language server
@@ -52,6 +59,16 @@ pub struct WorldState {
     pub vfs: Arc<RwLock<Vfs>>,
     pub task_receiver: Receiver<VfsTask>,
     pub latest_requests: Arc<RwLock<LatestRequests>>,
+    /// The last semantic tokens sent for each document, used to compute
+    /// deltas.
+    pub semantic_tokens_cache: Arc<RwLock<FxHashMap<Url, SemanticTokens>>>,
+    /// Edits applied to overlays since the last `process_changes`, used to
+    /// reparse the changed files incrementally.
+    pub file_edits: FxHashMap<FileId, Vec<AtomTextEdit>>,
+    /// Keeps the proc-macro server alive for as long as the crate graph
+    /// refers to its expanders.
+    pub proc_macro_client: ProcMacroClient,
+    pub check_watcher: CheckWatcher,
 }
 
 /// An immutable snapshot of the world's state at a point in time.
@@ -61,6 +78,8 @@ pub struct WorldSnapshot {
     pub analysis: Analysis,
     pub vfs: Arc<RwLock<Vfs>>,
     pub latest_requests: Arc<RwLock<LatestRequests>>,
+    pub check_watcher: Arc<RwLock<CheckWatcherSharedState>>,
+    pub semantic_tokens_cache: Arc<RwLock<FxHashMap<Url, SemanticTokens>>>,
 }
 
 impl WorldState {
@@ -97,11 +116,13 @@ impl WorldState {
         let task_sender = Box::new(move |t| task_sender.send(t).unwrap());
         let (mut vfs, vfs_roots) = Vfs::new(roots, task_sender, watch);
         let roots_to_scan = vfs_roots.len();
+        let mut source_roots = FxHashMap::default();
         for r in vfs_roots {
             let vfs_root_path = vfs.root2path(r);
             let is_local = folder_roots.iter().any(|it| vfs_root_path.starts_with(it));
             change.add_root(SourceRootId(r.0), is_local);
             change.set_debug_root_path(SourceRootId(r.0), vfs_root_path.display().to_string());
+            source_roots.insert(vfs_root_path, SourceRootId(r.0));
         }
 
         // FIXME: Read default cfgs from config
@@ -112,6 +133,14 @@ impl WorldState {
             opts
         };
 
+        let proc_macro_client = match &options.proc_macro_srv {
+            None => ProcMacroClient::dummy(),
+            Some(srv) => ProcMacroClient::extern_process(srv).unwrap_or_else(|err| {
+                log::error!("failed to start proc-macro server {}: {}", srv.display(), err);
+                ProcMacroClient::dummy()
+            }),
+        };
+
         // Create crate graph from all the workspaces
         let mut crate_graph = CrateGraph::default();
         let mut load = |path: &std::path::Path| {
@@ -119,7 +148,12 @@ impl WorldState {
             vfs_file.map(|f| FileId(f.0))
         };
         for ws in workspaces.iter() {
-            let (graph, crate_names) = ws.to_crate_graph(&default_cfg_options, &mut load);
+            let (graph, crate_names) = ws.to_crate_graph(
+                &default_cfg_options,
+                &proc_macro_client,
+                &source_roots,
+                &mut load,
+            );
             let shift = crate_graph.extend(graph);
             for (crate_id, name) in crate_names {
                 change.set_debug_crate_name(crate_id.shift(shift), name)
@@ -127,6 +161,26 @@ impl WorldState {
         }
         change.set_crate_graph(crate_graph);
 
+        // FIXME: Figure out the multi-workspace situation
+        let check_watcher = if !options.cargo_watch.enable {
+            CheckWatcher::dummy()
+        } else {
+            workspaces
+                .iter()
+                .find_map(|w| match w {
+                    ProjectWorkspace::Cargo { cargo, .. } => Some(cargo),
+                    ProjectWorkspace::Json { .. } => None,
+                })
+                .map(|cargo| {
+                    let cargo_project_root = cargo.workspace_root().to_path_buf();
+                    CheckWatcher::new(&options.cargo_watch, cargo_project_root)
+                })
+                .unwrap_or_else(|| {
+                    log::warn!("cargo check watching is only supported for cargo workspaces");
+                    CheckWatcher::dummy()
+                })
+        };
+
         let mut analysis_host = AnalysisHost::new(lru_capacity, feature_flags);
         analysis_host.apply_change(change);
         WorldState {
@@ -138,6 +192,10 @@ impl WorldState {
             vfs: Arc::new(RwLock::new(vfs)),
             task_receiver,
             latest_requests: Default::default(),
+            semantic_tokens_cache: Default::default(),
+            file_edits: Default::default(),
+            proc_macro_client,
+            check_watcher,
         }
     }
 
@@ -147,6 +205,7 @@ impl WorldState {
         &mut self,
     ) -> Option<Vec<(SourceRootId, Vec<(FileId, RelativePathBuf, Arc<String>)>)>> {
         let changes = self.vfs.write().commit_changes();
+        let mut file_edits = std::mem::take(&mut self.file_edits);
         if changes.is_empty() {
             return None;
         }
@@ -177,7 +236,11 @@ impl WorldState {
                     change.remove_file(SourceRootId(root.0), FileId(file.0), path)
                 }
                 VfsChange::ChangeFile { file, text } => {
-                    change.change_file(FileId(file.0), text);
+                    let file_id = FileId(file.0);
+                    match file_edits.remove(&file_id) {
+                        Some(edits) => change.edit_file(file_id, text, edits),
+                        None => change.change_file(file_id, text),
+                    }
                 }
             }
         }
@@ -199,6 +262,8 @@ impl WorldState {
             analysis: self.analysis_host.analysis(),
             vfs: Arc::clone(&self.vfs),
             latest_requests: Arc::clone(&self.latest_requests),
+            check_watcher: Arc::clone(&self.check_watcher.shared),
+            semantic_tokens_cache: Arc::clone(&self.semantic_tokens_cache),
         }
     }
 
@@ -9,8 +9,12 @@ mod syntax_bridge;
 mod tt_iter;
 mod subtree_source;
 
+use std::fmt;
+
 pub use tt::{Delimiter, Punct};
 
+use ra_syntax::SmolStr;
+
 use crate::{
     parser::{parse_pattern, Op},
     tt_iter::TtIter,
@@ -21,17 +25,117 @@ pub enum ParseError {
     Expected(String),
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExpandError {
-    NoMatchingRule,
+    /// The input didn't match any of the rules. Carries the reason for the
+    /// rule that came closest to matching, if any.
+    NoMatchingRule(Option<MatchError>),
     UnexpectedToken,
     BindingError(String),
     ConversionError,
     InvalidRepeat,
+    ProcMacroError(tt::ExpansionError),
+    Other(String),
+}
+
+/// Describes why the input of a macro call doesn't match a rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchError {
+    /// Index of the rule in the macro definition.
+    pub rule: usize,
+    /// Whether no rule matched the call, in which case `rule` is only the
+    /// closest one and the bindings are whatever it managed to match.
+    pub failed: bool,
+    /// The input token that couldn't be matched and its text, or `None` if
+    /// the input ended before the rule did.
+    pub token: Option<(tt::TokenId, SmolStr)>,
+    /// What the rule expected instead.
+    pub expected: String,
+}
+
+/// Describes which rule of a `macro_rules!` macro was used for an expansion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchInfo {
+    /// Index of the rule in the macro definition.
+    pub rule: usize,
+    /// The metavariables of the rule and what they were bound to, sorted by
+    /// name. Repetitions are shown as `[a, b]`.
+    pub bindings: Vec<(SmolStr, String)>,
+}
+
+impl fmt::Display for ExpandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExpandError::NoMatchingRule(Some(it)) => fmt::Display::fmt(it, f),
+            ExpandError::NoMatchingRule(None) => f.write_str("no rules matched the macro call"),
+            ExpandError::UnexpectedToken => f.write_str("unexpected token"),
+            ExpandError::BindingError(it) => f.write_str(it),
+            ExpandError::ConversionError => f.write_str("could not convert tokens"),
+            ExpandError::InvalidRepeat => f.write_str("invalid repetition in macro definition"),
+            ExpandError::ProcMacroError(it) => write!(f, "proc macro failed: {:?}", it),
+            ExpandError::Other(it) => f.write_str(it),
+        }
+    }
+}
+
+impl fmt::Display for MatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.token {
+            Some((_, text)) => write!(f, "no rules expected the token `{}`", text)?,
+            None => f.write_str("unexpected end of macro invocation")?,
+        }
+        write!(f, " (rule #{} expected {})", self.rule + 1, self.expected)
+    }
+}
+
+/// The result of a macro expansion. If the expansion failed partway through,
+/// `value` holds a best-effort result and `err` says what went wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpandResult<T> {
+    pub value: T,
+    pub err: Option<ExpandError>,
+}
+
+impl<T> ExpandResult<T> {
+    pub fn ok(value: T) -> ExpandResult<T> {
+        ExpandResult { value, err: None }
+    }
+
+    pub fn only_err(err: ExpandError) -> ExpandResult<T>
+    where
+        T: Default,
+    {
+        ExpandResult { value: T::default(), err: Some(err) }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> ExpandResult<U> {
+        ExpandResult { value: f(self.value), err: self.err }
+    }
+
+    /// Discards the best-effort value if there was an error.
+    pub fn result(self) -> Result<T, ExpandError> {
+        match self.err {
+            Some(err) => Err(err),
+            None => Ok(self.value),
+        }
+    }
+}
+
+impl<T: Default> From<Result<T, ExpandError>> for ExpandResult<T> {
+    fn from(result: Result<T, ExpandError>) -> ExpandResult<T> {
+        result.map_or_else(ExpandResult::only_err, ExpandResult::ok)
+    }
+}
+
+impl From<tt::ExpansionError> for ExpandError {
+    fn from(it: tt::ExpansionError) -> Self {
+        ExpandError::ProcMacroError(it)
+    }
 }
 
 pub use crate::syntax_bridge::{
     ast_to_token_tree, syntax_node_to_token_tree, token_tree_to_syntax_node, TokenMap,
+    TokenTextRange,
 };
 
 /// This struct contains AST for a single `macro_rules` definition. What might
@@ -149,11 +253,76 @@ impl MacroRules {
         Ok(MacroRules { rules, shift: Shift::new(tt) })
     }
 
-    pub fn expand(&self, tt: &tt::Subtree) -> Result<tt::Subtree, ExpandError> {
+    /// Parses the body of a `macro` (macros 2.0) definition. It is either a
+    /// list of rules separated by `;` or `,`, as in
+    /// `macro m { ($e:expr) => { .. } }`, or a single rule, as in
+    /// `macro m($e:expr) { .. }`.
+    pub fn parse_macro2(tt: &tt::Subtree) -> Result<MacroRules, ParseError> {
+        let mut src = TtIter::new(tt);
+        let mut rules = Vec::new();
+        if tt.delimiter.map(|it| it.kind) == Some(tt::DelimiterKind::Brace) {
+            while src.len() > 0 {
+                let rule = Rule::parse(&mut src)?;
+                rules.push(rule);
+                if let Err(()) = src.expect_any_char(&[';', ',']) {
+                    if src.len() > 0 {
+                        return Err(ParseError::Expected("expected `;` or `,`".to_string()));
+                    }
+                    break;
+                }
+            }
+        } else {
+            let mut lhs = src
+                .expect_subtree()
+                .map_err(|()| ParseError::Expected("expected subtree".to_string()))?
+                .clone();
+            lhs.delimiter = None;
+            let mut rhs = src
+                .expect_subtree()
+                .map_err(|()| ParseError::Expected("expected subtree".to_string()))?
+                .clone();
+            rhs.delimiter = None;
+            rules.push(Rule { lhs, rhs });
+        }
+
+        for rule in rules.iter() {
+            validate(&rule.lhs)?;
+        }
+
+        Ok(MacroRules { rules, shift: Shift::new(tt) })
+    }
+
+    /// Expands the macro call. If no rule matches, the rule that got furthest
+    /// into the input is still expanded, so that a partially typed call
+    /// produces something for the IDE to work with.
+    pub fn expand(&self, tt: &tt::Subtree) -> ExpandResult<tt::Subtree> {
+        self.expand_shifted(tt, mbe_expander::expand)
+    }
+
+    /// Like `expand`, but also tells which rule was used and what its
+    /// metavariables were bound to, which helps with debugging macros.
+    pub fn expand_with_match_info(
+        &self,
+        tt: &tt::Subtree,
+    ) -> ExpandResult<(tt::Subtree, Option<MatchInfo>)> {
+        self.expand_shifted(tt, mbe_expander::expand_with_match_info)
+    }
+
+    fn expand_shifted<T>(
+        &self,
+        tt: &tt::Subtree,
+        expand: impl FnOnce(&MacroRules, &tt::Subtree) -> ExpandResult<T>,
+    ) -> ExpandResult<T> {
         // apply shift
         let mut tt = tt.clone();
         self.shift.shift_all(&mut tt);
-        mbe_expander::expand(self, &tt)
+        let mut res = expand(self, &tt);
+        if let Some(ExpandError::NoMatchingRule(Some(err))) = &mut res.err {
+            if let Some((id, _)) = &mut err.token {
+                *id = self.shift.unshift(*id).unwrap_or(*id);
+            }
+        }
+        res
     }
 
     pub fn map_id_down(&self, id: tt::TokenId) -> tt::TokenId {
@@ -5,22 +5,80 @@
 mod matcher;
 mod transcriber;
 
+use std::fmt;
+
 use ra_syntax::SmolStr;
 use rustc_hash::FxHashMap;
 
-use crate::ExpandError;
+use crate::{ExpandError, ExpandResult, MatchError, MatchInfo};
+
+pub(crate) fn expand(rules: &crate::MacroRules, input: &tt::Subtree) -> ExpandResult<tt::Subtree> {
+    expand_rules(rules, input).map(|(value, _)| value)
+}
 
-pub(crate) fn expand(
+pub(crate) fn expand_with_match_info(
     rules: &crate::MacroRules,
     input: &tt::Subtree,
-) -> Result<tt::Subtree, ExpandError> {
-    rules.rules.iter().find_map(|it| expand_rule(it, input).ok()).ok_or(ExpandError::NoMatchingRule)
+) -> ExpandResult<(tt::Subtree, Option<MatchInfo>)> {
+    let ExpandResult { value: (value, rule_match), err } = expand_rules(rules, input);
+    let info = rule_match.map(|(rule, bindings)| {
+        let mut bindings: Vec<_> = bindings
+            .inner
+            .iter()
+            .map(|(name, binding)| (name.clone(), binding.to_string()))
+            .collect();
+        bindings.sort();
+        MatchInfo { rule, failed: err.is_some(), bindings }
+    });
+    ExpandResult { value: (value, info), err }
 }
 
-fn expand_rule(rule: &crate::Rule, input: &tt::Subtree) -> Result<tt::Subtree, ExpandError> {
-    let bindings = matcher::match_(&rule.lhs, input)?;
-    let res = transcriber::transcribe(&rule.rhs, &bindings)?;
-    Ok(res)
+/// Expands the input with the first rule that matches it, or the closest one
+/// if none does. Also returns the index of that rule and its bindings.
+fn expand_rules(
+    rules: &crate::MacroRules,
+    input: &tt::Subtree,
+) -> ExpandResult<(tt::Subtree, Option<(usize, Bindings)>)> {
+    // The rule that got furthest into the input before failing, along with the
+    // position of the failure and whatever it managed to bind.
+    let mut closest: Option<(usize, MatchError, Bindings, &crate::Rule)> = None;
+    let mut tokens = None;
+    for (idx, rule) in rules.rules.iter().enumerate() {
+        let matcher::Match { bindings, err } = match matcher::match_(&rule.lhs, input) {
+            Ok(it) => it,
+            // The rule itself is broken, try the next one
+            Err(_) => continue,
+        };
+        let mut err = match err {
+            Some(it) => it,
+            None => match transcriber::transcribe(&rule.rhs, &bindings) {
+                Ok(it) => return ExpandResult::ok((it, Some((idx, bindings)))),
+                Err(_) => continue,
+            },
+        };
+        err.rule = idx;
+        let tokens = tokens.get_or_insert_with(|| matcher::tokens(input));
+        let position = match &err.token {
+            Some(token) => tokens.iter().position(|it| it == token).unwrap_or(0),
+            None => tokens.len(),
+        };
+        if closest.as_ref().map_or(true, |(it, ..)| position > *it) {
+            closest = Some((position, err, bindings, rule));
+        }
+    }
+    match closest {
+        Some((_, err, bindings, rule)) => {
+            // Expand the closest rule with what it matched, so that a macro
+            // call which is still being typed has an expansion.
+            let value = transcriber::transcribe(&rule.rhs, &bindings).unwrap_or_default();
+            let rule_match = Some((err.rule, bindings));
+            ExpandResult {
+                value: (value, rule_match),
+                err: Some(ExpandError::NoMatchingRule(Some(err))),
+            }
+        }
+        None => ExpandResult::only_err(ExpandError::NoMatchingRule(None)),
+    }
 }
 
 /// The actual algorithm for expansion is not too hard, but is pretty tricky.
@@ -80,6 +138,27 @@ enum Binding {
     Empty,
 }
 
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Fragment(Fragment::Tokens(it)) | Binding::Fragment(Fragment::Ast(it)) => {
+                fmt::Display::fmt(it, f)
+            }
+            Binding::Nested(bindings) => {
+                f.write_str("[")?;
+                for (idx, binding) in bindings.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    fmt::Display::fmt(binding, f)?;
+                }
+                f.write_str("]")
+            }
+            Binding::Empty => f.write_str("[]"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum Fragment {
     /// token fragments are just copy-pasted into the output
@@ -146,6 +225,8 @@ mod tests {
         let (invocation_tt, _) =
             ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
 
-        expand_rule(&rules.rules[0], &invocation_tt)
+        let rule = &rules.rules[0];
+        let bindings = matcher::match_(&rule.lhs, &invocation_tt)?.bindings;
+        transcriber::transcribe(&rule.rhs, &bindings)
     }
 }
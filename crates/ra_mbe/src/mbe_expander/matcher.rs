@@ -5,7 +5,7 @@ use crate::{
     parser::{parse_pattern, Op, RepeatKind, Separator},
     subtree_source::SubtreeTokenSource,
     tt_iter::TtIter,
-    ExpandError,
+    ExpandError, ExpandResult, MatchError,
 };
 
 use ra_parser::{FragmentKind::*, TreeSink};
@@ -49,36 +49,129 @@ impl Bindings {
     }
 }
 
-macro_rules! err {
-    () => {
-        ExpandError::BindingError(format!(""))
-    };
-    ($($tt:tt)*) => {
-        ExpandError::BindingError(format!($($tt)*))
-    };
-}
-
-macro_rules! bail {
-    ($($tt:tt)*) => {
-        return Err(err!($($tt)*))
-    };
+/// The outcome of matching a rule against the input. If the input doesn't
+/// match, `bindings` still holds what was bound up to the mismatch, with the
+/// remaining variables bound to nothing.
+#[derive(Debug, Default)]
+pub(super) struct Match {
+    pub(super) bindings: Bindings,
+    pub(super) err: Option<MatchError>,
 }
 
-pub(super) fn match_(pattern: &tt::Subtree, src: &tt::Subtree) -> Result<Bindings, ExpandError> {
+/// Matches `src` against `pattern`. An `Err` means that the rule itself is
+/// broken, a mismatch is reported in `Match::err`.
+pub(super) fn match_(pattern: &tt::Subtree, src: &tt::Subtree) -> Result<Match, ExpandError> {
     assert!(pattern.delimiter == None);
 
-    let mut res = Bindings::default();
+    let mut res = Match::default();
     let mut src = TtIter::new(src);
 
-    match_subtree(&mut res, pattern, &mut src)?;
-
-    if src.len() > 0 {
-        bail!("leftover tokens");
+    let matched = match_subtree(&mut res.bindings, pattern, &mut src).and_then(|()| {
+        if src.len() > 0 {
+            return Err(mismatch(next_token(&src), "the end of the input"));
+        }
+        Ok(())
+    });
+    match matched {
+        Ok(()) => (),
+        Err(ExpandError::NoMatchingRule(Some(err))) => {
+            res.err = Some(err);
+            bind_missing_vars(&mut res.bindings, pattern)?;
+        }
+        Err(err) => return Err(err),
     }
 
     Ok(res)
 }
 
+/// Binds the variables that the match didn't get to as empty, so that a rule
+/// which only partially matched can still be transcribed.
+fn bind_missing_vars(bindings: &mut Bindings, pattern: &tt::Subtree) -> Result<(), ExpandError> {
+    for op in parse_pattern(pattern) {
+        match op? {
+            Op::Var { name, .. } => {
+                if !bindings.inner.contains_key(name) {
+                    bindings.push_optional(name);
+                }
+            }
+            Op::TokenTree(tt::TokenTree::Leaf(_)) => (),
+            Op::TokenTree(tt::TokenTree::Subtree(subtree)) => bind_missing_vars(bindings, subtree)?,
+            Op::Repeat { subtree, .. } => {
+                let mut vars = Vec::new();
+                collect_vars(&mut vars, subtree)?;
+                for var in vars {
+                    if !bindings.inner.contains_key(&var) {
+                        bindings.push_empty(&var);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Creates the error for an input token that doesn't match the rule. `token`
+/// is `None` if the input ended too early.
+fn mismatch(token: Option<(tt::TokenId, SmolStr)>, expected: impl Into<String>) -> ExpandError {
+    // The index of the rule is filled in by the caller
+    ExpandError::NoMatchingRule(Some(MatchError { rule: 0, token, expected: expected.into() }))
+}
+
+fn next_token(src: &TtIter) -> Option<(tt::TokenId, SmolStr)> {
+    src.clone().next().and_then(first_token)
+}
+
+fn first_token(tt: &tt::TokenTree) -> Option<(tt::TokenId, SmolStr)> {
+    match tt {
+        tt::TokenTree::Leaf(leaf) => {
+            let id = match leaf {
+                tt::Leaf::Literal(it) => it.id,
+                tt::Leaf::Punct(it) => it.id,
+                tt::Leaf::Ident(it) => it.id,
+            };
+            Some((id, leaf.to_string().into()))
+        }
+        tt::TokenTree::Subtree(subtree) => match subtree.delimiter {
+            Some(delimiter) => Some((delimiter.id, delimiter_text(delimiter.kind, false).into())),
+            None => subtree.token_trees.first().and_then(first_token),
+        },
+    }
+}
+
+/// Lists the tokens of `subtree` in order, used to find out which rule got
+/// furthest into the input.
+pub(super) fn tokens(subtree: &tt::Subtree) -> Vec<(tt::TokenId, SmolStr)> {
+    let mut res = Vec::new();
+    go(&mut res, subtree);
+    return res;
+
+    fn go(res: &mut Vec<(tt::TokenId, SmolStr)>, subtree: &tt::Subtree) {
+        if let Some(delimiter) = subtree.delimiter {
+            res.push((delimiter.id, delimiter_text(delimiter.kind, false).into()));
+        }
+        for tt in subtree.token_trees.iter() {
+            match tt {
+                tt::TokenTree::Leaf(_) => res.extend(first_token(tt)),
+                tt::TokenTree::Subtree(it) => go(res, it),
+            }
+        }
+        if let Some(delimiter) = subtree.delimiter {
+            res.push((delimiter.id, delimiter_text(delimiter.kind, true).into()));
+        }
+    }
+}
+
+fn delimiter_text(kind: tt::DelimiterKind, close: bool) -> &'static str {
+    match (kind, close) {
+        (tt::DelimiterKind::Parenthesis, false) => "(",
+        (tt::DelimiterKind::Parenthesis, true) => ")",
+        (tt::DelimiterKind::Brace, false) => "{",
+        (tt::DelimiterKind::Brace, true) => "}",
+        (tt::DelimiterKind::Bracket, false) => "[",
+        (tt::DelimiterKind::Bracket, true) => "]",
+    }
+}
+
 fn match_subtree(
     bindings: &mut Bindings,
     pattern: &tt::Subtree,
@@ -87,7 +180,10 @@ fn match_subtree(
     for op in parse_pattern(pattern) {
         match op? {
             Op::TokenTree(tt::TokenTree::Leaf(lhs)) => {
-                let rhs = src.expect_leaf().map_err(|()| err!("expected leaf: `{}`", lhs))?;
+                let token = next_token(src);
+                let rhs = src
+                    .expect_leaf()
+                    .map_err(|()| mismatch(token.clone(), format!("`{}`", lhs)))?;
                 match (lhs, rhs) {
                     (
                         tt::Leaf::Punct(tt::Punct { char: lhs, .. }),
@@ -101,28 +197,52 @@ fn match_subtree(
                         tt::Leaf::Literal(tt::Literal { text: lhs, .. }),
                         tt::Leaf::Literal(tt::Literal { text: rhs, .. }),
                     ) if lhs == rhs => (),
-                    _ => Err(ExpandError::UnexpectedToken)?,
+                    _ => return Err(mismatch(token, format!("`{}`", lhs))),
                 }
             }
             Op::TokenTree(tt::TokenTree::Subtree(lhs)) => {
-                let rhs = src.expect_subtree().map_err(|()| err!("expected subtree"))?;
+                let token = next_token(src);
+                let (open, close) = match lhs.delimiter_kind() {
+                    Some(kind) => (delimiter_text(kind, false), delimiter_text(kind, true)),
+                    None => ("", ""),
+                };
+                let rhs = src
+                    .expect_subtree()
+                    .map_err(|()| mismatch(token.clone(), format!("`{}`", open)))?;
                 if lhs.delimiter_kind() != rhs.delimiter_kind() {
-                    bail!("mismatched delimiter")
+                    return Err(mismatch(token, format!("`{}`", open)));
                 }
                 let mut src = TtIter::new(rhs);
-                match_subtree(bindings, lhs, &mut src)?;
+                match_subtree(bindings, lhs, &mut src).map_err(|err| match err {
+                    // The input of the subtree ended, point at its closing delimiter
+                    ExpandError::NoMatchingRule(Some(MatchError {
+                        rule,
+                        token: None,
+                        expected,
+                    })) => {
+                        let token = rhs.delimiter.map(|it| (it.id, close.into()));
+                        ExpandError::NoMatchingRule(Some(MatchError { rule, token, expected }))
+                    }
+                    err => err,
+                })?;
                 if src.len() > 0 {
-                    bail!("leftover tokens");
+                    return Err(mismatch(next_token(&src), format!("`{}`", close)));
                 }
             }
             Op::Var { name, kind } => {
                 let kind = kind.as_ref().ok_or(ExpandError::UnexpectedToken)?;
-                match match_meta_var(kind.as_str(), src)? {
+                let ExpandResult { value: fragment, err } = match_meta_var(kind.as_str(), src);
+                // A fragment which only partially parsed is still bound, this
+                // keeps the expansion of calls that are being typed useful.
+                match fragment {
                     Some(fragment) => {
                         bindings.inner.insert(name.clone(), Binding::Fragment(fragment));
                     }
                     None => bindings.push_optional(name),
                 }
+                if let Some(err) = err {
+                    return Err(err);
+                }
             }
             Op::Repeat { subtree, kind, separator } => {
                 match_repeat(bindings, subtree, kind, separator, src)?
@@ -164,10 +284,12 @@ impl<'a> TtIter<'a> {
         Ok(ident)
     }
 
+    /// Parses a fragment of the given kind from the input. If parsing fails,
+    /// the tokens the parser got through are still consumed and returned.
     pub(crate) fn expect_fragment(
         &mut self,
         fragment_kind: ra_parser::FragmentKind,
-    ) -> Result<tt::TokenTree, ()> {
+    ) -> ExpandResult<Option<tt::TokenTree>> {
         pub(crate) struct OffsetTokenSink<'a> {
             pub(crate) cursor: Cursor<'a>,
             pub(crate) error: bool,
@@ -192,38 +314,47 @@ impl<'a> TtIter<'a> {
 
         ra_parser::parse_fragment(&mut src, &mut sink, fragment_kind);
 
+        let mut err = None;
         if !sink.cursor.is_root() || sink.error {
-            return Err(());
+            err = Some(ExpandError::UnexpectedToken);
         }
 
         let mut curr = buffer.begin();
         let mut res = vec![];
 
-        while curr != sink.cursor {
-            if let Some(token) = curr.token_tree() {
-                res.push(token);
+        // If the parser stopped inside of a subtree, there is no sensible
+        // prefix of the input to return.
+        if sink.cursor.is_root() {
+            while curr != sink.cursor {
+                if let Some(token) = curr.token_tree() {
+                    res.push(token);
+                }
+                curr = curr.bump();
             }
-            curr = curr.bump();
         }
         self.inner = self.inner.as_slice()[res.len()..].iter();
-        match res.len() {
-            0 => Err(()),
-            1 => Ok(res[0].clone()),
-            _ => Ok(tt::TokenTree::Subtree(tt::Subtree {
+        let value = match res.len() {
+            0 => {
+                err = err.or(Some(ExpandError::UnexpectedToken));
+                None
+            }
+            1 => Some(res[0].clone()),
+            _ => Some(tt::TokenTree::Subtree(tt::Subtree {
                 delimiter: None,
                 token_trees: res.into_iter().cloned().collect(),
             })),
-        }
+        };
+        ExpandResult { value, err }
     }
 
     pub(crate) fn eat_vis(&mut self) -> Option<tt::TokenTree> {
         let mut fork = self.clone();
         match fork.expect_fragment(Visibility) {
-            Ok(tt) => {
+            ExpandResult { value: Some(tt), err: None } => {
                 *self = fork;
                 Some(tt)
             }
-            Err(()) => None,
+            _ => None,
         }
     }
 }
@@ -239,6 +370,7 @@ pub(super) fn match_repeat(
     // This should be replaced by a propper macro-by-example implementation
     let mut limit = 65536;
     let mut counter = 0;
+    let mut first_err = None;
 
     for i in 0.. {
         let mut fork = src.clone();
@@ -267,12 +399,19 @@ pub(super) fn match_repeat(
                     }
                 }
             }
-            Err(_) => break,
+            Err(err) => {
+                if counter == 0 {
+                    first_err = Some(err);
+                }
+                break;
+            }
         }
     }
 
     match (kind, counter) {
-        (RepeatKind::OneOrMore, 0) => return Err(ExpandError::UnexpectedToken),
+        (RepeatKind::OneOrMore, 0) => {
+            return Err(first_err.unwrap_or_else(|| mismatch(next_token(src), "a repetition")))
+        }
         (_, 0) => {
             // Collect all empty variables in subtrees
             let mut vars = Vec::new();
@@ -286,7 +425,7 @@ pub(super) fn match_repeat(
     Ok(())
 }
 
-fn match_meta_var(kind: &str, input: &mut TtIter) -> Result<Option<Fragment>, ExpandError> {
+fn match_meta_var(kind: &str, input: &mut TtIter) -> ExpandResult<Option<Fragment>> {
     let fragment = match kind {
         "path" => Path,
         "expr" => Expr,
@@ -296,34 +435,53 @@ fn match_meta_var(kind: &str, input: &mut TtIter) -> Result<Option<Fragment>, Ex
         "block" => Block,
         "meta" => MetaItem,
         "item" => Item,
-        _ => {
-            let tt = match kind {
-                "ident" => {
-                    let ident = input.expect_ident().map_err(|()| err!("expected ident"))?.clone();
-                    tt::Leaf::from(ident).into()
-                }
-                "tt" => input.next().ok_or_else(|| err!())?.clone(),
-                "lifetime" => {
-                    let ident = input.expect_lifetime().map_err(|()| err!())?;
-                    tt::Leaf::Ident(ident.clone()).into()
-                }
-                "literal" => {
-                    let literal = input.expect_literal().map_err(|()| err!())?.clone();
-                    tt::Leaf::from(literal).into()
-                }
-                // `vis` is optional
-                "vis" => match input.eat_vis() {
-                    Some(vis) => vis,
-                    None => return Ok(None),
-                },
-                _ => return Err(ExpandError::UnexpectedToken),
-            };
-            return Ok(Some(Fragment::Tokens(tt)));
+        _ => return match_token_var(kind, input).into(),
+    };
+    let expected = match kind {
+        "path" => "a path",
+        "expr" => "an expression",
+        "ty" => "a type",
+        "pat" => "a pattern",
+        "stmt" => "a statement",
+        "block" => "a block",
+        "meta" => "a meta item",
+        _ => "an item",
+    };
+    let ExpandResult { value: tt, err } = input.expect_fragment(fragment);
+    let fragment =
+        tt.map(|tt| if kind == "expr" { Fragment::Ast(tt) } else { Fragment::Tokens(tt) });
+    // Point at the token where the parser gave up
+    let err = err.map(|_| mismatch(next_token(input), expected));
+    ExpandResult { value: fragment, err }
+}
+
+/// Matches the variable kinds that are single token trees rather than
+/// fragments parsed by the parser.
+fn match_token_var(kind: &str, input: &mut TtIter) -> Result<Option<Fragment>, ExpandError> {
+    let token = next_token(input);
+    let err = |expected: &str| mismatch(token.clone(), expected);
+    let tt = match kind {
+        "ident" => {
+            let ident = input.expect_ident().map_err(|()| err("an identifier"))?.clone();
+            tt::Leaf::from(ident).into()
+        }
+        "tt" => input.next().ok_or_else(|| err("a token tree"))?.clone(),
+        "lifetime" => {
+            let ident = input.expect_lifetime().map_err(|()| err("a lifetime"))?;
+            tt::Leaf::Ident(ident.clone()).into()
+        }
+        "literal" => {
+            let literal = input.expect_literal().map_err(|()| err("a literal"))?.clone();
+            tt::Leaf::from(literal).into()
         }
+        // `vis` is optional
+        "vis" => match input.eat_vis() {
+            Some(vis) => vis,
+            None => return Ok(None),
+        },
+        _ => return Err(ExpandError::UnexpectedToken),
     };
-    let tt = input.expect_fragment(fragment).map_err(|()| err!())?;
-    let fragment = if kind == "expr" { Fragment::Ast(tt) } else { Fragment::Tokens(tt) };
-    Ok(Some(fragment))
+    Ok(Some(Fragment::Tokens(tt)))
 }
 
 fn collect_vars(buf: &mut Vec<SmolStr>, pattern: &tt::Subtree) -> Result<(), ExpandError> {
@@ -1368,12 +1368,145 @@ fn expr_interpolation() {
     assert_eq!(expanded.to_string(), "map(x+foo)");
 }
 
+#[test]
+fn test_no_matching_rule_reports_closest_rule() {
+    let rules = parse_macro(
+        r#"
+        macro_rules! foo {
+            ($a:ident) => {};
+            ($a:ident, $b:ident) => {};
+            ([$($a:ident)+]) => {};
+        }
+        "#,
+    );
+    rules.assert_expand_err(
+        "foo!(a, 1);",
+        "no rules expected the token `1` (rule #2 expected an identifier)",
+    );
+    rules.assert_expand_err(
+        "foo!(a,);",
+        "unexpected end of macro invocation (rule #2 expected an identifier)",
+    );
+    rules.assert_expand_err(
+        "foo!(a b);",
+        "no rules expected the token `b` (rule #1 expected the end of the input)",
+    );
+    rules.assert_expand_err(
+        "foo!([]);",
+        "no rules expected the token `]` (rule #3 expected an identifier)",
+    );
+}
+
+#[test]
+fn test_partial_expansion_of_closest_rule() {
+    let rules = parse_macro(
+        r#"
+        macro_rules! foo {
+            ($a:ident, $b:ident) => { ($a, $b) };
+            ($e:expr) => { $e };
+        }
+        "#,
+    );
+
+    let res = rules.expand_partial("foo!(a.);");
+    assert_eq!(res.value.to_string(), "a .");
+    assert_eq!(
+        res.err.unwrap().to_string(),
+        "unexpected end of macro invocation (rule #2 expected an expression)"
+    );
+
+    let res = rules.expand_partial("foo!(a,);");
+    assert_eq!(res.value.to_string(), "(a ,)");
+    assert_eq!(
+        res.err.unwrap().to_string(),
+        "unexpected end of macro invocation (rule #1 expected an identifier)"
+    );
+}
+
+#[test]
+fn test_expand_with_match_info() {
+    let rules = parse_macro(
+        r#"
+        macro_rules! foo {
+            ($a:ident) => { $a };
+            ($a:ident, $($e:expr),*) => { $a($($e),*) };
+        }
+        "#,
+    );
+
+    let invocation = "foo!(f, 1 + 1, 2);";
+    let source_file = ast::SourceFile::parse(invocation).ok().unwrap();
+    let macro_invocation =
+        source_file.syntax().descendants().find_map(ast::MacroCall::cast).unwrap();
+    let (invocation_tt, _) = ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
+
+    let res = rules.rules.expand_with_match_info(&invocation_tt);
+    assert!(res.err.is_none());
+    let info = res.value.1.unwrap();
+    assert_eq!(info.rule, 1);
+    assert!(!info.failed);
+    assert_eq!(
+        info.bindings,
+        vec![("a".into(), "f".to_string()), ("e".into(), "[1 + 1, 2]".to_string())]
+    );
+
+    let invocation = "foo!(f, 1; 2);";
+    let source_file = ast::SourceFile::parse(invocation).ok().unwrap();
+    let macro_invocation =
+        source_file.syntax().descendants().find_map(ast::MacroCall::cast).unwrap();
+    let (invocation_tt, _) = ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
+
+    let res = rules.rules.expand_with_match_info(&invocation_tt);
+    assert!(res.err.is_some());
+    let info = res.value.1.unwrap();
+    assert_eq!(info.rule, 1);
+    assert!(info.failed);
+}
+
+#[test]
+fn test_macro2_single_rule() {
+    parse_macro2(
+        r#"
+macro foo($x:ident) {
+    fn $x() {}
+}
+"#,
+    )
+    .assert_expand_items("foo!(bar);", "fn bar () {}");
+}
+
+#[test]
+fn test_macro2_multiple_rules() {
+    let rules = parse_macro2(
+        r#"
+macro foo {
+    ($x:ident) => {
+        fn $x() {}
+    },
+    ($x:ident, $y:ident) => {
+        struct $x; struct $y;
+    }
+}
+"#,
+    );
+    rules.assert_expand_items("foo!(bar);", "fn bar () {}");
+    rules.assert_expand_items("foo!(Bar, Baz);", "struct Bar ; struct Baz ;");
+}
+
 pub(crate) struct MacroFixture {
     rules: MacroRules,
 }
 
 impl MacroFixture {
     pub(crate) fn expand_tt(&self, invocation: &str) -> tt::Subtree {
+        self.try_expand_tt(invocation).unwrap()
+    }
+
+    fn try_expand_tt(&self, invocation: &str) -> Result<tt::Subtree, ExpandError> {
+        self.expand_partial(invocation).result()
+    }
+
+    fn expand_partial(&self, invocation: &str) -> ExpandResult<tt::Subtree> {
         let source_file = ast::SourceFile::parse(invocation).ok().unwrap();
         let macro_invocation =
             source_file.syntax().descendants().find_map(ast::MacroCall::cast).unwrap();
@@ -1381,7 +1514,12 @@ impl MacroFixture {
         let (invocation_tt, _) =
             ast_to_token_tree(&macro_invocation.token_tree().unwrap()).unwrap();
 
-        self.rules.expand(&invocation_tt).unwrap()
+        self.rules.expand(&invocation_tt)
+    }
+
+    fn assert_expand_err(&self, invocation: &str, expected: &str) {
+        let err = self.try_expand_tt(invocation).unwrap_err();
+        assert_eq!(err.to_string(), expected);
     }
 
     fn expand_items(&self, invocation: &str) -> SyntaxNode {
@@ -1456,6 +1594,16 @@ pub(crate) fn parse_macro(macro_definition: &str) -> MacroFixture {
     MacroFixture { rules }
 }
 
+pub(crate) fn parse_macro2(macro_definition: &str) -> MacroFixture {
+    let source_file = ast::SourceFile::parse(macro_definition).ok().unwrap();
+    let macro_definition =
+        source_file.syntax().descendants().find_map(ast::MacroDef::cast).unwrap();
+
+    let (definition_tt, _) = ast_to_token_tree(&macro_definition.token_tree().unwrap()).unwrap();
+    let rules = MacroRules::parse_macro2(&definition_tt).unwrap();
+    MacroFixture { rules }
+}
+
 fn debug_dump_ignore_spaces(node: &ra_syntax::SyntaxNode) -> String {
     let mut level = 0;
     let mut buf = String::new();
@@ -0,0 +1,91 @@
+//! A stand-in for the real proc-macro server, used to test the client side of
+//! the protocol. It speaks the same protocol over stdio, but instead of
+//! loading dylibs it knows a couple of toy macros:
+//!
+//! * `#[derive(Marker)]` expands to `impl Marker for $name {}`,
+//! * `identity!(..)` expands to its input,
+//! * `#[prepend(..)]` expands to its arguments followed by the item,
+//! * `crash!()` makes the server exit without replying.
+
+use std::io;
+
+use ra_proc_macro::{
+    msg::{ErrorCode, Message, Request, Response, ResponseError, PROTOCOL_VERSION},
+    ExpansionResult, ExpansionTask, ListMacrosResult, ProcMacroKind,
+};
+use ra_tt::{Delimiter, DelimiterKind, Ident, Leaf, Subtree, TokenId, TokenTree};
+
+fn main() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut reader = stdin.lock();
+    let mut writer = stdout.lock();
+    while let Some(req) = Request::read(&mut reader)? {
+        handle(req).write(&mut writer)?;
+    }
+    Ok(())
+}
+
+fn handle(req: Request) -> Response {
+    match req {
+        Request::ApiVersion => Response::ApiVersion(PROTOCOL_VERSION),
+        Request::ListMacro(_) => Response::ListMacro(ListMacrosResult {
+            macros: vec![
+                ("Marker".to_string(), ProcMacroKind::CustomDerive),
+                ("identity".to_string(), ProcMacroKind::FuncLike),
+                ("prepend".to_string(), ProcMacroKind::Attr),
+                ("crash".to_string(), ProcMacroKind::FuncLike),
+            ],
+        }),
+        Request::ExpansionMacro(task) => match expand(task) {
+            Ok(expansion) => Response::ExpansionMacro(ExpansionResult { expansion }),
+            Err(message) => {
+                Response::Error(ResponseError { code: ErrorCode::ExpansionError, message })
+            }
+        },
+    }
+}
+
+fn expand(task: ExpansionTask) -> Result<Subtree, String> {
+    match task.macro_name.as_str() {
+        "identity" => Ok(task.macro_body),
+        "prepend" => {
+            let mut expansion = task.attributes.unwrap_or_default();
+            expansion.token_trees.extend(task.macro_body.token_trees);
+            Ok(expansion)
+        }
+        "crash" => std::process::exit(101),
+        "Marker" => {
+            let mut name = task.macro_body.token_trees.iter().skip_while(|tt| match tt {
+                TokenTree::Leaf(Leaf::Ident(it)) => it.text != "struct",
+                _ => true,
+            });
+            let name = match name.nth(1) {
+                Some(TokenTree::Leaf(Leaf::Ident(it))) => it.text.clone(),
+                _ => return Err("expected a struct".into()),
+            };
+            let body = Subtree {
+                delimiter: Some(Delimiter {
+                    id: TokenId::unspecified(),
+                    kind: DelimiterKind::Brace,
+                }),
+                token_trees: vec![],
+            };
+            Ok(Subtree {
+                delimiter: None,
+                token_trees: vec![
+                    ident("impl"),
+                    ident("Marker"),
+                    ident("for"),
+                    ident(name.as_str()),
+                    TokenTree::Subtree(body),
+                ],
+            })
+        }
+        _ => Err(format!("unknown macro: {}", task.macro_name)),
+    }
+}
+
+fn ident(text: &str) -> TokenTree {
+    TokenTree::Leaf(Leaf::Ident(Ident { text: text.into(), id: TokenId::unspecified() }))
+}
@@ -0,0 +1,146 @@
+//! Client-side proc-macro support.
+//!
+//! Procedural macros are compiled to dylibs which have to be loaded into a
+//! process to run. We don't want to do that inside rust-analyzer itself: a
+//! misbehaving macro could crash or hang the whole server, and the dylib must
+//! be built against the exact same `proc_macro` ABI as the loader. So the
+//! expansion is delegated to a separate proc-macro server process, and this
+//! crate implements the client side of the protocol spoken with it.
+
+mod rpc;
+mod process;
+pub mod msg;
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ra_tt::{SmolStr, Subtree};
+
+use crate::process::{ProcMacroProcessSrv, ProcMacroProcessThread};
+
+pub use rpc::{ExpansionResult, ExpansionTask, ListMacrosResult, ListMacrosTask, ProcMacroKind};
+
+#[derive(Debug, Clone)]
+pub struct ProcMacroProcessExpander {
+    process: Arc<ProcMacroProcessSrv>,
+    dylib_path: PathBuf,
+    name: SmolStr,
+}
+
+impl Eq for ProcMacroProcessExpander {}
+impl PartialEq for ProcMacroProcessExpander {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.dylib_path == other.dylib_path
+            && Arc::ptr_eq(&self.process, &other.process)
+    }
+}
+
+impl ra_tt::TokenExpander for ProcMacroProcessExpander {
+    fn expand(
+        &self,
+        subtree: &Subtree,
+        attr: Option<&Subtree>,
+    ) -> Result<Subtree, ra_tt::ExpansionError> {
+        let task = ExpansionTask {
+            macro_body: subtree.clone(),
+            macro_name: self.name.to_string(),
+            attributes: attr.cloned(),
+            lib: self.dylib_path.clone(),
+        };
+
+        let result: ExpansionResult = self.process.send_task(msg::Request::ExpansionMacro(task))?;
+        Ok(result.expansion)
+    }
+}
+
+#[derive(Debug)]
+enum ProcMacroClientKind {
+    Process {
+        process: Arc<ProcMacroProcessSrv>,
+        #[allow(unused)]
+        thread: ProcMacroProcessThread,
+    },
+    Dummy,
+}
+
+/// Handle to the proc-macro server.
+///
+/// A dummy client knows no macros at all, which is what we use when no server
+/// is configured.
+#[derive(Debug)]
+pub struct ProcMacroClient {
+    kind: ProcMacroClientKind,
+}
+
+impl ProcMacroClient {
+    /// Spawns the server at `process_path` and checks that it speaks our
+    /// version of the protocol.
+    pub fn extern_process(process_path: &Path) -> io::Result<ProcMacroClient> {
+        let (thread, process) = ProcMacroProcessSrv::run(process_path)?;
+        let version = process.version().map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("failed to query proc-macro server version: {:?}", err),
+            )
+        })?;
+        if version != msg::PROTOCOL_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "proc-macro server speaks protocol version {}, expected {}",
+                    version,
+                    msg::PROTOCOL_VERSION
+                ),
+            ));
+        }
+        Ok(ProcMacroClient {
+            kind: ProcMacroClientKind::Process { process: Arc::new(process), thread },
+        })
+    }
+
+    pub fn dummy() -> ProcMacroClient {
+        ProcMacroClient { kind: ProcMacroClientKind::Dummy }
+    }
+
+    /// Lists the macros exported by the proc-macro dylib at `dylib_path`,
+    /// together with expanders which run them in the server.
+    pub fn by_dylib_path(
+        &self,
+        dylib_path: &Path,
+    ) -> Vec<(SmolStr, ProcMacroKind, Arc<dyn ra_tt::TokenExpander>)> {
+        match &self.kind {
+            ProcMacroClientKind::Dummy => vec![],
+            ProcMacroClientKind::Process { process, .. } => {
+                let macros = match process.find_proc_macros(dylib_path) {
+                    Err(err) => {
+                        log::error!(
+                            "failed to list proc macros of {}: {:?}",
+                            dylib_path.display(),
+                            err
+                        );
+                        return vec![];
+                    }
+                    Ok(macros) => macros,
+                };
+
+                macros
+                    .into_iter()
+                    .map(|(name, kind)| {
+                        let name = SmolStr::new(&name);
+                        let expander: Arc<dyn ra_tt::TokenExpander> =
+                            Arc::new(ProcMacroProcessExpander {
+                                process: process.clone(),
+                                name: name.clone(),
+                                dylib_path: dylib_path.into(),
+                            });
+                        (name, kind, expander)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Handles the lifetime of the proc-macro server process and the message
+//! passing with it.
+
+use std::{
+    convert::{TryFrom, TryInto},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    sync::{Arc, Weak},
+};
+
+use crossbeam_channel::{bounded, Receiver, Sender};
+use ra_tt::ExpansionError;
+
+use crate::{
+    msg::{ErrorCode, Message, Request, Response, ResponseError},
+    rpc::{ListMacrosResult, ListMacrosTask, ProcMacroKind},
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct ProcMacroProcessSrv {
+    inner: Option<Weak<Sender<Task>>>,
+}
+
+/// Owns the thread which talks to the server. Dropping it shuts the server
+/// down.
+#[derive(Debug)]
+pub(crate) struct ProcMacroProcessThread {
+    // XXX: drop order is significant
+    #[allow(unused)]
+    sender: Arc<Sender<Task>>,
+    #[allow(unused)]
+    handle: jod_thread::JoinHandle<()>,
+}
+
+struct Task {
+    req: Request,
+    result_tx: Sender<Option<Response>>,
+}
+
+impl ProcMacroProcessSrv {
+    pub fn run(process_path: &Path) -> io::Result<(ProcMacroProcessThread, ProcMacroProcessSrv)> {
+        let process = Process::run(process_path)?;
+
+        let (task_tx, task_rx) = bounded(0);
+        let handle = jod_thread::spawn(move || {
+            client_loop(task_rx, process);
+        });
+
+        let task_tx = Arc::new(task_tx);
+        let srv = ProcMacroProcessSrv { inner: Some(Arc::downgrade(&task_tx)) };
+        let thread = ProcMacroProcessThread { handle, sender: task_tx };
+
+        Ok((thread, srv))
+    }
+
+    pub fn version(&self) -> Result<u32, ExpansionError> {
+        self.send_task(Request::ApiVersion)
+    }
+
+    pub fn find_proc_macros(
+        &self,
+        dylib_path: &Path,
+    ) -> Result<Vec<(String, ProcMacroKind)>, ExpansionError> {
+        let task = ListMacrosTask { lib: dylib_path.to_path_buf() };
+
+        let result: ListMacrosResult = self.send_task(Request::ListMacro(task))?;
+        Ok(result.macros)
+    }
+
+    pub fn send_task<R>(&self, req: Request) -> Result<R, ExpansionError>
+    where
+        R: TryFrom<Response, Error = &'static str>,
+    {
+        let sender = match &self.inner {
+            None => return Err(ExpansionError::Unknown("No sender is found.".to_string())),
+            Some(it) => it,
+        };
+
+        let (result_tx, result_rx) = bounded(0);
+        let sender = match sender.upgrade() {
+            None => return Err(ExpansionError::Unknown("Proc macro process is closed.".into())),
+            Some(it) => it,
+        };
+        sender
+            .send(Task { req, result_tx })
+            .map_err(|_| ExpansionError::Unknown("Proc macro thread is closed.".into()))?;
+        let res = result_rx
+            .recv()
+            .map_err(|_| ExpansionError::Unknown("Proc macro thread is closed.".into()))?;
+
+        match res {
+            Some(Response::Error(err)) => Err(ExpansionError::ExpansionError(err.message)),
+            Some(res) => Ok(res.try_into().map_err(|err| {
+                ExpansionError::Unknown(format!("Fail to get response, reason : {:#?} ", err))
+            })?),
+            None => Err(ExpansionError::Unknown("Empty result".into())),
+        }
+    }
+}
+
+fn client_loop(task_rx: Receiver<Task>, mut process: Process) {
+    let (mut stdin, mut stdout) = match process.stdio() {
+        None => return,
+        Some(it) => it,
+    };
+
+    for task in task_rx {
+        let Task { req, result_tx } = task;
+
+        match send_request(&mut stdin, &mut stdout, req) {
+            Ok(res) => {
+                if let Err(err) = send_result(&result_tx, Some(res)) {
+                    log::error!("failed to deliver proc-macro response: {:?}", err);
+                }
+            }
+            Err(err) => {
+                log::error!("proc-macro server failed: {}", err);
+                let res = Response::Error(ResponseError {
+                    code: ErrorCode::ServerErrorEnd,
+                    message: "Server closed".into(),
+                });
+                if let Err(err) = send_result(&result_tx, res.into()) {
+                    log::error!("failed to deliver proc-macro response: {:?}", err);
+                }
+                // Restart the process
+                if process.restart().is_err() {
+                    break;
+                }
+                let stdio = match process.stdio() {
+                    None => break,
+                    Some(it) => it,
+                };
+                stdin = stdio.0;
+                stdout = stdio.1;
+            }
+        }
+    }
+}
+
+/// Hands the response back to the requester, which may have gone away in the
+/// meantime (for example, because its query was cancelled).
+fn send_result(
+    result_tx: &Sender<Option<Response>>,
+    res: Option<Response>,
+) -> Result<(), ExpansionError> {
+    result_tx
+        .send(res)
+        .map_err(|_| ExpansionError::Unknown("Proc macro request was abandoned.".into()))
+}
+
+struct Process {
+    path: PathBuf,
+    child: Child,
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+    }
+}
+
+impl Process {
+    fn run(process_path: &Path) -> io::Result<Process> {
+        let path = process_path.to_path_buf();
+        let child = mk_child(&path)?;
+        Ok(Process { path, child })
+    }
+
+    fn restart(&mut self) -> io::Result<()> {
+        let _ = self.child.kill();
+        self.child = mk_child(&self.path)?;
+        Ok(())
+    }
+
+    fn stdio(&mut self) -> Option<(impl Write, impl BufRead)> {
+        let stdin = self.child.stdin.take()?;
+        let stdout = self.child.stdout.take()?;
+        let read = BufReader::new(stdout);
+
+        Some((stdin, read))
+    }
+}
+
+fn mk_child(path: &Path) -> io::Result<Child> {
+    Command::new(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+}
+
+/// Sends `req` to the server and waits for the response. The server closing
+/// its output counts as an error as well, so that it gets restarted.
+fn send_request(
+    writer: &mut impl Write,
+    reader: &mut impl BufRead,
+    req: Request,
+) -> io::Result<Response> {
+    req.write(writer)?;
+    Response::read(reader)?.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::UnexpectedEof, "proc-macro server closed its output")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_task_fails_when_thread_is_gone() {
+        let (task_tx, task_rx) = bounded(0);
+        drop(task_rx);
+        let task_tx = Arc::new(task_tx);
+        let srv = ProcMacroProcessSrv { inner: Some(Arc::downgrade(&task_tx)) };
+        assert!(srv.version().is_err());
+    }
+}
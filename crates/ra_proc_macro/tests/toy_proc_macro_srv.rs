@@ -0,0 +1,86 @@
+//! Drives the client against the `toy_proc_macro_srv` binary of this crate,
+//! which serves a couple of toy macros over stdio.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use ra_proc_macro::{ProcMacroClient, ProcMacroKind};
+use ra_tt::{Ident, Leaf, SmolStr, Subtree, TokenExpander, TokenId, TokenTree};
+
+fn toy_srv_path() -> PathBuf {
+    // Integration tests are built into `target/<profile>/deps`, and the
+    // binaries of the crate one directory above.
+    let mut dir = env::current_exe().unwrap();
+    dir.pop();
+    if dir.ends_with("deps") {
+        dir.pop();
+    }
+    dir.join(format!("toy_proc_macro_srv{}", env::consts::EXE_SUFFIX))
+}
+
+type Macros = Vec<(SmolStr, ProcMacroKind, Arc<dyn TokenExpander>)>;
+
+/// Starts a server and lists its macros. The server shuts down when the
+/// returned client is dropped.
+fn toy_macros() -> (ProcMacroClient, Macros) {
+    let client = ProcMacroClient::extern_process(&toy_srv_path()).unwrap();
+    let macros = client.by_dylib_path(Path::new("libtoy.so"));
+    (client, macros)
+}
+
+fn expander(macros: &Macros, name: &str) -> Arc<dyn TokenExpander> {
+    macros.iter().find(|(it, ..)| it.as_str() == name).unwrap().2.clone()
+}
+
+fn toks(text: &[&str]) -> Subtree {
+    Subtree {
+        delimiter: None,
+        token_trees: text
+            .iter()
+            .map(|it| TokenTree::Leaf(Leaf::Ident(Ident { text: (*it).into(), id: TokenId(0) })))
+            .collect(),
+    }
+}
+
+#[test]
+fn lists_macros() {
+    let (_client, macros) = toy_macros();
+    let mut macros =
+        macros.iter().map(|(name, kind, _)| (name.as_str(), *kind)).collect::<Vec<_>>();
+    macros.sort_by_key(|(name, _)| *name);
+    assert_eq!(
+        macros,
+        vec![
+            ("Marker", ProcMacroKind::CustomDerive),
+            ("crash", ProcMacroKind::FuncLike),
+            ("identity", ProcMacroKind::FuncLike),
+            ("prepend", ProcMacroKind::Attr),
+        ]
+    );
+}
+
+#[test]
+fn expands_derive_and_attribute() {
+    let (_client, macros) = toy_macros();
+
+    let derive = expander(&macros, "Marker");
+    let expansion = derive.expand(&toks(&["pub", "struct", "Foo"]), None).unwrap();
+    assert_eq!(expansion.to_string(), "impl Marker for Foo {}");
+
+    let attr = expander(&macros, "prepend");
+    let expansion = attr.expand(&toks(&["fn", "foo"]), Some(&toks(&["pub"]))).unwrap();
+    assert_eq!(expansion.to_string(), "pub fn foo");
+}
+
+#[test]
+fn restarts_crashed_server() {
+    let (_client, macros) = toy_macros();
+
+    assert!(expander(&macros, "crash").expand(&toks(&[]), None).is_err());
+
+    let expansion = expander(&macros, "identity").expand(&toks(&["a", "b"]), None).unwrap();
+    assert_eq!(expansion.to_string(), "a b");
+}
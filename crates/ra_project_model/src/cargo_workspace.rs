@@ -1,6 +1,10 @@
 //! FIXME: write short doc here
 
-use std::path::{Path, PathBuf};
+use std::{
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
 
 use cargo_metadata::{CargoOpt, MetadataCommand};
 use ra_arena::{impl_arena_id, Arena, RawId};
@@ -36,11 +40,20 @@ pub struct CargoFeatures {
     /// List of features to activate.
     /// This will be ignored if `cargo_all_features` is true.
     pub features: Vec<String>,
+
+    /// Runs cargo check on launch to figure out the correct values of `OUT_DIR`
+    /// and the paths of compiled proc-macro dylibs
+    pub load_out_dirs_from_check: bool,
 }
 
 impl Default for CargoFeatures {
     fn default() -> Self {
-        CargoFeatures { no_default_features: false, all_features: true, features: Vec::new() }
+        CargoFeatures {
+            no_default_features: false,
+            all_features: true,
+            features: Vec::new(),
+            load_out_dirs_from_check: false,
+        }
     }
 }
 
@@ -55,12 +68,15 @@ impl_arena_id!(Target);
 #[derive(Debug, Clone)]
 struct PackageData {
     name: String,
+    version: String,
     manifest: PathBuf,
     targets: Vec<Target>,
     is_member: bool,
     dependencies: Vec<PackageDependency>,
     edition: Edition,
     features: Vec<String>,
+    out_dir: Option<PathBuf>,
+    proc_macro_dylib_path: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
@@ -76,6 +92,7 @@ struct TargetData {
     root: PathBuf,
     kind: TargetKind,
     is_proc_macro: bool,
+    required_features: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -110,6 +127,9 @@ impl Package {
     pub fn name(self, ws: &CargoWorkspace) -> &str {
         ws.packages[self].name.as_str()
     }
+    pub fn version(self, ws: &CargoWorkspace) -> &str {
+        ws.packages[self].version.as_str()
+    }
     pub fn root(self, ws: &CargoWorkspace) -> &Path {
         ws.packages[self].manifest.parent().unwrap()
     }
@@ -119,6 +139,14 @@ impl Package {
     pub fn features(self, ws: &CargoWorkspace) -> &[String] {
         &ws.packages[self].features
     }
+    /// `OUT_DIR` of the package's build script, if it has been executed.
+    pub fn out_dir(self, ws: &CargoWorkspace) -> Option<&Path> {
+        ws.packages[self].out_dir.as_ref().map(PathBuf::as_path)
+    }
+    /// Compiled dylib of the package's proc-macro target, if it has been built.
+    pub fn proc_macro_dylib_path(self, ws: &CargoWorkspace) -> Option<&Path> {
+        ws.packages[self].proc_macro_dylib_path.as_ref().map(PathBuf::as_path)
+    }
     pub fn targets<'a>(self, ws: &'a CargoWorkspace) -> impl Iterator<Item = Target> + 'a {
         ws.packages[self].targets.iter().cloned()
     }
@@ -150,6 +178,11 @@ impl Target {
     pub fn is_proc_macro(self, ws: &CargoWorkspace) -> bool {
         ws.targets[self].is_proc_macro
     }
+    /// Features which have to be enabled for the target to be built, as set
+    /// with `required-features` in `Cargo.toml`.
+    pub fn required_features(self, ws: &CargoWorkspace) -> &[String] {
+        &ws.targets[self].required_features
+    }
 }
 
 impl CargoWorkspace {
@@ -172,6 +205,10 @@ impl CargoWorkspace {
             meta.current_dir(parent);
         }
         let meta = meta.exec().map_err(|e| format!("cargo metadata failed: {}", e))?;
+        let mut resources = ExternResources::default();
+        if cargo_features.load_out_dirs_from_check {
+            resources = load_extern_resources(cargo_toml, cargo_features);
+        }
         let mut pkg_by_id = FxHashMap::default();
         let mut packages = Arena::default();
         let mut targets = Arena::default();
@@ -179,17 +216,21 @@ impl CargoWorkspace {
         let ws_members = &meta.workspace_members;
 
         for meta_pkg in meta.packages {
-            let cargo_metadata::Package { id, edition, name, manifest_path, .. } = meta_pkg;
+            let cargo_metadata::Package { id, edition, name, version, manifest_path, .. } =
+                meta_pkg;
             let is_member = ws_members.contains(&id);
             let edition = edition.parse::<Edition>()?;
             let pkg = packages.alloc(PackageData {
                 name,
+                version: version.to_string(),
                 manifest: manifest_path,
                 targets: Vec::new(),
                 is_member,
                 edition,
                 dependencies: Vec::new(),
                 features: Vec::new(),
+                out_dir: resources.out_dirs.get(&id.repr).cloned(),
+                proc_macro_dylib_path: resources.proc_dylib_paths.get(&id.repr).cloned(),
             });
             let pkg_data = &mut packages[pkg];
             pkg_by_id.insert(id, pkg);
@@ -201,6 +242,7 @@ impl CargoWorkspace {
                     root: meta_tgt.src_path.clone(),
                     kind: TargetKind::new(meta_tgt.kind.as_slice()),
                     is_proc_macro,
+                    required_features: meta_tgt.required_features,
                 });
                 pkg_data.targets.push(tgt);
             }
@@ -225,4 +267,118 @@ impl CargoWorkspace {
     pub fn target_by_root(&self, root: &Path) -> Option<Target> {
         self.packages().filter_map(|pkg| pkg.targets(self).find(|it| it.root(self) == root)).next()
     }
+
+    pub fn workspace_root(&self) -> &Path {
+        &self.workspace_root
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ExternResources {
+    out_dirs: FxHashMap<String, PathBuf>,
+    proc_dylib_paths: FxHashMap<String, PathBuf>,
+}
+
+/// Runs `cargo check` and collects, keyed by package id, the `OUT_DIR`s
+/// reported by executed build scripts and the dylibs of compiled proc-macro
+/// crates.
+///
+/// Failures are logged and result in empty maps: missing resources only
+/// degrade `env!("OUT_DIR")` and proc-macro expansion, which is not worth
+/// failing the whole workspace load for.
+fn load_extern_resources(cargo_toml: &Path, cargo_features: &CargoFeatures) -> ExternResources {
+    let mut args: Vec<String> = vec![
+        "check".into(),
+        "--message-format=json".into(),
+        "--manifest-path".into(),
+        cargo_toml.display().to_string(),
+    ];
+
+    if cargo_features.all_features {
+        args.push("--all-features".into());
+    } else if cargo_features.no_default_features {
+        // FIXME: `NoDefaultFeatures` is mutual exclusive with `SomeFeatures`
+        // https://github.com/oli-obk/cargo_metadata/issues/79
+        args.push("--no-default-features".into());
+    } else if !cargo_features.features.is_empty() {
+        args.push("--features".into());
+        args.push(cargo_features.features.join(" "));
+    }
+
+    let mut res = ExternResources::default();
+    let mut child = match Command::new("cargo")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .stdin(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            log::error!("failed to run cargo check to load extern resources: {}", e);
+            return res;
+        }
+    };
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            let message = match serde_json::from_str::<CargoMessage>(&line) {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+            let package_id = match message.package_id {
+                Some(it) => it,
+                None => continue,
+            };
+            match message.reason.as_str() {
+                "build-script-executed" => {
+                    if let Some(out_dir) = message.out_dir {
+                        res.out_dirs.insert(package_id, out_dir);
+                    }
+                }
+                "compiler-artifact" => {
+                    let is_proc_macro = message
+                        .target
+                        .map_or(false, |it| it.kind.iter().any(|k| k == "proc-macro"));
+                    if !is_proc_macro {
+                        continue;
+                    }
+                    if let Some(dylib) = message.filenames.into_iter().find(|it| is_dylib(it)) {
+                        res.proc_dylib_paths.insert(package_id, dylib);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let _ = child.wait();
+    res
+}
+
+/// The subset of cargo's JSON messages we are interested in.
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    package_id: Option<String>,
+    out_dir: Option<PathBuf>,
+    target: Option<CargoMessageTarget>,
+    #[serde(default)]
+    filenames: Vec<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct CargoMessageTarget {
+    kind: Vec<String>,
+}
+
+fn is_dylib(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => ext == "so" || ext == "dylib" || ext == "dll",
+        None => false,
+    }
 }
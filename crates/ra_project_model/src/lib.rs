@@ -13,7 +13,8 @@ use std::{
 };
 
 use ra_cfg::CfgOptions;
-use ra_db::{CrateGraph, CrateId, Edition, Env, FileId};
+use ra_db::{CrateGraph, CrateId, Edition, Env, FileId, ProcMacro, ProcMacroKind, SourceRootId};
+use ra_proc_macro::ProcMacroClient;
 use rustc_hash::FxHashMap;
 use serde_json::from_reader;
 
@@ -22,6 +23,7 @@ pub use crate::{
     json_project::JsonProject,
     sysroot::Sysroot,
 };
+pub use ra_proc_macro::ProcMacroClient;
 
 // FIXME use proper error enum
 pub type Result<T> = ::std::result::Result<T, Box<dyn Error + Send + Sync>>;
@@ -103,6 +105,9 @@ impl ProjectWorkspace {
                     let root = pkg.root(&cargo).to_path_buf();
                     let member = pkg.is_member(&cargo);
                     roots.push(PackageRoot::new(root, member));
+                    if let Some(out_dir) = pkg.out_dir(&cargo) {
+                        roots.push(PackageRoot::new(out_dir.to_path_buf(), false));
+                    }
                 }
                 for krate in sysroot.crates() {
                     roots.push(PackageRoot::new(krate.root_dir(&sysroot).to_path_buf(), false))
@@ -121,9 +126,14 @@ impl ProjectWorkspace {
         }
     }
 
+    /// Lowers the workspace to a `CrateGraph`. `source_roots` maps the paths
+    /// of the loaded roots (see `to_roots`) to their source roots, so that
+    /// `include!` can refer to the `OUT_DIR` of a package.
     pub fn to_crate_graph(
         &self,
         default_cfg_options: &CfgOptions,
+        proc_macro_client: &ProcMacroClient,
+        source_roots: &FxHashMap<PathBuf, SourceRootId>,
         load: &mut dyn FnMut(&Path) -> Option<FileId>,
     ) -> (CrateGraph, FxHashMap<CrateId, String>) {
         let mut crate_graph = CrateGraph::default();
@@ -155,6 +165,7 @@ impl ProjectWorkspace {
                                 edition,
                                 cfg_options,
                                 Env::default(),
+                                Default::default(),
                             ),
                         );
                     }
@@ -194,6 +205,7 @@ impl ProjectWorkspace {
                             Edition::Edition2018,
                             cfg_options,
                             Env::default(),
+                            Default::default(),
                         );
                         sysroot_crates.insert(krate, crate_id);
                         names.insert(crate_id, krate.name(&sysroot).to_string());
@@ -232,13 +244,26 @@ impl ProjectWorkspace {
                                 opts.insert_features(pkg.features(&cargo).iter().map(Into::into));
                                 opts
                             };
+                            let env = package_env(&cargo, pkg);
+                            let proc_macro = if tgt.is_proc_macro(&cargo) {
+                                load_proc_macros(proc_macro_client, &cargo, pkg)
+                            } else {
+                                Vec::new()
+                            };
                             let crate_id = crate_graph.add_crate_root(
                                 file_id,
                                 edition,
                                 cfg_options,
-                                Env::default(),
+                                env,
+                                proc_macro,
                             );
                             names.insert(crate_id, pkg.name(&cargo).to_string());
+                            if let Some(out_dir) = pkg.out_dir(&cargo) {
+                                if let Some(&root) = source_roots.get(out_dir) {
+                                    let out_dir = out_dir.to_path_buf();
+                                    crate_graph.add_extern_root(crate_id, out_dir, root);
+                                }
+                            }
                             if tgt.kind(&cargo) == TargetKind::Lib {
                                 lib_tgt = Some(crate_id);
                                 pkg_to_lib_crate.insert(pkg, crate_id);
@@ -333,6 +358,52 @@ impl ProjectWorkspace {
     }
 }
 
+/// Environment variables Cargo sets when compiling `pkg`, as observed by
+/// `env!` and `option_env!`.
+fn package_env(cargo: &CargoWorkspace, pkg: Package) -> Env {
+    let mut env = Env::default();
+    let version = pkg.version(cargo);
+    env.set("CARGO_PKG_NAME", pkg.name(cargo).to_string());
+    env.set("CARGO_PKG_VERSION", version.to_string());
+    let (version, pre) = match version.find('-') {
+        Some(idx) => (&version[..idx], &version[idx + 1..]),
+        None => (version, ""),
+    };
+    let mut parts = version.split('.');
+    env.set("CARGO_PKG_VERSION_MAJOR", parts.next().unwrap_or_default().to_string());
+    env.set("CARGO_PKG_VERSION_MINOR", parts.next().unwrap_or_default().to_string());
+    env.set("CARGO_PKG_VERSION_PATCH", parts.next().unwrap_or_default().to_string());
+    env.set("CARGO_PKG_VERSION_PRE", pre.to_string());
+    env.set("CARGO_MANIFEST_DIR", pkg.root(cargo).display().to_string());
+    if let Some(out_dir) = pkg.out_dir(cargo) {
+        env.set("OUT_DIR", out_dir.display().to_string());
+    }
+    env
+}
+
+fn load_proc_macros(
+    client: &ProcMacroClient,
+    cargo: &CargoWorkspace,
+    pkg: Package,
+) -> Vec<ProcMacro> {
+    let dylib_path = match pkg.proc_macro_dylib_path(cargo) {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    client
+        .by_dylib_path(dylib_path)
+        .into_iter()
+        .map(|(name, kind, expander)| {
+            let kind = match kind {
+                ra_proc_macro::ProcMacroKind::CustomDerive => ProcMacroKind::CustomDerive,
+                ra_proc_macro::ProcMacroKind::FuncLike => ProcMacroKind::FuncLike,
+                ra_proc_macro::ProcMacroKind::Attr => ProcMacroKind::Attr,
+            };
+            ProcMacro { name, kind, expander }
+        })
+        .collect()
+}
+
 fn find_rust_project_json(path: &Path) -> Option<PathBuf> {
     if path.ends_with("rust-project.json") {
         return Some(path.to_path_buf());
@@ -333,6 +333,25 @@ impl ast::Literal {
             _ => unreachable!(),
         }
     }
+
+    /// The value of an integer literal, ignoring its suffix.
+    pub fn int_value(&self) -> Option<u128> {
+        let suffix = match self.kind() {
+            LiteralKind::IntNumber { suffix } => suffix,
+            _ => return None,
+        };
+        let token = self.token();
+        let text = token.text();
+        let text = &text[..text.len() - suffix.map_or(0, |it| it.len())];
+        let text = text.replace('_', "");
+        let (radix, digits) = match text.get(..2) {
+            Some("0x") => (16, &text[2..]),
+            Some("0o") => (8, &text[2..]),
+            Some("0b") => (2, &text[2..]),
+            _ => (10, &text[..]),
+        };
+        u128::from_str_radix(digits, radix).ok()
+    }
 }
 
 impl ast::BlockExpr {
@@ -353,6 +372,38 @@ impl ast::BlockExpr {
             _ => true,
         }
     }
+
+    pub fn is_async(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![async])
+    }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![unsafe])
+    }
+}
+
+impl ast::BreakExpr {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
+impl ast::ContinueExpr {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
+impl ast::LambdaExpr {
+    pub fn is_async(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![async])
+    }
 }
 
 #[test]
@@ -362,6 +413,23 @@ fn test_literal_with_attr() {
     assert_eq!(lit.token().text(), r#""Hello""#);
 }
 
+#[test]
+fn test_literal_int_value() {
+    fn check(text: &str, expected: Option<u128>) {
+        let parse = ast::SourceFile::parse(&format!("const _: usize = {};", text));
+        let lit = parse.tree().syntax().descendants().find_map(ast::Literal::cast).unwrap();
+        assert_eq!(lit.int_value(), expected);
+    }
+
+    check("92", Some(92));
+    check("1_000usize", Some(1000));
+    check("0xff_u8", Some(255));
+    check("0o17", Some(15));
+    check("0b101", Some(5));
+    check("1.5", None);
+    check(r#""92""#, None);
+}
+
 impl ast::RecordField {
     pub fn parent_record_lit(&self) -> ast::RecordLit {
         self.syntax().ancestors().find_map(ast::RecordLit::cast).unwrap()
@@ -2,7 +2,7 @@
 //! Extensions for various expressions live in a sibling `expr_extensions` module.
 
 use crate::{
-    ast::{self, child_opt, children, AstNode, AttrInput, SyntaxNode},
+    ast::{self, child_opt, children, AstNode, AstToken, AttrInput, SyntaxNode},
     SmolStr, SyntaxElement,
     SyntaxKind::*,
     SyntaxToken, T,
@@ -221,6 +221,14 @@ impl ast::FnDef {
             .and_then(|it| it.into_token())
             .filter(|it| it.kind() == T![;])
     }
+
+    pub fn is_async(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![async])
+    }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![unsafe])
+    }
 }
 
 impl ast::LetStmt {
@@ -285,6 +293,12 @@ impl ast::BindPat {
     }
 }
 
+impl ast::StaticDef {
+    pub fn is_mut(&self) -> bool {
+        self.syntax().children_with_tokens().any(|n| n.kind() == T![mut])
+    }
+}
+
 impl ast::PointerType {
     pub fn is_mut(&self) -> bool {
         self.syntax().children_with_tokens().any(|n| n.kind() == T![mut])
@@ -295,6 +309,13 @@ impl ast::ReferenceType {
     pub fn is_mut(&self) -> bool {
         self.syntax().children_with_tokens().any(|n| n.kind() == T![mut])
     }
+
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -316,6 +337,13 @@ impl ast::SelfParam {
             .expect("invalid tree: self param must have self")
     }
 
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+
     pub fn kind(&self) -> SelfParamKind {
         let borrowed = self.syntax().children_with_tokens().any(|n| n.kind() == T![&]);
         if borrowed {
@@ -343,6 +371,33 @@ impl ast::LifetimeParam {
             .filter_map(|it| it.into_token())
             .find(|it| it.kind() == LIFETIME)
     }
+
+    /// The lifetimes after the colon, i.e. `'b` and `'c` in `'a: 'b + 'c`.
+    pub fn lifetime_bounds(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .skip_while(|it| it.kind() != T![:])
+            .filter(|it| it.kind() == LIFETIME)
+    }
+}
+
+impl ast::Label {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
+impl ast::LifetimeArg {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
 }
 
 impl ast::TypeParam {
@@ -409,3 +464,73 @@ impl ast::TraitDef {
         self.syntax().children_with_tokens().any(|t| t.kind() == T![auto])
     }
 }
+
+impl ast::Macro {
+    pub fn token_tree(&self) -> Option<ast::TokenTree> {
+        match self {
+            ast::Macro::MacroCall(it) => it.token_tree(),
+            ast::Macro::MacroDef(it) => it.token_tree(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VisibilityKind {
+    In(ast::Path),
+    PubCrate,
+    PubSuper,
+    PubSelf,
+    Pub,
+}
+
+impl ast::Visibility {
+    pub fn kind(&self) -> VisibilityKind {
+        if let Some(path) = child_opt(self) {
+            VisibilityKind::In(path)
+        } else if self.has_token(T![crate]) {
+            VisibilityKind::PubCrate
+        } else if self.has_token(T![super]) {
+            VisibilityKind::PubSuper
+        } else if self.has_token(T![self]) {
+            VisibilityKind::PubSelf
+        } else {
+            VisibilityKind::Pub
+        }
+    }
+
+    fn has_token(&self, kind: SyntaxKind) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == kind)
+    }
+}
+
+impl ast::Abi {
+    /// The name of the ABI, like `C` in `extern "C"`. A bare `extern` uses the
+    /// C ABI.
+    pub fn name(&self) -> Option<String> {
+        let token = self
+            .syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|it| it.kind() == STRING || it.kind() == RAW_STRING);
+        match token {
+            None => Some("C".to_string()),
+            Some(token) if token.kind() == STRING => ast::String::cast(token)?.value(),
+            Some(token) => ast::RawString::cast(token)?.value(),
+        }
+    }
+}
+
+impl ast::ExternItem {
+    /// The `extern { .. }` block this item is declared in, if any.
+    pub fn extern_block(&self) -> Option<ast::ExternBlock> {
+        let item_list = self.syntax().parent().and_then(ast::ExternItemList::cast)?;
+        item_list.syntax().parent().and_then(ast::ExternBlock::cast)
+    }
+}
+
+impl ast::ParamList {
+    /// True if the list ends with `...`, as in variadic foreign functions.
+    pub fn is_variadic(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![...])
+    }
+}
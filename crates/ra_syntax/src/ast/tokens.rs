@@ -2,7 +2,7 @@
 
 use crate::{
     ast::AstToken,
-    SyntaxKind::{COMMENT, RAW_STRING, STRING, WHITESPACE},
+    SyntaxKind::{CHAR, COMMENT, RAW_STRING, STRING, WHITESPACE},
     SyntaxToken, TextRange, TextUnit,
 };
 
@@ -173,6 +173,30 @@ impl RawString {
     }
 }
 
+pub struct Char(SyntaxToken);
+
+impl AstToken for Char {
+    fn cast(token: SyntaxToken) -> Option<Self> {
+        match token.kind() {
+            CHAR => Some(Char(token)),
+            _ => None,
+        }
+    }
+    fn syntax(&self) -> &SyntaxToken {
+        &self.0
+    }
+}
+
+impl Char {
+    pub fn value(&self) -> Option<char> {
+        let text = self.text().as_str();
+        if text.len() < 2 || !text.starts_with('\'') || !text.ends_with('\'') {
+            return None;
+        }
+        rustc_lexer::unescape::unescape_char(&text[1..text.len() - 1]).ok()
+    }
+}
+
 fn find_usual_string_range(s: &str) -> Option<TextRange> {
     let left_quote = s.find('"')?;
     let right_quote = s.rfind('"')?;
@@ -14,9 +14,12 @@ macro_rules! impl_froms {
     }
 }
 
-use std::fmt;
+use std::{
+    fmt::{self, Debug},
+    panic::RefUnwindSafe,
+};
 
-use smol_str::SmolStr;
+pub use smol_str::SmolStr;
 
 /// Represents identity of the token.
 ///
@@ -178,3 +181,21 @@ impl Subtree {
 }
 
 pub mod buffer;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpansionError {
+    IOError(String),
+    JsonError(String),
+    Unknown(String),
+    ExpansionError(String),
+}
+
+/// Something that can expand a token tree, like a procedural macro loaded into
+/// a separate process.
+///
+/// `attrs` is the argument of an attribute macro (`#[attr(..)]`); it is `None`
+/// for derives and function-like macros.
+pub trait TokenExpander: Debug + Send + Sync + RefUnwindSafe {
+    fn expand(&self, subtree: &Subtree, attrs: Option<&Subtree>)
+        -> Result<Subtree, ExpansionError>;
+}
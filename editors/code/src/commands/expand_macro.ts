@@ -71,6 +71,21 @@ interface MacroExpandParams {
 interface ExpandedMacro {
     name: string;
     expansion: string;
+    steps: ExpansionStep;
+}
+
+interface ExpansionStep {
+    name: string;
+    rule: number | null;
+    failed: boolean;
+    bindings: MacroBinding[];
+    expansion: string;
+    nested: ExpansionStep[];
+}
+
+interface MacroBinding {
+    name: string;
+    value: string;
 }
 
 function code_format(expanded: ExpandedMacro): string {
@@ -78,6 +93,32 @@ function code_format(expanded: ExpandedMacro): string {
     result += '// ' + '='.repeat(result.length - 3);
     result += '\n\n';
     result += expanded.expansion;
+    result += '\n\n// Expansion steps\n';
+    result += '// ' + '='.repeat(15);
+    result += '\n';
+    result += steps_format(expanded.steps, 0);
+
+    return result;
+}
+
+function steps_format(step: ExpansionStep, depth: number): string {
+    const indent = '    '.repeat(depth);
+    let header = `${step.name}!`;
+    if (step.rule != null && step.failed) {
+        header += ` (no rule matched, closest is rule #${step.rule + 1})`;
+    } else if (step.rule != null) {
+        header += ` (rule #${step.rule + 1})`;
+    }
+    let result = `\n${indent}// ${header}\n`;
+    for (const binding of step.bindings) {
+        result += `${indent}//     $${binding.name} = ${binding.value}\n`;
+    }
+    for (const line of step.expansion.split('\n')) {
+        result += `${indent}${line}\n`;
+    }
+    for (const nested of step.nested) {
+        result += steps_format(nested, depth + 1);
+    }
 
     return result;
 }
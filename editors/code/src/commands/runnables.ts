@@ -140,7 +140,11 @@ export async function handleSingle(runnable: Runnable) {
 export async function interactivelyStartCargoWatch(
     context: vscode.ExtensionContext,
 ): Promise<CargoWatchProvider | undefined> {
-    if (Server.config.cargoWatchOptions.enableOnStartup === 'disabled') {
+    if (
+        Server.config.cargoWatchOptions.enableOnStartup === 'disabled' ||
+        // The server checks on save by itself
+        Server.config.cargoWatchOptions.onSave
+    ) {
         return;
     }
 
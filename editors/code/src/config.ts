@@ -14,12 +14,14 @@ export interface CargoWatchOptions {
     trace: CargoWatchTraceOptions;
     ignore: string[];
     allTargets: boolean;
+    onSave: boolean;
 }
 
 export interface CargoFeatures {
     noDefaultFeatures: boolean;
     allFeatures: boolean;
     features: string[];
+    loadOutDirsFromCheck: boolean;
 }
 
 export class Config {
@@ -33,8 +35,10 @@ export class Config {
     public excludeGlobs = [];
     public useClientWatching = true;
     public featureFlags = {};
+    public completionTemplates = [];
     // for internal use
     public withSysroot: null | boolean = null;
+    public procMacroSrv: null | string = null;
     public cargoWatchOptions: CargoWatchOptions = {
         enableOnStartup: 'ask',
         trace: 'off',
@@ -42,11 +46,13 @@ export class Config {
         command: '',
         ignore: [],
         allTargets: true,
+        onSave: false,
     };
     public cargoFeatures: CargoFeatures = {
         noDefaultFeatures: false,
         allFeatures: true,
         features: [],
+        loadOutDirsFromCheck: false,
     };
 
     private prevEnhancedTyping: null | boolean = null;
@@ -141,6 +147,13 @@ export class Config {
             );
         }
 
+        if (config.has('cargo-watch.onSave')) {
+            this.cargoWatchOptions.onSave = config.get<boolean>(
+                'cargo-watch.onSave',
+                false,
+            );
+        }
+
         if (config.has('lruCapacity')) {
             this.lruCapacity = config.get('lruCapacity') as number;
         }
@@ -162,9 +175,15 @@ export class Config {
         if (config.has('featureFlags')) {
             this.featureFlags = config.get('featureFlags') || {};
         }
+        if (config.has('completionTemplates')) {
+            this.completionTemplates = config.get('completionTemplates') || [];
+        }
         if (config.has('withSysroot')) {
             this.withSysroot = config.get('withSysroot') || false;
         }
+        if (config.has('procMacroSrv')) {
+            this.procMacroSrv = config.get('procMacroSrv') || null;
+        }
 
         if (config.has('cargoFeatures.noDefaultFeatures')) {
             this.cargoFeatures.noDefaultFeatures = config.get(
@@ -184,6 +203,12 @@ export class Config {
                 [],
             );
         }
+        if (config.has('cargoFeatures.loadOutDirsFromCheck')) {
+            this.cargoFeatures.loadOutDirsFromCheck = config.get(
+                'cargoFeatures.loadOutDirsFromCheck',
+                false,
+            );
+        }
 
         if (
             this.prevCargoFeatures !== null &&
@@ -191,6 +216,8 @@ export class Config {
                 this.prevCargoFeatures.allFeatures ||
                 this.cargoFeatures.noDefaultFeatures !==
                     this.prevCargoFeatures.noDefaultFeatures ||
+                this.cargoFeatures.loadOutDirsFromCheck !==
+                    this.prevCargoFeatures.loadOutDirsFromCheck ||
                 this.cargoFeatures.features.length !==
                     this.prevCargoFeatures.features.length ||
                 this.cargoFeatures.features.some(
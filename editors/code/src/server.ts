@@ -58,8 +58,18 @@ export class Server {
                 excludeGlobs: Server.config.excludeGlobs,
                 useClientWatching: Server.config.useClientWatching,
                 featureFlags: Server.config.featureFlags,
+                completionTemplates: Server.config.completionTemplates,
                 withSysroot: Server.config.withSysroot,
                 cargoFeatures: Server.config.cargoFeatures,
+                procMacroSrv: Server.config.procMacroSrv,
+                cargoWatchEnable: Server.config.cargoWatchOptions.onSave,
+                cargoWatchArgs: Server.config.cargoWatchOptions.arguments
+                    .split(' ')
+                    .filter(arg => arg.length > 0),
+                cargoWatchCommand:
+                    Server.config.cargoWatchOptions.command || 'check',
+                cargoWatchAllTargets:
+                    Server.config.cargoWatchOptions.allTargets,
             },
             traceOutputChannel,
         };